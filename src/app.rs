@@ -1,10 +1,31 @@
+use crate::aws::profile::{
+    available_profiles, check_profile, env_credentials, is_sso_profile, load_config_profiles,
+    resolve_profile, resolve_region, sso_token_expiry,
+};
+use crate::aws::utils::{ErrorKind, classify_error, display_width, format_age, format_remaining};
 use crate::aws::{
-    AwsClient, DynamoDbItem, Ec2Item, Ec2Service, EcsItem, EcsService, IamItem, IamService,
-    LambdaItem, LambdaService, MwaaItem, MwaaService, S3Item, S3NavigationAction, S3Service,
+    AccountIdentity, AwsClient, BucketTag, ClientOptions, CloudwatchItem, CloudwatchService,
+    CredentialReportEntry, DynamoDbItem, Ec2Column, Ec2Columns, Ec2Instance, Ec2Item, Ec2Service,
+    EcsItem, EcsService, EcsTask, FolderStats, IamItem, IamService, InFlight, IndexDetail,
+    LambdaItem, LambdaService, LambdaVersion, MetricRange, MetricSeries, MwaaItem, MwaaService,
+    Paging, Progress, S3Item, S3NavigationAction, S3ObjectVersion, S3Service, upload_key,
+    validate_bucket_name, with_timeout,
 };
-use anyhow::Result;
+use crate::config::Config;
+use crate::keymap::{Action, KeyMap};
+use anyhow::{Context, Result};
+use aws_credential_types::Credentials;
+use aws_smithy_types::error::display::DisplayErrorContext;
+use ratatui::layout::Rect;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use tempfile::TempPath;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::task::JoinHandle;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ServiceType {
     EC2,
     S3,
@@ -17,6 +38,24 @@ pub enum ServiceType {
 }
 
 impl ServiceType {
+    pub const ALL: [ServiceType; 8] = [
+        ServiceType::EC2,
+        ServiceType::S3,
+        ServiceType::IAM,
+        ServiceType::CloudWatch,
+        ServiceType::DynamoDB,
+        ServiceType::ECS,
+        ServiceType::MWAA,
+        ServiceType::Lambda,
+    ];
+
+    // Case-insensitive lookup by short name, e.g. "ec2" or "dynamodb"
+    pub fn from_name(name: &str) -> Option<ServiceType> {
+        Self::ALL
+            .into_iter()
+            .find(|s| s.short_name().eq_ignore_ascii_case(name))
+    }
+
     pub fn as_str(&self) -> &str {
         match self {
             ServiceType::EC2 => "EC2 Instances",
@@ -75,6 +114,801 @@ pub enum LoadingState {
     Error,
 }
 
+// A mutating action waiting for the user to confirm it in a prompt popup
+#[derive(Clone, Debug, PartialEq)]
+pub enum PendingAction {
+    StopEcsTask {
+        cluster: String,
+        task_id: String,
+    },
+    FilterEc2ByTag,
+    GroupS3BucketsByTag,
+    RestoreS3Object {
+        bucket: String,
+        key: String,
+    },
+    DeleteS3Object {
+        bucket: String,
+        key: String,
+    },
+    DeleteS3Prefix {
+        bucket: String,
+        prefix: String,
+    },
+    DeleteDynamoDbTable {
+        table: String,
+    },
+    CreateDynamoDbTable,
+    RenameEc2Instance {
+        instance_id: String,
+    },
+    CreateS3Bucket,
+    DeleteS3Bucket {
+        bucket: String,
+    },
+    JumpToS3Prefix {
+        bucket: String,
+    },
+    StopEc2Instances {
+        instance_ids: Vec<String>,
+    },
+    SummarizeS3Bucket {
+        bucket: String,
+    },
+    SsoLogin {
+        profile: String,
+    },
+    Reconnect,
+    DeleteS3Objects {
+        bucket: String,
+        keys: Vec<String>,
+    },
+    ChooseS3UploadFile {
+        bucket: String,
+        prefix: String,
+    },
+    UploadS3Object {
+        bucket: String,
+        path: PathBuf,
+    },
+    DownloadS3Object {
+        bucket: String,
+        key: String,
+        version_id: Option<String>,
+    },
+}
+
+impl PendingAction {
+    /// Whether confirming the action changes anything in AWS or writes a local file. Read-only
+    /// mode refuses these, and new actions count as mutating unless listed here.
+    pub fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            PendingAction::FilterEc2ByTag
+                | PendingAction::GroupS3BucketsByTag
+                | PendingAction::JumpToS3Prefix { .. }
+                | PendingAction::SummarizeS3Bucket { .. }
+                | PendingAction::SsoLogin { .. }
+                | PendingAction::Reconnect
+        )
+    }
+}
+
+// Resource whose describe response the raw JSON view shows
+enum RawResource {
+    Ec2Instance(String),
+    DynamoDbTable(String),
+    LambdaFunction(String),
+}
+
+// What the detail popup showed before drilling into a GSI
+#[derive(Clone, Debug)]
+pub struct DetailParent {
+    pub index_name: String,
+    pub content: Vec<(String, String)>,
+    pub scroll: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Prompt {
+    pub title: String,
+    pub lines: Vec<String>,    // Context shown above the input
+    pub input_label: String,   // Label for the editable value
+    pub input: Option<String>, // Editable value, None for a plain yes/no prompt
+    pub action: PendingAction,
+}
+
+const EVENT_LOG_CAPACITY: usize = 500;
+
+// `current_path` of the IAM credential report, the one level below the user list
+const CREDENTIAL_REPORT: &str = "credential-report";
+
+// Text the user must type to confirm a recursive delete
+const DELETE_CONFIRMATION: &str = "DELETE";
+
+/// Starts the status of an action that dry-run mode reported instead of making.
+pub const DRY_RUN_PREFIX: &str = "(dry-run)";
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_FRAME: Duration = Duration::from_millis(100);
+
+// `aws ssm start-session` needs this next to the CLI
+const SSM_PLUGIN: &str = "session-manager-plugin";
+
+// Log events `l` shows from a Lambda function's latest log stream
+const LOG_TAIL_EVENTS: i32 = 50;
+
+// Storage classes the S3 object filter cycles through
+const STORAGE_CLASS_FILTERS: [&str; 3] = ["STANDARD", "GLACIER", "DEEP_ARCHIVE"];
+
+// Zero-byte keys ending in "/" that the console and some sync tools create to stand in for a
+// folder. Sizes arrive formatted, and real folders show as "DIR"
+fn is_folder_placeholder(name: &str, size: &str) -> bool {
+    name.ends_with('/') && size == "0 B"
+}
+
+fn placeholders_hidden(count: usize) -> String {
+    match count {
+        0 => String::new(),
+        1 => " (1 folder placeholder hidden)".to_string(),
+        n => format!(" ({} folder placeholders hidden)", n),
+    }
+}
+
+fn next_storage_class_filter(current: &Option<String>) -> Option<String> {
+    let next = match current {
+        None => 0,
+        Some(class) => match STORAGE_CLASS_FILTERS.iter().position(|c| c == class) {
+            Some(index) => index + 1,
+            None => 0,
+        },
+    };
+    STORAGE_CLASS_FILTERS.get(next).map(|c| c.to_string())
+}
+
+// One-line summary of a service for the dashboard
+async fn summarize_service(client: &AwsClient, service: ServiceType, timeout: Duration) -> String {
+    let summary = async {
+        let summary = match service {
+            ServiceType::EC2 => {
+                let instances = client
+                    .list_ec2_instances(None, Paging::default(), &Progress::default())
+                    .await?;
+                let count = |state: &str| {
+                    instances
+                        .iter()
+                        .filter(|i| i.state.eq_ignore_ascii_case(state))
+                        .count()
+                };
+                format!(
+                    "{} running, {} stopped ({} total)",
+                    count("running"),
+                    count("stopped"),
+                    instances.len()
+                )
+            }
+            ServiceType::S3 => format!("{} buckets", client.list_s3_buckets().await?.len()),
+            ServiceType::IAM => format!("{} users", client.list_iam_users().await?.len()),
+            ServiceType::CloudWatch => format!(
+                "{} alarm(s) in ALARM",
+                client.count_cloudwatch_alarms_firing().await?
+            ),
+            ServiceType::DynamoDB => {
+                format!(
+                    "{} tables",
+                    client
+                        .list_dynamodb_tables(Paging::default(), &Progress::default())
+                        .await?
+                        .len()
+                )
+            }
+            ServiceType::ECS => format!("{} clusters", client.list_ecs_clusters().await?.len()),
+            ServiceType::MWAA => format!(
+                "{} environments",
+                client.list_mwaa_environments().await?.len()
+            ),
+            ServiceType::Lambda => {
+                format!("{} functions", client.list_lambda_functions().await?.len())
+            }
+        };
+        anyhow::Ok(summary)
+    };
+
+    with_timeout(timeout, summary)
+        .await
+        .unwrap_or_else(|e| format!("Error: {}", classify_error(&e).summary()))
+}
+
+// Raw result of listing one level of a service, before it is formatted into rows
+#[derive(Clone)]
+enum Listing {
+    Ec2(Vec<Ec2Instance>),
+    Ec2AllRegions {
+        instances: Vec<Ec2Instance>,
+        failed: Vec<(String, String)>, // Regions that couldn't be listed, with the error
+    },
+    S3Buckets(Vec<(String, String)>),
+    S3BucketGroups {
+        key: String,
+        buckets: Vec<(String, String, BucketTag)>, // Name, creation date and tag
+    },
+    S3Objects {
+        bucket: String,
+        prefix: String,
+        objects: Vec<(String, String, String, String)>,
+        folders: HashMap<String, FolderStats>, // Empty unless folder sizes are on
+    },
+    S3Versions {
+        bucket: String,
+        prefix: String,
+        versions: Vec<S3ObjectVersion>,
+    },
+    Iam(Vec<(String, String, String, String)>),
+    IamCredentialReport(Vec<CredentialReportEntry>),
+    CloudWatch(Vec<(String, String, bool)>),
+    DynamoDb(Vec<(String, String, String, String)>),
+    EcsClusters(Vec<(String, String)>),
+    EcsServices {
+        cluster: String,
+        services: Vec<(String, String)>,
+    },
+    EcsTasks {
+        cluster: String,
+        service: String,
+        tasks: Vec<EcsTask>,
+    },
+    Mwaa(Vec<String>),
+    Lambda(Vec<(String, String, String)>),
+    LambdaVersions {
+        function: String,
+        versions: Vec<LambdaVersion>,
+    },
+}
+
+impl Listing {
+    // Width the formatters give the column that stretches with the data: the name (or ARN, with
+    // `full_arns`), or the task definition for ECS tasks. 20 is their minimum
+    fn name_column_len(&self, full_arns: bool) -> usize {
+        fn longest<T>(rows: &[T], len: impl Fn(&T) -> usize) -> usize {
+            rows.iter().map(len).max().unwrap_or(0)
+        }
+        let longest = match self {
+            Listing::Ec2(instances) | Listing::Ec2AllRegions { instances, .. } => {
+                longest(instances, |i| display_width(&i.name))
+            }
+            Listing::S3Buckets(buckets) => longest(buckets, |(name, _)| display_width(name)),
+            Listing::S3BucketGroups { buckets, .. } => {
+                longest(buckets, |(name, ..)| display_width(name))
+            }
+            Listing::S3Objects { objects, .. } => {
+                longest(objects, |(name, ..)| display_width(name))
+            }
+            Listing::S3Versions { versions, .. } => longest(versions, |v| display_width(&v.name)),
+            Listing::Iam(users) if full_arns => longest(users, |(.., arn)| display_width(arn)),
+            Listing::Iam(users) => longest(users, |(name, ..)| display_width(name)),
+            Listing::IamCredentialReport(entries) => longest(entries, |e| display_width(&e.user)),
+            Listing::CloudWatch(alarms) => longest(alarms, |(name, ..)| display_width(name)),
+            Listing::DynamoDb(tables) => longest(tables, |(name, ..)| display_width(name)),
+            Listing::EcsClusters(rows) | Listing::EcsServices { services: rows, .. } => {
+                longest(rows, |(name, arn)| {
+                    display_width(if full_arns { arn } else { name })
+                })
+            }
+            Listing::EcsTasks { tasks, .. } => longest(tasks, |t| display_width(&t.definition)),
+            Listing::Mwaa(envs) => longest(envs, |name| display_width(name)),
+            Listing::Lambda(functions) => longest(functions, |(name, ..)| display_width(name)),
+            Listing::LambdaVersions { versions, .. } => {
+                longest(versions, |v| display_width(&v.aliases.join(", ")))
+            }
+        };
+        longest.max(20)
+    }
+
+    // Each resource's row id, as `App::row_id` gives it, with a fingerprint of its fields.
+    // Timestamps are raw, so relative times ticking over don't count as a change
+    fn keyed_rows(&self) -> Vec<(String, String)> {
+        fn keyed<T: std::fmt::Debug>(
+            rows: &[T],
+            id: impl Fn(&T) -> String,
+        ) -> Vec<(String, String)> {
+            rows.iter()
+                .map(|row| (id(row), format!("{:?}", row)))
+                .collect()
+        }
+        match self {
+            Listing::Ec2(instances) | Listing::Ec2AllRegions { instances, .. } => {
+                keyed(instances, |i| i.id.clone())
+            }
+            Listing::S3Buckets(buckets) => keyed(buckets, |(name, _)| name.clone()),
+            Listing::S3BucketGroups { buckets, .. } => keyed(buckets, |(name, ..)| name.clone()),
+            Listing::S3Objects {
+                bucket,
+                prefix,
+                objects,
+                ..
+            } => keyed(objects, |(name, ..)| {
+                format!("s3://{}/{}{}", bucket, prefix, name)
+            }),
+            Listing::S3Versions {
+                bucket,
+                prefix,
+                versions,
+            } => keyed(versions, |v| {
+                s3_version_uri(bucket, &format!("{}{}", prefix, v.name), &v.version_id)
+            }),
+            Listing::Iam(users) => keyed(users, |(name, ..)| name.clone()),
+            Listing::IamCredentialReport(entries) => keyed(entries, |e| e.user.clone()),
+            Listing::CloudWatch(alarms) => keyed(alarms, |(name, ..)| name.clone()),
+            Listing::DynamoDb(tables) => keyed(tables, |(name, ..)| name.clone()),
+            Listing::EcsClusters(clusters) => keyed(clusters, |(name, _)| name.clone()),
+            Listing::EcsServices { services, .. } => keyed(services, |(name, _)| name.clone()),
+            Listing::EcsTasks { tasks, .. } => keyed(tasks, |t| t.id.clone()),
+            Listing::Mwaa(envs) => keyed(envs, String::clone),
+            Listing::Lambda(functions) => keyed(functions, |(name, ..)| name.clone()),
+            Listing::LambdaVersions { function, versions } => {
+                keyed(versions, |v| format!("{}:{}", function, v.version))
+            }
+        }
+    }
+
+    // Drops what the paginated listings fetched past `max`. Returns whether anything was dropped
+    fn cap(&mut self, max: usize) -> bool {
+        fn cap<T>(rows: &mut Vec<T>, max: usize) -> bool {
+            let over = rows.len() > max;
+            rows.truncate(max);
+            over
+        }
+        match self {
+            Listing::Ec2(instances) | Listing::Ec2AllRegions { instances, .. } => {
+                cap(instances, max)
+            }
+            Listing::S3Objects { objects, .. } => cap(objects, max),
+            Listing::S3Versions { versions, .. } => cap(versions, max),
+            Listing::CloudWatch(alarms) => cap(alarms, max),
+            Listing::DynamoDb(tables) => cap(tables, max),
+            _ => false,
+        }
+    }
+}
+
+/// How a row differs from the snapshot taken with S.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowChange {
+    Added,
+    Changed,
+}
+
+// A listing saved to compare later refreshes of the same location against
+struct Snapshot {
+    service: usize,
+    path: Option<String>,
+    rows: HashMap<String, (String, String)>, // Row id -> fingerprint and the row as shown
+}
+
+// A listing running on the runtime; dropping it aborts the request
+struct RefreshTask {
+    handle: JoinHandle<Result<Listing>>,
+    progress: UnboundedReceiver<String>,
+    after_status: Option<String>,
+    reselect: Option<SelectedRow>,
+}
+
+// The resource under the cursor when a reload started, so the cursor can go back to it
+#[derive(Clone, Debug, PartialEq)]
+struct SelectedRow {
+    service: usize,
+    path: Option<String>,
+    id: String,
+}
+
+impl Drop for RefreshTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// A resource the detail popup keeps describing on a timer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WatchTarget {
+    Ec2Instance(String),
+    EcsTask { cluster: String, task_id: String },
+    CloudWatchAlarm(String),
+}
+
+impl WatchTarget {
+    pub fn label(&self) -> String {
+        match self {
+            WatchTarget::Ec2Instance(id) => format!("instance {}", id),
+            WatchTarget::EcsTask { task_id, .. } => format!("task {}", task_id),
+            WatchTarget::CloudWatchAlarm(name) => format!("alarm {}", name),
+        }
+    }
+}
+
+// Credential errors in a row after which only an explicit r or re-authentication calls AWS
+const AUTH_FAILURE_LIMIT: usize = 3;
+
+// Time left on the credentials below which the header countdown turns red and a refresh is offered
+const EXPIRY_WARNING: Duration = Duration::from_secs(5 * 60);
+
+// How long a detail row stays highlighted after its value changed
+const WATCH_FLASH: Duration = Duration::from_secs(2);
+
+// The watched resource and its next describe call
+struct Watch {
+    target: WatchTarget,
+    fetch: Option<JoinHandle<Result<WatchedDetails>>>,
+    next_poll: Instant,
+    polls: usize,
+    changed: HashMap<String, Instant>, // Detail key -> when its value last changed
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        if let Some(fetch) = &self.fetch {
+            fetch.abort();
+        }
+    }
+}
+
+type WatchedDetails = (Vec<(String, String)>, Option<MetricSeries>);
+
+// A detail popup load running on the runtime; dropping it aborts the request
+struct DetailTask {
+    handle: JoinHandle<Result<DetailUpdate>>,
+    progress: Option<UnboundedReceiver<String>>, // For loads that report as they go
+}
+
+impl Drop for DetailTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+// A recursive count or delete under an S3 prefix, running in the background
+struct PrefixTask {
+    handle: JoinHandle<Result<PrefixResult>>,
+    progress: UnboundedReceiver<String>,
+    deleting: bool, // Cancelling leaves what was already deleted gone
+}
+
+impl Drop for PrefixTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+// What a finished prefix task found or did
+enum PrefixResult {
+    Counted {
+        bucket: String,
+        prefix: String,
+        count: usize,
+    },
+    WouldDelete {
+        bucket: String,
+        prefix: String,
+        count: usize,
+    },
+    Deleted {
+        bucket: String,
+        prefix: String,
+        deleted: usize,
+    },
+}
+
+// The dashboard's summaries, gathered in the background
+struct DashboardTask {
+    handle: JoinHandle<Vec<(usize, String)>>, // Service index and its summary
+}
+
+impl Drop for DashboardTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+// What a finished detail load puts in the popup
+enum DetailUpdate {
+    LambdaLogs(Option<Vec<(String, String)>>), // None when the log group doesn't exist yet
+    Metric(Option<MetricSeries>),
+    BucketSummary {
+        bucket: String,
+        rows: Vec<(String, String)>,
+    },
+}
+
+async fn describe_watched(
+    client: AwsClient,
+    target: WatchTarget,
+    range: MetricRange,
+) -> Result<WatchedDetails> {
+    match target {
+        WatchTarget::Ec2Instance(id) => Ok((client.describe_ec2_instance(&id).await?, None)),
+        WatchTarget::EcsTask { cluster, task_id } => {
+            Ok((client.describe_ecs_task(&cluster, &task_id).await?, None))
+        }
+        WatchTarget::CloudWatchAlarm(name) => client.describe_cloudwatch_alarm(&name, range).await,
+    }
+}
+
+// Which EC2 instances a listing covers
+#[derive(Clone, Debug, Default)]
+struct Ec2Scope {
+    tag_filter: Option<(String, String)>,
+    all_regions: bool,
+}
+
+// How S3 listings are widened or arranged
+#[derive(Clone, Debug, Default)]
+struct S3Scope {
+    versions: bool,           // Object versions inside a bucket
+    group_by: Option<String>, // Tag key the bucket list is grouped by
+    folder_stats: bool,       // Count what each folder holds
+}
+
+// Lists `service` at `path` (S3 bucket/prefix, ECS cluster/service or Lambda function),
+// reporting pages as they load
+async fn fetch_listing(
+    client: AwsClient,
+    service: ServiceType,
+    path: Option<String>,
+    ec2: Ec2Scope,
+    paging: Paging,
+    s3: S3Scope,
+    progress: Progress,
+) -> Result<Listing> {
+    let listing = match service {
+        ServiceType::EC2 if ec2.all_regions => {
+            let (instances, failed) = client
+                .list_ec2_instances_all_regions(ec2.tag_filter.as_ref(), paging, &progress)
+                .await?;
+            Listing::Ec2AllRegions { instances, failed }
+        }
+        ServiceType::EC2 => Listing::Ec2(
+            client
+                .list_ec2_instances(ec2.tag_filter.as_ref(), paging, &progress)
+                .await?,
+        ),
+        ServiceType::S3 => match path {
+            Some(path) => {
+                let (bucket, prefix) = path.split_once('/').unwrap_or((&path, ""));
+                if s3.versions {
+                    let versions = client
+                        .list_s3_object_versions(bucket, prefix, paging, &progress)
+                        .await?;
+                    return Ok(Listing::S3Versions {
+                        bucket: bucket.to_string(),
+                        prefix: prefix.to_string(),
+                        versions,
+                    });
+                }
+                let objects = client
+                    .list_s3_objects(bucket, prefix, paging, &progress)
+                    .await?;
+                // A call per folder, so only when asked for
+                let folders = if s3.folder_stats {
+                    let names: Vec<String> = objects
+                        .iter()
+                        .filter(|(_, size, ..)| size == "DIR")
+                        .map(|(name, ..)| name.clone())
+                        .collect();
+                    client
+                        .s3_folder_stats(bucket, prefix, &names, &progress)
+                        .await
+                } else {
+                    HashMap::new()
+                };
+                Listing::S3Objects {
+                    bucket: bucket.to_string(),
+                    prefix: prefix.to_string(),
+                    objects,
+                    folders,
+                }
+            }
+            None => {
+                let buckets = client.list_s3_buckets().await?;
+                let Some(key) = s3.group_by else {
+                    return Ok(Listing::S3Buckets(buckets));
+                };
+                // Tags take a call per bucket, so they are only read once grouping asks for them
+                let names: Vec<String> = buckets.iter().map(|(name, _)| name.clone()).collect();
+                let values = client.s3_bucket_tag_values(&names, &key, &progress).await;
+                Listing::S3BucketGroups {
+                    key,
+                    buckets: buckets
+                        .into_iter()
+                        .zip(values)
+                        .map(|((name, date), value)| (name, date, value))
+                        .collect(),
+                }
+            }
+        },
+        ServiceType::IAM => match path {
+            Some(_) => Listing::IamCredentialReport(client.iam_credential_report(&progress).await?),
+            None => Listing::Iam(client.list_iam_users().await?),
+        },
+        ServiceType::CloudWatch => {
+            Listing::CloudWatch(client.list_cloudwatch_alarms(paging).await?)
+        }
+        ServiceType::DynamoDB => {
+            Listing::DynamoDb(client.list_dynamodb_tables(paging, &progress).await?)
+        }
+        ServiceType::ECS => match path.as_deref().map(|p| p.split_once('/')) {
+            None => Listing::EcsClusters(client.list_ecs_clusters().await?),
+            Some(None) => {
+                let cluster = path.unwrap_or_default();
+                let services = client.list_ecs_services(&cluster).await?;
+                Listing::EcsServices { cluster, services }
+            }
+            Some(Some((cluster, service))) => {
+                let tasks = client.list_ecs_tasks(cluster, Some(service)).await?;
+                Listing::EcsTasks {
+                    cluster: cluster.to_string(),
+                    service: service.to_string(),
+                    tasks,
+                }
+            }
+        },
+        ServiceType::MWAA => Listing::Mwaa(client.list_mwaa_environments().await?),
+        ServiceType::Lambda => match path {
+            Some(function) => Listing::LambdaVersions {
+                versions: client.list_lambda_versions(&function).await?,
+                function,
+            },
+            None => Listing::Lambda(client.list_lambda_functions().await?),
+        },
+    };
+    Ok(listing)
+}
+
+// Row id of an object version. A folder has no version id and keeps the plain URI
+fn s3_version_uri(bucket: &str, key: &str, version_id: &str) -> String {
+    if version_id.is_empty() {
+        format!("s3://{}/{}", bucket, key)
+    } else {
+        format!("s3://{}/{}?versionId={}", bucket, key, version_id)
+    }
+}
+
+/// "123456789012 (acme-prod)", or the bare id when the account has no alias.
+pub fn account_label(account: &AccountIdentity) -> String {
+    match &account.alias {
+        Some(alias) => format!("{} ({})", account.account_id, alias),
+        None => account.account_id.clone(),
+    }
+}
+
+// Local path typed in a prompt, with a leading ~ standing for the home directory
+fn expand_home(input: &str) -> PathBuf {
+    match (input.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(input),
+    }
+}
+
+// Parses "<tier> <days>", e.g. "bulk 3", into the SDK tier name and a positive day count
+fn parse_restore_request(input: &str) -> Option<(&'static str, i32)> {
+    let mut parts = input.split_whitespace();
+    let tier = match parts.next()?.to_lowercase().as_str() {
+        "expedited" => "Expedited",
+        "standard" => "Standard",
+        "bulk" => "Bulk",
+        _ => return None,
+    };
+    let days = parts.next()?.parse().ok().filter(|&d: &i32| d > 0)?;
+    parts.next().is_none().then_some((tier, days))
+}
+
+// Indented lines for a value that is a JSON object or array; plain strings and numbers stay inline
+fn pretty_json(value: &str) -> Option<Vec<String>> {
+    let value = value.trim();
+    if !(value.starts_with('{') || value.starts_with('[')) {
+        return None;
+    }
+    let parsed: serde_json::Value = serde_json::from_str(value).ok()?;
+    let pretty = serde_json::to_string_pretty(&parsed).ok()?;
+    Some(pretty.lines().map(String::from).collect())
+}
+
+// Matches `query` as a case-insensitive subsequence of `candidate`, e.g. "dy" in "DynamoDB".
+// Lower scores are better: matches that start early and skip few characters rank first.
+fn fuzzy_score(candidate: &str, query: &str) -> Option<usize> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    for (n, q) in query.to_lowercase().chars().enumerate() {
+        let found = pos + candidate[pos..].iter().position(|&c| c == q)?;
+        // The first character pays for where it starts, later ones for the gap they skip
+        score += if n == 0 { found } else { found - pos };
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+// Whether `program` is an executable file in one of the `path` directories
+fn on_path(program: &str, path: Option<&std::ffi::OsStr>) -> bool {
+    let names = if cfg!(windows) {
+        vec![format!("{}.exe", program), program.to_string()]
+    } else {
+        vec![program.to_string()]
+    };
+    path.is_some_and(|path| {
+        std::env::split_paths(path).any(|dir| names.iter().any(|name| dir.join(name).is_file()))
+    })
+}
+
+// Joins `args` and then `identity` (see `App::cli_identity_args`) into a copy-pasteable `aws`
+// command
+fn aws_cli_command(args: &[&str], identity: &[String]) -> String {
+    let mut parts = vec!["aws".to_string()];
+    parts.extend(args.iter().map(|arg| shell_quote(arg)));
+    parts.extend(identity.iter().map(|arg| shell_quote(arg)));
+    parts.join(" ")
+}
+
+// Single-quotes `arg` unless it only contains characters the shell leaves alone
+fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+// Parses "<table> <partition key>[:S|N|B]" into the table, key name and attribute type
+fn parse_create_table(input: &str) -> Option<(String, String, &'static str)> {
+    let mut parts = input.split_whitespace();
+    let table = parts.next()?;
+    let key = parts.next()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let (key, key_type) = key.rsplit_once(':').unwrap_or((key, "S"));
+    let key_type = match key_type.to_uppercase().as_str() {
+        "S" => "S",
+        "N" => "N",
+        "B" => "B",
+        _ => return None,
+    };
+    (!key.is_empty()).then(|| (table.to_string(), key.to_string(), key_type))
+}
+
+// First few ids for a bulk confirmation, with a count of the rest
+fn preview(ids: &[String]) -> Vec<String> {
+    const SHOWN: usize = 5;
+    let mut lines: Vec<String> = ids
+        .iter()
+        .take(SHOWN)
+        .map(|id| format!("  {}", id))
+        .collect();
+    if ids.len() > SHOWN {
+        lines.push(format!("  ... and {} more", ids.len() - SHOWN));
+    }
+    lines
+}
+
+// A bulk prompt that asked for the count only goes ahead when the typed count matches
+fn bulk_confirmed(input: Option<&str>, count: usize) -> bool {
+    input.is_none_or(|input| input.trim() == count.to_string())
+}
+
+// Errors that retrying with the same credentials can't fix
+fn is_auth_error(kind: &ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::ExpiredToken | ErrorKind::InvalidCredentials
+    )
+}
+
+// Parses `Key=Value`; the value may itself contain '='
+fn parse_tag_filter(input: &str) -> Option<(String, String)> {
+    let (key, value) = input.split_once('=')?;
+    let key = key.trim();
+    (!key.is_empty()).then(|| (key.to_string(), value.trim().to_string()))
+}
+
 pub struct App {
     pub services: Vec<ServiceInfo>,
     pub active_service: usize,
@@ -83,24 +917,97 @@ pub struct App {
     pub status_message: String,
     pub loading_state: LoadingState,
     pub aws_client: Option<AwsClient>,
-    pub error_message: Option<String>,
+    pub error_message: Option<String>, // Full text of the last load error, sources included
+    pub show_error_popup: bool,
+    pub error_scroll: u16,
     pub show_service_popup: bool,
     pub popup_selected_index: usize,
+    pub service_filter: String, // Typed in the service popup to narrow the list
+    pub show_palette: bool,
+    pub palette_filter: String, // Typed in the command palette to narrow the actions
+    pub palette_selected: usize, // Index into `palette_actions`
+    pub show_column_picker: bool,
+    pub column_picker_selected: usize, // Index into `column_choices`
     pub profile_name: String,
+    pub env_credentials: Option<String>, // Shown instead of the profile under aws-vault and the like
+    pub sso_login_requested: Option<String>, // Profile for run_app to run `aws sso login` for
+    pub ssm_session_requested: Option<String>, // Instance for run_app to open an SSM session on
+    pub editor_requested: Option<TempPath>, // Temp file for run_app to open in $EDITOR; deleted on drop
+    pub region: Option<String>,
+    pub client_options: ClientOptions,
+    pub account: Option<AccountIdentity>, // Resolved with STS once the client connects
+    pub credentials_expiry: Option<SystemTime>, // None for credentials that don't expire
+    expiry_prompted: bool,                // The refresh prompt was offered for this expiry
+    connect_attempts: usize,              // Failed client setups since the last success
+    auth_failures: usize, // Credential errors in a row; AUTH_FAILURE_LIMIT pauses loading
+    pub config: Config,
+    pub read_only: bool, // Refuse actions that change resources
+    pub dry_run: bool,   // Report the calls confirmed actions would make instead of making them
+    pub keymap: KeyMap,
+    pub in_flight: InFlight,         // AWS calls running in spawned tasks
+    pub marked_rows: HashSet<usize>, // Rows picked with x for a bulk action
+    pub last_refreshed: HashMap<usize, Instant>, // Last successful listing per service tab
+    last_listing: Option<(usize, Listing)>, // Kept with its service tab to re-lay out columns
+    pub capped_at: Option<usize>,    // The listing hit the `max_items` cap and was cut here
+    snapshot: Option<Snapshot>,
+    pub show_diff: bool, // Highlight rows that changed since the snapshot
+    pub pending_count: Option<usize>, // Vim-style count typed before a movement, e.g. the 5 in 5j
+    pub letter_jump_pending: bool, // The next letter jumps to a name starting with it
     pub show_detail_popup: bool,
     pub detail_content: Vec<(String, String)>, // Key-value pairs for details
+    pub detail_metric: Option<MetricSeries>,   // Charted below the details when present
+    pub metric_range: MetricRange,             // How far back alarm metrics are charted
     pub detail_loading: bool,
-    pub detail_scroll: usize,
+    pub detail_scroll: usize,                // Selected row of `detail_rows`
+    pub detail_pretty: HashSet<usize>,       // Details shown as pretty-printed JSON
+    pub detail_indexes: Vec<IndexDetail>,    // DynamoDB GSI rows that open their own view
+    pub detail_parent: Option<DetailParent>, // Table details to return to from a GSI
+    pub detail_curated: Option<Vec<(String, String)>>, // Curated details hidden by the raw view
+    pub detail_logs: Option<String>,         // Log group whose events the popup shows
     pub animation_frame: usize,
+    animation_start: Instant, // Spinner frames count from here, so they follow the wall clock
     pub show_quit_confirm: bool,
+    pub should_quit: bool,
+    pub event_log: Vec<String>, // Timestamped history of status messages
+    pub show_event_log: bool,
+    pub show_dashboard: bool,
+    pub dashboard_rows: Vec<(usize, String)>, // Service index and its one-line summary
+    pub dashboard_selected: usize,
+    pub event_log_scroll: usize,
+    pub prompt: Option<Prompt>,
+    pub prompt_scroll: usize, // First visible context line of the prompt
     pub current_path: Option<String>, // For S3 navigation (bucket/prefix)
     pub s3_items: Vec<S3Item>,
     pub iam_items: Vec<IamItem>,
     pub dynamodb_items: Vec<DynamoDbItem>,
     pub ec2_items: Vec<Ec2Item>,
+    pub ec2_instances: Vec<Ec2Instance>, // Last listing, kept so the columns can be switched
+    pub ec2_columns: Ec2Columns,
     pub ecs_items: Vec<EcsItem>,
     pub mwaa_items: Vec<MwaaItem>,
     pub lambda_items: Vec<LambdaItem>,
+    pub cloudwatch_items: Vec<CloudwatchItem>,
+    pub cloudwatch_alarms: Vec<(String, String, bool)>, // Last listing: name, state, composite
+    pub iam_report: Vec<CredentialReportEntry>,         // Last credential report, for details
+    pub alarm_state_filter: Option<String>,
+    pub search_query: String, // Case-insensitive filter on the main list
+    pub search_editing: bool,
+    pub ec2_tag_filter: Option<(String, String)>, // Server-side `tag:Key=Value` filter
+    pub ec2_all_regions: bool, // List EC2 instances in every enabled region, not just one
+    pub s3_storage_class_filter: Option<String>,
+    pub s3_show_versions: bool, // List object versions and delete markers inside the bucket
+    pub s3_group_by: Option<String>, // Tag key the bucket list is grouped by
+    pub s3_folder_stats: bool,  // Count the objects and size directly inside each folder
+    pub show_arns: bool,        // ECS and IAM lists show full ARNs instead of short names
+    pub list_area: Rect,        // Last rendered main list area, for mouse hit-testing
+    pub list_offset: usize,     // First visible row of the main list
+    pub header_service_areas: Vec<(Rect, usize)>, // Clickable favorites in the header
+    pub breadcrumb_areas: Vec<(Rect, usize)>, // Clickable S3/ECS path levels
+    refresh: Option<RefreshTask>, // Listing still loading in the background
+    watch: Option<Watch>,       // Resource the detail popup is polling
+    detail_task: Option<DetailTask>, // Detail popup content still loading in the background
+    dashboard_task: Option<DashboardTask>, // Dashboard summaries still loading
+    prefix_task: Option<PrefixTask>, // S3 prefix being counted or deleted
 }
 
 impl Default for App {
@@ -111,10 +1018,24 @@ impl Default for App {
 
 impl App {
     pub fn new() -> Self {
-        // Get AWS profile name from environment or default to "default"
-        let profile_name = std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string());
+        Self::with_options(ClientOptions::default(), Config::default())
+    }
 
-        Self {
+    pub fn with_options(client_options: ClientOptions, config: Config) -> Self {
+        // Get AWS profile name from options, then environment, or default to "default"
+        let profile_name = resolve_profile(client_options.profile.as_deref());
+        // An explicit --region wins over the one saved on the last exit
+        let region = resolve_region(
+            &profile_name,
+            client_options
+                .region
+                .as_deref()
+                .or(config.region.as_deref()),
+        );
+
+        let (keymap, keymap_warnings) = KeyMap::from_config(&config.keys);
+
+        let mut app = Self {
             services: vec![
                 ServiceInfo::new(ServiceType::EC2, true), // EC2 is favorite by default
                 ServiceInfo::new(ServiceType::S3, true),  // S3 is favorite by default
@@ -132,55 +1053,222 @@ impl App {
             loading_state: LoadingState::Idle,
             aws_client: None,
             error_message: None,
+            show_error_popup: false,
+            error_scroll: 0,
             show_service_popup: false,
             popup_selected_index: 0,
+            service_filter: String::new(),
+            show_palette: false,
+            palette_filter: String::new(),
+            palette_selected: 0,
+            show_column_picker: false,
+            column_picker_selected: 0,
+            env_credentials: env_credentials(client_options.profile.as_deref()),
             profile_name,
+            sso_login_requested: None,
+            ssm_session_requested: None,
+            editor_requested: None,
+            connect_attempts: 0,
+            auth_failures: 0,
+            region,
+            client_options,
+            account: None,
+            credentials_expiry: None,
+            expiry_prompted: false,
+            read_only: config.read_only,
+            dry_run: config.dry_run,
+            keymap,
+            in_flight: InFlight::default(),
+            marked_rows: HashSet::new(),
+            last_refreshed: HashMap::new(),
+            last_listing: None,
+            capped_at: None,
+            snapshot: None,
+            show_diff: false,
+            pending_count: None,
+            letter_jump_pending: false,
+            config,
             show_detail_popup: false,
             detail_content: Vec::new(),
+            detail_metric: None,
+            metric_range: MetricRange::default(),
             detail_loading: false,
             detail_scroll: 0,
+            detail_pretty: HashSet::new(),
+            detail_indexes: Vec::new(),
+            detail_parent: None,
+            detail_curated: None,
+            detail_logs: None,
             animation_frame: 0,
+            animation_start: Instant::now(),
             show_quit_confirm: false,
+            should_quit: false,
+            event_log: Vec::new(),
+            show_event_log: false,
+            show_dashboard: false,
+            dashboard_rows: Vec::new(),
+            dashboard_selected: 0,
+            event_log_scroll: 0,
+            prompt: None,
+            prompt_scroll: 0,
             current_path: None,
             s3_items: Vec::new(),
             iam_items: Vec::new(),
             dynamodb_items: Vec::new(),
             ec2_items: Vec::new(),
+            ec2_instances: Vec::new(),
+            ec2_columns: Ec2Columns::default(),
             ecs_items: Vec::new(),
             mwaa_items: Vec::new(),
             lambda_items: Vec::new(),
+            cloudwatch_items: Vec::new(),
+            cloudwatch_alarms: Vec::new(),
+            iam_report: Vec::new(),
+            alarm_state_filter: None,
+            search_query: String::new(),
+            search_editing: false,
+            ec2_tag_filter: None,
+            ec2_all_regions: false,
+            s3_storage_class_filter: None,
+            s3_show_versions: false,
+            s3_group_by: None,
+            s3_folder_stats: false,
+            show_arns: false,
+            list_area: Rect::default(),
+            list_offset: 0,
+            header_service_areas: Vec::new(),
+            breadcrumb_areas: Vec::new(),
+            refresh: None,
+            watch: None,
+            detail_task: None,
+            dashboard_task: None,
+            prefix_task: None,
+        };
+        // The saved index may point past the end if services were removed since
+        if let Some(index) = app.config.active_service
+            && index < app.services.len()
+        {
+            app.active_service = index;
         }
+        if let Ok(Some(service)) = app.config.default_service()
+            && let Some(index) = app.services.iter().position(|s| s.service_type == service)
+        {
+            app.active_service = index;
+        }
+        // Bad key overrides shouldn't stop the app; the event log lists them
+        for warning in keymap_warnings {
+            app.set_status(format!("Config: {}", warning));
+        }
+        app
+    }
+
+    /// Records the open service and region in the config file so the next launch resumes there.
+    pub fn save_session(&mut self) -> Result<()> {
+        self.config.active_service = Some(self.active_service);
+        self.config.region = self.region.clone();
+        self.config.save_session()
     }
 
     pub async fn initialize_aws_client(&mut self) -> Result<()> {
         self.loading_state = LoadingState::Loading;
-        self.status_message = "Connecting to AWS...".to_string();
+        self.set_status("Connecting to AWS...".to_string());
+
+        // Pass the resolved region explicitly so the SDK queries what the header shows
+        let options = ClientOptions {
+            region: self.region.clone(),
+            ..self.client_options.clone()
+        };
 
-        match AwsClient::new().await {
+        self.account = None;
+        self.credentials_expiry = None;
+        self.expiry_prompted = false;
+        // Credentials injected into the environment don't need the profile to exist
+        let profile_check = match &self.env_credentials {
+            Some(_) => Ok(()),
+            None => check_profile(&self.profile_name, &available_profiles()),
+        };
+        let client = match profile_check {
+            Ok(()) => AwsClient::with_options(&options).await,
+            Err(e) => Err(e),
+        };
+        match client {
             Ok(client) => {
+                // Creating the client makes no call, so this is also the first credentials check
+                let status =
+                    match with_timeout(self.request_timeout(), client.account_identity()).await {
+                        Ok(account) => {
+                            self.account = Some(account);
+                            self.auth_failures = 0;
+                            self.credentials_expiry = self.read_credentials_expiry(&client).await;
+                            "AWS client initialized. Press r to load resources.".to_string()
+                        }
+                        Err(e) => format!(
+                            "AWS client initialized, but the account id could not be resolved: {}",
+                            e
+                        ),
+                    };
                 self.aws_client = Some(client);
+                self.connect_attempts = 0;
                 self.loading_state = LoadingState::Loaded;
-                self.status_message =
-                    "AWS client initialized. Press r to load resources.".to_string();
+                self.set_status(status);
                 self.items = vec!["Press 'r' to refresh and load resources".to_string()];
                 Ok(())
             }
             Err(e) => {
+                self.connect_attempts += 1;
                 self.loading_state = LoadingState::Error;
                 self.error_message = Some(format!("Failed to initialize AWS client: {}", e));
-                self.status_message =
-                    "Error: Failed to connect to AWS. Check credentials.".to_string();
+                let attempt = match self.connect_attempts {
+                    1 => String::new(),
+                    n => format!(" (attempt {})", n),
+                };
+                self.set_status(format!(
+                    "Error: Failed to connect to AWS{}. Check credentials, then press r to retry.",
+                    attempt
+                ));
                 self.items = vec![
                     "Failed to initialize AWS client".to_string(),
                     "Please check your AWS credentials and configuration".to_string(),
                     format!("Error: {}", e),
+                    "".to_string(),
+                    "Press r to reconnect once they are fixed".to_string(),
                 ];
                 Err(e)
             }
         }
     }
 
+    /// Appends a digit to the pending count. A leading 0 is ignored.
+    pub fn push_count_digit(&mut self, digit: u32) {
+        if digit == 0 && self.pending_count.is_none() {
+            return;
+        }
+        let count = self.pending_count.unwrap_or(0) * 10 + digit as usize;
+        self.pending_count = Some(count.min(9999));
+    }
+
+    pub fn clear_count(&mut self) {
+        self.pending_count = None;
+    }
+
+    // Movements repeat this many times; 1 when no count was typed
+    fn take_count(&mut self) -> usize {
+        self.pending_count.take().unwrap_or(1)
+    }
+
     pub fn next_item(&mut self) {
+        for _ in 0..self.take_count() {
+            self.step_next_item();
+        }
+    }
+
+    pub fn previous_item(&mut self) {
+        for _ in 0..self.take_count() {
+            self.step_previous_item();
+        }
+    }
+
+    fn step_next_item(&mut self) {
         if self.items.is_empty() {
             return;
         }
@@ -199,7 +1287,7 @@ impl App {
         }
     }
 
-    pub fn previous_item(&mut self) {
+    fn step_previous_item(&mut self) {
         if self.items.is_empty() {
             return;
         }
@@ -223,76 +1311,305 @@ impl App {
         }
     }
 
-    fn is_selectable(&self, index: usize) -> bool {
-        match self.get_active_service().service_type {
-            ServiceType::S3 => {
-                if index < self.s3_items.len() {
-                    return !matches!(self.s3_items[index], S3Item::Header | S3Item::Separator);
-                }
-            }
-            ServiceType::IAM => {
-                if index < self.iam_items.len() {
-                    return !matches!(self.iam_items[index], IamItem::Header | IamItem::Separator);
-                }
-            }
-            ServiceType::DynamoDB => {
-                if index < self.dynamodb_items.len() {
-                    return !matches!(
-                        self.dynamodb_items[index],
-                        DynamoDbItem::Header | DynamoDbItem::Separator
-                    );
-                }
-            }
-            ServiceType::EC2 => {
-                if index < self.ec2_items.len() {
-                    return !matches!(self.ec2_items[index], Ec2Item::Header | Ec2Item::Separator);
-                }
-            }
-            ServiceType::ECS => {
-                if index < self.ecs_items.len() {
-                    return !matches!(self.ecs_items[index], EcsItem::Header | EcsItem::Separator);
-                }
-            }
-            ServiceType::MWAA => {
-                if index < self.mwaa_items.len() {
-                    return !matches!(
-                        self.mwaa_items[index],
-                        MwaaItem::Header | MwaaItem::Separator
-                    );
-                }
-            }
-            ServiceType::Lambda => {
-                if index < self.lambda_items.len() {
-                    return !matches!(
-                        self.lambda_items[index],
-                        LambdaItem::Header | LambdaItem::Separator
-                    );
-                }
-            }
-            _ => {}
+    pub fn first_item(&mut self) {
+        if let Some(index) = (0..self.items.len()).find(|&i| self.is_selectable(i)) {
+            self.selected_index = index;
         }
-        true
     }
 
-    pub async fn select_item(&mut self) -> Result<()> {
-        if self.selected_index < self.items.len() {
-            let selected = self.items[self.selected_index].clone();
+    pub fn last_item(&mut self) {
+        if let Some(index) = (0..self.items.len()).rev().find(|&i| self.is_selectable(i)) {
+            self.selected_index = index;
+        }
+    }
 
-            // Handle S3 navigation
-            if matches!(self.get_active_service().service_type, ServiceType::S3) {
-                let action = if self.selected_index < self.s3_items.len() {
-                    S3Service::handle_selection(
-                        &self.s3_items[self.selected_index],
-                        &self.current_path,
-                    )
-                } else {
-                    S3NavigationAction::None
-                };
+    /// Waits for a letter to jump to. Letters are bound to actions, so the jump needs its own
+    /// key first.
+    pub fn start_letter_jump(&mut self) {
+        self.letter_jump_pending = true;
+    }
 
-                match action {
-                    S3NavigationAction::EnterBucket(path) => {
-                        self.current_path = Some(path);
-                        self.refresh_resources().await?;
+    pub fn cancel_letter_jump(&mut self) {
+        self.letter_jump_pending = false;
+    }
+
+    /// Moves to the next selectable row whose name starts with `letter`, ignoring case and
+    /// wrapping around at the end.
+    pub fn jump_to_letter(&mut self, letter: char) {
+        self.letter_jump_pending = false;
+        let starts_with_letter = |row| {
+            self.row_name(row)
+                .and_then(|name| name.chars().next())
+                .is_some_and(|first| first.to_lowercase().eq(letter.to_lowercase()))
+        };
+        let rows = self.items.len();
+        let found = (1..=rows)
+            .map(|step| (self.selected_index + step) % rows)
+            .find(|&row| self.is_selectable(row) && starts_with_letter(row));
+        match found {
+            Some(row) => self.selected_index = row,
+            None => self.set_status(format!("No name starts with {}", letter)),
+        }
+    }
+
+    pub fn page_down(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        // Land on the last selectable row within one page below the cursor
+        let target = (self.selected_index + self.page_size()).min(self.items.len() - 1);
+        if let Some(index) = (self.selected_index + 1..=target)
+            .rev()
+            .find(|&i| self.is_selectable(i))
+        {
+            self.selected_index = index;
+        }
+    }
+
+    pub fn page_up(&mut self) {
+        // Land on the first selectable row within one page above the cursor
+        let target = self.selected_index.saturating_sub(self.page_size());
+        if let Some(index) = (target..self.selected_index).find(|&i| self.is_selectable(i)) {
+            self.selected_index = index;
+        }
+    }
+
+    // Columns available to the table formatters; unlimited before the first draw
+    fn list_width(&self) -> usize {
+        match self.list_area.width {
+            0..=2 => usize::MAX,
+            width => (width - 2) as usize,
+        }
+    }
+
+    // Number of rows visible in the main list (inside its borders)
+    fn page_size(&self) -> usize {
+        (self.list_area.height.saturating_sub(2) as usize).max(1)
+    }
+
+    /// Number of real resources in the loaded list, excluding header, separator and parent rows.
+    /// `None` while the list holds a placeholder message rather than a formatted listing.
+    pub fn resource_count(&self) -> Option<usize> {
+        self.resource_rows().map(|rows| rows.len())
+    }
+
+    /// Number of resources passing the search filter, or `None` when no filter is active.
+    pub fn matched_resource_count(&self) -> Option<usize> {
+        if self.search_query.is_empty() {
+            return None;
+        }
+        self.resource_rows()
+            .map(|rows| rows.into_iter().filter(|&i| self.matches_search(i)).count())
+    }
+
+    /// The cursor's place among the resources passing the search, e.g. `(Some(12), 42)`.
+    /// The position is `None` while the cursor is on a row that isn't a resource, such as `..`.
+    pub fn scroll_position(&self) -> Option<(Option<usize>, usize)> {
+        let rows: Vec<usize> = self
+            .resource_rows()?
+            .into_iter()
+            .filter(|&i| self.matches_search(i))
+            .collect();
+        if rows.is_empty() {
+            return None;
+        }
+        let position = rows.iter().position(|&i| i == self.selected_index);
+        Some((position.map(|p| p + 1), rows.len()))
+    }
+
+    // Indices of the rows that hold real resources
+    fn resource_rows(&self) -> Option<Vec<usize>> {
+        if self.loading_state != LoadingState::Loaded {
+            return None;
+        }
+
+        // The formatters return the display rows and item vecs in parallel. An empty listing
+        // is followed by guidance rows that have no item
+        fn rows<T>(
+            items: &[T],
+            len: usize,
+            is_resource: impl Fn(&T) -> bool,
+        ) -> Option<Vec<usize>> {
+            (items.len() <= len).then(|| {
+                items
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, item)| is_resource(item))
+                    .map(|(i, _)| i)
+                    .collect()
+            })
+        }
+
+        let len = self.items.len();
+        match self.get_active_service().service_type {
+            ServiceType::EC2 => rows(&self.ec2_items, len, |item| {
+                matches!(item, Ec2Item::Instance { .. })
+            }),
+            ServiceType::S3 => rows(&self.s3_items, len, |item| {
+                matches!(
+                    item,
+                    S3Item::Bucket(_)
+                        | S3Item::Folder(_)
+                        | S3Item::Object(_)
+                        | S3Item::Version { .. }
+                )
+            }),
+            ServiceType::IAM => rows(&self.iam_items, len, |item| {
+                matches!(item, IamItem::User(_) | IamItem::ReportEntry { .. })
+            }),
+            ServiceType::CloudWatch => rows(&self.cloudwatch_items, len, |item| {
+                matches!(item, CloudwatchItem::Alarm { .. })
+            }),
+            ServiceType::DynamoDB => rows(&self.dynamodb_items, len, |item| {
+                matches!(item, DynamoDbItem::Table { .. })
+            }),
+            ServiceType::ECS => rows(&self.ecs_items, len, |item| {
+                matches!(
+                    item,
+                    EcsItem::Cluster(_) | EcsItem::Service(_) | EcsItem::Task { .. }
+                )
+            }),
+            ServiceType::MWAA => rows(&self.mwaa_items, len, |item| {
+                matches!(item, MwaaItem::Environment(_))
+            }),
+            ServiceType::Lambda => rows(&self.lambda_items, len, |item| {
+                matches!(item, LambdaItem::Function(_) | LambdaItem::Version(_))
+            }),
+        }
+    }
+
+    pub fn matches_search(&self, index: usize) -> bool {
+        self.search_query.is_empty()
+            || self.items.get(index).is_some_and(|item| {
+                item.to_ascii_lowercase()
+                    .contains(&self.search_query.to_ascii_lowercase())
+            })
+    }
+
+    /// Indices of the rows to render: headers and separators always, item rows if they match.
+    /// Dense mode leaves out the column header and its separator, which the title shows instead.
+    pub fn visible_rows(&self) -> Vec<usize> {
+        let skip = if self.column_header().is_some() { 2 } else { 0 };
+        (skip..self.items.len())
+            .filter(|&i| !self.is_item_row(i) || self.matches_search(i))
+            .collect()
+    }
+
+    /// The column header of a loaded listing when dense mode moves it into the list title.
+    pub fn column_header(&self) -> Option<&str> {
+        // Every formatter starts a non-empty listing with a header and a separator row
+        let listed = self.config.dense
+            && self.resource_rows().is_some()
+            && self.items.len() > 2
+            && !self.is_item_row(0)
+            && !self.is_item_row(1);
+        listed.then(|| self.items[0].trim_end())
+    }
+
+    /// Switches between the regular list and dense rows without the separator line.
+    pub fn toggle_dense(&mut self) {
+        self.config.dense = !self.config.dense;
+        self.list_offset = 0;
+        self.set_status(if self.config.dense {
+            "Dense rows: column header in the title"
+        } else {
+            "Regular rows"
+        });
+    }
+
+    pub fn start_search(&mut self) {
+        self.search_editing = true;
+    }
+
+    pub fn search_input(&mut self, c: char) {
+        self.search_query.push(c);
+        self.reselect_after_filter();
+    }
+
+    pub fn search_backspace(&mut self) {
+        self.search_query.pop();
+        self.reselect_after_filter();
+    }
+
+    pub fn finish_search(&mut self) {
+        self.search_editing = false;
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search_query.clear();
+        self.search_editing = false;
+        self.list_offset = 0;
+    }
+
+    // Keep the cursor on a row that is still visible
+    fn reselect_after_filter(&mut self) {
+        self.list_offset = 0;
+        if !self.is_selectable(self.selected_index) {
+            self.first_item();
+        }
+    }
+
+    pub fn is_selectable(&self, index: usize) -> bool {
+        self.is_item_row(index) && self.matches_search(index)
+    }
+
+    /// Whether the row holds an item rather than a header or separator.
+    pub fn is_item_row(&self, index: usize) -> bool {
+        match self.get_active_service().service_type {
+            ServiceType::S3 => self.s3_items.get(index).is_none_or(|item| {
+                !matches!(item, S3Item::Header | S3Item::Separator | S3Item::Group(_))
+            }),
+            ServiceType::IAM => self
+                .iam_items
+                .get(index)
+                .is_none_or(|item| !matches!(item, IamItem::Header | IamItem::Separator)),
+            ServiceType::DynamoDB => self
+                .dynamodb_items
+                .get(index)
+                .is_none_or(|item| !matches!(item, DynamoDbItem::Header | DynamoDbItem::Separator)),
+            ServiceType::EC2 => self
+                .ec2_items
+                .get(index)
+                .is_none_or(|item| !matches!(item, Ec2Item::Header | Ec2Item::Separator)),
+            ServiceType::ECS => self
+                .ecs_items
+                .get(index)
+                .is_none_or(|item| !matches!(item, EcsItem::Header | EcsItem::Separator)),
+            ServiceType::MWAA => self
+                .mwaa_items
+                .get(index)
+                .is_none_or(|item| !matches!(item, MwaaItem::Header | MwaaItem::Separator)),
+            ServiceType::Lambda => self
+                .lambda_items
+                .get(index)
+                .is_none_or(|item| !matches!(item, LambdaItem::Header | LambdaItem::Separator)),
+            ServiceType::CloudWatch => self.cloudwatch_items.get(index).is_none_or(|item| {
+                !matches!(item, CloudwatchItem::Header | CloudwatchItem::Separator)
+            }),
+        }
+    }
+
+    pub async fn select_item(&mut self) -> Result<()> {
+        if self.selected_index < self.items.len() {
+            let selected = self.items[self.selected_index].clone();
+
+            // Handle S3 navigation
+            if matches!(self.get_active_service().service_type, ServiceType::S3) {
+                let action = if self.selected_index < self.s3_items.len() {
+                    S3Service::handle_selection(
+                        &self.s3_items[self.selected_index],
+                        &self.current_path,
+                    )
+                } else {
+                    S3NavigationAction::None
+                };
+
+                match action {
+                    S3NavigationAction::EnterBucket(path) => {
+                        self.current_path = Some(path);
+                        // Versions are only listed once checked against the bucket's versioning
+                        self.s3_show_versions = false;
+                        self.refresh_resources().await?;
                         return Ok(());
                     }
                     S3NavigationAction::EnterFolder(path) => {
@@ -320,7 +1637,7 @@ impl App {
                     }
                     S3NavigationAction::None => {
                         if self.current_path.is_none() {
-                            self.status_message = "Please select a bucket row".to_string();
+                            self.set_status("Please select a bucket row".to_string());
                         }
                         return Ok(());
                     }
@@ -358,61 +1675,521 @@ impl App {
                         _ => {}
                     }
                 }
+            } else if self.get_active_service().service_type == ServiceType::IAM {
+                if let Some(IamItem::ParentDir) = self.iam_items.get(self.selected_index) {
+                    self.current_path = None;
+                    self.refresh_resources().await?;
+                    return Ok(());
+                }
+            } else if self.get_active_service().service_type == ServiceType::Lambda {
+                match self.lambda_items.get(self.selected_index) {
+                    Some(LambdaItem::Function(name)) => {
+                        self.current_path = Some(name.clone());
+                        self.refresh_resources().await?;
+                        return Ok(());
+                    }
+                    Some(LambdaItem::ParentDir) => {
+                        self.current_path = None;
+                        self.refresh_resources().await?;
+                        return Ok(());
+                    }
+                    _ => {}
+                }
             }
 
-            self.status_message = format!("Selected: {}", selected);
+            self.set_status(format!("Selected: {}", selected));
         }
         Ok(())
     }
 
+    pub fn click_list_row(&mut self, column: u16, row: u16) {
+        let area = self.list_area;
+        // Ignore clicks on the border
+        if column <= area.x
+            || column >= area.x + area.width.saturating_sub(1)
+            || row <= area.y
+            || row >= area.y + area.height.saturating_sub(1)
+        {
+            return;
+        }
+
+        // The list only renders rows that pass the search filter
+        let position = (row - area.y - 1) as usize + self.list_offset;
+        if let Some(&index) = self.visible_rows().get(position)
+            && self.is_selectable(index)
+        {
+            self.selected_index = index;
+        }
+    }
+
+    pub fn click_header(&mut self, column: u16, row: u16) {
+        let clicked = self.header_service_areas.iter().find(|(area, _)| {
+            column >= area.x
+                && column < area.x + area.width
+                && row >= area.y
+                && row < area.y + area.height
+        });
+        if let Some(&(_, index)) = clicked
+            && index != self.active_service
+        {
+            self.switch_service(index);
+        }
+    }
+
+    /// Levels of the current S3 or ECS location, root first, e.g. `Buckets > bucket > a`.
+    /// Empty at the top level and for services without a hierarchy.
+    pub fn breadcrumbs(&self) -> Vec<String> {
+        let root = match self.get_active_service().service_type {
+            ServiceType::S3 => "Buckets",
+            ServiceType::ECS => "Clusters",
+            ServiceType::Lambda => "Functions",
+            ServiceType::IAM => "Users",
+            _ => return Vec::new(),
+        };
+        let Some(path) = &self.current_path else {
+            return Vec::new();
+        };
+        std::iter::once(root.to_string())
+            .chain(
+                path.split('/')
+                    .filter(|part| !part.is_empty())
+                    .map(String::from),
+            )
+            .collect()
+    }
+
+    /// Navigates to an ancestor level from `breadcrumbs`; 0 is the top-level list.
+    pub async fn jump_to_breadcrumb(&mut self, level: usize) -> Result<()> {
+        let crumbs = self.breadcrumbs();
+        // The last crumb is the current location
+        if level + 1 >= crumbs.len() {
+            return Ok(());
+        }
+        self.current_path = match level {
+            0 => None,
+            _ => {
+                let path = crumbs[1..=level].join("/");
+                // S3 paths keep the trailing slash of a prefix, ECS paths are cluster/service
+                if self.get_active_service().service_type == ServiceType::S3 {
+                    Some(path + "/")
+                } else {
+                    Some(path)
+                }
+            }
+        };
+        self.refresh_resources().await
+    }
+
+    /// `<n>b` jumps to breadcrumb n (counted from 1); a bare `b` goes up one level.
+    pub async fn jump_to_counted_breadcrumb(&mut self) -> Result<()> {
+        let level = match self.pending_count.take() {
+            Some(count) => count - 1,
+            None => match self.breadcrumbs().len() {
+                0 | 1 => return Ok(()),
+                len => len - 2,
+            },
+        };
+        self.jump_to_breadcrumb(level).await
+    }
+
+    pub fn breadcrumb_at(&self, column: u16, row: u16) -> Option<usize> {
+        self.breadcrumb_areas
+            .iter()
+            .find(|(area, _)| {
+                column >= area.x
+                    && column < area.x + area.width
+                    && row >= area.y
+                    && row < area.y + area.height
+            })
+            .map(|&(_, level)| level)
+    }
+
     pub fn get_active_service(&self) -> &ServiceInfo {
         &self.services[self.active_service]
     }
 
     pub fn toggle_service_popup(&mut self) {
         self.show_service_popup = !self.show_service_popup;
+        self.service_filter.clear();
         if self.show_service_popup {
             self.popup_selected_index = self.active_service;
         }
     }
 
+    /// Indices of the services shown in the popup: all of them, or the fuzzy matches for
+    /// `service_filter`, best match first.
+    pub fn popup_services(&self) -> Vec<usize> {
+        if self.service_filter.is_empty() {
+            return (0..self.services.len()).collect();
+        }
+        let mut scored: Vec<(usize, usize)> = self
+            .services
+            .iter()
+            .enumerate()
+            .filter_map(|(i, service)| {
+                let score = [service.short_name(), service.as_str()]
+                    .into_iter()
+                    .filter_map(|name| fuzzy_score(name, &self.service_filter))
+                    .min()?;
+                Some((score, i))
+            })
+            .collect();
+        scored.sort();
+        scored.into_iter().map(|(_, i)| i).collect()
+    }
+
+    pub fn service_filter_input(&mut self, c: char) {
+        self.service_filter.push(c);
+        self.select_top_service_match();
+    }
+
+    pub fn service_filter_backspace(&mut self) {
+        self.service_filter.pop();
+        self.select_top_service_match();
+    }
+
+    pub fn clear_service_filter(&mut self) {
+        self.service_filter.clear();
+    }
+
+    fn select_top_service_match(&mut self) {
+        if let Some(&first) = self.popup_services().first() {
+            self.popup_selected_index = first;
+        }
+    }
+
     pub fn popup_next(&mut self) {
-        if !self.services.is_empty() {
-            self.popup_selected_index = (self.popup_selected_index + 1) % self.services.len();
+        let visible = self.popup_services();
+        if visible.is_empty() {
+            return;
         }
+        let next = match visible.iter().position(|&i| i == self.popup_selected_index) {
+            Some(pos) => (pos + 1) % visible.len(),
+            None => 0,
+        };
+        self.popup_selected_index = visible[next];
     }
 
     pub fn popup_previous(&mut self) {
-        if !self.services.is_empty() {
-            if self.popup_selected_index > 0 {
-                self.popup_selected_index -= 1;
-            } else {
-                self.popup_selected_index = self.services.len() - 1;
-            }
+        let visible = self.popup_services();
+        if visible.is_empty() {
+            return;
         }
+        let previous = match visible.iter().position(|&i| i == self.popup_selected_index) {
+            Some(0) | None => visible.len() - 1,
+            Some(pos) => pos - 1,
+        };
+        self.popup_selected_index = visible[previous];
     }
 
     pub fn select_popup_service(&mut self) {
-        self.active_service = self.popup_selected_index;
+        // Nothing matches the filter, so there is nothing to open
+        if !self.popup_services().contains(&self.popup_selected_index) {
+            return;
+        }
+        self.service_filter.clear();
+        self.switch_service(self.popup_selected_index);
+    }
+
+    pub fn toggle_palette(&mut self) {
+        self.show_palette = !self.show_palette;
+        self.palette_filter.clear();
+        self.palette_selected = 0;
+    }
+
+    /// Actions the palette offers in the current view: the ones that would do something here,
+    /// or the fuzzy matches for `palette_filter`, best match first.
+    pub fn palette_actions(&self) -> Vec<Action> {
+        let available = Action::ALL
+            .into_iter()
+            .filter(|action| action.in_palette() && self.action_available(*action));
+        if self.palette_filter.is_empty() {
+            return available.collect();
+        }
+        let mut scored: Vec<(usize, Action)> = available
+            .filter_map(|action| {
+                let score = [action.label(), action.name()]
+                    .into_iter()
+                    .filter_map(|name| fuzzy_score(name, &self.palette_filter))
+                    .min()?;
+                Some((score, action))
+            })
+            .collect();
+        scored.sort_by_key(|&(score, _)| score);
+        scored.into_iter().map(|(_, action)| action).collect()
+    }
+
+    fn action_available(&self, action: Action) -> bool {
+        self.action_allowed(action, self.writable())
+    }
+
+    // Dry-run mode changes nothing, so it lets read-only mode preview the mutating actions
+    fn writable(&self) -> bool {
+        !self.read_only || self.dry_run
+    }
+
+    /// Whether `action` can run in the current view. Otherwise the status says why, so a key
+    /// bound to something that doesn't apply here isn't silently dropped.
+    pub fn check_action(&mut self, action: Action) -> bool {
+        if self.action_available(action) {
+            return true;
+        }
+        let message = if !self.writable() && self.action_allowed(action, true) {
+            format!("Read-only mode: {} is disabled", action.label())
+        } else {
+            format!("{} isn't available here", action.label())
+        };
+        self.set_status(message);
+        false
+    }
+
+    fn action_allowed(&self, action: Action, writable: bool) -> bool {
+        let service = self.get_active_service().service_type;
+        match action {
+            Action::ReassumeRole => self.client_options.assume_role.is_some(),
+            Action::ErrorDetails => self.error_message.is_some(),
+            Action::Diff => self.snapshot.is_some(),
+            Action::Watch => self.watch_target().is_some(),
+            Action::Connect | Action::AllRegions => service == ServiceType::EC2,
+            Action::Breadcrumb => !self.breadcrumbs().is_empty(),
+            Action::Stop => writable && matches!(service, ServiceType::EC2 | ServiceType::ECS),
+            Action::Rename => writable && service == ServiceType::EC2,
+            Action::TagFilter | Action::Columns => service == ServiceType::EC2,
+            Action::PickColumns => !self.column_choices().is_empty(),
+            Action::StateFilter => service == ServiceType::CloudWatch,
+            Action::GroupByTag => service == ServiceType::S3 && self.current_path.is_none(),
+            Action::FullArns => matches!(service, ServiceType::ECS | ServiceType::IAM),
+            Action::CredentialReport => service == ServiceType::IAM,
+            Action::StorageClassFilter
+            | Action::JumpToPrefix
+            | Action::Versions
+            | Action::FolderStats => self.s3_location().is_some(),
+            Action::Download => {
+                writable
+                    && (self.selected_s3_object().is_some() || self.selected_s3_version().is_some())
+            }
+            Action::Restore | Action::Upload => writable && self.s3_location().is_some(),
+            Action::Delete => {
+                writable && matches!(service, ServiceType::S3 | ServiceType::DynamoDB)
+            }
+            Action::Create => {
+                writable
+                    && (service == ServiceType::DynamoDB
+                        || (service == ServiceType::S3 && self.current_path.is_none()))
+            }
+            _ => true,
+        }
+    }
+
+    pub fn palette_input(&mut self, c: char) {
+        self.palette_filter.push(c);
+        self.palette_selected = 0;
+    }
+
+    pub fn palette_backspace(&mut self) {
+        self.palette_filter.pop();
+        self.palette_selected = 0;
+    }
+
+    pub fn palette_next(&mut self) {
+        let count = self.palette_actions().len();
+        if count > 0 {
+            self.palette_selected = (self.palette_selected + 1) % count;
+        }
+    }
+
+    pub fn palette_previous(&mut self) {
+        let count = self.palette_actions().len();
+        if count > 0 {
+            self.palette_selected = (self.palette_selected + count - 1) % count;
+        }
+    }
+
+    /// Closes the palette and returns the highlighted action for the caller to run.
+    pub fn take_palette_action(&mut self) -> Option<Action> {
+        let action = self.palette_actions().get(self.palette_selected).copied();
+        if action.is_some() {
+            self.toggle_palette();
+        }
+        action
+    }
+
+    // Columns the EC2 table shows: the ones picked with C, or else the `v` preset's
+    fn ec2_visible_columns(&self) -> Vec<Ec2Column> {
+        match self.config.columns.get("ec2") {
+            Some(names) => names
+                .iter()
+                .filter_map(|name| Ec2Column::from_name(name))
+                .collect(),
+            None => self.ec2_columns.columns().to_vec(),
+        }
+    }
+
+    // Optional columns of the listing on screen outside EC2, by config name and header
+    fn listing_columns(&self) -> &'static [(&'static str, &'static str)] {
+        match &self.last_listing {
+            Some((service, listing)) if *service == self.active_service => match listing {
+                Listing::S3Objects { .. } => &S3Service::OBJECT_COLUMNS,
+                Listing::DynamoDb(_) => &crate::aws::DynamoDbService::TABLE_COLUMNS,
+                Listing::Lambda(_) => &LambdaService::FUNCTION_COLUMNS,
+                Listing::EcsTasks { .. } => &EcsService::TASK_COLUMNS,
+                _ => &[],
+            },
+            _ => &[],
+        }
+    }
+
+    // Names among `columns` unticked in the picker; none until the service's columns are picked
+    fn hidden_columns(&self, columns: &[(&'static str, &'static str)]) -> Vec<&'static str> {
+        let Some(shown) = self.config.columns.get(&self.service_key()) else {
+            return Vec::new();
+        };
+        columns
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| !shown.iter().any(|s| s == name))
+            .collect()
+    }
+
+    /// Optional columns of the current view and whether each shows. Empty for views whose
+    /// columns are fixed.
+    pub fn column_choices(&self) -> Vec<(&'static str, bool)> {
+        if self.get_active_service().service_type == ServiceType::EC2 {
+            let visible = self.ec2_visible_columns();
+            return Ec2Column::ALL
+                .into_iter()
+                .map(|column| (column.header(), visible.contains(&column)))
+                .collect();
+        }
+        let columns = self.listing_columns();
+        let hidden = self.hidden_columns(columns);
+        columns
+            .iter()
+            .map(|(name, header)| (*header, !hidden.contains(name)))
+            .collect()
+    }
+
+    pub fn toggle_column_picker(&mut self) {
+        if !self.show_column_picker && self.column_choices().is_empty() {
+            self.set_status(format!(
+                "{} has no optional columns",
+                self.get_active_service().as_str()
+            ));
+            return;
+        }
+        self.show_column_picker = !self.show_column_picker;
+        self.column_picker_selected = 0;
+    }
+
+    pub fn column_picker_next(&mut self) {
+        let count = self.column_choices().len();
+        if count > 0 {
+            self.column_picker_selected = (self.column_picker_selected + 1) % count;
+        }
+    }
+
+    pub fn column_picker_previous(&mut self) {
+        let count = self.column_choices().len();
+        if count > 0 {
+            self.column_picker_selected = (self.column_picker_selected + count - 1) % count;
+        }
+    }
+
+    /// Shows or hides the highlighted column and reformats the table. The choice is saved to the
+    /// config file on exit.
+    pub fn toggle_picked_column(&mut self) {
+        if self.get_active_service().service_type != ServiceType::EC2 {
+            self.toggle_listing_column();
+            return;
+        }
+        let Some(&column) = Ec2Column::ALL.get(self.column_picker_selected) else {
+            return;
+        };
+        let visible = self.ec2_visible_columns();
+        let shown = !visible.contains(&column);
+        // Stored in table order so the columns don't shuffle as they are toggled
+        let names = Ec2Column::ALL
+            .into_iter()
+            .filter(|c| {
+                if *c == column {
+                    shown
+                } else {
+                    visible.contains(c)
+                }
+            })
+            .map(|c| c.name().to_string())
+            .collect();
+        self.config.columns.insert(self.service_key(), names);
+        if self.loading_state == LoadingState::Loaded {
+            self.format_ec2_instances();
+        }
+        self.set_status(format!(
+            "{} {} column",
+            if shown { "Showing" } else { "Hiding" },
+            column.header()
+        ));
+    }
+
+    // The picker for the optional columns of a non-EC2 listing
+    fn toggle_listing_column(&mut self) {
+        let columns = self.listing_columns();
+        let Some(&(column, header)) = columns.get(self.column_picker_selected) else {
+            return;
+        };
+        let hidden = self.hidden_columns(columns);
+        let shown = hidden.contains(&column);
+        let names = columns
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| {
+                if *name == column {
+                    shown
+                } else {
+                    !hidden.contains(name)
+                }
+            })
+            .map(String::from)
+            .collect();
+        self.config.columns.insert(self.service_key(), names);
+        self.relayout_listing();
+        self.set_status(format!(
+            "{} {} column",
+            if shown { "Showing" } else { "Hiding" },
+            header
+        ));
+    }
+
+    pub fn switch_service(&mut self, index: usize) {
+        if index >= self.services.len() {
+            return;
+        }
+        self.active_service = index;
         self.show_service_popup = false;
+        self.marked_rows.clear();
         self.selected_index = 0;
+        self.list_offset = 0;
         self.loading_state = LoadingState::Idle;
         self.items = vec![format!(
             "Press 'r' to load {} resources",
             self.services[self.active_service].as_str()
         )];
-        self.status_message = format!(
+        self.set_status(format!(
             "Switched to {}. Press r to refresh.",
             self.services[self.active_service].as_str()
-        );
+        ));
         self.current_path = None; // Reset path when switching services
+        self.s3_show_versions = false;
+        self.show_arns = false;
+        self.capped_at = None;
+        self.refresh = None; // Drop a listing of the previous service still in flight
+        self.clear_search();
     }
 
     pub fn toggle_favorite(&mut self) {
         if self.show_service_popup && self.popup_selected_index < self.services.len() {
             self.services[self.popup_selected_index].favorite =
                 !self.services[self.popup_selected_index].favorite;
-            self.status_message = format!(
+            self.set_status(format!(
                 "{} {}",
                 if self.services[self.popup_selected_index].favorite {
                     "Added to"
@@ -420,7 +2197,7 @@ impl App {
                     "Removed from"
                 },
                 "favorites"
-            );
+            ));
         }
     }
 
@@ -432,621 +2209,6126 @@ impl App {
             .collect()
     }
 
-    pub fn close_detail_popup(&mut self) {
-        self.show_detail_popup = false;
-        self.detail_content.clear();
-        self.detail_scroll = 0;
-    }
-
-    pub fn detail_scroll_up(&mut self) {
-        if self.detail_scroll > 0 {
-            self.detail_scroll -= 1;
+    pub fn toggle_dashboard(&mut self) {
+        if self.show_dashboard {
+            self.close_dashboard();
+        } else {
+            self.show_dashboard = true;
+            self.refresh_dashboard();
         }
     }
 
-    pub fn detail_scroll_down(&mut self) {
-        if self.detail_scroll < self.detail_content.len().saturating_sub(1) {
-            self.detail_scroll += 1;
-        }
+    /// Leaves the dashboard, abandoning summaries still loading.
+    pub fn close_dashboard(&mut self) {
+        self.show_dashboard = false;
+        self.dashboard_task = None;
     }
 
-    pub async fn show_resource_details(&mut self) -> Result<()> {
-        if self.items.is_empty() || self.selected_index >= self.items.len() {
-            return Ok(());
-        }
-
+    /// Summarizes every favorite service in the background, querying them concurrently.
+    /// `poll_dashboard` shows the rows once all of them are in.
+    pub fn refresh_dashboard(&mut self) {
         let client = match &self.aws_client {
-            Some(c) => c,
+            Some(c) => c.clone(),
             None => {
-                self.status_message = "AWS client not initialized".to_string();
-                return Ok(());
+                self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+                return;
             }
         };
 
-        // Check if it's an S3 folder or parent dir
-        if matches!(self.get_active_service().service_type, ServiceType::S3)
-            && self.selected_index < self.s3_items.len()
-        {
-            match &self.s3_items[self.selected_index] {
-                S3Item::Folder(name) => {
-                    self.show_detail_popup = true;
-                    self.detail_loading = false;
-                    self.detail_content = vec![
-                        ("Name".to_string(), name.clone()),
-                        ("Type".to_string(), "Folder".to_string()),
-                    ];
-                    self.status_message = format!("Viewing details for folder {}", name);
-                    return Ok(());
+        let services: Vec<(usize, ServiceType)> = self
+            .get_favorite_services()
+            .into_iter()
+            .map(|(index, service)| (index, service.service_type))
+            .collect();
+        let timeout = self.request_timeout();
+        let in_flight = self.in_flight.clone();
+        self.set_status(format!(
+            "Summarizing {} service(s)... (Esc to cancel)",
+            services.len()
+        ));
+        let handle = tokio::spawn(async move {
+            // Dropping the set when the task is aborted aborts the summaries too
+            let mut tasks = tokio::task::JoinSet::new();
+            for (index, service_type) in services {
+                let client = client.clone();
+                tasks.spawn(in_flight.track(async move {
+                    (
+                        index,
+                        summarize_service(&client, service_type, timeout).await,
+                    )
+                }));
+            }
+            let mut rows = Vec::new();
+            while let Some(joined) = tasks.join_next().await {
+                if let Ok(row) = joined {
+                    rows.push(row);
                 }
-                S3Item::ParentDir => {
-                    self.status_message = "Parent Directory".to_string();
-                    return Ok(());
+            }
+            rows.sort_by_key(|(index, _)| *index);
+            rows
+        });
+        self.dashboard_task = Some(DashboardTask { handle });
+    }
+
+    pub fn is_loading_dashboard(&self) -> bool {
+        self.dashboard_task.is_some()
+    }
+
+    /// Shows the dashboard's rows once its background load finishes. Called on every tick of
+    /// the event loop.
+    pub async fn poll_dashboard(&mut self) {
+        let Some(task) = &mut self.dashboard_task else {
+            return;
+        };
+        if !task.handle.is_finished() {
+            return;
+        }
+        let result = (&mut task.handle).await;
+        self.dashboard_task = None;
+        let rows = match result {
+            Ok(rows) => rows,
+            Err(e) => {
+                self.set_status(format!("Dashboard task failed: {}", e));
+                return;
+            }
+        };
+        self.dashboard_selected = self.dashboard_selected.min(rows.len().saturating_sub(1));
+        self.set_status(format!(
+            "Dashboard: {} service(s). Enter to open, r to refresh, Esc to close",
+            rows.len()
+        ));
+        self.dashboard_rows = rows;
+    }
+
+    /// Stops the summaries still loading and keeps the dashboard open. Returns whether there
+    /// were any.
+    pub fn cancel_dashboard(&mut self) -> bool {
+        if self.dashboard_task.take().is_none() {
+            return false;
+        }
+        self.set_status("Cancelled the dashboard summaries");
+        true
+    }
+
+    pub fn dashboard_next(&mut self) {
+        if !self.dashboard_rows.is_empty() {
+            self.dashboard_selected = (self.dashboard_selected + 1) % self.dashboard_rows.len();
+        }
+    }
+
+    pub fn dashboard_previous(&mut self) {
+        if !self.dashboard_rows.is_empty() {
+            self.dashboard_selected = self
+                .dashboard_selected
+                .checked_sub(1)
+                .unwrap_or(self.dashboard_rows.len() - 1);
+        }
+    }
+
+    /// Leaves the dashboard for the selected service's list. Returns whether a service was chosen.
+    pub fn open_dashboard_row(&mut self) -> bool {
+        let Some(&(index, _)) = self.dashboard_rows.get(self.dashboard_selected) else {
+            return false;
+        };
+        self.show_dashboard = false;
+        self.switch_service(index);
+        true
+    }
+
+    pub fn close_detail_popup(&mut self) {
+        self.show_detail_popup = false;
+        self.detail_content.clear();
+        self.detail_metric = None;
+        self.detail_scroll = 0;
+        self.detail_pretty.clear();
+        self.detail_indexes.clear();
+        self.detail_parent = None;
+        self.detail_curated = None;
+        self.detail_logs = None;
+        self.detail_task = None;
+        self.watch = None;
+    }
+
+    // The selected EC2 instance, ECS task or CloudWatch alarm
+    fn watch_target(&self) -> Option<WatchTarget> {
+        let index = self.selected_index;
+        match self.get_active_service().service_type {
+            ServiceType::EC2 => match self.ec2_items.get(index)? {
+                Ec2Item::Instance { id, .. } => Some(WatchTarget::Ec2Instance(id.clone())),
+                _ => None,
+            },
+            ServiceType::ECS => match self.ecs_items.get(index)? {
+                EcsItem::Task { id, .. } => Some(WatchTarget::EcsTask {
+                    cluster: self.current_path.as_deref()?.split('/').next()?.to_string(),
+                    task_id: id.clone(),
+                }),
+                _ => None,
+            },
+            ServiceType::CloudWatch => match self.cloudwatch_items.get(index)? {
+                CloudwatchItem::Alarm { name, .. } => {
+                    Some(WatchTarget::CloudWatchAlarm(name.clone()))
                 }
-                S3Item::Header | S3Item::Separator => {
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Opens the selected instance, task or alarm in a detail popup that describes it again
+    /// every `watch_interval_secs` and highlights the rows that changed.
+    pub fn start_watch(&mut self) {
+        let Some(target) = self.watch_target() else {
+            self.set_status("Select an EC2 instance, ECS task or CloudWatch alarm to watch");
+            return;
+        };
+        if let WatchTarget::Ec2Instance(id) = &target
+            && self.refuse_other_region(id)
+        {
+            return;
+        }
+        if self.aws_client.is_none() {
+            self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+            return;
+        }
+        self.close_detail_popup();
+        self.show_detail_popup = true;
+        self.detail_loading = true;
+        self.detail_content = vec![("Loading...".to_string(), "".to_string())];
+        self.set_status(format!(
+            "Watching {} every {}s. Esc to stop",
+            target.label(),
+            self.watch_interval().as_secs()
+        ));
+        self.watch = Some(Watch {
+            target,
+            fetch: None,
+            next_poll: Instant::now(),
+            polls: 0,
+            changed: HashMap::new(),
+        });
+    }
+
+    fn watch_interval(&self) -> Duration {
+        Duration::from_secs(self.config.watch_interval_secs.max(1))
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.watch.is_some()
+    }
+
+    /// "Watching instance i-0abc · every 5s" for the detail popup title.
+    pub fn watch_title(&self) -> Option<String> {
+        let watch = self.watch.as_ref()?;
+        Some(format!(
+            "Watching {} · every {}s",
+            watch.target.label(),
+            self.watch_interval().as_secs()
+        ))
+    }
+
+    /// Polls applied so far and rows still highlighted, so the loop redraws when either moves.
+    pub fn watch_frame(&self) -> Option<(usize, usize)> {
+        let watch = self.watch.as_ref()?;
+        let flashing = watch
+            .changed
+            .values()
+            .filter(|at| at.elapsed() < WATCH_FLASH)
+            .count();
+        Some((watch.polls, flashing))
+    }
+
+    /// Whether the watched detail row `key` changed within the last couple of seconds.
+    pub fn watch_flashing(&self, key: &str) -> bool {
+        self.watch
+            .as_ref()
+            .and_then(|watch| watch.changed.get(key))
+            .is_some_and(|at| at.elapsed() < WATCH_FLASH)
+    }
+
+    /// Charts the alarm's metric over the next time range: 1h, 6h, 24h, 7d, then 1h again.
+    /// A watched alarm picks the range up on an immediate poll; otherwise the metric loads in
+    /// the background and the chart keeps the old range until it arrives.
+    pub fn cycle_metric_range(&mut self) {
+        if self.detail_metric.is_none() || self.detail_loading {
+            return;
+        }
+        self.metric_range = self.metric_range.next();
+        self.set_status(format!("Charting the last {}", self.metric_range.label()));
+        if let Some(watch) = &mut self.watch {
+            if let Some(fetch) = watch.fetch.take() {
+                fetch.abort();
+            }
+            watch.next_poll = Instant::now();
+            return;
+        }
+
+        let Some(client) = self.aws_client.clone() else {
+            return;
+        };
+        let Some(name) = self
+            .detail_content
+            .iter()
+            .find(|(key, _)| key == "Alarm Name")
+            .map(|(_, name)| name.clone())
+        else {
+            return;
+        };
+        let range = self.metric_range;
+        let load = with_timeout(self.request_timeout(), async move {
+            let (_, metric) = client
+                .describe_cloudwatch_alarm(&name, range)
+                .await
+                .with_context(|| format!("Error loading metric for {}", name))?;
+            Ok(DetailUpdate::Metric(metric))
+        });
+        self.detail_task = Some(DetailTask {
+            handle: tokio::spawn(self.in_flight.track(load)),
+            progress: None,
+        });
+    }
+
+    /// Starts the next describe call once the interval is up and applies the last one when it
+    /// finishes. Called on every tick of the event loop while watching.
+    pub async fn poll_watch(&mut self) -> Result<()> {
+        let timeout = self.request_timeout();
+        let paused = self.auth_paused();
+        let Some(watch) = &mut self.watch else {
+            return Ok(());
+        };
+        match &mut watch.fetch {
+            None if Instant::now() >= watch.next_poll && !paused => {
+                let Some(client) = self.aws_client.clone() else {
                     return Ok(());
+                };
+                let describe = describe_watched(client, watch.target.clone(), self.metric_range);
+                let fetch = self.in_flight.track(with_timeout(timeout, describe));
+                watch.fetch = Some(tokio::spawn(fetch));
+            }
+            Some(fetch) if fetch.is_finished() => {
+                let result = match fetch.await {
+                    Ok(result) => result,
+                    Err(e) => Err(anyhow::anyhow!("Watch task failed: {}", e)),
+                };
+                watch.fetch = None;
+                self.apply_watch(result);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn apply_watch(&mut self, result: Result<WatchedDetails>) {
+        let interval = self.watch_interval();
+        let Some(watch) = &mut self.watch else {
+            return;
+        };
+        watch.next_poll = Instant::now() + interval;
+        let label = watch.target.label();
+        let (details, metric) = match result {
+            Ok(details) => details,
+            Err(e) => {
+                // Keep the last details up; the next poll may well succeed
+                if is_auth_error(&classify_error(&e)) {
+                    self.auth_failures += 1;
                 }
-                _ => {}
+                self.set_status(format!("Error refreshing {}: {}", label, e));
+                return;
             }
+        };
+        self.auth_failures = 0;
+
+        // The first poll only fills the popup in
+        let changed: Vec<(String, String)> = if watch.polls == 0 {
+            Vec::new()
+        } else {
+            details
+                .iter()
+                .filter(|(key, value)| {
+                    !self
+                        .detail_content
+                        .iter()
+                        .any(|(old_key, old_value)| old_key == key && old_value == value)
+                })
+                .cloned()
+                .collect()
+        };
+        watch.polls += 1;
+        let now = Instant::now();
+        for (key, _) in &changed {
+            watch.changed.insert(key.clone(), now);
+        }
+        self.detail_content = details;
+        self.detail_metric = metric;
+        self.detail_loading = false;
+        if let Some((key, value)) = changed.first() {
+            self.set_status(format!("{}: {} is now {}", label, key, value));
         }
+    }
 
-        let resource_line = &self.items[self.selected_index];
+    /// Switches the detail popup between the curated details and the full describe response
+    /// as pretty-printed JSON. Covers EC2 instances, DynamoDB tables and Lambda functions.
+    pub async fn toggle_detail_raw(&mut self) -> Result<()> {
+        if let Some(curated) = self.detail_curated.take() {
+            self.detail_content = curated;
+            self.detail_scroll = 0;
+            self.detail_pretty.clear();
+            return Ok(());
+        }
+        // The next poll of a watched resource would replace the raw view anyway
+        if self.detail_loading
+            || self.detail_parent.is_some()
+            || self.detail_logs.is_some()
+            || self.watch.is_some()
+        {
+            return Ok(());
+        }
+        let client = match &self.aws_client {
+            Some(c) => c.clone(),
+            None => {
+                self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+                return Ok(());
+            }
+        };
 
-        // Extract resource name based on service type
-        let resource_name = match self.get_active_service().service_type {
-            ServiceType::S3 => {
-                if let Some(path) = &self.current_path {
-                    // We are inside a bucket, show object details
-                    // The selected line is a table row: "Name  Size  Date"
-                    let name = resource_line
-                        .split_whitespace()
-                        .next()
-                        .unwrap_or(resource_line);
-                    // Construct full key
-                    let parts: Vec<&str> = path.splitn(2, '/').collect();
-                    let prefix = if parts.len() > 1 { parts[1] } else { "" };
-                    format!("{}{}", prefix, name)
-                } else {
-                    // For S3 buckets, extract bucket name from table format
-                    // Skip header and separator rows
-                    if self.selected_index <= 1 {
-                        self.status_message = "Please select a bucket row".to_string();
-                        return Ok(());
-                    }
-                    // Extract bucket name (everything before the two spaces and date)
-                    resource_line
-                        .split_whitespace()
-                        .next()
-                        .unwrap_or(resource_line)
-                        .to_string()
+        let row = self.selected_index;
+        let fetch = match self.get_active_service().service_type {
+            ServiceType::EC2 => match self.ec2_items.get(row) {
+                Some(Ec2Item::Instance { id, .. }) => Some(RawResource::Ec2Instance(id.clone())),
+                _ => None,
+            },
+            ServiceType::DynamoDB => match self.dynamodb_items.get(row) {
+                Some(DynamoDbItem::Table { name, .. }) => {
+                    Some(RawResource::DynamoDbTable(name.clone()))
                 }
+                _ => None,
+            },
+            ServiceType::Lambda => self
+                .lambda_function_at(row)
+                .map(RawResource::LambdaFunction),
+            _ => None,
+        };
+        let Some(fetch) = fetch else {
+            self.set_status(
+                "Raw JSON is available for EC2 instances, DynamoDB tables and Lambda functions",
+            );
+            return Ok(());
+        };
+
+        let result = with_timeout(self.request_timeout(), async {
+            match &fetch {
+                RawResource::Ec2Instance(id) => client.describe_ec2_instance_raw(id).await,
+                RawResource::DynamoDbTable(name) => client.describe_dynamodb_table_raw(name).await,
+                RawResource::LambdaFunction(name) => client
+                    .get_lambda_function(name)
+                    .await
+                    .map(|config| LambdaService::function_json(&config)),
             }
-            ServiceType::DynamoDB => {
-                // Extract table name from DynamoDbItem
-                if self.selected_index < self.dynamodb_items.len() {
-                    if let DynamoDbItem::Table(name) = &self.dynamodb_items[self.selected_index] {
-                        name.clone()
-                    } else {
-                        self.status_message = "Please select a table row".to_string();
-                        return Ok(());
-                    }
-                } else {
-                    resource_line.clone()
-                }
+        })
+        .await;
+        match result {
+            Ok(raw) => {
+                let curated = std::mem::replace(
+                    &mut self.detail_content,
+                    vec![("Raw JSON".to_string(), raw.to_string())],
+                );
+                self.detail_curated = Some(curated);
+                self.detail_pretty = HashSet::from([0]);
+                self.detail_scroll = 0;
             }
-            _ => resource_line.clone(),
+            Err(e) => self.set_status(format!("Error loading raw JSON: {}", e)),
+        }
+        Ok(())
+    }
+
+    /// The bucket whose curated details the popup shows, if any.
+    pub fn detail_bucket(&self) -> Option<&str> {
+        if self.detail_loading
+            || self.detail_curated.is_some()
+            || self.get_active_service().service_type != ServiceType::S3
+            || self.current_path.is_some()
+        {
+            return None;
+        }
+        match self.s3_items.get(self.selected_index) {
+            Some(S3Item::Bucket(bucket)) => Some(bucket),
+            _ => None,
+        }
+    }
+
+    /// The unqualified Lambda function shown in the detail popup, if any.
+    pub fn detail_lambda_function(&self) -> Option<&str> {
+        if self.detail_loading
+            || self.detail_curated.is_some()
+            || self.detail_logs.is_some()
+            || self.get_active_service().service_type != ServiceType::Lambda
+        {
+            return None;
+        }
+        match self.lambda_items.get(self.selected_index) {
+            Some(LambdaItem::Function(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Leaves the detail popup for the versions and aliases of the function it shows.
+    pub async fn open_lambda_versions(&mut self) -> Result<()> {
+        let Some(function) = self.detail_lambda_function().map(String::from) else {
+            return Ok(());
         };
+        self.close_detail_popup();
+        self.current_path = Some(function);
+        self.refresh_resources().await
+    }
 
-        // Show popup with loading state
-        self.show_detail_popup = true;
+    /// Replaces the Lambda function's details with the last events of its most recent log
+    /// stream, loaded in the background.
+    pub fn tail_lambda_logs(&mut self) {
+        if self.detail_lambda_function().is_none() {
+            return;
+        }
+        let Some(log_group) = self
+            .detail_content
+            .iter()
+            .find(|(key, _)| key == "Log Group")
+            .map(|(_, group)| group.clone())
+        else {
+            return;
+        };
+        let Some(client) = self.aws_client.clone() else {
+            self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+            return;
+        };
         self.detail_loading = true;
         self.detail_content = vec![("Loading...".to_string(), "".to_string())];
+        self.detail_scroll = 0;
+        self.detail_pretty.clear();
+        self.detail_logs = Some(log_group.clone());
+        self.set_status(format!(
+            "Loading the last {} events of {}...",
+            LOG_TAIL_EVENTS, log_group
+        ));
 
-        // Fetch details based on service type
-        // Fetch details based on service type
-        let result = match self.get_active_service().service_type {
-            ServiceType::S3 => {
-                if let Some(path) = &self.current_path {
-                    // We are inside a bucket, so resource_name is the full key
-                    // split it into bucket and key
-                    let parts: Vec<&str> = path.splitn(2, '/').collect();
-                    let bucket = parts[0];
-                    // So resource_name is the key (including prefix).
-                    // We just need the bucket name.
+        let tail = with_timeout(self.request_timeout(), async move {
+            let events = client
+                .tail_lambda_logs(&log_group, LOG_TAIL_EVENTS)
+                .await
+                .with_context(|| format!("Failed to read {}", log_group))?;
+            Ok(DetailUpdate::LambdaLogs(events))
+        });
+        self.detail_task = Some(DetailTask {
+            handle: tokio::spawn(self.in_flight.track(tail)),
+            progress: None,
+        });
+    }
 
-                    client.get_s3_object_details(bucket, &resource_name).await
-                } else {
-                    client.get_s3_bucket_details(&resource_name).await
-                }
+    pub fn is_loading_detail(&self) -> bool {
+        self.detail_task.is_some()
+    }
+
+    /// Puts a background detail load into the popup once it finishes. Called on every tick of
+    /// the event loop while one runs.
+    pub async fn poll_detail_task(&mut self) {
+        let Some(task) = &mut self.detail_task else {
+            return;
+        };
+        // Progress is transient, so it skips the event log
+        if let Some(progress) = &mut task.progress {
+            while let Ok(message) = progress.try_recv() {
+                self.status_message = message;
             }
-            ServiceType::EC2 => {
-                // Extract instance ID from Ec2Item
-                if self.selected_index < self.ec2_items.len() {
-                    if let Ec2Item::Instance(id) = &self.ec2_items[self.selected_index] {
-                        // For now, just show a simple detail with instance ID
-                        Ok(vec![("Instance ID".to_string(), id.clone())])
-                    } else {
-                        Ok(vec![("Instance ID".to_string(), resource_name.clone())])
-                    }
-                } else {
-                    Ok(vec![("Instance ID".to_string(), resource_name.clone())])
+        }
+        if !task.handle.is_finished() {
+            return;
+        }
+        let result = match (&mut task.handle).await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow::anyhow!("Detail task failed: {}", e)),
+        };
+        self.detail_task = None;
+        // Loads that add to the details, like a summary or a chart, leave them up on failure
+        let placeholder = std::mem::take(&mut self.detail_loading);
+        match result {
+            Ok(update) => self.apply_detail_update(update),
+            Err(e) => {
+                if placeholder {
+                    self.detail_content = vec![("Error".to_string(), format!("{:#}", e))];
                 }
+                self.set_status(format!("{:#}", e));
             }
-            ServiceType::IAM => {
-                // If we have structured items, use them to get the name
-                if self.selected_index < self.iam_items.len() {
-                    if let IamItem::User(name) = &self.iam_items[self.selected_index] {
-                        Ok(vec![("User Name".to_string(), name.clone())])
-                    } else {
-                        Ok(vec![("User Name".to_string(), resource_name.clone())])
-                    }
-                } else {
-                    Ok(vec![("User Name".to_string(), resource_name.clone())])
-                }
+        }
+    }
+
+    /// Stops the detail load still running, e.g. a bucket scan taking too long, and leaves
+    /// the popup open. Returns whether there was one.
+    pub fn cancel_detail_task(&mut self) -> bool {
+        if self.detail_task.take().is_none() {
+            return false;
+        }
+        if self.detail_loading {
+            self.detail_loading = false;
+            self.detail_content = vec![("Cancelled".to_string(), String::new())];
+        }
+        self.set_status("Cancelled");
+        true
+    }
+
+    fn apply_detail_update(&mut self, update: DetailUpdate) {
+        match update {
+            DetailUpdate::Metric(metric) => self.detail_metric = metric,
+            DetailUpdate::BucketSummary { bucket, rows } => {
+                self.detail_content.push(("".to_string(), "".to_string()));
+                self.detail_content
+                    .push(("Summary".to_string(), "".to_string()));
+                self.detail_content.extend(rows);
+                self.set_status(format!("Summarized s3://{}", bucket));
             }
-            ServiceType::CloudWatch => Ok(vec![("Alarm Name".to_string(), resource_name.clone())]),
-            ServiceType::DynamoDB => client.get_dynamodb_table_details(&resource_name).await,
-            ServiceType::ECS => {
-                // For now just show name
-                Ok(vec![("Name".to_string(), resource_name.clone())])
-            }
-            ServiceType::MWAA => {
-                // Extract environment name from MwaaItem
-                if self.selected_index < self.mwaa_items.len() {
-                    if let MwaaItem::Environment(name) = &self.mwaa_items[self.selected_index] {
-                        match client.get_mwaa_environment(name).await {
-                            Ok(env) => Ok(MwaaService::get_environment_details_pairs(&env)),
-                            Err(e) => Err(e),
-                        }
-                    } else {
-                        Ok(vec![("Name".to_string(), resource_name.clone())])
+            DetailUpdate::LambdaLogs(events) => {
+                let group = self.detail_logs.clone().unwrap_or_default();
+                self.detail_content = match events {
+                    Some(events) if !events.is_empty() => {
+                        self.set_status(format!("Showing {} events of {}", events.len(), group));
+                        events
                     }
-                } else {
-                    Ok(vec![("Name".to_string(), resource_name.clone())])
-                }
-            }
-            ServiceType::Lambda => {
-                // Extract function name from LambdaItem
-                if self.selected_index < self.lambda_items.len() {
-                    if let LambdaItem::Function(name) = &self.lambda_items[self.selected_index] {
-                        match client.get_lambda_function(name).await {
-                            Ok(config) => Ok(LambdaService::get_function_details_pairs(&config)),
-                            Err(e) => Err(e),
-                        }
-                    } else {
-                        Ok(vec![("Name".to_string(), resource_name.clone())])
+                    Some(_) => {
+                        self.set_status(format!("{} has no log events yet", group));
+                        vec![("Events".to_string(), "None logged yet".to_string())]
                     }
-                } else {
-                    Ok(vec![("Name".to_string(), resource_name.clone())])
-                }
+                    None => {
+                        self.set_status(format!("{} doesn't exist yet", group));
+                        vec![
+                            ("Log Group".to_string(), group),
+                            (
+                                "Events".to_string(),
+                                "None yet: the group is created when the function first runs"
+                                    .to_string(),
+                            ),
+                        ]
+                    }
+                };
             }
+        }
+    }
+
+    /// Asks before scanning the bucket shown in the detail popup for its object count, size,
+    /// storage classes and policy. Listing every object can take a while on big buckets.
+    pub fn prompt_s3_bucket_summary(&mut self) {
+        let Some(bucket) = self.detail_bucket().map(String::from) else {
+            return;
         };
+        self.open_prompt(Prompt {
+            title: "Summarize Bucket".to_string(),
+            lines: vec![
+                format!("List every object in s3://{} to count them?", bucket),
+                "Large buckets take a while and incur LIST request charges".to_string(),
+            ],
+            input_label: String::new(),
+            input: None,
+            action: PendingAction::SummarizeS3Bucket { bucket },
+        });
+    }
 
-        match result {
-            Ok(details) => {
-                self.detail_content = details;
-                self.detail_loading = false;
-                self.status_message = format!("Viewing details for {}", resource_name);
-            }
-            Err(e) => {
-                self.detail_content = vec![
-                    ("Error".to_string(), "Failed to load details".to_string()),
-                    ("Details".to_string(), format!("{}", e)),
-                ];
-                self.detail_loading = false;
-                self.status_message = format!("Error loading details: {}", e);
-            }
+    /// Opens the selected GSI row of a DynamoDB table in a nested detail view.
+    pub fn open_detail_index(&mut self) {
+        if self.detail_parent.is_some() || self.detail_curated.is_some() {
+            return;
         }
+        let Some(&(row, _)) = self.detail_rows().get(self.detail_scroll) else {
+            return;
+        };
+        let Some(index) = self.detail_indexes.iter().find(|i| i.row == row).cloned() else {
+            return;
+        };
+        let content = std::mem::replace(&mut self.detail_content, index.details);
+        self.detail_parent = Some(DetailParent {
+            index_name: index.name.clone(),
+            content,
+            scroll: self.detail_scroll,
+        });
+        self.detail_scroll = 0;
+        self.detail_pretty.clear();
+        self.set_status(format!("Viewing index {}. Esc to go back", index.name));
+    }
+
+    /// Returns from a GSI to its table's details. False when there is nothing to go back to.
+    pub fn detail_back(&mut self) -> bool {
+        let Some(parent) = self.detail_parent.take() else {
+            return false;
+        };
+        self.detail_content = parent.content;
+        self.detail_scroll = parent.scroll;
+        self.detail_pretty.clear();
+        true
+    }
+
+    pub fn detail_scroll_up(&mut self) {
+        if self.detail_scroll > 0 {
+            self.detail_scroll -= 1;
+        }
+    }
+
+    pub fn detail_scroll_down(&mut self) {
+        if self.detail_scroll < self.detail_rows().len().saturating_sub(1) {
+            self.detail_scroll += 1;
+        }
+    }
+
+    /// Rows of the detail popup as (detail index, JSON line). Every detail has a row with
+    /// `None`; details toggled to JSON add one row per pretty-printed line below it.
+    pub fn detail_rows(&self) -> Vec<(usize, Option<String>)> {
+        let mut rows = Vec::new();
+        for (index, (_, value)) in self.detail_content.iter().enumerate() {
+            rows.push((index, None));
+            if self.detail_pretty.contains(&index)
+                && let Some(lines) = pretty_json(value)
+            {
+                rows.extend(lines.into_iter().map(|line| (index, Some(line))));
+            }
+        }
+        rows
+    }
+
+    /// Pretty-prints the selected detail when its value is a JSON object or array, or collapses
+    /// it again. Other values are left alone.
+    pub fn toggle_detail_json(&mut self) {
+        let Some(&(index, _)) = self.detail_rows().get(self.detail_scroll) else {
+            return;
+        };
+        if !self.detail_pretty.remove(&index) {
+            if pretty_json(&self.detail_content[index].1).is_none() {
+                return;
+            }
+            self.detail_pretty.insert(index);
+        }
+        // Keep the cursor on the detail's own row
+        self.detail_scroll = self
+            .detail_rows()
+            .iter()
+            .position(|&(i, _)| i == index)
+            .unwrap_or(0);
+    }
+
+    /// The popup's details as plain text, with JSON values pretty-printed under their key. The
+    /// raw view is just its JSON.
+    pub fn detail_text(&self) -> String {
+        if self.detail_curated.is_some()
+            && let [(_, raw)] = self.detail_content.as_slice()
+        {
+            return pretty_json(raw).map_or_else(|| raw.clone(), |lines| lines.join("\n")) + "\n";
+        }
+        let mut text = String::new();
+        for (key, value) in &self.detail_content {
+            match pretty_json(value) {
+                Some(lines) => {
+                    text.push_str(&format!("{}:\n", key));
+                    for line in lines {
+                        text.push_str(&format!("  {}\n", line));
+                    }
+                }
+                None if value.is_empty() => text.push_str(&format!("{}\n", key)),
+                None => text.push_str(&format!("{}: {}\n", key, value)),
+            }
+        }
+        text
+    }
+
+    /// Writes the details to a temp file for `run_app` to open in `$EDITOR`, which reads long
+    /// policies and describe output better than the popup.
+    pub fn open_detail_in_editor(&mut self) {
+        if self.detail_loading || self.detail_content.is_empty() {
+            return;
+        }
+        let extension = if self.detail_curated.is_some() {
+            "json"
+        } else {
+            "txt"
+        };
+        // A fresh file with a random name and owner-only permissions, so nothing else on the
+        // machine can prepare or read it
+        let file = tempfile::Builder::new()
+            .prefix("awsome-details-")
+            .suffix(&format!(".{}", extension))
+            .tempfile();
+        let written = file.and_then(|mut file| {
+            file.write_all(self.detail_text().as_bytes())?;
+            Ok(file.into_temp_path())
+        });
+        match written {
+            Ok(path) => self.editor_requested = Some(path),
+            Err(e) => self.set_status(format!("Could not write the details to a temp file: {}", e)),
+        }
+    }
+
+    /// Reports how the editor `run_app` ran for `open_detail_in_editor` exited.
+    pub fn finish_editor(&mut self, result: Result<()>) {
+        if let Err(e) = result {
+            self.set_status(format!("Editor failed: {}", e));
+        }
+    }
+
+    pub async fn show_resource_details(&mut self) -> Result<()> {
+        if self.items.is_empty() || self.selected_index >= self.items.len() {
+            return Ok(());
+        }
+
+        let client = match &self.aws_client {
+            Some(c) => c.clone(),
+            None => {
+                self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+                return Ok(());
+            }
+        };
+
+        // Check if it's an S3 folder or parent dir
+        if matches!(self.get_active_service().service_type, ServiceType::S3)
+            && self.selected_index < self.s3_items.len()
+        {
+            match &self.s3_items[self.selected_index] {
+                S3Item::Folder(name) => {
+                    self.show_detail_popup = true;
+                    self.detail_loading = false;
+                    self.detail_content = vec![
+                        ("Name".to_string(), name.clone()),
+                        ("Type".to_string(), "Folder".to_string()),
+                    ];
+                    self.set_status(format!("Viewing details for folder {}", name));
+                    return Ok(());
+                }
+                S3Item::Version {
+                    name,
+                    version_id,
+                    delete_marker: true,
+                } => {
+                    // HEAD on a delete marker fails, and there is nothing more to show
+                    self.show_detail_popup = true;
+                    self.detail_loading = false;
+                    self.detail_content = vec![
+                        ("Name".to_string(), name.clone()),
+                        ("Version ID".to_string(), version_id.clone()),
+                        ("Type".to_string(), "Delete marker".to_string()),
+                    ];
+                    self.set_status(format!("Viewing delete marker for {}", name));
+                    return Ok(());
+                }
+                S3Item::ParentDir => {
+                    self.set_status("Parent Directory".to_string());
+                    return Ok(());
+                }
+                S3Item::Header | S3Item::Separator | S3Item::Group(_) => {
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        let resource_line = &self.items[self.selected_index];
+
+        // Extract resource name based on service type
+        let resource_name = match self.get_active_service().service_type {
+            ServiceType::S3 => {
+                // Row text may be truncated to fit the pane, so prefer the structured item
+                let item_name = match self.s3_items.get(self.selected_index) {
+                    Some(
+                        S3Item::Bucket(name)
+                        | S3Item::Folder(name)
+                        | S3Item::Object(name)
+                        | S3Item::Version { name, .. },
+                    ) => Some(name.as_str()),
+                    _ => None,
+                };
+                if let Some(path) = &self.current_path {
+                    // We are inside a bucket, show object details
+                    // The selected line is a table row: "Name  Size  Date"
+                    let name = item_name.unwrap_or_else(|| {
+                        resource_line
+                            .split_whitespace()
+                            .next()
+                            .unwrap_or(resource_line)
+                    });
+                    // Construct full key
+                    let parts: Vec<&str> = path.splitn(2, '/').collect();
+                    let prefix = if parts.len() > 1 { parts[1] } else { "" };
+                    format!("{}{}", prefix, name)
+                } else {
+                    // For S3 buckets, extract bucket name from table format
+                    // Skip header and separator rows
+                    if self.selected_index <= 1 {
+                        self.set_status("Please select a bucket row".to_string());
+                        return Ok(());
+                    }
+                    // Extract bucket name (everything before the two spaces and date)
+                    item_name
+                        .unwrap_or_else(|| {
+                            resource_line
+                                .split_whitespace()
+                                .next()
+                                .unwrap_or(resource_line)
+                        })
+                        .to_string()
+                }
+            }
+            ServiceType::DynamoDB => {
+                // Extract table name from DynamoDbItem
+                if self.selected_index < self.dynamodb_items.len() {
+                    if let DynamoDbItem::Table { name, .. } =
+                        &self.dynamodb_items[self.selected_index]
+                    {
+                        name.clone()
+                    } else {
+                        self.set_status("Please select a table row".to_string());
+                        return Ok(());
+                    }
+                } else {
+                    resource_line.clone()
+                }
+            }
+            ServiceType::CloudWatch => match self.cloudwatch_items.get(self.selected_index) {
+                Some(CloudwatchItem::Alarm { name, .. }) => name.clone(),
+                _ => {
+                    self.set_status("Please select an alarm row".to_string());
+                    return Ok(());
+                }
+            },
+            _ => resource_line.clone(),
+        };
+        if let Some(Ec2Item::Instance { id, .. }) = self.ec2_items.get(self.selected_index)
+            && self.get_active_service().service_type == ServiceType::EC2
+            && self.refuse_other_region(&id.clone())
+        {
+            return Ok(());
+        }
+
+        // Show popup with loading state
+        self.show_detail_popup = true;
+        self.detail_loading = true;
+        self.detail_content = vec![("Loading...".to_string(), "".to_string())];
+        self.detail_metric = None;
+        self.detail_scroll = 0;
+        self.detail_pretty.clear();
+        self.detail_indexes.clear();
+        self.detail_parent = None;
+        self.detail_curated = None;
+        self.detail_logs = None;
+        self.detail_task = None;
+
+        // Fetch details based on service type
+        let timeout = self.request_timeout();
+        let result = with_timeout(timeout, async {
+            match self.get_active_service().service_type {
+                ServiceType::S3 => {
+                    if let Some(path) = &self.current_path {
+                        // We are inside a bucket, so resource_name is the full key
+                        // split it into bucket and key
+                        let parts: Vec<&str> = path.splitn(2, '/').collect();
+                        let bucket = parts[0];
+                        // So resource_name is the key (including prefix).
+                        // We just need the bucket name.
+                        let version_id = match self.s3_items.get(self.selected_index) {
+                            Some(S3Item::Version { version_id, .. }) => Some(version_id.as_str()),
+                            _ => None,
+                        };
+
+                        client
+                            .get_s3_object_details(bucket, &resource_name, version_id)
+                            .await
+                    } else {
+                        client.get_s3_bucket_details(&resource_name).await
+                    }
+                }
+                ServiceType::EC2 => {
+                    // Extract instance ID from Ec2Item
+                    if self.selected_index < self.ec2_items.len() {
+                        if let Ec2Item::Instance { id, .. } = &self.ec2_items[self.selected_index] {
+                            client.describe_ec2_instance(id).await
+                        } else {
+                            Ok(vec![("Instance ID".to_string(), resource_name.clone())])
+                        }
+                    } else {
+                        Ok(vec![("Instance ID".to_string(), resource_name.clone())])
+                    }
+                }
+                ServiceType::IAM => {
+                    // If we have structured items, use them to get the name
+                    match self.iam_items.get(self.selected_index) {
+                        Some(IamItem::User(name)) => {
+                            Ok(vec![("User Name".to_string(), name.clone())])
+                        }
+                        Some(IamItem::ReportEntry { user, .. }) => Ok(self
+                            .iam_report
+                            .iter()
+                            .find(|entry| entry.user == *user)
+                            .map(|entry| entry.details_pairs(chrono::Utc::now()))
+                            .unwrap_or_else(|| vec![("User".to_string(), user.clone())])),
+                        _ => Ok(vec![("User Name".to_string(), resource_name.clone())]),
+                    }
+                }
+                ServiceType::CloudWatch => {
+                    match client
+                        .describe_cloudwatch_alarm(&resource_name, self.metric_range)
+                        .await
+                    {
+                        Ok((details, metric)) => {
+                            self.detail_metric = metric;
+                            Ok(details)
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                ServiceType::DynamoDB => {
+                    match client.get_dynamodb_table_details(&resource_name).await {
+                        Ok((details, indexes)) => {
+                            self.detail_indexes = indexes;
+                            Ok(details)
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                ServiceType::ECS => {
+                    let cluster = self
+                        .current_path
+                        .as_deref()
+                        .and_then(|path| path.split('/').next());
+                    match (self.ecs_items.get(self.selected_index), cluster) {
+                        (Some(EcsItem::Task { id, .. }), Some(cluster)) => {
+                            client.describe_ecs_task(cluster, id).await
+                        }
+                        (Some(EcsItem::Service(service)), Some(cluster)) => {
+                            client.describe_ecs_service(cluster, service).await
+                        }
+                        _ => Ok(vec![("Name".to_string(), resource_name.clone())]),
+                    }
+                }
+                ServiceType::MWAA => {
+                    // Extract environment name from MwaaItem
+                    if self.selected_index < self.mwaa_items.len() {
+                        if let MwaaItem::Environment(name) = &self.mwaa_items[self.selected_index] {
+                            match client.get_mwaa_environment(name).await {
+                                Ok(env) => Ok(MwaaService::get_environment_details_pairs(&env)),
+                                Err(e) => Err(e),
+                            }
+                        } else {
+                            Ok(vec![("Name".to_string(), resource_name.clone())])
+                        }
+                    } else {
+                        Ok(vec![("Name".to_string(), resource_name.clone())])
+                    }
+                }
+                ServiceType::Lambda => match self.lambda_function_at(self.selected_index) {
+                    Some(name) => client
+                        .get_lambda_function(&name)
+                        .await
+                        .map(|config| LambdaService::get_function_details_pairs(&config)),
+                    None => Ok(vec![("Name".to_string(), resource_name.clone())]),
+                },
+            }
+        })
+        .await;
+
+        match result {
+            Ok(details) => {
+                self.detail_content = details;
+                self.detail_loading = false;
+                self.set_status(format!("Viewing details for {}", resource_name));
+            }
+            Err(e) => {
+                self.detail_content = vec![
+                    ("Error".to_string(), "Failed to load details".to_string()),
+                    ("Details".to_string(), format!("{}", e)),
+                ];
+                self.detail_loading = false;
+                self.set_status(format!("Error loading details: {}", e));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reloads the active service, connecting first when the client failed to initialize.
+    pub async fn refresh_or_reconnect(&mut self) -> Result<()> {
+        // The credentials may have been fixed in another terminal since
+        if self.aws_client.is_none() && self.initialize_aws_client().await.is_err() {
+            return Ok(());
+        }
+        // Bucket tags are cached across loads; an explicit reload reads them again
+        if let Some(client) = &self.aws_client {
+            client.s3_clear_bucket_tags();
+        }
+        // An explicit reload is the way to try the credentials again once they are paused
+        self.start_refresh()
+    }
+
+    /// Whether enough credential errors came in a row that loads and watch polls stop calling
+    /// AWS until the user re-authenticates or presses r.
+    pub fn auth_paused(&self) -> bool {
+        self.auth_failures >= AUTH_FAILURE_LIMIT
+    }
+
+    /// Starts listing the active service in the background. `poll_refresh` applies progress
+    /// updates and the final result as they arrive, so the UI keeps drawing meanwhile.
+    pub async fn refresh_resources(&mut self) -> Result<()> {
+        if self.auth_paused() {
+            self.loading_state = LoadingState::Error;
+            self.items = vec![
+                format!(
+                    "Paused after {} credential errors in a row",
+                    self.auth_failures
+                ),
+                "".to_string(),
+                "- Re-authenticate, e.g. `aws sso login` or A to re-assume the role".to_string(),
+                "- Then press r to try the credentials again".to_string(),
+            ];
+            self.set_status("Paused: re-authenticate, then press r");
+            return Ok(());
+        }
+        self.start_refresh()
+    }
+
+    fn start_refresh(&mut self) -> Result<()> {
+        let client = match &self.aws_client {
+            Some(c) => c.clone(),
+            None => {
+                self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+                return Ok(());
+            }
+        };
+
+        // A reload replacing an unfinished one keeps aiming for the row the first one saw
+        let reselect = match self.refresh.take() {
+            Some(task) => task.reselect.clone(),
+            None => self.selected_row(),
+        };
+        self.loading_state = LoadingState::Loading;
+        self.items = vec!["Loading...".to_string()];
+        self.set_status(format!(
+            "Loading {} resources...",
+            self.get_active_service().as_str()
+        ));
+
+        let (progress, progress_rx) = Progress::channel();
+        let listing = fetch_listing(
+            client,
+            self.get_active_service().service_type,
+            self.current_path.clone(),
+            Ec2Scope {
+                tag_filter: self.ec2_tag_filter.clone(),
+                all_regions: self.ec2_all_regions,
+            },
+            self.paging(),
+            S3Scope {
+                versions: self.s3_show_versions,
+                group_by: self.s3_group_by.clone(),
+                folder_stats: self.s3_folder_stats,
+            },
+            progress,
+        );
+        let handle = tokio::spawn(
+            self.in_flight
+                .track(with_timeout(self.request_timeout(), listing)),
+        );
+        self.refresh = Some(RefreshTask {
+            handle,
+            progress: progress_rx,
+            after_status: None,
+            reselect,
+        });
+        Ok(())
+    }
+
+    fn selected_row(&self) -> Option<SelectedRow> {
+        if self.loading_state != LoadingState::Loaded {
+            return None;
+        }
+        Some(SelectedRow {
+            service: self.active_service,
+            path: self.current_path.clone(),
+            id: self.row_id(self.selected_index)?,
+        })
+    }
+
+    // Puts the cursor back on the resource selected before a reload of the same list. A
+    // resource that is gone leaves it on the first row
+    fn restore_selection(&mut self, selected: SelectedRow) {
+        if selected.service != self.active_service || selected.path != self.current_path {
+            return;
+        }
+        if let Some(row) = (0..self.items.len())
+            .find(|&row| self.is_selectable(row) && self.row_id(row).as_ref() == Some(&selected.id))
+        {
+            self.selected_index = row;
+        }
+    }
+
+    fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.config.request_timeout_secs.max(1))
+    }
+
+    pub fn is_refreshing(&self) -> bool {
+        self.refresh.is_some()
+    }
+
+    /// Abandons the running listing; dropping the task aborts its requests.
+    pub fn cancel_refresh(&mut self) {
+        if self.refresh.take().is_none() {
+            return;
+        }
+        self.loading_state = LoadingState::Idle;
+        self.items = vec!["Cancelled. Press r to load again".to_string()];
+        self.set_status(format!(
+            "Cancelled loading {}",
+            self.get_active_service().as_str()
+        ));
+    }
+
+    /// Shows `message` once the running refresh succeeds instead of the usual "Loaded N" status.
+    pub fn set_status_after_refresh(&mut self, message: impl Into<String>) {
+        match &mut self.refresh {
+            Some(task) => task.after_status = Some(message.into()),
+            None => self.set_status(message),
+        }
+    }
+
+    /// Drains progress from the background refresh into the status bar and applies its result
+    /// once it finishes. Called on every tick of the event loop.
+    pub async fn poll_refresh(&mut self) -> Result<()> {
+        let Some(task) = &mut self.refresh else {
+            return Ok(());
+        };
+        let mut latest = None;
+        while let Ok(message) = task.progress.try_recv() {
+            latest = Some(message);
+        }
+        let finished = task.handle.is_finished();
+        // Progress is transient, so it skips the event log
+        if let Some(message) = latest {
+            self.status_message = message;
+        }
+        if !finished {
+            return Ok(());
+        }
+
+        let Some(mut task) = self.refresh.take() else {
+            return Ok(());
+        };
+        let result = match (&mut task.handle).await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow::anyhow!("Listing task failed: {}", e)),
+        };
+        self.apply_listing(result)?;
+        if self.loading_state != LoadingState::Loaded {
+            return Ok(());
+        }
+        self.refresh_credentials_expiry().await;
+        if let Some(selected) = task.reselect.take() {
+            self.restore_selection(selected);
+        }
+        if let Some(message) = task.after_status.take() {
+            self.set_status(message);
+        }
+        Ok(())
+    }
+
+    fn apply_listing(&mut self, result: Result<Listing>) -> Result<()> {
+        let listing = match result {
+            Ok(listing) => listing,
+            Err(e) => return self.handle_resource_error(e),
+        };
+        self.loading_state = LoadingState::Loaded;
+        self.error_message = None;
+        self.auth_failures = 0;
+        self.last_refreshed
+            .insert(self.active_service, Instant::now());
+        // Marks point at rows of the old listing
+        self.marked_rows.clear();
+        let mut listing = listing;
+        self.capped_at = self.paging().max_items.filter(|&max| listing.cap(max));
+        self.last_listing = Some((self.active_service, listing.clone()));
+        self.show_listing(listing);
+        Ok(())
+    }
+
+    // Formats a listing into rows and sets the status and selection for it
+    fn show_listing(&mut self, listing: Listing) {
+        match listing {
+            Listing::Ec2(instances) => self.show_ec2_instances(instances, Vec::new()),
+            Listing::Ec2AllRegions { instances, failed } => {
+                self.show_ec2_instances(instances, failed)
+            }
+            Listing::S3Objects {
+                bucket,
+                prefix,
+                mut objects,
+                folders,
+            } => {
+                // Folders stay visible so the filter applies while browsing
+                if let Some(class) = &self.s3_storage_class_filter {
+                    objects.retain(|(_, size, _, object_class)| {
+                        size == "DIR" || object_class == class
+                    });
+                }
+                let listed = objects.len();
+                if self.config.hide_folder_placeholders {
+                    objects.retain(|(name, size, _, _)| !is_folder_placeholder(name, size));
+                }
+                let hidden = listed - objects.len();
+                let (items, s3_items) = S3Service::format_object_list(
+                    &objects,
+                    &folders,
+                    &self.hidden_columns(&S3Service::OBJECT_COLUMNS),
+                    self.config.time_format,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.s3_items = s3_items;
+                self.set_status(format!(
+                    "Browsing s3://{}/{}{}",
+                    bucket,
+                    prefix,
+                    placeholders_hidden(hidden)
+                ));
+                self.select_first_row();
+            }
+            Listing::S3Versions {
+                bucket,
+                prefix,
+                mut versions,
+            } => {
+                let listed = versions.len();
+                if self.config.hide_folder_placeholders {
+                    versions.retain(|version| {
+                        version.delete_marker
+                            || !is_folder_placeholder(&version.name, &version.size)
+                    });
+                }
+                let hidden = listed - versions.len();
+                let (items, s3_items) = S3Service::format_version_list(
+                    &versions,
+                    self.config.time_format,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.s3_items = s3_items;
+                self.set_status(format!(
+                    "Browsing versions in s3://{}/{}{}",
+                    bucket,
+                    prefix,
+                    placeholders_hidden(hidden)
+                ));
+                self.select_first_row();
+            }
+            Listing::S3Buckets(buckets) => {
+                let (items, s3_items) = S3Service::format_bucket_list(
+                    &buckets,
+                    self.config.time_format,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.s3_items = s3_items;
+                self.set_loaded_status(buckets.len(), Some("buckets"));
+            }
+            Listing::S3BucketGroups { key, buckets } => {
+                let (items, s3_items) = S3Service::format_bucket_groups(
+                    &buckets,
+                    &key,
+                    self.config.time_format,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                let groups = s3_items
+                    .iter()
+                    .filter(|item| matches!(item, S3Item::Group(_)))
+                    .count();
+                self.items = items;
+                self.s3_items = s3_items;
+                let noun = format!("buckets in {} group(s) by tag {}", groups, key);
+                self.set_loaded_status(buckets.len(), Some(&noun));
+            }
+            Listing::Iam(users) => {
+                let (items, iam_items) = IamService::format_user_list(
+                    &users,
+                    self.show_arns,
+                    self.config.time_format,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.iam_items = iam_items;
+                self.set_loaded_status(users.len(), None);
+            }
+            Listing::IamCredentialReport(entries) => {
+                let (items, iam_items) = IamService::format_credential_report(
+                    &entries,
+                    self.config.time_format,
+                    chrono::Utc::now(),
+                    self.name_width(),
+                    self.list_width(),
+                );
+                let flagged = iam_items
+                    .iter()
+                    .filter(|item| {
+                        matches!(item, IamItem::ReportEntry { findings, .. } if !findings.is_empty())
+                    })
+                    .count();
+                self.items = items;
+                self.iam_items = iam_items;
+                self.set_status(format!(
+                    "Credential report: {} user(s), {} flagged",
+                    entries.len(),
+                    flagged
+                ));
+                self.iam_report = entries;
+                self.select_first_row();
+            }
+            Listing::CloudWatch(alarms) => {
+                self.cloudwatch_alarms = alarms;
+                self.format_cloudwatch_alarms();
+                self.set_loaded_status(self.cloudwatch_alarms.len(), None);
+            }
+            Listing::DynamoDb(tables) => {
+                use crate::aws::DynamoDbService;
+                let (items, dynamodb_items) = DynamoDbService::format_table_list(
+                    &tables,
+                    &self.hidden_columns(&DynamoDbService::TABLE_COLUMNS),
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.dynamodb_items = dynamodb_items;
+                self.set_loaded_status(tables.len(), Some("tables"));
+            }
+            Listing::EcsServices { cluster, services } => {
+                let (items, ecs_items) = EcsService::format_service_list(
+                    &services,
+                    &cluster,
+                    self.show_arns,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.ecs_items = ecs_items;
+                self.set_status(format!("Browsing cluster {}", cluster));
+                self.select_first_row();
+            }
+            Listing::EcsTasks {
+                cluster,
+                service,
+                tasks,
+            } => {
+                let (items, ecs_items) = EcsService::format_task_list(
+                    &tasks,
+                    &service,
+                    self.show_arns,
+                    &self.hidden_columns(&EcsService::TASK_COLUMNS),
+                    self.config.time_format,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.ecs_items = ecs_items;
+                let hint = if self.read_only {
+                    ""
+                } else {
+                    " (s: stop task)"
+                };
+                self.set_status(format!("Browsing tasks in {}/{}{}", cluster, service, hint));
+                self.select_first_row();
+            }
+            Listing::EcsClusters(clusters) => {
+                let (items, ecs_items) = EcsService::format_cluster_list(
+                    &clusters,
+                    self.show_arns,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.ecs_items = ecs_items;
+                self.set_loaded_status(clusters.len(), Some("clusters"));
+            }
+            Listing::Mwaa(envs) => {
+                let (items, mwaa_items) = MwaaService::format_environment_list(
+                    &envs,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.mwaa_items = mwaa_items;
+                self.set_loaded_status(envs.len(), Some("environments"));
+            }
+            Listing::Lambda(functions) => {
+                let (items, lambda_items) = LambdaService::format_function_list(
+                    &functions,
+                    &self.hidden_columns(&LambdaService::FUNCTION_COLUMNS),
+                    self.config.time_format,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.lambda_items = lambda_items;
+                self.set_loaded_status(functions.len(), None);
+            }
+            Listing::LambdaVersions { function, versions } => {
+                let (items, lambda_items) = LambdaService::format_version_list(
+                    &function,
+                    &versions,
+                    self.config.time_format,
+                    self.name_width(),
+                    self.list_width(),
+                );
+                self.items = items;
+                self.lambda_items = lambda_items;
+                self.set_status(format!("Browsing versions of {}", function));
+                self.select_first_row();
+            }
+        }
+    }
+
+    // Config key for the active service's column width, e.g. "ec2"
+    fn service_key(&self) -> String {
+        self.get_active_service().short_name().to_lowercase()
+    }
+
+    /// Saves the current listing so later refreshes of it highlight what changed.
+    pub fn take_snapshot(&mut self) {
+        let listing = match &self.last_listing {
+            Some((service, listing))
+                if *service == self.active_service
+                    && self.loading_state == LoadingState::Loaded =>
+            {
+                listing
+            }
+            _ => {
+                self.set_status("Load the list before taking a snapshot");
+                return;
+            }
+        };
+        let shown: HashMap<String, &String> = (0..self.items.len())
+            .filter_map(|row| Some((self.row_id(row)?, &self.items[row])))
+            .collect();
+        let rows: HashMap<String, (String, String)> = listing
+            .keyed_rows()
+            .into_iter()
+            .map(|(id, fingerprint)| {
+                let text = shown.get(&id).map_or_else(|| id.clone(), |t| t.to_string());
+                (id, (fingerprint, text))
+            })
+            .collect();
+        let count = rows.len();
+        self.snapshot = Some(Snapshot {
+            service: self.active_service,
+            path: self.current_path.clone(),
+            rows,
+        });
+        self.show_diff = true;
+        self.set_status(format!(
+            "Snapshot of {} row(s) taken. Refresh to see what changed; = toggles the diff",
+            count
+        ));
+    }
+
+    pub fn toggle_diff(&mut self) {
+        if self.snapshot.is_none() {
+            self.set_status("Press S to take a snapshot first");
+            return;
+        }
+        self.show_diff = !self.show_diff;
+    }
+
+    // The snapshot to compare with, when it was taken of what is on screen, and the
+    // current listing's rows
+    fn diff_base(&self) -> Option<(&Snapshot, HashMap<String, String>)> {
+        let snapshot = self.snapshot.as_ref().filter(|s| {
+            self.show_diff && s.service == self.active_service && s.path == self.current_path
+        })?;
+        match &self.last_listing {
+            Some((service, listing)) if *service == self.active_service => {
+                Some((snapshot, listing.keyed_rows().into_iter().collect()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Rows added or changed since the snapshot, by row index.
+    pub fn row_changes(&self) -> HashMap<usize, RowChange> {
+        let Some((snapshot, current)) = self.diff_base() else {
+            return HashMap::new();
+        };
+        (0..self.items.len())
+            .filter_map(|row| {
+                let id = self.row_id(row)?;
+                let change = match snapshot.rows.get(&id) {
+                    None => RowChange::Added,
+                    Some((fingerprint, _)) if current.get(&id) != Some(fingerprint) => {
+                        RowChange::Changed
+                    }
+                    Some(_) => return None,
+                };
+                Some((row, change))
+            })
+            .collect()
+    }
+
+    /// Rows of the snapshot that are gone from the listing, as they were shown.
+    pub fn removed_rows(&self) -> Vec<String> {
+        let Some((snapshot, current)) = self.diff_base() else {
+            return Vec::new();
+        };
+        let mut removed: Vec<(&String, &String)> = snapshot
+            .rows
+            .iter()
+            .filter(|(id, _)| !current.contains_key(*id))
+            .map(|(id, (_, text))| (id, text))
+            .collect();
+        removed.sort();
+        removed.into_iter().map(|(_, text)| text.clone()).collect()
+    }
+
+    // Page size and item cap for the active service's listings
+    fn paging(&self) -> Paging {
+        Paging {
+            page_size: self.config.page_size,
+            max_items: self.config.max_items.get(&self.service_key()).copied(),
+        }
+    }
+
+    // Minimum width pinned for the active service's name column
+    fn name_width(&self) -> Option<usize> {
+        self.config.column_widths.get(&self.service_key()).copied()
+    }
+
+    /// Lays the loaded listing out again without refetching, e.g. for a pane that was resized,
+    /// keeping the selection. That isn't news, so the status bar and event log stay as they were.
+    pub fn relayout_listing(&mut self) {
+        let Some((service, listing)) = self.last_listing.clone() else {
+            return;
+        };
+        if service != self.active_service || self.loading_state != LoadingState::Loaded {
+            return;
+        }
+        let selected = self.selected_index;
+        let status = self.status_message.clone();
+        let event_log = self.event_log.clone();
+        self.show_listing(listing);
+        self.selected_index = selected.min(self.items.len().saturating_sub(1));
+        self.status_message = status;
+        self.event_log = event_log;
+    }
+
+    /// Widens or narrows the name column of the active service by `delta` characters and
+    /// lays out the rows again without refetching. The width is kept in the config, so it
+    /// survives refreshes and restarts; narrowing to the data's own width unpins it.
+    pub fn nudge_column_width(&mut self, delta: isize) {
+        let Some((service, listing)) = self.last_listing.clone() else {
+            return;
+        };
+        if service != self.active_service || self.loading_state != LoadingState::Loaded {
+            return;
+        }
+
+        let natural = listing.name_column_len(self.show_arns);
+        let width = self
+            .name_width()
+            .unwrap_or(natural)
+            .max(natural)
+            .saturating_add_signed(delta);
+        let key = self.service_key();
+        if width > natural {
+            self.config.column_widths.insert(key, width);
+        } else {
+            self.config.column_widths.remove(&key);
+        }
+
+        let selected = self.selected_index;
+        self.show_listing(listing);
+        self.selected_index = selected.min(self.items.len().saturating_sub(1));
+        match self.name_width() {
+            Some(width) => self.set_status(format!("Name column pinned to {} characters", width)),
+            None => self.set_status("Name column fits the data"),
+        }
+    }
+
+    // Lines under "No X found" saying where we looked and what to try; most resources are
+    // regional, so an empty list usually means the wrong region rather than none at all
+    fn empty_state_guidance(&self) -> Vec<String> {
+        let region = self.region.as_deref().unwrap_or("the default region");
+        let credentials = match &self.env_credentials {
+            Some(label) => label.clone(),
+            None => format!("profile {}", self.profile_name),
+        };
+        let mut lines = vec![
+            String::new(),
+            format!("Looked in {} with {}.", region, credentials),
+        ];
+        let hint = match self.get_active_service().service_type {
+            ServiceType::EC2 => {
+                "- Instances are regional: check the region, or restart with --region"
+            }
+            ServiceType::CloudWatch => {
+                "- Alarms are regional and may exist in another region: try --region"
+            }
+            ServiceType::S3 if self.read_only => "- This account has no buckets yet",
+            ServiceType::S3 => "- Press n to create a bucket",
+            ServiceType::DynamoDB if self.read_only => "- Tables are regional: check the region",
+            ServiceType::DynamoDB => {
+                "- Tables are regional: check the region, or press n to create one"
+            }
+            ServiceType::IAM => "- IAM is global: the account may sign in through SSO instead",
+            ServiceType::ECS | ServiceType::MWAA | ServiceType::Lambda => {
+                "- These are regional: check the region, or restart with --region"
+            }
+        };
+        lines.push(hint.to_string());
+        lines
+    }
+
+    // Lays out EC2 instances. `failed` are the regions an all-regions listing couldn't list,
+    // named in the status with their errors behind E
+    fn show_ec2_instances(&mut self, instances: Vec<Ec2Instance>, failed: Vec<(String, String)>) {
+        self.ec2_instances = instances;
+        self.format_ec2_instances();
+        let regions: HashSet<&str> = self
+            .ec2_instances
+            .iter()
+            .map(|i| i.region.as_str())
+            .filter(|r| !r.is_empty())
+            .collect();
+        let noun =
+            (!regions.is_empty()).then(|| format!("instances across {} region(s)", regions.len()));
+        self.set_loaded_status(self.ec2_instances.len(), noun.as_deref());
+        if failed.is_empty() {
+            return;
+        }
+        let names: Vec<&str> = failed.iter().map(|(region, _)| region.as_str()).collect();
+        self.set_status(format!(
+            "{} region(s) failed: {}. E for details",
+            failed.len(),
+            names.join(", ")
+        ));
+        let errors: Vec<String> = failed
+            .iter()
+            .map(|(region, error)| format!("{}: {}", region, error))
+            .collect();
+        self.error_message = Some(errors.join("\n"));
+    }
+
+    // "Loaded 3 buckets", or "Loaded 3 resources (EC2 Instances)" without a noun.
+    // Selects the first resource.
+    fn set_loaded_status(&mut self, count: usize, noun: Option<&str>) {
+        let service = self.get_active_service().as_str().to_string();
+        if count == 0 {
+            self.items.extend(self.empty_state_guidance());
+            self.set_status(format!("No resources found for {}", service));
+            self.selected_index = 0;
+            return;
+        }
+        match noun {
+            Some(noun) => self.set_status(format!("Loaded {} {}", count, noun)),
+            None => self.set_status(format!("Loaded {} resources ({})", count, service)),
+        }
+        self.select_first_row();
+    }
+
+    // Puts the cursor on the first resource of a fresh listing. Without one (an empty folder,
+    // or a filter that left nothing) it takes the first row that can be selected at all, such
+    // as "..", and never a row past the end
+    fn select_first_row(&mut self) {
+        let first = self
+            .resource_rows()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|&i| self.matches_search(i))
+            .or_else(|| (0..self.items.len()).find(|&i| self.is_selectable(i)));
+        self.selected_index = first.unwrap_or(0).min(self.items.len().saturating_sub(1));
+    }
+
+    fn handle_resource_error(&mut self, e: anyhow::Error) -> Result<()> {
+        let kind = classify_error(&e);
+        self.loading_state = LoadingState::Error;
+        // The SDK's Display stops at "service error"; the context walks the source chain down
+        // to the error code and request id
+        self.error_message = Some(DisplayErrorContext(&*e).to_string());
+        self.items = vec![
+            format!(
+                "Error loading {}: {}",
+                self.get_active_service().as_str(),
+                kind.summary()
+            ),
+            "".to_string(),
+        ];
+        self.items.extend(kind.guidance(&self.profile_name));
+        self.items
+            .push("- Press E for the full error and request id".to_string());
+        self.set_status(format!("Error: {}", kind.summary()));
+
+        let expired = is_auth_error(&kind);
+        if expired {
+            self.auth_failures += 1;
+            if self.auth_paused() {
+                self.items.push(format!(
+                    "- Paused after {} failures in a row: nothing loads until you \
+                     re-authenticate or press r",
+                    self.auth_failures
+                ));
+            }
+        }
+        // An SSO profile can be renewed from here; the base credentials of an assumed role
+        // may be SSO too, so this comes first
+        if expired
+            && self.prompt.is_none()
+            && is_sso_profile(&load_config_profiles(), &self.profile_name)
+        {
+            let profile = self.profile_name.clone();
+            self.open_prompt(Prompt {
+                title: "SSO Login".to_string(),
+                lines: vec![
+                    format!("The SSO session for profile {} has expired.", profile),
+                    format!("Run `aws sso login --profile {}` now?", profile),
+                ],
+                input_label: String::new(),
+                input: None,
+                action: PendingAction::SsoLogin { profile },
+            });
+            return Ok(());
+        }
+
+        // Assumed-role sessions can be renewed in place
+        if expired && let Some(role) = self.client_options.assumed_role_name() {
+            self.items
+                .push(format!("- Press A to re-assume role {}", role));
+            self.set_status(format!(
+                "Error: {}. Press A to re-assume the role",
+                kind.summary()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reconnects and reloads after `run_app` has run `aws sso login`.
+    pub async fn finish_sso_login(&mut self, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => {
+                self.set_status("SSO login succeeded, reconnecting...");
+                if self.initialize_aws_client().await.is_ok() {
+                    self.refresh_resources().await?;
+                }
+            }
+            Err(e) => self.set_status(format!("SSO login failed: {}", e)),
+        }
+        Ok(())
+    }
+
+    /// When the session runs out. SSO profiles count down to the SSO token, since the role
+    /// credentials it hands out are renewed from it; anything else to the credentials' own expiry.
+    async fn read_credentials_expiry(&self, client: &AwsClient) -> Option<SystemTime> {
+        if self.env_credentials.is_none() && self.client_options.assume_role.is_none() {
+            let sso = sso_token_expiry(&load_config_profiles(), &self.profile_name);
+            if sso.is_some() {
+                return sso;
+            }
+        }
+        tokio::time::timeout(self.request_timeout(), client.credentials_expiry())
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Re-reads the expiry after a refresh, since the SDK renews credentials as they run out and
+    /// `aws sso login` in another terminal extends the token. A new expiry gets its own prompt.
+    async fn refresh_credentials_expiry(&mut self) {
+        let Some(client) = self.aws_client.clone() else {
+            return;
+        };
+        let expiry = self.read_credentials_expiry(&client).await;
+        if expiry != self.credentials_expiry {
+            self.credentials_expiry = expiry;
+            self.expiry_prompted = false;
+        }
+    }
+
+    /// Header countdown to the credentials running out, e.g. "creds expire in 42m", and
+    /// whether it is close enough to show in red.
+    pub fn credentials_countdown(&self, now: SystemTime) -> Option<(String, bool)> {
+        let expiry = self.credentials_expiry?;
+        Some(match expiry.duration_since(now) {
+            Ok(left) if !left.is_zero() => (
+                format!("creds expire in {}", format_remaining(left)),
+                left < EXPIRY_WARNING,
+            ),
+            _ => ("creds expired".to_string(), true),
+        })
+    }
+
+    /// Offers to renew the credentials once they are about to expire, once per session: an SSO
+    /// login for SSO profiles, otherwise a reconnect, which assumes the role again or re-reads
+    /// the profile.
+    pub fn check_credentials_expiry(&mut self, now: SystemTime) {
+        let Some(expiry) = self.credentials_expiry else {
+            return;
+        };
+        let left = expiry.duration_since(now).unwrap_or_default();
+        if self.expiry_prompted || left >= EXPIRY_WARNING || self.prompt.is_some() {
+            return;
+        }
+        self.expiry_prompted = true;
+        let when = if left.is_zero() {
+            "have expired".to_string()
+        } else {
+            format!("expire in {}", format_remaining(left))
+        };
+        if is_sso_profile(&load_config_profiles(), &self.profile_name) {
+            let profile = self.profile_name.clone();
+            self.open_prompt(Prompt {
+                title: "SSO Login".to_string(),
+                lines: vec![
+                    format!("The credentials for profile {} {}.", profile, when),
+                    format!("Run `aws sso login --profile {}` now?", profile),
+                ],
+                input_label: String::new(),
+                input: None,
+                action: PendingAction::SsoLogin { profile },
+            });
+        } else {
+            let source = match self.client_options.assumed_role_name() {
+                Some(role) => format!("role {}", role),
+                None => format!("profile {}", self.profile_name),
+            };
+            self.open_prompt(Prompt {
+                title: "Refresh Credentials".to_string(),
+                lines: vec![
+                    format!("The credentials for {} {}.", source, when),
+                    "Reconnect to fetch new ones now?".to_string(),
+                ],
+                input_label: String::new(),
+                input: None,
+                action: PendingAction::Reconnect,
+            });
+        }
+    }
+
+    pub async fn reassume_role(&mut self) -> Result<()> {
+        if self.client_options.assume_role.is_none() {
+            return Ok(());
+        }
+        if self.initialize_aws_client().await.is_ok() {
+            self.refresh_resources().await?;
+        }
+        Ok(())
+    }
+
+    /// Moves the spinner to the frame for the current time, however long since the last tick.
+    pub fn tick_animation(&mut self) {
+        let elapsed = self.animation_start.elapsed().as_millis() / SPINNER_FRAME.as_millis();
+        self.animation_frame = (elapsed % SPINNER_FRAMES.len() as u128) as usize;
+    }
+
+    pub fn get_loading_spinner(&self) -> &str {
+        SPINNER_FRAMES[self.animation_frame]
+    }
+
+    pub fn is_loading(&self) -> bool {
+        matches!(self.loading_state, LoadingState::Loading) || self.detail_loading
+    }
+
+    /// Whether anything is waiting on AWS: a load, a background listing or other calls.
+    pub fn is_busy(&self) -> bool {
+        self.is_loading()
+            || self.is_refreshing()
+            || self.is_loading_dashboard()
+            || self.is_running_prefix_task()
+            || self.in_flight.count() > 0
+    }
+
+    /// Marks or unmarks the selected row for a bulk action and moves to the next row.
+    pub fn toggle_mark(&mut self) {
+        let index = self.selected_index;
+        if index >= self.items.len() || !self.is_item_row(index) {
+            return;
+        }
+        if !self.marked_rows.remove(&index) {
+            self.marked_rows.insert(index);
+        }
+        self.next_item();
+        self.set_status(format!("{} marked. Esc to clear", self.marked_rows.len()));
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked_rows.clear();
+        self.set_status("Cleared marks");
+    }
+
+    // Marked rows in list order, or the selected row when nothing is marked
+    fn target_rows(&self) -> Vec<usize> {
+        if self.marked_rows.is_empty() {
+            return vec![self.selected_index];
+        }
+        let mut rows: Vec<usize> = self.marked_rows.iter().copied().collect();
+        rows.sort_unstable();
+        rows
+    }
+
+    /// Stops the marked EC2 instances (or the selected one), or the selected ECS task.
+    pub fn stop_selected_resource(&mut self) {
+        match self.get_active_service().service_type {
+            ServiceType::EC2 => self.stop_selected_ec2_instances(),
+            ServiceType::ECS => self.stop_selected_ecs_task(),
+            _ => {}
+        }
+    }
+
+    pub fn stop_selected_ec2_instances(&mut self) {
+        let instance_ids: Vec<String> = self
+            .target_rows()
+            .into_iter()
+            .filter_map(|row| match self.ec2_items.get(row) {
+                Some(Ec2Item::Instance { id, .. }) => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+        if instance_ids.is_empty() {
+            self.set_status("Select an instance to stop");
+            return;
+        }
+        if instance_ids.iter().any(|id| self.refuse_other_region(id)) {
+            return;
+        }
+
+        let question = format!("Stop {} instance(s)?", instance_ids.len());
+        self.open_bulk_prompt(
+            "Stop EC2 Instances",
+            question,
+            &instance_ids,
+            PendingAction::StopEc2Instances {
+                instance_ids: instance_ids.clone(),
+            },
+        );
+    }
+
+    pub fn stop_selected_ecs_task(&mut self) {
+        if self.get_active_service().service_type != ServiceType::ECS {
+            return;
+        }
+        let Some(EcsItem::Task { id, definition, .. }) = self.ecs_items.get(self.selected_index)
+        else {
+            self.set_status("Select a task to stop".to_string());
+            return;
+        };
+        let Some(cluster) = self
+            .current_path
+            .as_deref()
+            .and_then(|path| path.split('/').next())
+        else {
+            return;
+        };
+
+        self.open_prompt(Prompt {
+            title: "Stop ECS Task".to_string(),
+            lines: vec![
+                format!("Task: {}", id),
+                format!("Definition: {}", definition),
+            ],
+            input_label: "Reason".to_string(),
+            input: Some("stopped via awsome".to_string()),
+            action: PendingAction::StopEcsTask {
+                cluster: cluster.to_string(),
+                task_id: id.clone(),
+            },
+        });
+    }
+
+    pub fn prompt_ec2_tag_filter(&mut self) {
+        if self.get_active_service().service_type != ServiceType::EC2 {
+            return;
+        }
+        let current = self
+            .ec2_tag_filter
+            .as_ref()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .unwrap_or_default();
+
+        self.open_prompt(Prompt {
+            title: "Filter EC2 by Tag".to_string(),
+            lines: vec!["Only list instances carrying this tag".to_string()],
+            input_label: "Key=Value".to_string(),
+            input: Some(current),
+            action: PendingAction::FilterEc2ByTag,
+        });
+    }
+
+    /// Asks `run_app` to hand the terminal to `aws ssm start-session` on the selected instance,
+    /// once it is running and the CLI and its Session Manager plugin can be found.
+    pub fn start_ssm_session(&mut self) {
+        let Some(Ec2Item::Instance { id, state, .. }) = self.ec2_items.get(self.selected_index)
+        else {
+            if self.get_active_service().service_type == ServiceType::EC2 {
+                self.set_status("Select an instance to connect to");
+            }
+            return;
+        };
+        if !state.eq_ignore_ascii_case("running") {
+            self.set_status(format!(
+                "{} is {}; only running instances take SSM sessions",
+                id,
+                state.to_lowercase()
+            ));
+            return;
+        }
+        let path = std::env::var_os("PATH");
+        if let Some(missing) = ["aws", SSM_PLUGIN]
+            .into_iter()
+            .find(|program| !on_path(program, path.as_deref()))
+        {
+            self.set_status(format!(
+                "Cannot start an SSM session: {} is not on the PATH",
+                missing
+            ));
+            return;
+        }
+        let instance_id = id.clone();
+        self.set_status(format!("Starting SSM session on {}...", instance_id));
+        self.ssm_session_requested = Some(instance_id);
+    }
+
+    /// The `aws` arguments and environment that open a session on `instance_id` as the app's
+    /// identity. `None` with the reason in the status when an assumed role's credentials can't
+    /// be resolved, rather than falling back to the profile's own.
+    pub async fn ssm_session_command(
+        &mut self,
+        instance_id: &str,
+    ) -> Option<(Vec<String>, Vec<(String, String)>)> {
+        let credentials = match (&self.client_options.assume_role, &self.aws_client) {
+            (None, _) => None,
+            (Some(_), Some(client)) => match client.credentials().await {
+                Ok(credentials) => Some(credentials),
+                Err(e) => {
+                    self.set_status(format!(
+                        "Cannot start an SSM session as the assumed role: {}",
+                        e
+                    ));
+                    return None;
+                }
+            },
+            (Some(_), None) => {
+                self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+                return None;
+            }
+        };
+        Some((
+            self.ssm_session_args(instance_id),
+            self.ssm_session_env(credentials.as_ref()),
+        ))
+    }
+
+    /// Arguments for `aws` that open a session on `instance_id` with the app's credentials and
+    /// endpoint. Injected and assumed-role credentials reach the CLI through the environment
+    /// instead of a profile.
+    pub fn ssm_session_args(&self, instance_id: &str) -> Vec<String> {
+        let mut args = vec![
+            "ssm".to_string(),
+            "start-session".to_string(),
+            "--target".to_string(),
+            instance_id.to_string(),
+        ];
+        args.extend(self.cli_identity_args(self.instance_region(instance_id)));
+        args
+    }
+
+    // Arguments that point an `aws` command at the app's identity and endpoint: `--profile`
+    // unless injected or assumed-role credentials stand in for it, `--region` and
+    // `--endpoint-url`
+    fn cli_identity_args(&self, region: Option<&str>) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.env_credentials.is_none() && self.client_options.assume_role.is_none() {
+            args.extend(["--profile".to_string(), self.profile_name.clone()]);
+        }
+        if let Some(region) = region {
+            args.extend(["--region".to_string(), region.to_string()]);
+        }
+        if let Some(url) = &self.client_options.endpoint_url {
+            args.extend(["--endpoint-url".to_string(), url.clone()]);
+        }
+        args
+    }
+
+    /// Environment for the `aws` CLI: the assumed role's credentials, which no profile holds,
+    /// and the FIPS and dual-stack switches the clients were built with.
+    pub fn ssm_session_env(&self, credentials: Option<&Credentials>) -> Vec<(String, String)> {
+        let mut env = Vec::new();
+        if let Some(credentials) = credentials {
+            env.push((
+                "AWS_ACCESS_KEY_ID".to_string(),
+                credentials.access_key_id().to_string(),
+            ));
+            env.push((
+                "AWS_SECRET_ACCESS_KEY".to_string(),
+                credentials.secret_access_key().to_string(),
+            ));
+            if let Some(token) = credentials.session_token() {
+                env.push(("AWS_SESSION_TOKEN".to_string(), token.to_string()));
+            }
+        }
+        if self.client_options.use_fips {
+            env.push(("AWS_USE_FIPS_ENDPOINT".to_string(), "true".to_string()));
+        }
+        if self.client_options.use_dual_stack {
+            env.push(("AWS_USE_DUALSTACK_ENDPOINT".to_string(), "true".to_string()));
+        }
+        env
+    }
+
+    // Region `instance_id` runs in: the one the all-regions listing found it in, otherwise the
+    // client's own
+    fn instance_region(&self, instance_id: &str) -> Option<&str> {
+        self.ec2_instances
+            .iter()
+            .find(|i| i.id == instance_id && !i.region.is_empty())
+            .map(|i| i.region.as_str())
+            .or(self.region.as_deref())
+    }
+
+    // The describe, stop and tag calls only reach the client's region, so an instance the
+    // all-regions listing found elsewhere is refused with the region to switch to
+    fn refuse_other_region(&mut self, instance_id: &str) -> bool {
+        let Some(region) = self
+            .instance_region(instance_id)
+            .filter(|region| Some(*region) != self.region.as_deref())
+        else {
+            return false;
+        };
+        let message = format!(
+            "{} is in {}; switch to that region to act on it",
+            instance_id, region
+        );
+        self.set_status(message);
+        true
+    }
+
+    /// Reports how the session `run_app` ran for `start_ssm_session` ended.
+    pub fn finish_ssm_session(&mut self, instance_id: &str, result: Result<()>) {
+        match result {
+            Ok(()) => self.set_status(format!("SSM session on {} ended", instance_id)),
+            Err(e) => self.set_status(format!("SSM session on {} failed: {}", instance_id, e)),
+        }
+    }
+
+    pub fn prompt_rename_ec2_instance(&mut self) {
+        let Some(Ec2Item::Instance { id, tags, .. }) = self.ec2_items.get(self.selected_index)
+        else {
+            if self.get_active_service().service_type == ServiceType::EC2 {
+                self.set_status("Select an instance to rename");
+            }
+            return;
+        };
+        let current = tags
+            .iter()
+            .find(|(key, _)| key == "Name")
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default();
+        let instance_id = id.clone();
+        if self.refuse_other_region(&instance_id) {
+            return;
+        }
+
+        self.open_prompt(Prompt {
+            title: "Rename EC2 Instance".to_string(),
+            lines: vec![format!("Set the Name tag of {}", instance_id)],
+            input_label: "Name".to_string(),
+            input: Some(current),
+            action: PendingAction::RenameEc2Instance { instance_id },
+        });
+    }
+
+    /// Switches the EC2 list between the current region and every region enabled for the
+    /// account. The latter costs a call per region, so it is off until asked for.
+    pub async fn toggle_ec2_all_regions(&mut self) -> Result<()> {
+        if self.get_active_service().service_type != ServiceType::EC2 {
+            return Ok(());
+        }
+        self.ec2_all_regions = !self.ec2_all_regions;
+        self.refresh_resources().await?;
+        self.set_status_after_refresh(if self.ec2_all_regions {
+            "Listing instances in every enabled region"
+        } else {
+            "Listing instances in the current region"
+        });
+        Ok(())
+    }
+
+    /// Adds the Items and Size columns for the folders inside a bucket. Counting takes a call
+    /// per folder, made on the next load.
+    pub async fn toggle_s3_folder_stats(&mut self) -> Result<()> {
+        if self.s3_location().is_none() {
+            return Ok(());
+        }
+        self.s3_folder_stats = !self.s3_folder_stats;
+        self.refresh_resources().await?;
+        self.set_status_after_refresh(if self.s3_folder_stats {
+            "Showing what each folder holds"
+        } else {
+            "Showing folders without sizes"
+        });
+        Ok(())
+    }
+
+    /// Asks for the tag key to group the bucket list by. Grouping reads every bucket's tags,
+    /// so it only starts once a key is given; an empty key goes back to the plain list.
+    pub fn prompt_s3_group_by_tag(&mut self) {
+        if self.get_active_service().service_type != ServiceType::S3 || self.current_path.is_some()
+        {
+            return;
+        }
+        self.open_prompt(Prompt {
+            title: "Group Buckets by Tag".to_string(),
+            lines: vec![
+                "Group the buckets by the value of this tag".to_string(),
+                "Each bucket's tags are read once, then cached. Empty to stop grouping".to_string(),
+            ],
+            input_label: "Tag key".to_string(),
+            input: Some(self.s3_group_by.clone().unwrap_or_default()),
+            action: PendingAction::GroupS3BucketsByTag,
+        });
+    }
+
+    pub async fn clear_ec2_tag_filter(&mut self) -> Result<()> {
+        if self.ec2_tag_filter.take().is_some() {
+            self.refresh_resources().await?;
+            self.set_status_after_refresh("Cleared EC2 tag filter");
+        }
+        Ok(())
+    }
+
+    /// Cycles the S3 object filter: all → STANDARD → GLACIER → DEEP_ARCHIVE → all.
+    pub async fn cycle_s3_storage_class_filter(&mut self) -> Result<()> {
+        if self.get_active_service().service_type != ServiceType::S3 || self.current_path.is_none()
+        {
+            return Ok(());
+        }
+        self.s3_storage_class_filter = next_storage_class_filter(&self.s3_storage_class_filter);
+        self.refresh_resources().await?;
+        match &self.s3_storage_class_filter {
+            Some(class) => self.set_status_after_refresh(format!("Showing {} objects", class)),
+            None => self.set_status_after_refresh("Showing objects of every storage class"),
+        }
+        Ok(())
+    }
+
+    // Bucket and full key of the selected S3 object, when browsing inside a bucket
+    fn selected_s3_object(&self) -> Option<(String, String)> {
+        let S3Item::Object(name) = self.s3_items.get(self.selected_index)? else {
+            return None;
+        };
+        let (bucket, prefix) = self.s3_location()?;
+        Some((bucket.to_string(), format!("{}{}", prefix, name)))
+    }
+
+    // Bucket, full key and version id of the selected row of a version listing
+    fn selected_s3_version(&self) -> Option<(String, String, String)> {
+        let S3Item::Version {
+            name, version_id, ..
+        } = self.s3_items.get(self.selected_index)?
+        else {
+            return None;
+        };
+        let (bucket, prefix) = self.s3_location()?;
+        Some((
+            bucket.to_string(),
+            format!("{}{}", prefix, name),
+            version_id.clone(),
+        ))
+    }
+
+    /// Switches the bucket between its current objects and every version and delete marker.
+    /// Only offered once the bucket's versioning has been enabled, even if since suspended.
+    pub async fn toggle_s3_versions(&mut self) -> Result<()> {
+        let Some((bucket, _)) = self.s3_location() else {
+            if self.get_active_service().service_type == ServiceType::S3 {
+                self.set_status("Open a bucket to list its versions");
+            }
+            return Ok(());
+        };
+        let bucket = bucket.to_string();
+        if self.s3_show_versions {
+            self.s3_show_versions = false;
+            self.refresh_resources().await?;
+            self.set_status_after_refresh("Showing current objects");
+            return Ok(());
+        }
+
+        let Some(client) = self.aws_client.clone() else {
+            self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+            return Ok(());
+        };
+        let status = with_timeout(
+            self.request_timeout(),
+            client.get_s3_versioning_status(&bucket),
+        )
+        .await;
+        match status {
+            Ok(Some(status)) => {
+                self.s3_show_versions = true;
+                self.refresh_resources().await?;
+                self.set_status_after_refresh(format!(
+                    "Showing versions and delete markers (versioning {})",
+                    status
+                ));
+            }
+            Ok(None) => self.set_status(format!(
+                "Versioning has never been enabled on s3://{}",
+                bucket
+            )),
+            Err(e) => self.set_status(format!("Error checking versioning: {}", e)),
+        }
+        Ok(())
+    }
+
+    /// Asks where to save the selected object, or the selected version of it.
+    pub fn prompt_s3_download(&mut self) {
+        let selected = match self.selected_s3_version() {
+            Some(_)
+                if matches!(
+                    self.s3_items.get(self.selected_index),
+                    Some(S3Item::Version {
+                        delete_marker: true,
+                        ..
+                    })
+                ) =>
+            {
+                self.set_status("A delete marker has no content to download");
+                return;
+            }
+            Some((bucket, key, version_id)) => Some((bucket, key, Some(version_id))),
+            None => self
+                .selected_s3_object()
+                .map(|(bucket, key)| (bucket, key, None)),
+        };
+        let Some((bucket, key, version_id)) = selected else {
+            if self.s3_location().is_some() {
+                self.set_status("Select an object to download");
+            }
+            return;
+        };
+        let file_name = key.rsplit('/').next().unwrap_or(&key).to_string();
+        let mut lines = vec![format!("Object: s3://{}/{}", bucket, key)];
+        if let Some(version_id) = &version_id {
+            lines.push(format!("Version: {}", version_id));
+        }
+        self.open_prompt(Prompt {
+            title: "Download Object".to_string(),
+            lines,
+            input_label: "Save to".to_string(),
+            input: Some(file_name),
+            action: PendingAction::DownloadS3Object {
+                bucket,
+                key,
+                version_id,
+            },
+        });
+    }
+
+    // Runs the download in place of a refresh for its progress and Esc to cancel. The listing
+    // it leaves behind is the one already shown, so nothing is fetched again
+    fn start_s3_download(
+        &mut self,
+        client: AwsClient,
+        bucket: String,
+        key: String,
+        version_id: Option<String>,
+        path: PathBuf,
+    ) {
+        let Some((_, listing)) = self.last_listing.clone() else {
+            return;
+        };
+        self.loading_state = LoadingState::Loading;
+        self.items = vec![format!("Downloading s3://{}/{}...", bucket, key)];
+        self.set_status(format!(
+            "Downloading s3://{}/{} to {}... Esc to cancel",
+            bucket,
+            key,
+            path.display()
+        ));
+
+        let (progress, progress_rx) = Progress::channel();
+        let after_status = format!("Downloaded s3://{}/{} to {}", bucket, key, path.display());
+        let download = async move {
+            client
+                .download_s3_object(&bucket, &key, version_id.as_deref(), &path, &progress)
+                .await
+                .with_context(|| format!("Failed to download s3://{}/{}", bucket, key))?;
+            Ok(listing)
+        };
+        self.refresh = Some(RefreshTask {
+            handle: tokio::spawn(self.in_flight.track(download)),
+            progress: progress_rx,
+            after_status: Some(after_status),
+            reselect: self.selected_row(),
+        });
+    }
+
+    // Bucket and full prefix of the selected S3 folder
+    fn selected_s3_folder(&self) -> Option<(String, String)> {
+        let S3Item::Folder(name) = self.s3_items.get(self.selected_index)? else {
+            return None;
+        };
+        let (bucket, prefix) = self.s3_location()?;
+        Some((bucket.to_string(), format!("{}{}", prefix, name)))
+    }
+
+    /// Asks for a prefix to open directly inside the current bucket. A prefix not ending in `/`
+    /// lists only the keys and folders that start with it.
+    pub fn prompt_s3_prefix_jump(&mut self) {
+        let Some((bucket, prefix)) = self.s3_location() else {
+            if self.get_active_service().service_type == ServiceType::S3 {
+                self.set_status("Open a bucket to jump to a prefix");
+            }
+            return;
+        };
+        let (bucket, prefix) = (bucket.to_string(), prefix.to_string());
+        self.open_prompt(Prompt {
+            title: "Jump to Prefix".to_string(),
+            lines: vec![
+                format!("Bucket: s3://{}/", bucket),
+                "End with / to open a folder, or leave it partial to filter".to_string(),
+            ],
+            input_label: "Prefix".to_string(),
+            input: Some(prefix),
+            action: PendingAction::JumpToS3Prefix { bucket },
+        });
+    }
+
+    /// Asks for a local file to upload into the bucket and prefix being browsed.
+    pub fn prompt_s3_upload(&mut self) {
+        let Some((bucket, prefix)) = self.s3_location() else {
+            if self.get_active_service().service_type == ServiceType::S3 {
+                self.set_status("Open a bucket to upload a file");
+            }
+            return;
+        };
+        let (bucket, prefix) = (bucket.to_string(), prefix.to_string());
+        self.open_prompt(Prompt {
+            title: "Upload File".to_string(),
+            lines: vec![format!("Destination: s3://{}/{}", bucket, prefix)],
+            input_label: "Local file".to_string(),
+            input: Some(String::new()),
+            action: PendingAction::ChooseS3UploadFile { bucket, prefix },
+        });
+    }
+
+    // Runs the upload in place of a refresh so it reports progress, can be cancelled with Esc
+    // and lists the prefix again once the object is in
+    fn start_s3_upload(&mut self, client: AwsClient, bucket: String, key: String, path: PathBuf) {
+        self.loading_state = LoadingState::Loading;
+        self.items = vec![format!("Uploading {}...", path.display())];
+        self.set_status(format!(
+            "Uploading {} to s3://{}/{}... Esc to cancel",
+            path.display(),
+            bucket,
+            key
+        ));
+
+        let (progress, progress_rx) = Progress::channel();
+        let after_status = format!("Uploaded {} to s3://{}/{}", path.display(), bucket, key);
+        let listing = fetch_listing(
+            client.clone(),
+            ServiceType::S3,
+            self.current_path.clone(),
+            Ec2Scope::default(),
+            self.paging(),
+            S3Scope {
+                versions: self.s3_show_versions,
+                group_by: None,
+                folder_stats: self.s3_folder_stats,
+            },
+            progress.clone(),
+        );
+        // Uploads can take longer than the request timeout; only the listing gets one
+        let timeout = self.request_timeout();
+        let upload = async move {
+            client
+                .upload_s3_object(&bucket, &key, &path, &progress)
+                .await
+                .with_context(|| format!("Failed to upload {}", path.display()))?;
+            with_timeout(timeout, listing).await
+        };
+        self.refresh = Some(RefreshTask {
+            handle: tokio::spawn(self.in_flight.track(upload)),
+            progress: progress_rx,
+            after_status: Some(after_status),
+            reselect: self.selected_row(),
+        });
+    }
+
+    // Bucket and prefix being browsed
+    fn s3_location(&self) -> Option<(&str, &str)> {
+        if self.get_active_service().service_type != ServiceType::S3 {
+            return None;
+        }
+        // current_path is always "bucket/prefix", with an empty prefix at the bucket root
+        self.current_path.as_deref()?.split_once('/')
+    }
+
+    /// The `aws` CLI command that shows the selected resource, or the current listing when
+    /// nothing specific is selected.
+    pub fn selected_cli_command(&self) -> Option<String> {
+        let index = self.selected_index;
+        let args: Vec<String> = match self.get_active_service().service_type {
+            ServiceType::EC2 => match self.ec2_items.get(index)? {
+                Ec2Item::Instance { id, .. } => {
+                    vec![
+                        "ec2".into(),
+                        "describe-instances".into(),
+                        "--instance-ids".into(),
+                        id.clone(),
+                    ]
+                }
+                _ => return None,
+            },
+            ServiceType::S3 => {
+                if let Some((bucket, key, version_id)) = self.selected_s3_version() {
+                    vec![
+                        "s3api".into(),
+                        "head-object".into(),
+                        "--bucket".into(),
+                        bucket,
+                        "--key".into(),
+                        key,
+                        "--version-id".into(),
+                        version_id,
+                    ]
+                } else if let Some((bucket, key)) = self.selected_s3_object() {
+                    vec![
+                        "s3api".into(),
+                        "head-object".into(),
+                        "--bucket".into(),
+                        bucket,
+                        "--key".into(),
+                        key,
+                    ]
+                } else if let Some((bucket, prefix)) = self.selected_s3_folder() {
+                    vec![
+                        "s3".into(),
+                        "ls".into(),
+                        format!("s3://{}/{}", bucket, prefix),
+                    ]
+                } else if let Some((bucket, prefix)) = self.s3_location() {
+                    vec![
+                        "s3".into(),
+                        "ls".into(),
+                        format!("s3://{}/{}", bucket, prefix),
+                    ]
+                } else if let Some(S3Item::Bucket(bucket)) = self.s3_items.get(index) {
+                    vec!["s3".into(), "ls".into(), format!("s3://{}/", bucket)]
+                } else {
+                    vec!["s3".into(), "ls".into()]
+                }
+            }
+            ServiceType::IAM => match self.iam_items.get(index)? {
+                // The root user's report row, `<root_account>`, has no user to look up
+                IamItem::ReportEntry { user, .. } if user.starts_with('<') => {
+                    vec!["iam".into(), "get-credential-report".into()]
+                }
+                IamItem::User(name) | IamItem::ReportEntry { user: name, .. } => {
+                    vec![
+                        "iam".into(),
+                        "get-user".into(),
+                        "--user-name".into(),
+                        name.clone(),
+                    ]
+                }
+                _ => return None,
+            },
+            ServiceType::CloudWatch => match self.cloudwatch_items.get(index)? {
+                CloudwatchItem::Alarm { name, .. } => {
+                    vec![
+                        "cloudwatch".into(),
+                        "describe-alarms".into(),
+                        "--alarm-names".into(),
+                        name.clone(),
+                    ]
+                }
+                _ => return None,
+            },
+            ServiceType::DynamoDB => match self.dynamodb_items.get(index)? {
+                DynamoDbItem::Table { name, .. } => {
+                    vec![
+                        "dynamodb".into(),
+                        "describe-table".into(),
+                        "--table-name".into(),
+                        name.clone(),
+                    ]
+                }
+                _ => return None,
+            },
+            ServiceType::ECS => {
+                let cluster = self
+                    .current_path
+                    .as_deref()
+                    .map(|path| path.split('/').next().unwrap_or(path).to_string());
+                match (self.ecs_items.get(index)?, cluster) {
+                    (EcsItem::Cluster(name), _) => {
+                        vec![
+                            "ecs".into(),
+                            "describe-clusters".into(),
+                            "--clusters".into(),
+                            name.clone(),
+                        ]
+                    }
+                    (EcsItem::Service(name), Some(cluster)) => vec![
+                        "ecs".into(),
+                        "describe-services".into(),
+                        "--cluster".into(),
+                        cluster,
+                        "--services".into(),
+                        name.clone(),
+                    ],
+                    (EcsItem::Task { id, .. }, Some(cluster)) => vec![
+                        "ecs".into(),
+                        "describe-tasks".into(),
+                        "--cluster".into(),
+                        cluster,
+                        "--tasks".into(),
+                        id.clone(),
+                    ],
+                    _ => return None,
+                }
+            }
+            ServiceType::MWAA => match self.mwaa_items.get(index)? {
+                MwaaItem::Environment(name) => {
+                    vec![
+                        "mwaa".into(),
+                        "get-environment".into(),
+                        "--name".into(),
+                        name.clone(),
+                    ]
+                }
+                _ => return None,
+            },
+            ServiceType::Lambda => {
+                vec![
+                    "lambda".into(),
+                    "get-function".into(),
+                    "--function-name".into(),
+                    self.lambda_function_at(index)?,
+                ]
+            }
+        };
+        let region = match self.ec2_items.get(index) {
+            Some(Ec2Item::Instance { id, .. })
+                if self.get_active_service().service_type == ServiceType::EC2 =>
+            {
+                self.instance_region(id)
+            }
+            _ => self.region.as_deref(),
+        };
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        Some(aws_cli_command(&args, &self.cli_identity_args(region)))
+    }
+
+    // Function name on `row`, qualified with the version on a version row, e.g. "api:3"
+    fn lambda_function_at(&self, row: usize) -> Option<String> {
+        match self.lambda_items.get(row)? {
+            LambdaItem::Function(name) => Some(name.clone()),
+            LambdaItem::Version(version) => {
+                Some(format!("{}:{}", self.current_path.as_deref()?, version))
+            }
+            _ => None,
+        }
+    }
+
+    // Name the row is listed under: an instance's Name tag (its id without one), the bucket,
+    // folder or object name relative to the prefix, and so on
+    fn row_name(&self, row: usize) -> Option<&str> {
+        let name = match self.get_active_service().service_type {
+            ServiceType::EC2 => match self.ec2_items.get(row)? {
+                Ec2Item::Instance { id, tags, .. } => tags
+                    .iter()
+                    .find(|(key, value)| key == "Name" && !value.is_empty())
+                    .map_or(id, |(_, value)| value),
+                _ => return None,
+            },
+            ServiceType::S3 => match self.s3_items.get(row)? {
+                S3Item::Bucket(name)
+                | S3Item::Folder(name)
+                | S3Item::Object(name)
+                | S3Item::Version { name, .. } => name,
+                _ => return None,
+            },
+            ServiceType::IAM => match self.iam_items.get(row)? {
+                IamItem::User(name) | IamItem::ReportEntry { user: name, .. } => name,
+                _ => return None,
+            },
+            ServiceType::CloudWatch => match self.cloudwatch_items.get(row)? {
+                CloudwatchItem::Alarm { name, .. } => name,
+                _ => return None,
+            },
+            ServiceType::DynamoDB => match self.dynamodb_items.get(row)? {
+                DynamoDbItem::Table { name, .. } => name,
+                _ => return None,
+            },
+            ServiceType::ECS => match self.ecs_items.get(row)? {
+                EcsItem::Cluster(name) | EcsItem::Service(name) => name,
+                EcsItem::Task { id, .. } => id,
+                _ => return None,
+            },
+            ServiceType::MWAA => match self.mwaa_items.get(row)? {
+                MwaaItem::Environment(name) => name,
+                _ => return None,
+            },
+            ServiceType::Lambda => match self.lambda_items.get(row)? {
+                LambdaItem::Function(name) | LambdaItem::Version(name) => name,
+                _ => return None,
+            },
+        };
+        Some(name)
+    }
+
+    /// Identifier of the resource on `row`: instance id, table name, `s3://` URI and so on.
+    pub fn row_id(&self, row: usize) -> Option<String> {
+        match self.get_active_service().service_type {
+            ServiceType::EC2 => match self.ec2_items.get(row)? {
+                Ec2Item::Instance { id, .. } => Some(id.clone()),
+                _ => None,
+            },
+            ServiceType::S3 => match (self.s3_items.get(row)?, self.s3_location()) {
+                (S3Item::Bucket(name), _) => Some(name.clone()),
+                (S3Item::Folder(name) | S3Item::Object(name), Some((bucket, prefix))) => {
+                    Some(format!("s3://{}/{}{}", bucket, prefix, name))
+                }
+                (
+                    S3Item::Version {
+                        name, version_id, ..
+                    },
+                    Some((bucket, prefix)),
+                ) => Some(s3_version_uri(
+                    bucket,
+                    &format!("{}{}", prefix, name),
+                    version_id,
+                )),
+                _ => None,
+            },
+            ServiceType::IAM => match self.iam_items.get(row)? {
+                IamItem::User(name) | IamItem::ReportEntry { user: name, .. } => Some(name.clone()),
+                _ => None,
+            },
+            ServiceType::CloudWatch => match self.cloudwatch_items.get(row)? {
+                CloudwatchItem::Alarm { name, .. } => Some(name.clone()),
+                _ => None,
+            },
+            ServiceType::DynamoDB => match self.dynamodb_items.get(row)? {
+                DynamoDbItem::Table { name, .. } => Some(name.clone()),
+                _ => None,
+            },
+            ServiceType::ECS => match self.ecs_items.get(row)? {
+                EcsItem::Cluster(name) | EcsItem::Service(name) => Some(name.clone()),
+                EcsItem::Task { id, .. } => Some(id.clone()),
+                _ => None,
+            },
+            ServiceType::MWAA => match self.mwaa_items.get(row)? {
+                MwaaItem::Environment(name) => Some(name.clone()),
+                _ => None,
+            },
+            ServiceType::Lambda => self.lambda_function_at(row),
+        }
+    }
+
+    fn format_ec2_instances(&mut self) {
+        let (items, ec2_items) = Ec2Service::format_instance_list(
+            &self.ec2_instances,
+            &self.ec2_visible_columns(),
+            self.config.time_format,
+            self.name_width(),
+            self.list_width(),
+        );
+        self.items = items;
+        self.ec2_items = ec2_items;
+    }
+
+    fn format_cloudwatch_alarms(&mut self) {
+        let (items, cloudwatch_items) = CloudwatchService::format_alarm_list(
+            &self.cloudwatch_alarms,
+            self.alarm_state_filter.as_deref(),
+            self.name_width(),
+            self.list_width(),
+        );
+        self.items = items;
+        self.cloudwatch_items = cloudwatch_items;
+    }
+
+    /// Cycles the alarm list between all alarms, ALARM only and INSUFFICIENT_DATA only,
+    /// without refetching.
+    pub fn cycle_alarm_state_filter(&mut self) {
+        if self.get_active_service().service_type != ServiceType::CloudWatch
+            || self.loading_state != LoadingState::Loaded
+        {
+            return;
+        }
+        self.alarm_state_filter =
+            CloudwatchService::next_state_filter(self.alarm_state_filter.as_deref());
+        self.marked_rows.clear();
+        self.format_cloudwatch_alarms();
+        self.selected_index = if self.items.len() > 2 { 2 } else { 0 };
+        match &self.alarm_state_filter {
+            Some(state) => self.set_status(format!("Showing alarms in {} state", state)),
+            None => self.set_status("Showing alarms in every state"),
+        }
+    }
+
+    /// "Alarms: 2 ALARM, 40 OK" for the CloudWatch title.
+    pub fn alarm_state_summary(&self) -> Option<String> {
+        (self.get_active_service().service_type == ServiceType::CloudWatch
+            && self.loading_state == LoadingState::Loaded
+            && !self.cloudwatch_alarms.is_empty())
+        .then(|| CloudwatchService::summarize_states(&self.cloudwatch_alarms))
+    }
+
+    /// Switches the EC2 table between the status and launch columns without refetching.
+    pub fn toggle_ec2_columns(&mut self) {
+        if self.get_active_service().service_type != ServiceType::EC2
+            || self.loading_state != LoadingState::Loaded
+        {
+            return;
+        }
+        self.ec2_columns = self.ec2_columns.toggled();
+        // A preset replaces the columns picked one by one
+        self.config.columns.remove("ec2");
+        self.format_ec2_instances();
+        self.set_status(match self.ec2_columns {
+            Ec2Columns::Status => "Columns: state, type, public IP",
+            Ec2Columns::Launch => "Columns: AMI, key pair, launch time, uptime, AZ",
+        });
+    }
+
+    /// Switches the IAM tab between the user list and the credential report. IAM builds the
+    /// report on the first request, which the spinner covers, and reuses it for four hours.
+    pub async fn toggle_credential_report(&mut self) -> Result<()> {
+        if self.get_active_service().service_type != ServiceType::IAM {
+            return Ok(());
+        }
+        self.current_path = match self.current_path {
+            Some(_) => None,
+            None => Some(CREDENTIAL_REPORT.to_string()),
+        };
+        self.refresh_resources().await
+    }
+
+    /// Swaps the ECS and IAM lists between short names and full ARNs, laying the rows out
+    /// again without refetching.
+    pub fn toggle_full_arns(&mut self) {
+        if !matches!(
+            self.get_active_service().service_type,
+            ServiceType::ECS | ServiceType::IAM
+        ) {
+            return;
+        }
+        self.show_arns = !self.show_arns;
+        self.relayout_listing();
+        self.set_status(if self.show_arns {
+            "Showing full ARNs"
+        } else {
+            "Showing short names"
+        });
+    }
+
+    /// How long ago the active service last listed successfully, e.g. "updated 2m ago".
+    pub fn refreshed_label(&self) -> Option<String> {
+        let at = self.last_refreshed.get(&self.active_service)?;
+        Some(format!("updated {}", format_age(at.elapsed())))
+    }
+
+    /// Ids of every row the search leaves visible, in list order.
+    pub fn visible_ids(&self) -> Vec<String> {
+        self.visible_rows()
+            .into_iter()
+            .filter(|&row| self.is_item_row(row))
+            .filter_map(|row| self.row_id(row))
+            .collect()
+    }
+
+    /// Copies the ids of every visible row, one per line.
+    pub fn copy_visible_ids(&mut self) {
+        let ids = self.visible_ids();
+        self.copy_ids(&ids);
+    }
+
+    fn copy_ids(&mut self, ids: &[String]) {
+        if ids.is_empty() {
+            self.set_status("No ids to copy");
+            return;
+        }
+        match crate::clipboard::copy(&ids.join("\n")) {
+            Ok(via) => self.set_status(format!("Copied {} id(s) ({})", ids.len(), via)),
+            Err(e) => self.set_status(format!("Error copying to clipboard: {}", e)),
+        }
+    }
+
+    /// Copies the CLI command for the selected row, or the ids of the marked rows one per line.
+    pub fn copy_cli_command(&mut self) {
+        if !self.marked_rows.is_empty() {
+            let ids: Vec<String> = self
+                .target_rows()
+                .into_iter()
+                .filter_map(|row| self.row_id(row))
+                .collect();
+            self.copy_ids(&ids);
+            return;
+        }
+        let Some(command) = self.selected_cli_command() else {
+            self.set_status("Nothing selected to build a CLI command for");
+            return;
+        };
+        match crate::clipboard::copy(&command) {
+            Ok(via) => self.set_status(format!("Copied ({}): {}", via, command)),
+            Err(e) => self.set_status(format!("Error copying to clipboard: {}", e)),
+        }
+    }
+
+    // Every action prompt opens here, so read-only mode covers all of them
+    fn open_prompt(&mut self, mut prompt: Prompt) {
+        if !self.writable() && prompt.action.is_mutating() {
+            self.set_status(format!("Read-only mode: {} is disabled", prompt.title));
+            return;
+        }
+        if self.dry_run && prompt.action.is_mutating() {
+            prompt.lines.insert(
+                0,
+                "Dry run: confirming shows the AWS call instead of making it".to_string(),
+            );
+        }
+        if prompt.action.is_mutating()
+            && let Some(account) = self.account.as_ref().filter(|_| self.is_prod_account())
+        {
+            prompt.lines.insert(
+                0,
+                format!("⚠ PRODUCTION account {}", account_label(account)),
+            );
+        }
+        self.prompt = Some(prompt);
+        self.prompt_scroll = 0;
+    }
+
+    // Confirmation for an action on `ids`. With `confirm_bulk` on, more than one id lists them
+    // all and takes the typed count instead of y, so a stray keypress can't fire it
+    fn open_bulk_prompt(
+        &mut self,
+        title: &str,
+        question: String,
+        ids: &[String],
+        action: PendingAction,
+    ) {
+        let mut lines = vec![question];
+        if ids.len() > 1 && self.config.confirm_bulk {
+            lines.extend(ids.iter().map(|id| format!("  {}", id)));
+            lines.push(format!("Type {} to confirm", ids.len()));
+            self.open_prompt(Prompt {
+                title: title.to_string(),
+                lines,
+                input_label: "Count".to_string(),
+                input: Some(String::new()),
+                action,
+            });
+            return;
+        }
+        lines.extend(preview(ids));
+        self.open_prompt(Prompt {
+            title: title.to_string(),
+            lines,
+            input_label: String::new(),
+            input: None,
+            action,
+        });
+    }
+
+    pub fn prompt_scroll_down(&mut self) {
+        let rows = self.prompt.as_ref().map_or(0, |p| p.lines.len());
+        if self.prompt_scroll < rows.saturating_sub(1) {
+            self.prompt_scroll += 1;
+        }
+    }
+
+    pub fn prompt_scroll_up(&mut self) {
+        self.prompt_scroll = self.prompt_scroll.saturating_sub(1);
+    }
+
+    /// Whether the connected account is listed in `prod_accounts`.
+    pub fn is_prod_account(&self) -> bool {
+        self.account
+            .as_ref()
+            .is_some_and(|account| self.config.prod_accounts.contains(&account.account_id))
+    }
+
+    /// Asks to delete the selected S3 object/folder or DynamoDB table.
+    pub async fn delete_selected_resource(&mut self) -> Result<()> {
+        match self.get_active_service().service_type {
+            ServiceType::S3 => self.delete_selected_s3_item().await,
+            ServiceType::DynamoDB => {
+                self.delete_selected_dynamodb_table();
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Deleting a table is irreversible, so the user has to type its name rather than press y
+    pub fn delete_selected_dynamodb_table(&mut self) {
+        let Some(DynamoDbItem::Table { name: table, .. }) =
+            self.dynamodb_items.get(self.selected_index)
+        else {
+            self.set_status("Select a table to delete");
+            return;
+        };
+        let table = table.clone();
+        self.open_prompt(Prompt {
+            title: "Delete DynamoDB Table".to_string(),
+            lines: vec![
+                format!("Permanently delete table {} and all of its items?", table),
+                "Type the table name to confirm".to_string(),
+            ],
+            input_label: "Table name".to_string(),
+            input: Some(String::new()),
+            action: PendingAction::DeleteDynamoDbTable { table },
+        });
+    }
+
+    /// Asks for a new DynamoDB table, or a new S3 bucket at the bucket list.
+    pub fn prompt_create_resource(&mut self) {
+        match self.get_active_service().service_type {
+            ServiceType::DynamoDB => self.prompt_create_dynamodb_table(),
+            ServiceType::S3 if self.current_path.is_none() => self.prompt_create_s3_bucket(),
+            ServiceType::S3 => {
+                self.set_status("Go back to the bucket list to create a bucket, or U to upload")
+            }
+            _ => {}
+        }
+    }
+
+    pub fn prompt_create_s3_bucket(&mut self) {
+        let region = self.region.clone().unwrap_or_else(|| "default".to_string());
+        self.open_prompt(Prompt {
+            title: "Create S3 Bucket".to_string(),
+            lines: vec![format!("The bucket is created in region {}", region)],
+            input_label: "Bucket name".to_string(),
+            input: Some(String::new()),
+            action: PendingAction::CreateS3Bucket,
+        });
+    }
+
+    pub fn prompt_create_dynamodb_table(&mut self) {
+        if self.get_active_service().service_type != ServiceType::DynamoDB {
+            return;
+        }
+        self.open_prompt(Prompt {
+            title: "Create DynamoDB Table".to_string(),
+            lines: vec![
+                "Enter: <table name> <partition key>[:S|N|B]".to_string(),
+                "The table uses on-demand billing; the key type defaults to S".to_string(),
+            ],
+            input_label: "Table".to_string(),
+            input: Some(String::new()),
+            action: PendingAction::CreateDynamoDbTable,
+        });
+    }
+
+    pub async fn delete_selected_s3_item(&mut self) -> Result<()> {
+        if !self.marked_rows.is_empty() {
+            self.delete_marked_s3_objects();
+            return Ok(());
+        }
+        if let Some(S3Item::Bucket(bucket)) = self.s3_items.get(self.selected_index)
+            && self.current_path.is_none()
+        {
+            let bucket = bucket.clone();
+            self.open_prompt(Prompt {
+                title: "Delete S3 Bucket".to_string(),
+                lines: vec![
+                    format!("Delete bucket {}?", bucket),
+                    "Only empty buckets can be deleted".to_string(),
+                ],
+                input_label: String::new(),
+                input: None,
+                action: PendingAction::DeleteS3Bucket { bucket },
+            });
+            return Ok(());
+        }
+        if let Some((bucket, key)) = self.selected_s3_object() {
+            self.open_prompt(Prompt {
+                title: "Delete S3 Object".to_string(),
+                lines: vec![
+                    "Permanently delete this object?".to_string(),
+                    format!("s3://{}/{}", bucket, key),
+                ],
+                input_label: String::new(),
+                input: None,
+                action: PendingAction::DeleteS3Object { bucket, key },
+            });
+            return Ok(());
+        }
+
+        let Some((bucket, prefix)) = self.selected_s3_folder() else {
+            if self.get_active_service().service_type == ServiceType::S3 {
+                self.set_status("Select an object or folder to delete");
+            }
+            return Ok(());
+        };
+        let client = match &self.aws_client {
+            Some(c) => c.clone(),
+            None => {
+                self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+                return Ok(());
+            }
+        };
+
+        // Count first so the confirmation states exactly what will go
+        self.set_status(format!(
+            "Counting objects under s3://{}/{}... (Esc to cancel)",
+            bucket, prefix
+        ));
+        let (progress, updates) = Progress::channel();
+        let count = async move {
+            let count = client
+                .list_s3_keys(&bucket, &prefix, &progress)
+                .await
+                .with_context(|| format!("Error listing s3://{}/{}", bucket, prefix))?
+                .len();
+            Ok(PrefixResult::Counted {
+                bucket,
+                prefix,
+                count,
+            })
+        };
+        self.start_prefix_task(count, updates, false);
+        Ok(())
+    }
+
+    // Runs `work` on a prefix in the background; `poll_prefix_task` acts on its result
+    fn start_prefix_task(
+        &mut self,
+        work: impl Future<Output = Result<PrefixResult>> + Send + 'static,
+        progress: UnboundedReceiver<String>,
+        deleting: bool,
+    ) {
+        self.prefix_task = Some(PrefixTask {
+            handle: tokio::spawn(self.in_flight.track(work)),
+            progress,
+            deleting,
+        });
+    }
+
+    pub fn is_running_prefix_task(&self) -> bool {
+        self.prefix_task.is_some()
+    }
+
+    /// Drains the progress of a prefix count or delete into the status bar and acts on its
+    /// result once it finishes: the count opens the confirmation, a delete reloads the list.
+    /// Called on every tick of the event loop while one runs.
+    pub async fn poll_prefix_task(&mut self) -> Result<()> {
+        let Some(task) = &mut self.prefix_task else {
+            return Ok(());
+        };
+        // Progress is transient, so it skips the event log
+        while let Ok(message) = task.progress.try_recv() {
+            self.status_message = message;
+        }
+        if !task.handle.is_finished() {
+            return Ok(());
+        }
+        let result = match (&mut task.handle).await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow::anyhow!("S3 task failed: {}", e)),
+        };
+        self.prefix_task = None;
+        match result {
+            Ok(PrefixResult::Counted {
+                bucket,
+                prefix,
+                count,
+            }) => self.open_prompt(Prompt {
+                title: "Delete S3 Prefix".to_string(),
+                lines: vec![
+                    format!(
+                        "Permanently delete {} object(s) under s3://{}/{}?",
+                        count, bucket, prefix
+                    ),
+                    format!("Type {} to confirm", DELETE_CONFIRMATION),
+                ],
+                input_label: "Confirm".to_string(),
+                input: Some(String::new()),
+                action: PendingAction::DeleteS3Prefix { bucket, prefix },
+            }),
+            Ok(PrefixResult::WouldDelete {
+                bucket,
+                prefix,
+                count,
+            }) => {
+                let target = format!("s3://{}/{}", bucket, prefix);
+                self.report_dry_run(
+                    format!("Would delete {} object(s) under {}", count, target),
+                    &["s3", "rm", &target, "--recursive"],
+                );
+            }
+            Ok(PrefixResult::Deleted {
+                bucket,
+                prefix,
+                deleted,
+            }) => {
+                self.refresh_resources().await?;
+                self.set_status_after_refresh(format!(
+                    "Deleted {} object(s) under s3://{}/{}",
+                    deleted, bucket, prefix
+                ));
+            }
+            Err(e) => self.set_status(format!("{:#}", e)),
+        }
+        Ok(())
+    }
+
+    /// Stops the prefix count or delete still running. Returns whether there was one.
+    pub fn cancel_prefix_task(&mut self) -> bool {
+        let Some(task) = self.prefix_task.take() else {
+            return false;
+        };
+        self.set_status(if task.deleting {
+            "Stopped deleting; objects already deleted are gone. Press r to reload"
+        } else {
+            "Cancelled"
+        });
+        true
+    }
+
+    // Folders would need a recursive listing each, so bulk deletes take objects only
+    fn delete_marked_s3_objects(&mut self) {
+        let Some((bucket, prefix)) = self.s3_location() else {
+            return;
+        };
+        let bucket = bucket.to_string();
+        let keys: Vec<String> = self
+            .target_rows()
+            .into_iter()
+            .filter_map(|row| match self.s3_items.get(row) {
+                Some(S3Item::Object(name)) => Some(format!("{}{}", prefix, name)),
+                _ => None,
+            })
+            .collect();
+        if keys.is_empty() {
+            self.set_status("Mark objects to delete; folders can't be bulk deleted");
+            return;
+        }
+
+        let question = format!(
+            "Permanently delete {} object(s) from s3://{}?",
+            keys.len(),
+            bucket
+        );
+        self.open_bulk_prompt(
+            "Delete S3 Objects",
+            question,
+            &keys,
+            PendingAction::DeleteS3Objects {
+                bucket,
+                keys: keys.clone(),
+            },
+        );
+    }
+
+    pub async fn restore_selected_s3_object(&mut self) -> Result<()> {
+        let Some((bucket, key)) = self.selected_s3_object() else {
+            if self.get_active_service().service_type == ServiceType::S3 {
+                self.set_status("Select an object to restore");
+            }
+            return Ok(());
+        };
+        let client = match &self.aws_client {
+            Some(c) => c.clone(),
+            None => {
+                self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+                return Ok(());
+            }
+        };
+
+        let (storage_class, restore) = match client.get_s3_object_storage_class(&bucket, &key).await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                self.set_status(format!("Error reading {}: {}", key, e));
+                return Ok(());
+            }
+        };
+        if !matches!(storage_class.as_str(), "GLACIER" | "DEEP_ARCHIVE") {
+            self.set_status(format!(
+                "{} is {}; only GLACIER and DEEP_ARCHIVE objects need a restore",
+                key, storage_class
+            ));
+            return Ok(());
+        }
+
+        let mut lines = vec![
+            format!("Object: s3://{}/{}", bucket, key),
+            format!("Storage class: {}", storage_class),
+        ];
+        if let Some(restore) = restore {
+            lines.push(format!("Current restore: {}", restore));
+        }
+        lines.push("Tiers: Expedited, Standard, Bulk (no Expedited for DEEP_ARCHIVE)".to_string());
+
+        self.open_prompt(Prompt {
+            title: "Restore S3 Object".to_string(),
+            lines,
+            input_label: "Tier Days".to_string(),
+            input: Some("Standard 7".to_string()),
+            action: PendingAction::RestoreS3Object { bucket, key },
+        });
+        Ok(())
+    }
+
+    pub fn prompt_input(&mut self, c: char) {
+        if let Some(input) = self.prompt.as_mut().and_then(|p| p.input.as_mut()) {
+            input.push(c);
+        }
+    }
+
+    // Whether a text input has the keyboard: a prompt's, the palette's, the service filter or
+    // the search, checked in the order the key handler gives them the keys
+    fn text_input_active(&self) -> bool {
+        match &self.prompt {
+            Some(prompt) => prompt.input.is_some(),
+            None => self.show_palette || self.show_service_popup || self.search_editing,
+        }
+    }
+
+    /// Appends pasted text to whichever text input is active. The inputs are a single line, so
+    /// line breaks inside the text become spaces and trailing ones are dropped.
+    pub fn paste(&mut self, text: &str) {
+        if !self.text_input_active() {
+            return;
+        }
+        let text: String = text
+            .trim_end_matches(['\r', '\n'])
+            .replace("\r\n", " ")
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        if let Some(input) = self.prompt.as_mut().and_then(|p| p.input.as_mut()) {
+            input.push_str(&text);
+        } else if self.show_palette {
+            self.palette_filter.push_str(&text);
+            self.palette_selected = 0;
+        } else if self.show_service_popup {
+            self.service_filter.push_str(&text);
+            self.select_top_service_match();
+        } else {
+            self.search_query.push_str(&text);
+            self.reselect_after_filter();
+        }
+    }
+
+    /// Pastes the system clipboard into the active text input, for terminals that don't paste
+    /// on Ctrl+V themselves.
+    pub fn paste_clipboard(&mut self) {
+        if !self.text_input_active() {
+            return;
+        }
+        match crate::clipboard::paste() {
+            Ok(text) => self.paste(&text),
+            Err(e) => self.set_status(format!("Error pasting from clipboard: {}", e)),
+        }
+    }
+
+    pub fn prompt_backspace(&mut self) {
+        if let Some(input) = self.prompt.as_mut().and_then(|p| p.input.as_mut()) {
+            input.pop();
+        }
+    }
+
+    pub fn cancel_prompt(&mut self) {
+        if self.prompt.take().is_some() {
+            self.set_status("Cancelled".to_string());
+        }
+    }
+
+    pub async fn confirm_prompt(&mut self) -> Result<()> {
+        let Some(prompt) = self.prompt.take() else {
+            return Ok(());
+        };
+        let client = match &self.aws_client {
+            Some(c) => c.clone(),
+            None => {
+                self.set_status("AWS client not initialized. Press r to reconnect".to_string());
+                return Ok(());
+            }
+        };
+
+        match prompt.action {
+            PendingAction::StopEcsTask { cluster, task_id } => {
+                let reason = prompt.input.unwrap_or_default();
+                if self.dry_run {
+                    let mut args = vec!["ecs", "stop-task", "--cluster", &cluster, "--task"];
+                    args.push(&task_id);
+                    if !reason.is_empty() {
+                        args.extend(["--reason", &reason]);
+                    }
+                    self.report_dry_run(format!("Would stop task {}", task_id), &args);
+                    return Ok(());
+                }
+                match client.stop_ecs_task(&cluster, &task_id, &reason).await {
+                    Ok(()) => {
+                        self.refresh_resources().await?;
+                        self.set_status_after_refresh(format!("Stopped task {}", task_id));
+                    }
+                    Err(e) => {
+                        self.set_status(format!("Error stopping task {}: {}", task_id, e));
+                    }
+                }
+            }
+            PendingAction::FilterEc2ByTag => {
+                let input = prompt.input.unwrap_or_default();
+                match parse_tag_filter(&input) {
+                    Some(filter) => {
+                        self.ec2_tag_filter = Some(filter);
+                        self.refresh_resources().await?;
+                    }
+                    None if input.trim().is_empty() => self.clear_ec2_tag_filter().await?,
+                    None => self.set_status("Tag filter must look like Key=Value"),
+                }
+            }
+            PendingAction::GroupS3BucketsByTag => {
+                let key = prompt.input.unwrap_or_default().trim().to_string();
+                self.s3_group_by = (!key.is_empty()).then(|| key.clone());
+                self.refresh_resources().await?;
+                self.set_status_after_refresh(if key.is_empty() {
+                    "Stopped grouping buckets".to_string()
+                } else {
+                    format!("Grouping buckets by tag {}", key)
+                });
+            }
+            PendingAction::RestoreS3Object { bucket, key } => {
+                let input = prompt.input.unwrap_or_default();
+                let Some((tier, days)) = parse_restore_request(&input) else {
+                    self.set_status("Restore must look like <Expedited|Standard|Bulk> <days>");
+                    return Ok(());
+                };
+                if self.dry_run {
+                    let request = format!("Days={},GlacierJobParameters={{Tier={}}}", days, tier);
+                    self.report_dry_run(
+                        format!("Would request a restore of {}", key),
+                        &[
+                            "s3api",
+                            "restore-object",
+                            "--bucket",
+                            &bucket,
+                            "--key",
+                            &key,
+                            "--restore-request",
+                            &request,
+                        ],
+                    );
+                    return Ok(());
+                }
+                match client.restore_s3_object(&bucket, &key, tier, days).await {
+                    Ok(()) => self.set_status(format!(
+                        "Restore requested for {} ({} tier, {} days)",
+                        key, tier, days
+                    )),
+                    Err(e) if format!("{:#}", e).contains("RestoreAlreadyInProgress") => {
+                        self.set_status(format!("A restore is already in progress for {}", key));
+                    }
+                    Err(e) => self.set_status(format!("Error restoring {}: {}", key, e)),
+                }
+            }
+            PendingAction::DeleteS3Object { bucket, key } => {
+                if self.dry_run {
+                    self.report_dry_run(
+                        format!("Would delete s3://{}/{}", bucket, key),
+                        &["s3api", "delete-object", "--bucket", &bucket, "--key", &key],
+                    );
+                    return Ok(());
+                }
+                match client.delete_s3_object(&bucket, &key).await {
+                    Ok(()) => {
+                        self.refresh_resources().await?;
+                        self.set_status_after_refresh(format!("Deleted s3://{}/{}", bucket, key));
+                    }
+                    Err(e) => self.set_status(format!("Error deleting {}: {}", key, e)),
+                }
+            }
+            PendingAction::DeleteDynamoDbTable { table } => {
+                if prompt.input.as_deref() != Some(table.as_str()) {
+                    self.set_status("Delete cancelled: table name did not match");
+                    return Ok(());
+                }
+                if self.dry_run {
+                    self.report_dry_run(
+                        format!("Would delete table {}", table),
+                        &["dynamodb", "delete-table", "--table-name", &table],
+                    );
+                    return Ok(());
+                }
+                match client.delete_dynamodb_table(&table).await {
+                    Ok(()) => {
+                        self.refresh_resources().await?;
+                        self.set_status_after_refresh(format!("Deleting table {}", table));
+                    }
+                    Err(e) => self.set_status(format!("Error deleting table {}: {}", table, e)),
+                }
+            }
+            PendingAction::CreateDynamoDbTable => {
+                let input = prompt.input.unwrap_or_default();
+                let Some((table, key, key_type)) = parse_create_table(&input) else {
+                    self.set_status(
+                        "Create table must look like <table name> <partition key>[:S|N|B]",
+                    );
+                    return Ok(());
+                };
+                if self.dry_run {
+                    let attribute = format!("AttributeName={},AttributeType={}", key, key_type);
+                    let schema = format!("AttributeName={},KeyType=HASH", key);
+                    self.report_dry_run(
+                        format!("Would create table {}", table),
+                        &[
+                            "dynamodb",
+                            "create-table",
+                            "--table-name",
+                            &table,
+                            "--attribute-definitions",
+                            &attribute,
+                            "--key-schema",
+                            &schema,
+                            "--billing-mode",
+                            "PAY_PER_REQUEST",
+                        ],
+                    );
+                    return Ok(());
+                }
+                match client.create_dynamodb_table(&table, &key, key_type).await {
+                    Ok(()) => {
+                        self.refresh_resources().await?;
+                        self.set_status_after_refresh(format!("Creating table {}", table));
+                    }
+                    Err(e) => self.set_status(format!("Error creating table {}: {}", table, e)),
+                }
+            }
+            PendingAction::RenameEc2Instance { instance_id } => {
+                let name = prompt.input.unwrap_or_default().trim().to_string();
+                if name.is_empty() {
+                    self.set_status("Name can't be empty");
+                    return Ok(());
+                }
+                match client
+                    .rename_ec2_instance(&instance_id, &name, self.dry_run)
+                    .await
+                {
+                    Ok(()) if self.dry_run => {
+                        let tags = format!("Key=Name,Value={}", name);
+                        self.report_dry_run(
+                            format!("EC2 allows renaming {} to {}", instance_id, name),
+                            &[
+                                "ec2",
+                                "create-tags",
+                                "--resources",
+                                &instance_id,
+                                "--tags",
+                                &tags,
+                                "--dry-run",
+                            ],
+                        );
+                    }
+                    Err(e) if self.dry_run => self.set_status(format!(
+                        "{} EC2 refuses renaming {}: {}",
+                        DRY_RUN_PREFIX, instance_id, e
+                    )),
+                    Ok(()) => {
+                        self.refresh_resources().await?;
+                        self.set_status_after_refresh(format!(
+                            "Renamed {} to {}",
+                            instance_id, name
+                        ));
+                    }
+                    Err(e) => self.set_status(format!("Error renaming {}: {}", instance_id, e)),
+                }
+            }
+            PendingAction::JumpToS3Prefix { bucket } => {
+                let input = prompt.input.unwrap_or_default();
+                let prefix = input.trim().trim_start_matches('/');
+                self.current_path = Some(format!("{}/{}", bucket, prefix));
+                self.refresh_resources().await?;
+                if !prefix.is_empty() && !prefix.ends_with('/') {
+                    self.set_status_after_refresh(format!("Showing s3://{}/{}*", bucket, prefix));
+                }
+            }
+            PendingAction::SsoLogin { profile } => {
+                // The login prints a code and opens a browser, so it needs the real terminal
+                self.set_status(format!("Running aws sso login --profile {}...", profile));
+                self.sso_login_requested = Some(profile);
+            }
+            PendingAction::Reconnect => {
+                if self.initialize_aws_client().await.is_ok() {
+                    self.refresh_resources().await?;
+                }
+            }
+            PendingAction::SummarizeS3Bucket { bucket } => {
+                // Big buckets take many pages, so the scan runs in the background; closing the
+                // popup or Esc stops it
+                self.set_status(format!("Scanning s3://{}... Esc to cancel", bucket));
+                let (progress, updates) = Progress::channel();
+                let client = client.clone();
+                let scan = async move {
+                    let rows = client
+                        .get_s3_bucket_summary(&bucket, &progress)
+                        .await
+                        .context("Error summarizing bucket")?;
+                    Ok(DetailUpdate::BucketSummary { bucket, rows })
+                };
+                self.detail_task = Some(DetailTask {
+                    handle: tokio::spawn(self.in_flight.track(scan)),
+                    progress: Some(updates),
+                });
+            }
+            PendingAction::StopEc2Instances { instance_ids } => {
+                if !bulk_confirmed(prompt.input.as_deref(), instance_ids.len()) {
+                    self.set_status("Stop cancelled: count did not match");
+                    return Ok(());
+                }
+                match client.stop_ec2_instances(&instance_ids, self.dry_run).await {
+                    Ok(()) if self.dry_run => {
+                        let mut args = vec!["ec2", "stop-instances", "--instance-ids"];
+                        args.extend(instance_ids.iter().map(String::as_str));
+                        args.push("--dry-run");
+                        self.report_dry_run(
+                            format!("EC2 allows stopping {} instance(s)", instance_ids.len()),
+                            &args,
+                        );
+                    }
+                    Err(e) if self.dry_run => self.set_status(format!(
+                        "{} EC2 refuses stopping {} instance(s): {}",
+                        DRY_RUN_PREFIX,
+                        instance_ids.len(),
+                        e
+                    )),
+                    Ok(()) => {
+                        self.refresh_resources().await?;
+                        self.set_status_after_refresh(format!(
+                            "Stopping {} instance(s)",
+                            instance_ids.len()
+                        ));
+                    }
+                    Err(e) => self.set_status(format!("Error stopping instances: {}", e)),
+                }
+            }
+            PendingAction::DeleteS3Objects { bucket, keys } => {
+                if !bulk_confirmed(prompt.input.as_deref(), keys.len()) {
+                    self.set_status("Delete cancelled: count did not match");
+                    return Ok(());
+                }
+                if self.dry_run {
+                    let objects: Vec<String> =
+                        keys.iter().map(|key| format!("{{Key={}}}", key)).collect();
+                    let delete = format!("Objects=[{}]", objects.join(","));
+                    self.report_dry_run(
+                        format!("Would delete {} object(s) from s3://{}", keys.len(), bucket),
+                        &[
+                            "s3api",
+                            "delete-objects",
+                            "--bucket",
+                            &bucket,
+                            "--delete",
+                            &delete,
+                        ],
+                    );
+                    return Ok(());
+                }
+                match client
+                    .delete_s3_objects(&bucket, &keys, &Progress::default())
+                    .await
+                {
+                    Ok(deleted) => {
+                        self.refresh_resources().await?;
+                        self.set_status_after_refresh(format!(
+                            "Deleted {} object(s) from s3://{}",
+                            deleted, bucket
+                        ));
+                    }
+                    Err(e) => self.set_status(format!("Error deleting objects: {}", e)),
+                }
+            }
+            PendingAction::ChooseS3UploadFile { bucket, prefix } => {
+                let path = expand_home(prompt.input.unwrap_or_default().trim());
+                let (Some(key), true) = (upload_key(&prefix, &path), path.is_file()) else {
+                    self.set_status(format!("No file at {}", path.display()));
+                    return Ok(());
+                };
+                self.open_prompt(Prompt {
+                    title: "Upload File".to_string(),
+                    lines: vec![format!("File: {}", path.display())],
+                    input_label: format!("Key in s3://{}/", bucket),
+                    input: Some(key),
+                    action: PendingAction::UploadS3Object { bucket, path },
+                });
+            }
+            PendingAction::UploadS3Object { bucket, path } => {
+                let key = prompt.input.unwrap_or_default().trim().to_string();
+                if key.is_empty() || key.ends_with('/') {
+                    self.set_status("The key needs a file name");
+                    return Ok(());
+                }
+                if self.dry_run {
+                    let source = path.display().to_string();
+                    let target = format!("s3://{}/{}", bucket, key);
+                    self.report_dry_run(
+                        format!("Would upload {} to {}", source, target),
+                        &["s3", "cp", &source, &target],
+                    );
+                    return Ok(());
+                }
+                self.start_s3_upload(client, bucket, key, path);
+            }
+            PendingAction::DownloadS3Object {
+                bucket,
+                key,
+                version_id,
+            } => {
+                let path = expand_home(prompt.input.unwrap_or_default().trim());
+                if path.as_os_str().is_empty() || path.is_dir() {
+                    self.set_status("The download needs a file name");
+                    return Ok(());
+                }
+                if path.exists() {
+                    self.set_status(format!("{} already exists", path.display()));
+                    return Ok(());
+                }
+                if self.dry_run {
+                    let target = path.display().to_string();
+                    let mut args = vec!["s3api", "get-object", "--bucket", &bucket, "--key", &key];
+                    if let Some(version_id) = &version_id {
+                        args.extend(["--version-id", version_id]);
+                    }
+                    args.push(&target);
+                    self.report_dry_run(
+                        format!("Would download s3://{}/{} to {}", bucket, key, target),
+                        &args,
+                    );
+                    return Ok(());
+                }
+                self.start_s3_download(client, bucket, key, version_id, path);
+            }
+            PendingAction::CreateS3Bucket => {
+                let bucket = prompt.input.unwrap_or_default().trim().to_string();
+                if let Err(e) = validate_bucket_name(&bucket) {
+                    self.set_status(format!("Invalid bucket name {}: {}", bucket, e));
+                    return Ok(());
+                }
+                if self.dry_run {
+                    self.report_dry_run(
+                        format!("Would create bucket {}", bucket),
+                        &["s3api", "create-bucket", "--bucket", &bucket],
+                    );
+                    return Ok(());
+                }
+                match client.create_s3_bucket(&bucket).await {
+                    Ok(()) => {
+                        self.refresh_resources().await?;
+                        self.set_status_after_refresh(format!("Created bucket {}", bucket));
+                    }
+                    Err(e) => self.set_status(format!("Error creating bucket: {}", e)),
+                }
+            }
+            PendingAction::DeleteS3Bucket { bucket } => {
+                if self.dry_run {
+                    self.report_dry_run(
+                        format!("Would delete bucket {}", bucket),
+                        &["s3api", "delete-bucket", "--bucket", &bucket],
+                    );
+                    return Ok(());
+                }
+                match client.delete_s3_bucket(&bucket).await {
+                    Ok(()) => {
+                        self.refresh_resources().await?;
+                        self.set_status_after_refresh(format!("Deleted bucket {}", bucket));
+                    }
+                    Err(e) => self.set_status(format!("Error deleting bucket: {}", e)),
+                }
+            }
+            PendingAction::DeleteS3Prefix { bucket, prefix } => {
+                if prompt.input.as_deref() != Some(DELETE_CONFIRMATION) {
+                    self.set_status("Delete cancelled: confirmation text did not match");
+                    return Ok(());
+                }
+                let (progress, updates) = Progress::channel();
+                let dry_run = self.dry_run;
+                self.set_status(format!(
+                    "Listing objects under s3://{}/{}... (Esc to cancel)",
+                    bucket, prefix
+                ));
+                // Re-list so keys added since the prompt opened are included. Listing changes
+                // nothing, so in a dry run the count is what a real delete would cover
+                let work = async move {
+                    let keys = client
+                        .list_s3_keys(&bucket, &prefix, &progress)
+                        .await
+                        .with_context(|| format!("Error listing s3://{}/{}", bucket, prefix))?;
+                    if dry_run {
+                        return Ok(PrefixResult::WouldDelete {
+                            bucket,
+                            prefix,
+                            count: keys.len(),
+                        });
+                    }
+                    let deleted = client
+                        .delete_s3_objects(&bucket, &keys, &progress)
+                        .await
+                        .with_context(|| format!("Error deleting {}", prefix))?;
+                    Ok(PrefixResult::Deleted {
+                        bucket,
+                        prefix,
+                        deleted,
+                    })
+                };
+                self.start_prefix_task(work, updates, !dry_run);
+            }
+        }
+        Ok(())
+    }
+
+    // Dry-run mode reports the call a confirmed action would make in place of making it
+    fn report_dry_run(&mut self, summary: String, args: &[&str]) {
+        let command = aws_cli_command(args, &self.cli_identity_args(self.region.as_deref()));
+        self.set_status(format!("{} {}: {}", DRY_RUN_PREFIX, summary, command));
+    }
+
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.status_message = message.into();
+        self.event_log.push(format!(
+            "[{}] {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            self.status_message
+        ));
+        if self.event_log.len() > EVENT_LOG_CAPACITY {
+            let excess = self.event_log.len() - EVENT_LOG_CAPACITY;
+            self.event_log.drain(..excess);
+        }
+    }
+
+    pub fn toggle_event_log(&mut self) {
+        self.show_event_log = !self.show_event_log;
+        if self.show_event_log {
+            // Start at the most recent entries
+            self.event_log_scroll = self.event_log.len().saturating_sub(1);
+        }
+    }
+
+    pub fn event_log_scroll_up(&mut self) {
+        self.event_log_scroll = self.event_log_scroll.saturating_sub(1);
+    }
+
+    pub fn event_log_scroll_down(&mut self) {
+        if self.event_log_scroll < self.event_log.len().saturating_sub(1) {
+            self.event_log_scroll += 1;
+        }
+    }
+
+    pub fn toggle_error_details(&mut self) {
+        if self.show_error_popup {
+            self.show_error_popup = false;
+        } else if self.error_message.is_some() {
+            self.show_error_popup = true;
+            self.error_scroll = 0;
+        } else {
+            self.set_status("No error to show");
+        }
+    }
+
+    pub fn error_scroll_up(&mut self) {
+        self.error_scroll = self.error_scroll.saturating_sub(1);
+    }
+
+    pub fn error_scroll_down(&mut self) {
+        self.error_scroll = self.error_scroll.saturating_add(1);
+    }
+
+    pub fn show_quit_confirmation(&mut self) {
+        if self.config.confirm_quit {
+            self.show_quit_confirm = true;
+        } else {
+            self.should_quit = true;
+        }
+    }
+
+    pub fn hide_quit_confirmation(&mut self) {
+        self.show_quit_confirm = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The app holds the one AwsClient defined in src/aws/mod.rs, and background tasks get
+    // clones of it; a client type of its own, or one that can't cross tasks, stops this compiling
+    const _: fn(&App) -> Option<&crate::aws::AwsClient> = |app| app.aws_client.as_ref();
+    const _: fn() = || {
+        fn shared<T: Clone + Send + Sync + 'static>() {}
+        shared::<crate::aws::AwsClient>();
+    };
+
+    #[test]
+    fn test_service_type() {
+        assert_eq!(ServiceType::EC2.short_name(), "EC2");
+        assert_eq!(ServiceType::S3.short_name(), "S3");
+        assert_eq!(ServiceType::IAM.short_name(), "IAM");
+        assert_eq!(ServiceType::CloudWatch.short_name(), "CloudWatch");
+        assert_eq!(ServiceType::DynamoDB.short_name(), "DynamoDB");
+
+        assert_eq!(ServiceType::EC2.as_str(), "EC2 Instances");
+
+        assert_eq!(ServiceType::from_name("ec2"), Some(ServiceType::EC2));
+        assert_eq!(
+            ServiceType::from_name("DynamoDB"),
+            Some(ServiceType::DynamoDB)
+        );
+        assert_eq!(ServiceType::from_name("rds"), None);
+    }
+
+    #[test]
+    fn test_app_initialization() {
+        let app = App::new();
+        assert_eq!(app.active_service, 0);
+        assert_eq!(app.selected_index, 0);
+        assert_eq!(app.loading_state, LoadingState::Idle);
+        assert!(app.services[0].favorite); // EC2 is favorite
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut app = App::new();
+        app.items = vec![
+            "Item 1".to_string(),
+            "Item 2".to_string(),
+            "Item 3".to_string(),
+        ];
+
+        // Test next_item
+        app.next_item();
+        assert_eq!(app.selected_index, 1);
+        app.next_item();
+        assert_eq!(app.selected_index, 2);
+        app.next_item();
+        assert_eq!(app.selected_index, 0); // Wrap around
+
+        // Test previous_item
+        app.previous_item();
+        assert_eq!(app.selected_index, 2); // Wrap around
+        app.previous_item();
+        assert_eq!(app.selected_index, 1);
+    }
+
+    #[test]
+    fn test_is_selectable_s3() {
+        let mut app = App::new();
+        // Switch to S3 (index 1)
+        app.active_service = 1;
+
+        // Mock S3 items
+        app.s3_items = vec![
+            S3Item::Header,
+            S3Item::Separator,
+            S3Item::Bucket("bucket1".to_string()),
+        ];
+        app.items = vec![
+            "Header".to_string(),
+            "---".to_string(),
+            "bucket1".to_string(),
+        ];
+
+        // Header should not be selectable
+        assert!(!app.is_selectable(0));
+        // Separator should not be selectable
+        assert!(!app.is_selectable(1));
+        // Bucket should be selectable
+        assert!(app.is_selectable(2));
+
+        // Test navigation skipping non-selectable items
+        app.selected_index = 2;
+        app.next_item(); // Should wrap to 2 because 0 and 1 are not selectable
+        assert_eq!(app.selected_index, 2);
+
+        // If we force selection to 0 (which shouldn't happen normally but for test setup)
+        app.selected_index = 0;
+        app.next_item();
+        assert_eq!(app.selected_index, 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_keeps_selection() {
+        let mut app = App::new();
+        app.active_service = 6; // MWAA
+        let envs = |names: &[&str]| Listing::Mwaa(names.iter().map(|n| n.to_string()).collect());
+        app.apply_listing(Ok(envs(&["a", "b", "c"]))).unwrap();
+        app.next_item();
+        app.next_item();
+        assert_eq!(app.row_id(app.selected_index).as_deref(), Some("c"));
+
+        let reload = async |app: &mut App, listing: Listing| {
+            app.refresh = Some(RefreshTask {
+                handle: tokio::spawn(async move { Ok(listing) }),
+                progress: Progress::channel().1,
+                after_status: None,
+                reselect: app.selected_row(),
+            });
+            while app.refresh.is_some() {
+                tokio::task::yield_now().await;
+                app.poll_refresh().await.unwrap();
+            }
+        };
+        // The same resource stays selected though rows moved around it
+        reload(&mut app, envs(&["0", "a", "c"])).await;
+        assert_eq!(app.row_id(app.selected_index).as_deref(), Some("c"));
+
+        // Once it is gone the cursor goes to the first row
+        reload(&mut app, envs(&["a", "b"])).await;
+        assert_eq!(app.row_id(app.selected_index).as_deref(), Some("a"));
+    }
+
+    #[tokio::test]
+    async fn test_bucket_summary_task() {
+        let mut app = App::new();
+        app.show_detail_popup = true;
+        app.detail_content = vec![("Name".to_string(), "logs".to_string())];
+        let summary = |progress: Progress, release: tokio::sync::oneshot::Receiver<()>| {
+            tokio::spawn(async move {
+                progress.report("Scanned 1000 objects in s3://logs...");
+                let _ = release.await;
+                Ok(DetailUpdate::BucketSummary {
+                    bucket: "logs".to_string(),
+                    rows: vec![("Objects".to_string(), "1000".to_string())],
+                })
+            })
+        };
+
+        // Progress reaches the status bar while the scan runs, then the rows are appended
+        let (progress, updates) = Progress::channel();
+        let (release, held) = tokio::sync::oneshot::channel();
+        app.detail_task = Some(DetailTask {
+            handle: summary(progress, held),
+            progress: Some(updates),
+        });
+        while app.status_message != "Scanned 1000 objects in s3://logs..." {
+            tokio::task::yield_now().await;
+            app.poll_detail_task().await;
+        }
+        release.send(()).unwrap();
+        while app.is_loading_detail() {
+            tokio::task::yield_now().await;
+            app.poll_detail_task().await;
+        }
+        assert_eq!(app.detail_content.len(), 4);
+        assert_eq!(app.detail_content[3].1, "1000");
+
+        // Esc stops a scan and keeps the details
+        let (progress, updates) = Progress::channel();
+        let (_release, held) = tokio::sync::oneshot::channel();
+        app.detail_task = Some(DetailTask {
+            handle: summary(progress, held),
+            progress: Some(updates),
+        });
+        assert!(app.cancel_detail_task());
+        assert!(!app.is_loading_detail());
+        assert_eq!(app.detail_content.len(), 4);
+        assert_eq!(app.status_message, "Cancelled");
+        assert!(!app.cancel_detail_task());
+
+        // A failed chart load leaves the alarm's details up
+        app.detail_task = Some(DetailTask {
+            handle: tokio::spawn(async { Err(anyhow::anyhow!("Error loading metric for cpu")) }),
+            progress: None,
+        });
+        while app.is_loading_detail() {
+            tokio::task::yield_now().await;
+            app.poll_detail_task().await;
+        }
+        assert_eq!(app.detail_content.len(), 4);
+        assert_eq!(app.status_message, "Error loading metric for cpu");
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_task() {
+        let mut app = App::new();
+        app.show_dashboard = true;
+
+        // The rows appear once the summaries are in, without blocking the loop meanwhile
+        let (release, held) = tokio::sync::oneshot::channel::<()>();
+        app.dashboard_task = Some(DashboardTask {
+            handle: tokio::spawn(async move {
+                let _ = held.await;
+                vec![(0, "2 running, 1 stopped (3 total)".to_string())]
+            }),
+        });
+        assert!(app.is_busy());
+        app.poll_dashboard().await;
+        assert!(app.dashboard_rows.is_empty());
+        release.send(()).unwrap();
+        while app.is_loading_dashboard() {
+            tokio::task::yield_now().await;
+            app.poll_dashboard().await;
+        }
+        assert_eq!(app.dashboard_rows.len(), 1);
+        assert!(app.status_message.starts_with("Dashboard: 1 service(s)"));
+
+        // Esc stops a slow load and keeps the dashboard up
+        app.dashboard_task = Some(DashboardTask {
+            handle: tokio::spawn(std::future::pending()),
+        });
+        assert!(app.cancel_dashboard());
+        assert!(app.show_dashboard);
+        assert!(!app.is_loading_dashboard());
+        assert!(!app.cancel_dashboard());
+    }
+
+    #[tokio::test]
+    async fn test_prefix_task() {
+        let mut app = App::new();
+
+        // Progress reaches the status bar while counting, then the count opens the prompt
+        let (progress, updates) = Progress::channel();
+        let (release, held) = tokio::sync::oneshot::channel::<()>();
+        app.start_prefix_task(
+            async move {
+                progress.report("Listed 1000 objects under s3://bucket/logs/...");
+                let _ = held.await;
+                Ok(PrefixResult::Counted {
+                    bucket: "bucket".to_string(),
+                    prefix: "logs/".to_string(),
+                    count: 1500,
+                })
+            },
+            updates,
+            false,
+        );
+        assert!(app.is_busy());
+        while !app.status_message.starts_with("Listed") {
+            tokio::task::yield_now().await;
+            app.poll_prefix_task().await.unwrap();
+        }
+        assert!(app.prompt.is_none());
+        release.send(()).unwrap();
+        while app.is_running_prefix_task() {
+            tokio::task::yield_now().await;
+            app.poll_prefix_task().await.unwrap();
+        }
+        let prompt = app.prompt.take().unwrap();
+        assert_eq!(prompt.title, "Delete S3 Prefix");
+        assert!(prompt.lines[0].contains("1500 object(s) under s3://bucket/logs/"));
+
+        // Esc stops a slow delete and says what it leaves behind
+        let (_progress, updates) = Progress::channel();
+        app.start_prefix_task(std::future::pending(), updates, true);
+        assert!(app.cancel_prefix_task());
+        assert!(!app.is_running_prefix_task());
+        assert!(app.status_message.contains("already deleted"));
+        assert!(!app.cancel_prefix_task());
+    }
+
+    #[tokio::test]
+    async fn test_poll_refresh() {
+        let mut app = App::new();
+        app.active_service = 6; // MWAA
+        app.loading_state = LoadingState::Loading;
+
+        let (progress, progress_rx) = Progress::channel();
+        let (release, wait) = tokio::sync::oneshot::channel::<()>();
+        let handle = tokio::spawn(app.in_flight.track(async move {
+            let _ = wait.await;
+            Ok(Listing::Mwaa(vec!["env1".to_string()]))
+        }));
+        assert_eq!(app.in_flight.count(), 1);
+        app.refresh = Some(RefreshTask {
+            handle,
+            progress: progress_rx,
+            after_status: None,
+            reselect: None,
+        });
+
+        progress.report("Loaded 1 environments...");
+        app.poll_refresh().await.unwrap();
+        assert_eq!(app.status_message, "Loaded 1 environments...");
+        assert_eq!(app.loading_state, LoadingState::Loading);
+
+        app.set_status_after_refresh("Refreshed");
+        release.send(()).unwrap();
+        while app.refresh.is_some() {
+            tokio::task::yield_now().await;
+            app.poll_refresh().await.unwrap();
+        }
+        assert_eq!(app.loading_state, LoadingState::Loaded);
+        assert_eq!(app.status_message, "Refreshed");
+        assert!(app.items.iter().any(|item| item.contains("env1")));
+        assert_eq!(app.in_flight.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_auth_failures_pause_loading() {
+        let mut app = App::new();
+        app.profile_name = "awsome-test-missing".to_string();
+        app.active_service = 6; // MWAA
+        let denied = || {
+            Err(anyhow::anyhow!(
+                "InvalidClientTokenId: the token is invalid"
+            ))
+        };
+
+        // Other errors don't count towards the pause
+        app.apply_listing(Err(anyhow::anyhow!("dispatch failure")))
+            .unwrap();
+        for _ in 0..AUTH_FAILURE_LIMIT - 1 {
+            app.apply_listing(denied()).unwrap();
+        }
+        assert!(!app.auth_paused());
+        app.apply_listing(denied()).unwrap();
+        assert!(app.auth_paused());
+        assert!(app.items.iter().any(|item| item.contains("Paused after 3")));
+
+        // Navigating doesn't call AWS again while paused
+        app.refresh_resources().await.unwrap();
+        assert!(app.refresh.is_none());
+        assert_eq!(app.status_message, "Paused: re-authenticate, then press r");
+
+        app.apply_listing(Ok(Listing::Mwaa(vec!["env1".to_string()])))
+            .unwrap();
+        assert!(!app.auth_paused());
+    }
+
+    #[test]
+    fn test_credentials_countdown() {
+        let mut app = App::new();
+        app.profile_name = "awsome-test-missing".to_string();
+        let now = SystemTime::now();
+        assert_eq!(app.credentials_countdown(now), None);
+
+        app.credentials_expiry = Some(now + Duration::from_secs(42 * 60 + 5));
+        assert_eq!(
+            app.credentials_countdown(now),
+            Some(("creds expire in 42m".to_string(), false))
+        );
+        app.check_credentials_expiry(now);
+        assert!(app.prompt.is_none());
+
+        // Under five minutes turns red and offers a refresh, once
+        let later = now + Duration::from_secs(38 * 60);
+        assert_eq!(
+            app.credentials_countdown(later),
+            Some(("creds expire in 4m".to_string(), true))
+        );
+        app.check_credentials_expiry(later);
+        let prompt = app.prompt.take().unwrap();
+        assert!(matches!(prompt.action, PendingAction::Reconnect));
+        assert!(prompt.lines[0].contains("expire in 4m"));
+        app.check_credentials_expiry(later);
+        assert!(app.prompt.is_none());
+
+        let expired = now + Duration::from_secs(3600);
+        assert_eq!(
+            app.credentials_countdown(expired),
+            Some(("creds expired".to_string(), true))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_refresh() {
+        let mut app = App::new();
+        app.loading_state = LoadingState::Loading;
+        let handle = tokio::spawn(app.in_flight.track(std::future::pending()));
+        app.refresh = Some(RefreshTask {
+            handle,
+            progress: Progress::channel().1,
+            after_status: None,
+            reselect: None,
+        });
+        assert!(app.is_refreshing());
+
+        app.cancel_refresh();
+        assert!(!app.is_refreshing());
+        assert_eq!(app.loading_state, LoadingState::Idle);
+        assert!(app.status_message.starts_with("Cancelled loading"));
+        // The aborted task gives up its in-flight slot once the runtime drops it
+        while app.in_flight.count() > 0 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_breadcrumbs() {
+        let mut app = App::new();
+        assert!(app.breadcrumbs().is_empty()); // EC2 has no hierarchy
+
+        app.active_service = 1; // S3
+        assert!(app.breadcrumbs().is_empty());
+        app.current_path = Some("bucket/logs/2024/".to_string());
+        assert_eq!(app.breadcrumbs(), vec!["Buckets", "bucket", "logs", "2024"]);
+
+        // Jumping to the current level is a no-op
+        app.jump_to_breadcrumb(3).await.unwrap();
+        assert_eq!(app.current_path.as_deref(), Some("bucket/logs/2024/"));
+        // A bare b goes to the parent, 2b to the second crumb
+        app.jump_to_counted_breadcrumb().await.unwrap();
+        assert_eq!(app.current_path.as_deref(), Some("bucket/logs/"));
+        app.current_path = Some("bucket/logs/2024/".to_string());
+        app.push_count_digit(4);
+        app.jump_to_counted_breadcrumb().await.unwrap();
+        assert_eq!(app.pending_count, None);
+        assert_eq!(app.current_path.as_deref(), Some("bucket/logs/2024/"));
+        app.jump_to_breadcrumb(1).await.unwrap();
+        assert_eq!(app.current_path.as_deref(), Some("bucket/"));
+        app.jump_to_breadcrumb(0).await.unwrap();
+        assert_eq!(app.current_path, None);
+
+        app.active_service = 5; // ECS
+        app.current_path = Some("cluster1/service1".to_string());
+        assert_eq!(app.breadcrumbs(), vec!["Clusters", "cluster1", "service1"]);
+        app.jump_to_breadcrumb(1).await.unwrap();
+        assert_eq!(app.current_path.as_deref(), Some("cluster1"));
+    }
+
+    #[test]
+    fn test_jump_and_page_navigation() {
+        let mut app = App::new();
+        app.active_service = 1; // S3
+        app.s3_items = vec![S3Item::Header, S3Item::Separator];
+        app.items = vec!["Header".to_string(), "---".to_string()];
+        for i in 0..20 {
+            app.s3_items.push(S3Item::Bucket(format!("bucket{}", i)));
+            app.items.push(format!("bucket{}", i));
+        }
+        app.list_area = Rect::new(0, 0, 40, 7); // 5 visible rows
+
+        app.last_item();
+        assert_eq!(app.selected_index, 21);
+        app.first_item();
+        assert_eq!(app.selected_index, 2); // Skips header and separator
+
+        app.page_down();
+        assert_eq!(app.selected_index, 7);
+        app.page_up();
+        assert_eq!(app.selected_index, 2);
+        app.page_up();
+        assert_eq!(app.selected_index, 2); // Never lands on the header rows
+
+        app.selected_index = 19;
+        app.page_down();
+        assert_eq!(app.selected_index, 21); // Clamped to the end
+    }
+
+    #[test]
+    fn test_count_prefix() {
+        let mut app = App::new();
+        app.items = (0..30).map(|i| format!("instance{}", i)).collect();
+        app.selected_index = 0;
+
+        app.push_count_digit(0); // A leading 0 doesn't start a count
+        assert_eq!(app.pending_count, None);
+        app.push_count_digit(1);
+        app.push_count_digit(2);
+        assert_eq!(app.pending_count, Some(12));
+        app.next_item();
+        assert_eq!(app.pending_count, None);
+        assert_eq!(app.selected_index, 12);
+
+        app.next_item();
+        assert_eq!(app.selected_index, 13);
+
+        app.push_count_digit(5);
+        app.previous_item();
+        assert_eq!(app.selected_index, 8);
+
+        app.push_count_digit(3);
+        app.clear_count();
+        app.next_item();
+        assert_eq!(app.selected_index, 9);
+    }
+
+    #[test]
+    fn test_stop_task_prompt() {
+        let mut app = App::new();
+        app.active_service = 5; // ECS
+        app.current_path = Some("cluster1/service1".to_string());
+        app.ecs_items = vec![
+            EcsItem::Header,
+            EcsItem::Separator,
+            EcsItem::ParentDir,
+            EcsItem::Task {
+                id: "task1".to_string(),
+                definition: "def:1".to_string(),
+                status: "RUNNING".to_string(),
+            },
+        ];
+
+        // Parent dir is not a task
+        app.selected_index = 2;
+        app.stop_selected_ecs_task();
+        assert!(app.prompt.is_none());
+
+        app.selected_index = 3;
+        app.stop_selected_ecs_task();
+        let prompt = app.prompt.as_ref().unwrap();
+        assert_eq!(prompt.input.as_deref(), Some("stopped via awsome"));
+        assert_eq!(
+            prompt.action,
+            PendingAction::StopEcsTask {
+                cluster: "cluster1".to_string(),
+                task_id: "task1".to_string(),
+            }
+        );
+
+        app.prompt_backspace();
+        app.prompt_input('!');
+        assert_eq!(
+            app.prompt.as_ref().unwrap().input.as_deref(),
+            Some("stopped via awsom!")
+        );
+
+        app.cancel_prompt();
+        assert!(app.prompt.is_none());
+    }
+
+    #[test]
+    fn test_spinner_follows_clock() {
+        let mut app = App::new();
+        app.animation_start = Instant::now() - Duration::from_millis(350);
+        app.tick_animation();
+        assert_eq!(app.animation_frame, 3);
+        assert_eq!(app.get_loading_spinner(), "⠸");
+
+        // Wraps around instead of depending on how often it is ticked
+        app.animation_start = Instant::now() - Duration::from_millis(1250);
+        app.tick_animation();
+        assert_eq!(app.animation_frame, 2);
+    }
+
+    #[test]
+    fn test_refreshed_label() {
+        assert_eq!(format_age(Duration::from_secs(3)), "just now");
+        assert_eq!(format_age(Duration::from_secs(42)), "42s ago");
+        assert_eq!(format_age(Duration::from_secs(150)), "2m ago");
+        assert_eq!(format_age(Duration::from_secs(7200)), "2h ago");
+        assert_eq!(format_age(Duration::from_secs(200_000)), "2d ago");
+
+        // Each service tab keeps its own time
+        let mut app = App::new();
+        assert_eq!(app.refreshed_label(), None);
+        app.apply_listing(Ok(Listing::CloudWatch(Vec::new())))
+            .unwrap();
+        assert_eq!(app.refreshed_label().as_deref(), Some("updated just now"));
+        app.active_service = 2;
+        assert_eq!(app.refreshed_label(), None);
+    }
+
+    #[test]
+    fn test_visible_ids_follow_search() {
+        let mut app = App::new();
+        app.active_service = 4; // DynamoDB
+        app.dynamodb_items = vec![
+            DynamoDbItem::Header,
+            DynamoDbItem::Separator,
+            DynamoDbItem::Table {
+                name: "orders".to_string(),
+                status: "ACTIVE".to_string(),
+            },
+            DynamoDbItem::Table {
+                name: "users".to_string(),
+                status: "ACTIVE".to_string(),
+            },
+            DynamoDbItem::Table {
+                name: "orders-archive".to_string(),
+                status: "ACTIVE".to_string(),
+            },
+        ];
+        app.items = vec![
+            "Name      Status".to_string(),
+            "----".to_string(),
+            "orders    ACTIVE".to_string(),
+            "users     ACTIVE".to_string(),
+            "orders-archive ACTIVE".to_string(),
+        ];
+        assert_eq!(app.visible_ids(), vec!["orders", "users", "orders-archive"]);
+
+        app.search_query = "order".to_string();
+        assert_eq!(app.visible_ids(), vec!["orders", "orders-archive"]);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_actions_on_marked_rows() {
+        let instance = |id: &str| Ec2Item::Instance {
+            id: id.to_string(),
+            state: "running".to_string(),
+            tags: Vec::new(),
+        };
+        let mut app = App::new();
+        app.active_service = 0; // EC2
+        app.ec2_items = vec![
+            Ec2Item::Header,
+            Ec2Item::Separator,
+            instance("i-1"),
+            instance("i-2"),
+            instance("i-3"),
+        ];
+        app.items = vec![String::new(); 5];
+
+        // Headers can't be marked
+        app.selected_index = 0;
+        app.toggle_mark();
+        assert!(app.marked_rows.is_empty());
+
+        // Marking moves down so x can be pressed repeatedly
+        app.selected_index = 2;
+        app.toggle_mark();
+        app.toggle_mark();
+        assert_eq!(app.selected_index, 4);
+        app.selected_index = 3;
+        app.toggle_mark();
+        assert_eq!(app.marked_rows, HashSet::from([2]));
+        assert_eq!(app.row_id(2).as_deref(), Some("i-1"));
+
+        app.toggle_mark();
+        app.stop_selected_resource();
+        assert_eq!(
+            app.prompt.as_ref().unwrap().action,
+            PendingAction::StopEc2Instances {
+                instance_ids: vec!["i-1".to_string(), "i-3".to_string()],
+            }
+        );
+        // Every affected id is listed, and only the typed count confirms
+        let prompt = app.prompt.as_ref().unwrap();
+        assert_eq!(prompt.lines[1..3], ["  i-1", "  i-3"]);
+        assert_eq!(prompt.input.as_deref(), Some(""));
+        assert!(!bulk_confirmed(Some("y"), 2));
+        assert!(bulk_confirmed(Some(" 2 "), 2));
+        app.prompt_scroll_down();
+        app.prompt_scroll_down();
+        assert_eq!(app.prompt_scroll, 2);
+        app.cancel_prompt();
+
+        app.config.confirm_bulk = false;
+        app.stop_selected_resource();
+        assert!(app.prompt.as_ref().unwrap().input.is_none());
+        assert_eq!(app.prompt_scroll, 0);
+        app.cancel_prompt();
+
+        app.clear_marks();
+        app.selected_index = 3;
+        app.stop_selected_resource();
+        assert_eq!(
+            app.prompt.as_ref().unwrap().action,
+            PendingAction::StopEc2Instances {
+                instance_ids: vec!["i-2".to_string()],
+            }
+        );
+        app.cancel_prompt();
+
+        // Bulk S3 deletes take the marked objects and skip folders
+        app.active_service = 1; // S3
+        app.current_path = Some("bucket/logs/".to_string());
+        app.s3_items = vec![
+            S3Item::Header,
+            S3Item::Separator,
+            S3Item::Folder("2024/".to_string()),
+            S3Item::Object("a.txt".to_string()),
+            S3Item::Object("b.txt".to_string()),
+        ];
+        app.marked_rows = HashSet::from([2, 4]);
+        assert_eq!(app.row_id(2).as_deref(), Some("s3://bucket/logs/2024/"));
+        app.delete_selected_resource().await.unwrap();
+        assert_eq!(
+            app.prompt.as_ref().unwrap().action,
+            PendingAction::DeleteS3Objects {
+                bucket: "bucket".to_string(),
+                keys: vec!["logs/b.txt".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_empty_state_guidance() {
+        let mut app = App::new();
+        app.region = Some("eu-west-1".to_string());
+        app.profile_name = "dev".to_string();
+        app.apply_listing(Ok(Listing::Ec2(Vec::new()))).unwrap();
+        assert_eq!(app.items[0], "No EC2 Instances found");
+        assert_eq!(app.items[2], "Looked in eu-west-1 with profile dev.");
+        assert!(app.items[3].contains("--region"));
+        assert_eq!(app.resource_count(), Some(0));
+
+        app.active_service = 1; // S3
+        app.apply_listing(Ok(Listing::S3Buckets(Vec::new())))
+            .unwrap();
+        assert_eq!(app.items.last().unwrap(), "- Press n to create a bucket");
+        app.read_only = true;
+        app.apply_listing(Ok(Listing::S3Buckets(Vec::new())))
+            .unwrap();
+        assert_eq!(
+            app.items.last().unwrap(),
+            "- This account has no buckets yet"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let mut app = App::new();
+        app.config.time_format = crate::aws::TimeFormat::Absolute;
+        let instance = |id: &str, state: &str| Ec2Instance {
+            id: id.to_string(),
+            state: state.to_string(),
+            ..Ec2Instance::default()
+        };
+
+        app.take_snapshot();
+        assert_eq!(app.status_message, "Load the list before taking a snapshot");
+
+        app.apply_listing(Ok(Listing::Ec2(vec![
+            instance("i-1", "running"),
+            instance("i-2", "running"),
+        ])))
+        .unwrap();
+        app.take_snapshot();
+        assert!(app.show_diff);
+        assert!(app.row_changes().is_empty());
+
+        app.apply_listing(Ok(Listing::Ec2(vec![
+            instance("i-1", "stopped"),
+            instance("i-3", "pending"),
+        ])))
+        .unwrap();
+        let row = |id: &str| {
+            (0..app.items.len())
+                .find(|&row| app.row_id(row).as_deref() == Some(id))
+                .unwrap()
+        };
+        let changes = app.row_changes();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[&row("i-1")], RowChange::Changed);
+        assert_eq!(changes[&row("i-3")], RowChange::Added);
+        let removed = app.removed_rows();
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].starts_with("i-2"));
+
+        app.toggle_diff();
+        assert!(app.row_changes().is_empty());
+        assert!(app.removed_rows().is_empty());
+    }
+
+    #[test]
+    fn test_max_items_cap() {
+        let mut app = App::new();
+        app.active_service = 3; // CloudWatch
+        app.config.max_items.insert("cloudwatch".to_string(), 2);
+        let alarms = |count: usize| {
+            (0..count)
+                .map(|i| (format!("alarm-{}", i), "OK".to_string(), false))
+                .collect::<Vec<_>>()
+        };
+
+        app.apply_listing(Ok(Listing::CloudWatch(alarms(3))))
+            .unwrap();
+        assert_eq!(app.capped_at, Some(2));
+        assert_eq!(app.resource_count(), Some(2));
+
+        // Exactly at the cap means nothing was cut
+        app.apply_listing(Ok(Listing::CloudWatch(alarms(2))))
+            .unwrap();
+        assert_eq!(app.capped_at, None);
+    }
+
+    #[test]
+    fn test_alarm_state_filter() {
+        let mut app = App::new();
+        app.active_service = 3; // CloudWatch
+        let alarms = vec![
+            ("cpu-high".to_string(), "OK".to_string(), false),
+            ("disk-full".to_string(), "ALARM".to_string(), false),
+        ];
+        app.apply_listing(Ok(Listing::CloudWatch(alarms))).unwrap();
+        assert_eq!(app.row_id(app.selected_index).as_deref(), Some("disk-full"));
+        assert_eq!(app.resource_count(), Some(2));
+        assert_eq!(
+            app.alarm_state_summary().as_deref(),
+            Some("Alarms: 1 ALARM, 1 OK")
+        );
+
+        app.cycle_alarm_state_filter();
+        assert_eq!(app.visible_ids(), vec!["disk-full"]);
+        // Nothing is INSUFFICIENT_DATA, so only the message row is left
+        app.cycle_alarm_state_filter();
+        assert_eq!(app.resource_count(), Some(0));
+        assert_eq!(app.selected_index, 0);
+        app.cycle_alarm_state_filter();
+        assert_eq!(app.visible_ids().len(), 2);
+        // The counts cover every alarm, not just the filtered ones
+        assert_eq!(
+            app.alarm_state_summary().as_deref(),
+            Some("Alarms: 1 ALARM, 1 OK")
+        );
+    }
+
+    #[test]
+    fn test_group_buckets_by_tag() {
+        let mut app = App::new();
+        app.active_service = 1; // S3
+        app.prompt_s3_group_by_tag();
+        let prompt = app.prompt.take().unwrap();
+        assert_eq!(prompt.action, PendingAction::GroupS3BucketsByTag);
+        assert!(!prompt.action.is_mutating());
+
+        let bucket = |name: &str, team: &str| {
+            (
+                name.to_string(),
+                "2024-05-01T10:00:00Z".to_string(),
+                BucketTag::Value(team.to_string()),
+            )
+        };
+        app.apply_listing(Ok(Listing::S3BucketGroups {
+            key: "Team".to_string(),
+            buckets: vec![bucket("logs", "ops"), bucket("site", "web")],
+        }))
+        .unwrap();
+        assert_eq!(
+            app.status_message,
+            "Loaded 2 buckets in 2 group(s) by tag Team"
+        );
+        // Headings can't be selected; the cursor starts on the first bucket
+        assert!(!app.is_item_row(2));
+        assert_eq!(app.selected_index, 3);
+        assert_eq!(app.row_id(3).as_deref(), Some("logs"));
+
+        // Inside a bucket there is nothing to group
+        app.current_path = Some("logs/".to_string());
+        app.prompt_s3_group_by_tag();
+        assert!(app.prompt.is_none());
+    }
+
+    #[test]
+    fn test_nudge_column_width() {
+        let mut app = App::new();
+        app.active_service = 1; // S3
+        app.config.time_format = crate::aws::TimeFormat::Absolute;
+        let buckets = vec![("logs".to_string(), "2024-05-01T10:00:00Z".to_string())];
+        app.apply_listing(Ok(Listing::S3Buckets(buckets))).unwrap();
+        let date_column = |app: &App| app.items[2].find("2024").unwrap();
+        assert_eq!(date_column(&app), 22); // 20 wide plus the gap
+
+        app.nudge_column_width(4);
+        assert_eq!(app.config.column_widths["s3"], 24);
+        assert_eq!(date_column(&app), 26);
+        assert_eq!(app.selected_index, 2);
+
+        // A pinned width survives the next listing of the service
+        let buckets = vec![("logs".to_string(), "2024-05-01T10:00:00Z".to_string())];
+        app.apply_listing(Ok(Listing::S3Buckets(buckets))).unwrap();
+        assert_eq!(date_column(&app), 26);
+
+        // Narrowing back to the data's width unpins the column
+        app.nudge_column_width(-4);
+        assert!(app.config.column_widths.is_empty());
+        assert_eq!(date_column(&app), 22);
+
+        // Another tab's listing isn't laid out under this one
+        app.active_service = 2;
+        app.nudge_column_width(4);
+        assert!(app.config.column_widths.is_empty());
+    }
+
+    #[test]
+    fn test_relayout_after_resize() {
+        let mut app = App::new();
+        app.active_service = 1; // S3
+        app.config.time_format = crate::aws::TimeFormat::Absolute;
+        app.list_area = Rect::new(0, 0, 40, 10);
+        let name = "a-bucket-with-a-rather-long-name".to_string();
+        let buckets = vec![(name.clone(), "2024-05-01T10:00:00Z".to_string())];
+        app.apply_listing(Ok(Listing::S3Buckets(buckets))).unwrap();
+        // 38 columns inside the borders leave 16 for the name beside the 20-wide date
+        assert!(app.items[2].starts_with("a-bucket-with-a…  2024"));
+        app.set_status("Copied");
+        let logged = app.event_log.len();
+
+        app.list_area = Rect::new(0, 0, 80, 10);
+        app.relayout_listing();
+        assert!(app.items[2].starts_with(&format!("{}  2024", name)));
+        assert_eq!(app.status_message, "Copied");
+        assert_eq!(app.event_log.len(), logged);
+    }
+
+    #[test]
+    fn test_lambda_versions() {
+        let mut app = App::new();
+        app.active_service = 7; // Lambda
+        app.current_path = Some("api".to_string());
+        let versions = vec![LambdaVersion {
+            version: "3".to_string(),
+            aliases: vec!["prod".to_string()],
+            last_modified: "2024-05-01T10:00:00.000+0000".to_string(),
+            description: String::new(),
+        }];
+        app.apply_listing(Ok(Listing::LambdaVersions {
+            function: "api".to_string(),
+            versions,
+        }))
+        .unwrap();
+
+        assert_eq!(app.breadcrumbs(), vec!["Functions", "api"]);
+        assert_eq!(app.resource_count(), Some(1));
+        assert_eq!(app.row_id(3).as_deref(), Some("api:3"));
+        app.selected_index = 3;
+        assert!(
+            app.selected_cli_command()
+                .unwrap()
+                .contains("--function-name api:3")
+        );
+        // Versions are only offered from a function's own details
+        assert_eq!(app.detail_lambda_function(), None);
+    }
+
+    #[test]
+    fn test_credential_report() {
+        let mut app = App::new();
+        app.active_service = 2; // IAM
+        assert!(app.check_action(Action::CredentialReport));
+        app.current_path = Some(CREDENTIAL_REPORT.to_string());
+        let entries = vec![
+            CredentialReportEntry {
+                user: "<root_account>".to_string(),
+                access_keys: [None, None],
+                ..CredentialReportEntry::default()
+            },
+            CredentialReportEntry {
+                user: "alice".to_string(),
+                mfa_active: true,
+                access_keys: [None, None],
+                ..CredentialReportEntry::default()
+            },
+        ];
+        app.apply_listing(Ok(Listing::IamCredentialReport(entries)))
+            .unwrap();
+
+        assert_eq!(app.breadcrumbs(), vec!["Users", "credential-report"]);
+        assert_eq!(
+            app.status_message,
+            "Credential report: 2 user(s), 1 flagged"
+        );
+        assert_eq!(app.resource_count(), Some(2));
+        assert_eq!(app.row_id(4).as_deref(), Some("alice"));
+        app.selected_index = 3;
+        assert!(
+            app.selected_cli_command()
+                .unwrap()
+                .contains("iam get-credential-report")
+        );
+        app.selected_index = 4;
+        assert!(
+            app.selected_cli_command()
+                .unwrap()
+                .contains("--user-name alice")
+        );
+
+        app.active_service = 0;
+        assert!(!app.check_action(Action::CredentialReport));
+    }
+
+    #[test]
+    fn test_column_picker() {
+        let mut app = App::new();
+        app.switch_service(0); // EC2
+        app.apply_listing(Ok(Listing::Ec2(vec![Ec2Instance {
+            id: "i-0abc".to_string(),
+            name: "web".to_string(),
+            state: "running".to_string(),
+            public_ip: "1.2.3.4".to_string(),
+            image_id: "ami-0def".to_string(),
+            ..Ec2Instance::default()
+        }])))
+        .unwrap();
+
+        app.toggle_column_picker();
+        assert!(app.show_column_picker);
+        assert_eq!(app.column_choices()[2], ("Public IP", true));
+
+        // Hide the public IP and add the AMI
+        app.column_picker_selected = 2;
+        app.toggle_picked_column();
+        app.column_picker_selected = 3;
+        app.toggle_picked_column();
+        assert_eq!(app.config.columns["ec2"], vec!["state", "type", "ami"]);
+        assert!(!app.items[0].contains("Public IP"));
+        assert!(app.items[2].ends_with("ami-0def"));
+
+        // A preset takes over again
+        app.toggle_column_picker();
+        app.toggle_ec2_columns();
+        assert!(!app.config.columns.contains_key("ec2"));
+
+        app.switch_service(1); // S3
+        app.toggle_column_picker();
+        assert!(!app.show_column_picker);
+
+        // Other listings pick from their own optional columns
+        app.switch_service(4); // DynamoDB
+        app.apply_listing(Ok(Listing::DynamoDb(vec![(
+            "orders".to_string(),
+            "ACTIVE".to_string(),
+            "12".to_string(),
+            "3.0 KB".to_string(),
+        )])))
+        .unwrap();
+        app.toggle_column_picker();
+        assert!(app.show_column_picker);
+        assert_eq!(app.column_choices()[2], ("Size", true));
+        app.column_picker_selected = 2;
+        app.toggle_picked_column();
+        assert_eq!(app.config.columns["dynamodb"], vec!["status", "items"]);
+        assert!(!app.items[0].contains("Size"));
+        assert!(app.items[2].ends_with("12"));
+    }
+
+    #[test]
+    fn test_selection_after_listing() {
+        let mut app = App::new();
+        app.switch_service(1); // S3
+        app.current_path = Some("bucket/".to_string());
+        let listing = |objects: Vec<(String, String, String, String)>| Listing::S3Objects {
+            bucket: "bucket".to_string(),
+            prefix: String::new(),
+            objects,
+            folders: HashMap::new(),
+        };
+
+        // An empty folder has nothing but ".." to select
+        app.apply_listing(Ok(listing(Vec::new()))).unwrap();
+        assert_eq!(app.items.len(), 3);
+        assert_eq!(app.selected_index, 2);
+
+        // Otherwise the cursor skips ".." for the first object
+        let object = (
+            "a.txt".to_string(),
+            "1 B".to_string(),
+            "2024-01-01T00:00:00Z".to_string(),
+            "STANDARD".to_string(),
+        );
+        app.apply_listing(Ok(listing(vec![object]))).unwrap();
+        assert_eq!(app.selected_index, 3);
+
+        app.switch_service(3); // CloudWatch
+        app.alarm_state_filter = Some("ALARM".to_string());
+        app.apply_listing(Ok(Listing::CloudWatch(vec![(
+            "cpu".to_string(),
+            "OK".to_string(),
+            false,
+        )])))
+        .unwrap();
+        assert!(app.selected_index < app.items.len());
+    }
+
+    #[test]
+    fn test_jump_to_letter() {
+        let mut app = App::new();
+        app.switch_service(1); // S3
+        let buckets = ["alpha", "photos", "Prod", "zeta"]
+            .map(|name| (name.to_string(), "2024-01-01T00:00:00Z".to_string()));
+        app.apply_listing(Ok(Listing::S3Buckets(buckets.to_vec())))
+            .unwrap();
+        let name = |app: &App| app.row_name(app.selected_index).map(str::to_string);
+        assert_eq!(name(&app).as_deref(), Some("alpha"));
+
+        app.start_letter_jump();
+        app.jump_to_letter('p');
+        assert!(!app.letter_jump_pending);
+        assert_eq!(name(&app).as_deref(), Some("photos"));
+        app.jump_to_letter('P');
+        assert_eq!(name(&app).as_deref(), Some("Prod"));
+        // Wraps around to the first match
+        app.jump_to_letter('p');
+        assert_eq!(name(&app).as_deref(), Some("photos"));
+
+        app.jump_to_letter('x');
+        assert_eq!(name(&app).as_deref(), Some("photos"));
+        assert_eq!(app.status_message, "No name starts with x");
+    }
+
+    #[test]
+    fn test_hide_folder_placeholders() {
+        let mut app = App::new();
+        app.switch_service(1); // S3
+        app.current_path = Some("bucket/data/".to_string());
+        let object = |name: &str, size: &str| {
+            (
+                name.to_string(),
+                size.to_string(),
+                "2024-01-01T00:00:00Z".to_string(),
+                "STANDARD".to_string(),
+            )
+        };
+        let listing = || Listing::S3Objects {
+            bucket: "bucket".to_string(),
+            prefix: "data/".to_string(),
+            objects: vec![
+                object("raw/", "DIR"),
+                object("empty/", "0 B"),
+                object("a.csv", "0 B"),
+            ],
+            folders: HashMap::new(),
+        };
+
+        app.apply_listing(Ok(listing())).unwrap();
+        assert_eq!(app.resource_count(), Some(3));
+        assert_eq!(app.status_message, "Browsing s3://bucket/data/");
+
+        // Real folders and empty files without the trailing slash stay
+        app.config.hide_folder_placeholders = true;
+        app.apply_listing(Ok(listing())).unwrap();
+        assert_eq!(app.resource_count(), Some(2));
+        assert_eq!(
+            app.status_message,
+            "Browsing s3://bucket/data/ (1 folder placeholder hidden)"
+        );
+    }
+
+    #[test]
+    fn test_full_arns() {
+        let mut app = App::new();
+        app.switch_service(5); // ECS
+        assert_eq!(app.get_active_service().service_type, ServiceType::ECS);
+        let arn = "arn:aws:ecs:eu-west-1:123456789012:cluster/prod";
+        app.apply_listing(Ok(Listing::EcsClusters(vec![(
+            "prod".to_string(),
+            arn.to_string(),
+        )])))
+        .unwrap();
+        assert_eq!(app.items[2].trim_end(), "prod");
+
+        app.toggle_full_arns();
+        assert_eq!(app.items[2].trim_end(), arn);
+        // Copying and navigation keep using the name
+        assert_eq!(app.row_id(2).as_deref(), Some("prod"));
+
+        app.toggle_full_arns();
+        assert_eq!(app.items[2].trim_end(), "prod");
+
+        app.toggle_full_arns();
+        app.switch_service(0);
+        assert!(!app.show_arns);
+    }
+
+    #[test]
+    fn test_detail_text() {
+        let mut app = App::new();
+        app.show_detail_popup = true;
+        app.detail_content = vec![
+            ("Role".to_string(), "admin".to_string()),
+            ("".to_string(), "".to_string()),
+            (
+                "Policy".to_string(),
+                r#"{"Version":"2012-10-17"}"#.to_string(),
+            ),
+        ];
+        assert_eq!(
+            app.detail_text(),
+            "Role: admin\n\nPolicy:\n  {\n    \"Version\": \"2012-10-17\"\n  }\n"
+        );
+
+        app.open_detail_in_editor();
+        let path = app.editor_requested.take().unwrap();
+        assert_eq!(path.extension().unwrap(), "txt");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), app.detail_text());
+        // Another file for the same details gets a name of its own
+        app.open_detail_in_editor();
+        let other = app.editor_requested.take().unwrap();
+        assert_ne!(*path, *other);
+        // The file goes once run_app is done with it
+        let kept = path.to_path_buf();
+        drop(path);
+        assert!(!kept.exists());
+
+        // The raw view is written as the JSON alone
+        app.detail_curated = Some(Vec::new());
+        app.detail_content = vec![("Raw JSON".to_string(), r#"{"a":1}"#.to_string())];
+        assert_eq!(app.detail_text(), "{\n  \"a\": 1\n}\n");
+    }
+
+    #[test]
+    fn test_ssm_session() {
+        let mut app = App::new();
+        app.profile_name = "dev".to_string();
+        app.region = Some("eu-west-1".to_string());
+        app.ec2_items = vec![
+            Ec2Item::Header,
+            Ec2Item::Separator,
+            Ec2Item::Instance {
+                id: "i-0abc".to_string(),
+                state: "Stopped".to_string(),
+                tags: Vec::new(),
+            },
+        ];
+        app.selected_index = 2;
+        app.start_ssm_session();
+        assert!(app.ssm_session_requested.is_none());
+        assert!(app.status_message.contains("only running instances"));
+
+        assert_eq!(
+            app.ssm_session_args("i-0abc").join(" "),
+            "ssm start-session --target i-0abc --profile dev --region eu-west-1"
+        );
+        app.env_credentials = Some("env credentials".to_string());
+        assert!(
+            !app.ssm_session_args("i-0abc")
+                .contains(&"--profile".to_string())
+        );
+        assert!(app.ssm_session_env(None).is_empty());
+
+        // An assumed role's credentials aren't in any profile, so they go in the environment
+        app.env_credentials = None;
+        app.client_options.assume_role =
+            Some("arn:aws:iam::123456789012:role/ReadOnly".to_string());
+        app.client_options.endpoint_url = Some("http://localhost:4566".to_string());
+        app.client_options.use_fips = true;
+        assert_eq!(
+            app.ssm_session_args("i-0abc").join(" "),
+            "ssm start-session --target i-0abc --region eu-west-1 \
+             --endpoint-url http://localhost:4566"
+        );
+        let credentials =
+            Credentials::new("ASIAEXAMPLE", "secret", Some("token".into()), None, "test");
+        assert_eq!(
+            app.ssm_session_env(Some(&credentials)),
+            [
+                ("AWS_ACCESS_KEY_ID", "ASIAEXAMPLE"),
+                ("AWS_SECRET_ACCESS_KEY", "secret"),
+                ("AWS_SESSION_TOKEN", "token"),
+                ("AWS_USE_FIPS_ENDPOINT", "true"),
+            ]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+        );
+
+        let dir = std::env::temp_dir().join(format!("awsome-path-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(SSM_PLUGIN), "").unwrap();
+        let path = std::env::join_paths(["/nonexistent".as_ref(), dir.as_path()]).unwrap();
+        assert!(on_path(SSM_PLUGIN, Some(&path)));
+        assert!(!on_path("aws", Some(&path)));
+        assert!(!on_path(SSM_PLUGIN, None));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_ec2_all_regions() {
+        let mut app = App::new();
+        app.profile_name = "dev".to_string();
+        app.region = Some("eu-west-1".to_string());
+        let instance = |id: &str, region: &str| Ec2Instance {
+            id: id.to_string(),
+            state: "running".to_string(),
+            region: region.to_string(),
+            ..Ec2Instance::default()
+        };
+        app.apply_listing(Ok(Listing::Ec2(vec![
+            instance("i-home", "eu-west-1"),
+            instance("i-away", "us-east-1"),
+        ])))
+        .unwrap();
+        assert_eq!(app.status_message, "Loaded 2 instances across 2 region(s)");
+        assert!(app.items[0].contains("Region"));
+
+        // Calls on the client's own region go ahead
+        app.selected_index = 2;
+        app.prompt_rename_ec2_instance();
+        assert!(app.prompt.is_some());
+        app.prompt = None;
+
+        app.selected_index = 3;
+        app.prompt_rename_ec2_instance();
+        assert!(app.prompt.is_none());
+        assert_eq!(
+            app.status_message,
+            "i-away is in us-east-1; switch to that region to act on it"
+        );
+        app.stop_selected_ec2_instances();
+        assert!(app.prompt.is_none());
+        app.start_watch();
+        assert!(app.watch.is_none());
+
+        // The CLI and SSM sessions can name the instance's region themselves
+        assert!(
+            app.selected_cli_command()
+                .unwrap()
+                .ends_with("--region us-east-1")
+        );
+        assert!(
+            app.ssm_session_args("i-away")
+                .ends_with(&["--region".to_string(), "us-east-1".to_string()])
+        );
+
+        // Regions that fail are named, and the rest still list
+        app.apply_listing(Ok(Listing::Ec2AllRegions {
+            instances: vec![instance("i-home", "eu-west-1")],
+            failed: vec![
+                ("ap-east-1".to_string(), "AuthFailure".to_string()),
+                ("me-south-1".to_string(), "timed out".to_string()),
+            ],
+        }))
+        .unwrap();
+        assert_eq!(app.ec2_instances.len(), 1);
+        assert_eq!(
+            app.status_message,
+            "2 region(s) failed: ap-east-1, me-south-1. E for details"
+        );
+        assert_eq!(
+            app.error_message.as_deref(),
+            Some("ap-east-1: AuthFailure\nme-south-1: timed out")
+        );
+    }
+
+    #[test]
+    fn test_watch() {
+        let mut app = App::new();
+        app.ec2_items = vec![
+            Ec2Item::Header,
+            Ec2Item::Separator,
+            Ec2Item::Instance {
+                id: "i-0abc".to_string(),
+                state: "Pending".to_string(),
+                tags: Vec::new(),
+            },
+        ];
+        app.selected_index = 1;
+        assert_eq!(app.watch_target(), None);
+        app.selected_index = 2;
+        let target = app.watch_target().unwrap();
+        assert_eq!(target, WatchTarget::Ec2Instance("i-0abc".to_string()));
+
+        app.show_detail_popup = true;
+        app.watch = Some(Watch {
+            target,
+            fetch: None,
+            next_poll: Instant::now(),
+            polls: 0,
+            changed: HashMap::new(),
+        });
+        let details = |state: &str| {
+            vec![
+                ("Instance ID".to_string(), "i-0abc".to_string()),
+                ("State".to_string(), state.to_string()),
+            ]
+        };
+        // The first result only fills the popup in
+        app.apply_watch(Ok((details("pending"), None)));
+        assert!(!app.watch_flashing("State"));
+        assert_eq!(app.watch_frame(), Some((1, 0)));
+
+        app.apply_watch(Ok((details("running"), None)));
+        assert!(app.watch_flashing("State"));
+        assert!(!app.watch_flashing("Instance ID"));
+        assert_eq!(app.status_message, "instance i-0abc: State is now running");
+
+        // A failed poll leaves the last details up
+        app.apply_watch(Err(anyhow::anyhow!("throttled")));
+        assert_eq!(app.detail_content, details("running"));
+
+        app.close_detail_popup();
+        assert!(!app.is_watching());
+    }
+
+    #[test]
+    fn test_s3_versions() {
+        let mut app = App::new();
+        app.switch_service(1); // S3
+        app.current_path = Some("bucket/logs/".to_string());
+        let version = |version_id: &str, is_latest, delete_marker| S3ObjectVersion {
+            name: "app.log".to_string(),
+            version_id: version_id.to_string(),
+            is_latest,
+            delete_marker,
+            size: "1.00 KB".to_string(),
+            last_modified: "2024-05-01T10:00:00Z".to_string(),
+        };
+        app.apply_listing(Ok(Listing::S3Versions {
+            bucket: "bucket".to_string(),
+            prefix: "logs/".to_string(),
+            versions: vec![version("v2", true, true), version("v1", false, false)],
+        }))
+        .unwrap();
+
+        assert_eq!(app.resource_count(), Some(2));
+        assert_eq!(
+            app.row_id(4).as_deref(),
+            Some("s3://bucket/logs/app.log?versionId=v1")
+        );
+        app.selected_index = 4;
+        assert!(
+            app.selected_cli_command()
+                .unwrap()
+                .contains("--key logs/app.log --version-id v1")
+        );
+        app.prompt_s3_download();
+        assert_eq!(
+            app.prompt.as_ref().map(|p| &p.action),
+            Some(&PendingAction::DownloadS3Object {
+                bucket: "bucket".to_string(),
+                key: "logs/app.log".to_string(),
+                version_id: Some("v1".to_string()),
+            })
+        );
+        // Downloading writes a local file, so read-only mode refuses it
+        assert!(app.prompt.unwrap().action.is_mutating());
+
+        app.prompt = None;
+        app.selected_index = 3;
+        app.prompt_s3_download();
+        assert!(app.prompt.is_none());
+    }
+
+    #[test]
+    fn test_resource_count() {
+        let mut app = App::new();
+        app.switch_service(1); // S3
+        app.loading_state = LoadingState::Loaded;
+        app.items = vec![
+            "Name".to_string(),
+            "----".to_string(),
+            "..".to_string(),
+            "folder/".to_string(),
+            "file.txt".to_string(),
+        ];
+        app.s3_items = vec![
+            S3Item::Header,
+            S3Item::Separator,
+            S3Item::ParentDir,
+            S3Item::Folder("folder/".to_string()),
+            S3Item::Object("file.txt".to_string()),
+        ];
+        assert_eq!(app.resource_count(), Some(2));
+
+        // The parent row counts toward neither side of the position
+        app.selected_index = 2;
+        assert_eq!(app.scroll_position(), Some((None, 2)));
+        app.selected_index = 4;
+        assert_eq!(app.scroll_position(), Some((Some(2), 2)));
+
+        // Placeholder rows don't line up with the item vec
+        app.items = vec!["Press 'r' to refresh and load resources".to_string()];
+        assert_eq!(app.resource_count(), None);
+
+        app.loading_state = LoadingState::Loading;
+        assert_eq!(app.resource_count(), None);
+    }
+
+    #[test]
+    fn test_next_storage_class_filter() {
+        let mut filter = None;
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            filter = next_storage_class_filter(&filter);
+            seen.push(filter.clone());
+        }
+        assert_eq!(
+            seen,
+            vec![
+                Some("STANDARD".to_string()),
+                Some("GLACIER".to_string()),
+                Some("DEEP_ARCHIVE".to_string()),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dashboard_navigation() {
+        let mut app = App::new();
+        app.show_dashboard = true;
+        app.dashboard_rows = vec![
+            (0, "2 running, 1 stopped (3 total)".to_string()),
+            (1, "12 buckets".to_string()),
+        ];
+
+        app.dashboard_previous();
+        assert_eq!(app.dashboard_selected, 1);
+        app.dashboard_next();
+        assert_eq!(app.dashboard_selected, 0);
+        app.dashboard_next();
+
+        assert!(app.open_dashboard_row());
+        assert!(!app.show_dashboard);
+        assert_eq!(app.get_active_service().service_type, ServiceType::S3);
+    }
+
+    #[test]
+    fn test_parse_restore_request() {
+        assert_eq!(parse_restore_request("Standard 7"), Some(("Standard", 7)));
+        assert_eq!(parse_restore_request(" bulk  30 "), Some(("Bulk", 30)));
+        assert_eq!(parse_restore_request("EXPEDITED 1"), Some(("Expedited", 1)));
+        assert_eq!(parse_restore_request("Standard"), None);
+        assert_eq!(parse_restore_request("Standard 0"), None);
+        assert_eq!(parse_restore_request("Fast 7"), None);
+        assert_eq!(parse_restore_request("Standard 7 extra"), None);
+    }
+
+    #[test]
+    fn test_selected_cli_command() {
+        let mut app = App::new();
+        app.profile_name = "dev".to_string();
+        app.region = Some("eu-west-1".to_string());
+
+        app.active_service = 4; // DynamoDB
+        app.dynamodb_items = vec![
+            DynamoDbItem::Header,
+            DynamoDbItem::Separator,
+            DynamoDbItem::Table {
+                name: "orders".to_string(),
+                status: "ACTIVE".to_string(),
+            },
+        ];
+        app.selected_index = 0;
+        assert_eq!(app.selected_cli_command(), None);
+        app.selected_index = 2;
+        assert_eq!(
+            app.selected_cli_command().as_deref(),
+            Some(
+                "aws dynamodb describe-table --table-name orders --profile dev --region eu-west-1"
+            )
+        );
+
+        app.active_service = 1; // S3
+        app.current_path = Some("bucket/logs/".to_string());
+        app.s3_items = vec![
+            S3Item::Header,
+            S3Item::Separator,
+            S3Item::Object("my file.txt".to_string()),
+        ];
+        assert_eq!(
+            app.selected_cli_command().as_deref(),
+            Some(
+                "aws s3api head-object --bucket bucket --key 'logs/my file.txt' --profile dev --region eu-west-1"
+            )
+        );
+        app.selected_index = 1;
+        assert_eq!(
+            app.selected_cli_command().as_deref(),
+            Some("aws s3 ls s3://bucket/logs/ --profile dev --region eu-west-1")
+        );
+
+        // The copy acts as the app does: no profile for assumed-role or injected credentials,
+        // and the same endpoint
+        app.client_options.assume_role =
+            Some("arn:aws:iam::123456789012:role/ReadOnly".to_string());
+        app.client_options.endpoint_url = Some("http://localhost:4566".to_string());
+        assert_eq!(
+            app.selected_cli_command().as_deref(),
+            Some(
+                "aws s3 ls s3://bucket/logs/ --region eu-west-1 --endpoint-url http://localhost:4566"
+            )
+        );
+        app.client_options = ClientOptions::default();
+        app.env_credentials = Some("env credentials".to_string());
+        assert_eq!(
+            app.selected_cli_command().as_deref(),
+            Some("aws s3 ls s3://bucket/logs/ --region eu-west-1")
+        );
+
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn test_service_filter() {
+        assert_eq!(fuzzy_score("DynamoDB", "dy"), Some(0));
+        assert_eq!(fuzzy_score("CloudWatch Alarms", "cw"), Some(4));
+        assert_eq!(fuzzy_score("Lambda", "ld"), Some(3));
+        assert_eq!(fuzzy_score("Lambda", "dl"), None);
+
+        let mut app = App::new();
+        app.toggle_service_popup();
+        app.service_filter_input('d');
+        app.service_filter_input('y');
+        assert_eq!(app.popup_services(), vec![4]);
+        assert_eq!(app.popup_selected_index, 4);
+
+        app.service_filter_backspace();
+        app.service_filter_backspace();
+        app.service_filter_input('e');
+        // ECS and EC2 start with "e"; everything else merely contains one
+        let services = app.popup_services();
+        assert_eq!(&services[..2], &[0, 5]);
+        app.popup_next();
+        assert_eq!(app.popup_selected_index, 5);
+
+        app.service_filter_input('z');
+        app.service_filter_input('z');
+        assert!(app.popup_services().is_empty());
+        app.select_popup_service();
+        assert!(app.show_service_popup);
+
+        app.clear_service_filter();
+        assert_eq!(app.popup_services().len(), app.services.len());
+    }
+
+    #[test]
+    fn test_palette_actions() {
+        let mut app = App::new();
+        app.switch_service(0);
+        app.toggle_palette();
+        let actions = app.palette_actions();
+        assert!(actions.contains(&Action::TagFilter));
+        assert!(actions.contains(&Action::Stop));
+        // Movement, S3 and CloudWatch commands have nothing to do on the EC2 list
+        for hidden in [
+            Action::Next,
+            Action::Palette,
+            Action::Restore,
+            Action::StateFilter,
+        ] {
+            assert!(!actions.contains(&hidden));
+        }
+
+        app.read_only = true;
+        assert!(!app.palette_actions().contains(&Action::Stop));
+
+        app.palette_input('t');
+        app.palette_input('a');
+        app.palette_input('g');
+        assert_eq!(app.palette_actions()[0], Action::TagFilter);
+        app.palette_next();
+        app.palette_previous();
+        assert_eq!(app.take_palette_action(), Some(Action::TagFilter));
+        assert!(!app.show_palette);
+        assert!(app.palette_filter.is_empty());
+
+        app.toggle_palette();
+        app.palette_input('z');
+        app.palette_input('z');
+        assert_eq!(app.take_palette_action(), None);
+        assert!(app.show_palette);
+
+        // Keys for actions that don't apply here say so instead of doing nothing
+        assert!(app.check_action(Action::TagFilter));
+        assert!(!app.check_action(Action::Stop));
+        assert_eq!(app.status_message, "Read-only mode: Stop is disabled");
+        assert!(!app.check_action(Action::StateFilter));
+        assert_eq!(
+            app.status_message,
+            "Filter by alarm state isn't available here"
+        );
+    }
+
+    #[test]
+    fn test_prod_account_warning() {
+        let mut app = App::new();
+        app.config.prod_accounts = vec!["111111111111".to_string()];
+        app.active_service = 4; // DynamoDB
+        app.dynamodb_items = vec![
+            DynamoDbItem::Header,
+            DynamoDbItem::Separator,
+            DynamoDbItem::Table {
+                name: "orders".to_string(),
+                status: "ACTIVE".to_string(),
+            },
+        ];
+        app.selected_index = 2;
+
+        app.account = Some(AccountIdentity {
+            account_id: "222222222222".to_string(),
+            alias: None,
+        });
+        assert!(!app.is_prod_account());
+        app.delete_selected_dynamodb_table();
+        assert!(!app.prompt.take().unwrap().lines[0].contains("PRODUCTION"));
+
+        app.account = Some(AccountIdentity {
+            account_id: "111111111111".to_string(),
+            alias: Some("acme-prod".to_string()),
+        });
+        assert!(app.is_prod_account());
+        app.delete_selected_dynamodb_table();
+        assert_eq!(
+            app.prompt.take().unwrap().lines[0],
+            "⚠ PRODUCTION account 111111111111 (acme-prod)"
+        );
+    }
+
+    #[test]
+    fn test_read_only_refuses_mutating_prompts() {
+        let mut app = App::new();
+        app.read_only = true;
+        app.active_service = 4; // DynamoDB
+        app.dynamodb_items = vec![
+            DynamoDbItem::Header,
+            DynamoDbItem::Separator,
+            DynamoDbItem::Table {
+                name: "orders".to_string(),
+                status: "ACTIVE".to_string(),
+            },
+        ];
+        app.selected_index = 2;
 
-        Ok(())
-    }
+        app.delete_selected_dynamodb_table();
+        assert!(app.prompt.is_none());
+        assert!(app.status_message.contains("Read-only mode"));
+        app.prompt_create_dynamodb_table();
+        assert!(app.prompt.is_none());
 
-    pub async fn refresh_resources(&mut self) -> Result<()> {
-        let client = match &self.aws_client {
-            Some(c) => c,
-            None => {
-                self.status_message = "AWS client not initialized".to_string();
-                return Ok(());
-            }
-        };
+        app.active_service = 1; // S3
+        app.current_path = Some("logs/2024/".to_string());
+        app.prompt_s3_upload();
+        assert!(app.prompt.is_none());
 
-        self.loading_state = LoadingState::Loading;
-        self.items = vec!["Loading...".to_string()];
-        self.status_message = format!(
-            "Loading {} resources...",
-            self.get_active_service().as_str()
+        // A download writes a local file
+        app.s3_items = vec![
+            S3Item::Header,
+            S3Item::Separator,
+            S3Item::Object("app.log".to_string()),
+        ];
+        assert!(!app.check_action(Action::Download));
+        assert_eq!(
+            app.status_message,
+            "Read-only mode: Download object is disabled"
         );
+        app.prompt_s3_download();
+        assert!(app.prompt.is_none());
 
-        match self.get_active_service().service_type {
-            ServiceType::EC2 => {
-                match client.list_ec2_instances().await {
-                    Ok(instances) => {
-                        self.loading_state = LoadingState::Loaded;
-                        let (items, ec2_items) = Ec2Service::format_instance_list(&instances);
-                        self.items = items;
-                        self.ec2_items = ec2_items;
-
-                        if instances.is_empty() {
-                            self.status_message = format!(
-                                "No resources found for {}",
-                                self.get_active_service().as_str()
-                            );
-                            self.selected_index = 0;
-                        } else {
-                            self.status_message = format!(
-                                "Loaded {} resources ({})",
-                                instances.len(),
-                                self.get_active_service().as_str()
-                            );
-                            // Set selection to first item (skip header and separator)
-                            self.selected_index = 2;
-                        }
-                        self.error_message = None;
-                        Ok(())
-                    }
-                    Err(e) => self.handle_resource_error(e),
-                }
-            }
-            ServiceType::S3 => {
-                if let Some(path) = &self.current_path {
-                    // List objects in bucket/prefix
-                    let parts: Vec<&str> = path.splitn(2, '/').collect();
-                    let bucket = parts[0];
-                    let prefix = if parts.len() > 1 { parts[1] } else { "" };
+        // Filtering changes nothing in AWS
+        app.active_service = 0;
+        app.prompt_ec2_tag_filter();
+        assert!(app.prompt.is_some());
+    }
 
-                    match client.list_s3_objects(bucket, prefix).await {
-                        Ok(objects) => {
-                            self.loading_state = LoadingState::Loaded;
-                            let (items, s3_items) =
-                                S3Service::format_object_list(&objects, bucket, prefix);
-                            self.items = items;
-                            self.s3_items = s3_items;
-                            self.status_message = format!("Browsing s3://{}/{}", bucket, prefix);
-                            // Set selection to first item (skip header and separator)
-                            self.selected_index = 2;
-                        }
-                        Err(e) => self.handle_resource_error(e)?,
-                    }
-                    Ok(())
-                } else {
-                    match client.list_s3_buckets().await {
-                        Ok(buckets) => {
-                            self.loading_state = LoadingState::Loaded;
-                            let (items, s3_items) = S3Service::format_bucket_list(&buckets);
-                            self.items = items;
-                            self.s3_items = s3_items;
-                            if buckets.is_empty() {
-                                self.status_message = format!(
-                                    "No resources found for {}",
-                                    self.get_active_service().as_str()
-                                );
-                                self.selected_index = 0;
-                            } else {
-                                self.status_message = format!("Loaded {} buckets", buckets.len());
-                                // Set selection to first item (skip header and separator)
-                                self.selected_index = 2;
-                            }
-                            self.error_message = None;
-                            Ok(())
-                        }
-                        Err(e) => self.handle_resource_error(e),
-                    }
-                }
-            }
-            ServiceType::IAM => {
-                match client.list_iam_users().await {
-                    Ok(users) => {
-                        self.loading_state = LoadingState::Loaded;
-                        let (items, iam_items) = IamService::format_user_list(&users);
-                        self.items = items;
-                        self.iam_items = iam_items;
-
-                        if users.is_empty() {
-                            self.status_message = format!(
-                                "No resources found for {}",
-                                self.get_active_service().as_str()
-                            );
-                            self.selected_index = 0;
-                        } else {
-                            self.status_message = format!(
-                                "Loaded {} resources ({})",
-                                users.len(),
-                                self.get_active_service().as_str()
-                            );
-                            // Set selection to first item (skip header and separator)
-                            self.selected_index = 2;
-                        }
-                        self.error_message = None;
-                        Ok(())
-                    }
-                    Err(e) => self.handle_resource_error(e),
-                }
-            }
-            ServiceType::CloudWatch => match client.list_cloudwatch_alarms().await {
-                Ok(resources) => {
-                    self.loading_state = LoadingState::Loaded;
-                    if resources.is_empty() {
-                        self.items =
-                            vec![format!("No {} found", self.get_active_service().as_str())];
-                        self.status_message = format!(
-                            "No resources found for {}",
-                            self.get_active_service().as_str()
-                        );
-                    } else {
-                        self.items = resources;
-                        self.status_message = format!(
-                            "Loaded {} resources ({})",
-                            self.items.len(),
-                            self.get_active_service().as_str()
-                        );
-                    }
-                    self.selected_index = 0;
-                    self.error_message = None;
-                    Ok(())
-                }
-                Err(e) => self.handle_resource_error(e),
+    #[test]
+    fn test_dry_run_previews_mutating_prompts() {
+        let mut app = App::new();
+        app.read_only = true;
+        app.dry_run = true;
+        app.active_service = 4; // DynamoDB
+        app.dynamodb_items = vec![
+            DynamoDbItem::Header,
+            DynamoDbItem::Separator,
+            DynamoDbItem::Table {
+                name: "orders".to_string(),
+                status: "ACTIVE".to_string(),
             },
-            ServiceType::DynamoDB => {
-                match client.list_dynamodb_tables().await {
-                    Ok(tables) => {
-                        self.loading_state = LoadingState::Loaded;
-                        use crate::aws::DynamoDbService;
-                        let (items, dynamodb_items) = DynamoDbService::format_table_list(&tables);
-                        self.items = items;
-                        self.dynamodb_items = dynamodb_items;
-
-                        if tables.is_empty() {
-                            self.status_message = format!(
-                                "No resources found for {}",
-                                self.get_active_service().as_str()
-                            );
-                            self.selected_index = 0;
-                        } else {
-                            self.status_message = format!("Loaded {} tables", tables.len());
-                            // Set selection to first item (skip header and separator)
-                            self.selected_index = 2;
-                        }
-                        self.error_message = None;
-                        Ok(())
-                    }
-                    Err(e) => self.handle_resource_error(e),
-                }
-            }
-            ServiceType::ECS => {
-                if let Some(path) = &self.current_path {
-                    let parts: Vec<&str> = path.split('/').collect();
-                    if parts.len() == 1 {
-                        // List services in cluster
-                        let cluster = parts[0];
-                        match client.list_ecs_services(cluster).await {
-                            Ok(services) => {
-                                self.loading_state = LoadingState::Loaded;
-                                let (items, ecs_items) =
-                                    EcsService::format_service_list(&services, cluster);
-                                self.items = items;
-                                self.ecs_items = ecs_items;
-                                self.status_message = format!("Browsing cluster {}", cluster);
-                                self.selected_index = 2; // Skip header and separator
-                                Ok(())
-                            }
-                            Err(e) => self.handle_resource_error(e),
-                        }
-                    } else {
-                        // List tasks in service
-                        let cluster = parts[0];
-                        let service = parts[1];
-                        match client.list_ecs_tasks(cluster, Some(service)).await {
-                            Ok(tasks) => {
-                                self.loading_state = LoadingState::Loaded;
-                                let (items, ecs_items) =
-                                    EcsService::format_task_list(&tasks, cluster, Some(service));
-                                self.items = items;
-                                self.ecs_items = ecs_items;
-                                self.status_message =
-                                    format!("Browsing tasks in {}/{}", cluster, service);
-                                self.selected_index = 2; // Skip header and separator
-                                Ok(())
-                            }
-                            Err(e) => self.handle_resource_error(e),
-                        }
-                    }
-                } else {
-                    match client.list_ecs_clusters().await {
-                        Ok(clusters) => {
-                            self.loading_state = LoadingState::Loaded;
-                            let (items, ecs_items) = EcsService::format_cluster_list(&clusters);
-                            self.items = items;
-                            self.ecs_items = ecs_items;
-
-                            if clusters.is_empty() {
-                                self.status_message = format!(
-                                    "No resources found for {}",
-                                    self.get_active_service().as_str()
-                                );
-                                self.selected_index = 0;
-                            } else {
-                                self.status_message = format!("Loaded {} clusters", clusters.len());
-                                self.selected_index = 2;
-                            }
-                            self.error_message = None;
-                            Ok(())
-                        }
-                        Err(e) => self.handle_resource_error(e),
-                    }
-                }
-            }
-            ServiceType::MWAA => match client.list_mwaa_environments().await {
-                Ok(envs) => {
-                    self.loading_state = LoadingState::Loaded;
-                    let (items, mwaa_items) = MwaaService::format_environment_list(&envs);
-                    self.items = items;
-                    self.mwaa_items = mwaa_items;
+        ];
+        app.selected_index = 2;
 
-                    if envs.is_empty() {
-                        self.status_message = format!(
-                            "No resources found for {}",
-                            self.get_active_service().as_str()
-                        );
-                        self.selected_index = 0;
-                    } else {
-                        self.status_message = format!("Loaded {} environments", envs.len());
-                        self.selected_index = 2;
-                    }
-                    self.error_message = None;
-                    Ok(())
-                }
-                Err(e) => self.handle_resource_error(e),
-            },
-            ServiceType::Lambda => {
-                match client.list_lambda_functions().await {
-                    Ok(functions) => {
-                        self.loading_state = LoadingState::Loaded;
-                        let (items, lambda_items) = LambdaService::format_function_list(&functions);
-                        self.items = items;
-                        self.lambda_items = lambda_items;
-
-                        if functions.is_empty() {
-                            self.status_message = format!(
-                                "No resources found for {}",
-                                self.get_active_service().as_str()
-                            );
-                            self.selected_index = 0;
-                        } else {
-                            self.status_message = format!(
-                                "Loaded {} resources ({})",
-                                functions.len(),
-                                self.get_active_service().as_str()
-                            );
-                            // Set selection to first item (skip header and separator)
-                            self.selected_index = 2;
-                        }
-                        self.error_message = None;
-                        Ok(())
-                    }
-                    Err(e) => self.handle_resource_error(e),
-                }
-            }
-        }
+        // Nothing is changed, so read-only mode lets the prompt open, marked as a dry run
+        assert!(app.check_action(Action::Delete));
+        app.delete_selected_dynamodb_table();
+        let prompt = app.prompt.take().unwrap();
+        assert!(prompt.lines[0].starts_with("Dry run:"));
+
+        app.profile_name = "dev".to_string();
+        app.region = Some("eu-west-1".to_string());
+        app.report_dry_run(
+            "Would delete table orders".to_string(),
+            &["dynamodb", "delete-table", "--table-name", "orders"],
+        );
+        assert_eq!(
+            app.status_message,
+            "(dry-run) Would delete table orders: aws dynamodb delete-table --table-name orders \
+             --profile dev --region eu-west-1"
+        );
     }
 
-    fn handle_resource_error(&mut self, e: anyhow::Error) -> Result<()> {
-        self.loading_state = LoadingState::Error;
-        self.error_message = Some(format!("{}", e));
-        self.items = vec![
-            format!("Error loading {}", self.get_active_service().as_str()),
-            format!("Details: {}", e),
-            "".to_string(),
-            "Possible causes:".to_string(),
-            "- Invalid AWS credentials".to_string(),
-            "- Insufficient IAM permissions".to_string(),
-            "- Network connectivity issues".to_string(),
+    #[test]
+    fn test_dry_run_command_identity() {
+        let mut app = App::new();
+        app.profile_name = "dev".to_string();
+        app.region = Some("eu-west-1".to_string());
+        let args = ["dynamodb", "delete-table", "--table-name", "orders"];
+
+        // The role's credentials aren't in the profile, so naming it would act as the base
+        // identity; the command goes to the same endpoint as the app
+        app.client_options.assume_role = Some("arn:aws:iam::123456789012:role/Admin".to_string());
+        app.client_options.endpoint_url = Some("http://localhost:4566".to_string());
+        app.report_dry_run("Would delete table orders".to_string(), &args);
+        assert!(app.status_message.ends_with(
+            "aws dynamodb delete-table --table-name orders --region eu-west-1 \
+             --endpoint-url http://localhost:4566"
+        ));
+
+        // Injected keys would be overridden by a profile that may not even exist
+        app.client_options = ClientOptions::default();
+        app.env_credentials = Some("env credentials".to_string());
+        app.report_dry_run("Would delete table orders".to_string(), &args);
+        assert!(
+            app.status_message
+                .ends_with("aws dynamodb delete-table --table-name orders --region eu-west-1")
+        );
+    }
+
+    #[test]
+    fn test_detail_index_drill_down() {
+        let mut app = App::new();
+        app.show_detail_popup = true;
+        app.detail_content = vec![
+            ("Table Name".to_string(), "orders".to_string()),
+            ("Global Secondary Indexes".to_string(), "".to_string()),
+            ("".to_string(), "by-customer  ACTIVE  10".to_string()),
         ];
-        self.status_message = "Error: Failed to load resources".to_string();
-        Ok(())
+        app.detail_indexes = vec![IndexDetail {
+            row: 2,
+            name: "by-customer".to_string(),
+            details: vec![("Partition Key".to_string(), "customer (S)".to_string())],
+        }];
+
+        // Only index rows open
+        app.open_detail_index();
+        assert!(app.detail_parent.is_none());
+
+        app.detail_scroll = 2;
+        app.open_detail_index();
+        assert_eq!(app.detail_content[0].1, "customer (S)");
+        assert_eq!(app.detail_scroll, 0);
+
+        assert!(app.detail_back());
+        assert_eq!(app.detail_content.len(), 3);
+        assert_eq!(app.detail_scroll, 2);
+        assert!(!app.detail_back());
     }
 
-    pub fn tick_animation(&mut self) {
-        self.animation_frame = (self.animation_frame + 1) % 10;
+    #[test]
+    fn test_toggle_detail_json() {
+        let mut app = App::new();
+        app.detail_content = vec![
+            ("Name".to_string(), "fn".to_string()),
+            ("Environment".to_string(), r#"{"STAGE":"prod"}"#.to_string()),
+        ];
+
+        // Not JSON: nothing happens
+        app.toggle_detail_json();
+        assert!(app.detail_pretty.is_empty());
+
+        app.detail_scroll_down();
+        app.toggle_detail_json();
+        let rows = app.detail_rows();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[3], (1, Some(r#"  "STAGE": "prod""#.to_string())));
+        assert_eq!(app.detail_scroll, 1);
+
+        // Collapsing works from any of the JSON lines
+        app.detail_scroll = 3;
+        app.toggle_detail_json();
+        assert_eq!(app.detail_rows().len(), 2);
+        assert_eq!(app.detail_scroll, 1);
     }
 
-    pub fn get_loading_spinner(&self) -> &str {
-        const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
-        SPINNER_FRAMES[self.animation_frame]
+    #[test]
+    fn test_parse_create_table() {
+        assert_eq!(
+            parse_create_table("orders id"),
+            Some(("orders".to_string(), "id".to_string(), "S"))
+        );
+        assert_eq!(
+            parse_create_table(" events  ts:n "),
+            Some(("events".to_string(), "ts".to_string(), "N"))
+        );
+        assert_eq!(parse_create_table("orders"), None);
+        assert_eq!(parse_create_table("orders id:X"), None);
+        assert_eq!(parse_create_table("orders :S"), None);
+        assert_eq!(parse_create_table("orders id extra"), None);
     }
 
-    pub fn is_loading(&self) -> bool {
-        matches!(self.loading_state, LoadingState::Loading) || self.detail_loading
+    #[test]
+    fn test_delete_table_prompt() {
+        let mut app = App::new();
+        app.active_service = 4; // DynamoDB
+        app.dynamodb_items = vec![
+            DynamoDbItem::Header,
+            DynamoDbItem::Separator,
+            DynamoDbItem::Table {
+                name: "orders".to_string(),
+                status: "ACTIVE".to_string(),
+            },
+        ];
+
+        app.selected_index = 1;
+        app.delete_selected_dynamodb_table();
+        assert!(app.prompt.is_none());
+
+        app.selected_index = 2;
+        app.delete_selected_dynamodb_table();
+        let prompt = app.prompt.as_ref().unwrap();
+        assert_eq!(prompt.input.as_deref(), Some(""));
+        assert_eq!(
+            prompt.action,
+            PendingAction::DeleteDynamoDbTable {
+                table: "orders".to_string()
+            }
+        );
     }
 
-    pub fn show_quit_confirmation(&mut self) {
-        self.show_quit_confirm = true;
+    #[tokio::test]
+    async fn test_s3_bucket_prompts() {
+        let mut app = App::new();
+        app.active_service = 1; // S3
+        app.s3_items = vec![
+            S3Item::Header,
+            S3Item::Separator,
+            S3Item::Bucket("logs".to_string()),
+        ];
+        app.selected_index = 2;
+
+        app.delete_selected_resource().await.unwrap();
+        let prompt = app.prompt.take().unwrap();
+        assert_eq!(prompt.input, None);
+        assert_eq!(
+            prompt.action,
+            PendingAction::DeleteS3Bucket {
+                bucket: "logs".to_string()
+            }
+        );
+
+        app.prompt_create_resource();
+        assert_eq!(
+            app.prompt.take().map(|p| p.action),
+            Some(PendingAction::CreateS3Bucket)
+        );
+
+        // Buckets are only created from the bucket list
+        app.current_path = Some("logs/".to_string());
+        app.prompt_create_resource();
+        assert!(app.prompt.is_none());
     }
 
-    pub fn hide_quit_confirmation(&mut self) {
-        self.show_quit_confirm = false;
+    #[test]
+    fn test_paste_into_inputs() {
+        let mut app = App::new();
+        // Nothing takes text outside an input
+        app.paste("web");
+        assert!(app.search_query.is_empty());
+
+        app.start_search();
+        app.paste("web\n");
+        assert_eq!(app.search_query, "web");
+
+        app.toggle_service_popup();
+        app.paste("dyn");
+        assert_eq!(app.service_filter, "dyn");
+        assert_eq!(app.popup_services()[0], 4); // DynamoDB
+        app.toggle_service_popup();
+
+        app.toggle_palette();
+        app.paste("refresh");
+        assert_eq!(app.palette_filter, "refresh");
+        assert_eq!(app.search_query, "web");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_prefix_jump_prompt() {
+        let mut app = App::new();
+        app.active_service = 1; // S3
+        app.prompt_s3_prefix_jump();
+        assert!(app.prompt.is_none()); // Needs an open bucket
+
+        // Browsing isn't a change, so read-only mode allows it
+        app.read_only = true;
+        app.current_path = Some("logs/2024/".to_string());
+        app.prompt_s3_prefix_jump();
+        // A multi-line paste lands on the single input line
+        app.paste("05/\r\n01/\n");
+        let prompt = app.prompt.take().unwrap();
+        assert_eq!(prompt.input.as_deref(), Some("2024/05/ 01/"));
+        assert_eq!(
+            prompt.action,
+            PendingAction::JumpToS3Prefix {
+                bucket: "logs".to_string()
+            }
+        );
+    }
 
     #[test]
-    fn test_service_type() {
-        assert_eq!(ServiceType::EC2.short_name(), "EC2");
-        assert_eq!(ServiceType::S3.short_name(), "S3");
-        assert_eq!(ServiceType::IAM.short_name(), "IAM");
-        assert_eq!(ServiceType::CloudWatch.short_name(), "CloudWatch");
-        assert_eq!(ServiceType::DynamoDB.short_name(), "DynamoDB");
+    fn test_rename_instance_prompt() {
+        let mut app = App::new();
+        app.ec2_items = vec![
+            Ec2Item::Header,
+            Ec2Item::Separator,
+            Ec2Item::Instance {
+                id: "i-0abc".to_string(),
+                state: "Running".to_string(),
+                tags: vec![("Name".to_string(), "web-1".to_string())],
+            },
+        ];
 
-        assert_eq!(ServiceType::EC2.as_str(), "EC2 Instances");
+        app.selected_index = 0;
+        app.prompt_rename_ec2_instance();
+        assert!(app.prompt.is_none());
+
+        // Prefilled with the current name
+        app.selected_index = 2;
+        app.prompt_rename_ec2_instance();
+        let prompt = app.prompt.take().unwrap();
+        assert_eq!(prompt.input.as_deref(), Some("web-1"));
+        assert_eq!(
+            prompt.action,
+            PendingAction::RenameEc2Instance {
+                instance_id: "i-0abc".to_string()
+            }
+        );
+
+        app.read_only = true;
+        app.prompt_rename_ec2_instance();
+        assert!(app.prompt.is_none());
     }
 
     #[test]
-    fn test_app_initialization() {
-        let app = App::new();
-        assert_eq!(app.active_service, 0);
-        assert_eq!(app.selected_index, 0);
-        assert_eq!(app.loading_state, LoadingState::Idle);
-        assert!(app.services[0].favorite); // EC2 is favorite
+    fn test_parse_tag_filter() {
+        assert_eq!(
+            parse_tag_filter("Environment=prod"),
+            Some(("Environment".to_string(), "prod".to_string()))
+        );
+        assert_eq!(
+            parse_tag_filter(" Team = a=b "),
+            Some(("Team".to_string(), "a=b".to_string()))
+        );
+        assert_eq!(parse_tag_filter("Environment"), None);
+        assert_eq!(parse_tag_filter("=prod"), None);
     }
 
     #[test]
-    fn test_navigation() {
+    fn test_search_filter() {
         let mut app = App::new();
+        app.loading_state = LoadingState::Loaded;
         app.items = vec![
-            "Item 1".to_string(),
-            "Item 2".to_string(),
-            "Item 3".to_string(),
+            "Instance ID  Name".to_string(),
+            "-----------------".to_string(),
+            "i-1          web-1".to_string(),
+            "i-2          db-1".to_string(),
+            "i-3          WEB-2".to_string(),
         ];
+        let instance = |id: &str| Ec2Item::Instance {
+            id: id.to_string(),
+            state: "running".to_string(),
+            tags: Vec::new(),
+        };
+        app.ec2_items = vec![
+            Ec2Item::Header,
+            Ec2Item::Separator,
+            instance("i-1"),
+            instance("i-2"),
+            instance("i-3"),
+        ];
+        app.selected_index = 3;
+        assert_eq!(app.matched_resource_count(), None);
 
-        // Test next_item
-        app.next_item();
-        assert_eq!(app.selected_index, 1);
-        app.next_item();
+        app.start_search();
+        for c in "web".chars() {
+            app.search_input(c);
+        }
+        app.finish_search();
+
+        // Headers stay visible, the filter is case-insensitive
+        assert_eq!(app.visible_rows(), vec![0, 1, 2, 4]);
         assert_eq!(app.selected_index, 2);
+        assert_eq!(app.resource_count(), Some(3));
+        assert_eq!(app.matched_resource_count(), Some(2));
+
         app.next_item();
-        assert_eq!(app.selected_index, 0); // Wrap around
+        assert_eq!(app.selected_index, 4);
+        assert_eq!(app.scroll_position(), Some((Some(2), 2)));
 
-        // Test previous_item
-        app.previous_item();
-        assert_eq!(app.selected_index, 2); // Wrap around
-        app.previous_item();
-        assert_eq!(app.selected_index, 1);
+        app.clear_search();
+        assert_eq!(app.visible_rows().len(), 5);
+        assert_eq!(app.matched_resource_count(), None);
     }
 
     #[test]
-    fn test_is_selectable_s3() {
+    fn test_quit_without_confirmation() {
         let mut app = App::new();
-        // Switch to S3 (index 1)
-        app.active_service = 1;
+        app.show_quit_confirmation();
+        assert!(app.show_quit_confirm);
+        assert!(!app.should_quit);
 
-        // Mock S3 items
-        app.s3_items = vec![
-            S3Item::Header,
-            S3Item::Separator,
-            S3Item::Bucket("bucket1".to_string()),
-        ];
-        app.items = vec![
-            "Header".to_string(),
-            "---".to_string(),
-            "bucket1".to_string(),
-        ];
+        let mut app = App::with_options(
+            ClientOptions::default(),
+            Config {
+                confirm_quit: false,
+                ..Config::default()
+            },
+        );
+        app.show_quit_confirmation();
+        assert!(!app.show_quit_confirm);
+        assert!(app.should_quit);
+    }
 
-        // Header should not be selectable
-        assert!(!app.is_selectable(0));
-        // Separator should not be selectable
-        assert!(!app.is_selectable(1));
-        // Bucket should be selectable
-        assert!(app.is_selectable(2));
+    #[test]
+    fn test_restore_session() {
+        let config = Config {
+            active_service: Some(1),
+            region: Some("eu-west-1".to_string()),
+            ..Config::default()
+        };
+        let app = App::with_options(ClientOptions::default(), config.clone());
+        assert_eq!(app.active_service, 1);
+        assert_eq!(app.region.as_deref(), Some("eu-west-1"));
 
-        // Test navigation skipping non-selectable items
-        app.selected_index = 2;
-        app.next_item(); // Should wrap to 2 because 0 and 1 are not selectable
-        assert_eq!(app.selected_index, 2);
+        let options = ClientOptions {
+            region: Some("ap-south-1".to_string()),
+            ..ClientOptions::default()
+        };
+        let app = App::with_options(options, config);
+        assert_eq!(app.region.as_deref(), Some("ap-south-1"));
 
-        // If we force selection to 0 (which shouldn't happen normally but for test setup)
-        app.selected_index = 0;
-        app.next_item();
-        assert_eq!(app.selected_index, 2);
+        let config = Config {
+            active_service: Some(99),
+            ..Config::default()
+        };
+        let app = App::with_options(ClientOptions::default(), config);
+        assert_eq!(app.active_service, 0);
+
+        // The configured default wins over the last session
+        let config = Config {
+            default_service: Some("cloudwatch".to_string()),
+            active_service: Some(1),
+            ..Config::default()
+        };
+        let app = App::with_options(ClientOptions::default(), config);
+        assert_eq!(
+            app.get_active_service().service_type,
+            ServiceType::CloudWatch
+        );
+    }
+
+    #[test]
+    fn test_event_log_records_status_changes() {
+        let mut app = App::new();
+        app.set_status("first");
+        app.set_status("second");
+        assert_eq!(app.status_message, "second");
+        assert_eq!(app.event_log.len(), 2);
+        assert!(app.event_log[0].ends_with("] first"));
+
+        for i in 0..EVENT_LOG_CAPACITY {
+            app.set_status(format!("message {}", i));
+        }
+        assert_eq!(app.event_log.len(), EVENT_LOG_CAPACITY);
+        assert!(app.event_log[0].ends_with("] message 0"));
+
+        app.toggle_event_log();
+        assert!(app.show_event_log);
+        assert_eq!(app.event_log_scroll, EVENT_LOG_CAPACITY - 1);
+    }
+
+    #[test]
+    fn test_click_list_row() {
+        let mut app = App::new();
+        app.items = (0..10).map(|i| format!("Item {}", i)).collect();
+        app.list_area = Rect::new(0, 3, 40, 8);
+        app.list_offset = 4;
+
+        // First row inside the border maps to the first visible item
+        app.click_list_row(5, 4);
+        assert_eq!(app.selected_index, 4);
+        app.click_list_row(5, 6);
+        assert_eq!(app.selected_index, 6);
+
+        // Clicks on the border are ignored
+        app.click_list_row(5, 3);
+        assert_eq!(app.selected_index, 6);
+        app.click_list_row(0, 5);
+        assert_eq!(app.selected_index, 6);
+    }
+
+    #[test]
+    fn test_click_header_switches_service() {
+        let mut app = App::new();
+        app.header_service_areas = vec![(Rect::new(10, 1, 5, 1), 0), (Rect::new(17, 1, 4, 1), 1)];
+
+        app.click_header(18, 1);
+        assert_eq!(app.active_service, 1);
+        app.click_header(30, 1);
+        assert_eq!(app.active_service, 1);
     }
 }
@@ -1,5 +1,91 @@
+use crate::aws::utils::{
+    Paging, column_width, columns_width, display_width, fit_column_width, fit_to_width,
+};
 use anyhow::Result;
 use aws_sdk_cloudwatch::Client as CloudwatchClient;
+use aws_sdk_cloudwatch::primitives::DateTime;
+use aws_sdk_cloudwatch::types::{AlarmType, CompositeAlarm, Datapoint, StateValue, Statistic};
+use std::time::{Duration, SystemTime};
+
+/// Recent datapoints of an alarm's metric, oldest first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricSeries {
+    pub label: String, // e.g. "CPUUtilization (Average)"
+    pub values: Vec<f64>,
+    pub threshold: Option<f64>,
+    pub range: MetricRange,
+}
+
+/// How far back the alarm's metric chart reaches.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MetricRange {
+    #[default]
+    Hour,
+    SixHours,
+    Day,
+    Week,
+}
+
+impl MetricRange {
+    pub fn next(self) -> Self {
+        match self {
+            MetricRange::Hour => MetricRange::SixHours,
+            MetricRange::SixHours => MetricRange::Day,
+            MetricRange::Day => MetricRange::Week,
+            MetricRange::Week => MetricRange::Hour,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MetricRange::Hour => "1h",
+            MetricRange::SixHours => "6h",
+            MetricRange::Day => "24h",
+            MetricRange::Week => "7d",
+        }
+    }
+
+    fn duration(self) -> Duration {
+        let hours = match self {
+            MetricRange::Hour => 1,
+            MetricRange::SixHours => 6,
+            MetricRange::Day => 24,
+            MetricRange::Week => 24 * 7,
+        };
+        Duration::from_secs(hours * 3600)
+    }
+
+    // Seconds per datapoint: the alarm's own period where it fits, coarser for the longer
+    // ranges so a week stays at a chartable 168 points, well under the 1440 one call returns
+    fn period(self, alarm_period: Option<i32>) -> i32 {
+        let shortest = match self {
+            MetricRange::Hour => 60,
+            MetricRange::SixHours => 300,
+            MetricRange::Day => 900,
+            MetricRange::Week => 3600,
+        };
+        alarm_period
+            .unwrap_or(shortest)
+            .clamp(shortest, shortest * 5)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CloudwatchItem {
+    Header,
+    Separator,
+    Alarm { name: String, state: String },
+}
+
+// Firing alarms first, then the ones CloudWatch can't evaluate
+const ALARM_STATES: [&str; 3] = ["ALARM", "INSUFFICIENT_DATA", "OK"];
+
+fn state_rank(state: &str) -> usize {
+    ALARM_STATES
+        .iter()
+        .position(|s| *s == state)
+        .unwrap_or(ALARM_STATES.len())
+}
 
 #[derive(Clone)]
 pub struct CloudwatchService {
@@ -11,15 +97,494 @@ impl CloudwatchService {
         Self { client }
     }
 
-    pub async fn list_alarms(&self) -> Result<Vec<String>> {
-        let resp = self.client.describe_alarms().send().await?;
+    /// Alarm names with their state and whether they are composite, e.g. ("HighCPU", "ALARM",
+    /// false).
+    pub async fn list_alarms(&self, paging: Paging) -> Result<Vec<(String, String, bool)>> {
+        let mut alarms = Vec::new();
+        // Without alarm types only metric alarms come back
+        let mut pages = self
+            .client
+            .describe_alarms()
+            .alarm_types(AlarmType::MetricAlarm)
+            .alarm_types(AlarmType::CompositeAlarm)
+            .set_max_records(paging.page_size(1..=100))
+            .into_paginator()
+            .send();
+        while !paging.is_full(alarms.len())
+            && let Some(page) = pages.next().await
+        {
+            let page = page?;
+            let metric = page
+                .metric_alarms()
+                .iter()
+                .map(|a| (a.alarm_name(), a.state_value(), false));
+            let composite = page
+                .composite_alarms()
+                .iter()
+                .map(|a| (a.alarm_name(), a.state_value(), true));
+            for (name, state, is_composite) in metric.chain(composite) {
+                let Some(name) = name else {
+                    continue;
+                };
+                let state = state
+                    .map(|s| s.as_str().to_string())
+                    .unwrap_or_else(|| "UNKNOWN".to_string());
+                alarms.push((name.to_string(), state, is_composite));
+            }
+        }
+        Ok(alarms)
+    }
+
+    /// Alarms sorted by state, firing ones first, then by name. With `state` set only alarms
+    /// in that state are listed.
+    pub fn format_alarm_list(
+        alarms: &[(String, String, bool)],
+        state: Option<&str>,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<CloudwatchItem>) {
+        let mut alarms: Vec<&(String, String, bool)> = alarms
+            .iter()
+            .filter(|(_, s, _)| state.is_none_or(|state| s == state))
+            .collect();
+        if alarms.is_empty() {
+            let message = match state {
+                Some(state) => format!("No CloudWatch Alarms in {} state", state),
+                None => "No CloudWatch Alarms found".to_string(),
+            };
+            return (vec![message], vec![CloudwatchItem::Header]);
+        }
+        alarms.sort_by(|(a_name, a_state, _), (b_name, b_state, _)| {
+            state_rank(a_state)
+                .cmp(&state_rank(b_state))
+                .then_with(|| a_name.cmp(b_name))
+        });
+
+        let max_name_len = alarms
+            .iter()
+            .map(|(name, ..)| display_width(name))
+            .max()
+            .unwrap_or(20)
+            .max(20);
+        // A Type column only when there are composite alarms to tell apart
+        let typed = alarms.iter().any(|(_, _, composite)| *composite);
+        let state_len = column_width("State", alarms.iter().map(|(_, state, _)| state));
+        let rest_len = if typed {
+            columns_width(&[state_len, "Composite".len()])
+        } else {
+            columns_width(&[state_len])
+        };
+        let max_name_len = fit_column_width(
+            max_name_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
+        );
+        let row = |name: &str, state: &str, kind: &str| {
+            let name = fit_to_width(name, max_name_len);
+            if typed {
+                format!("{}  {:<width$}  {}", name, state, kind, width = state_len)
+                    .trim_end()
+                    .to_string()
+            } else {
+                format!("{}  {}", name, state)
+            }
+        };
+
+        let header = row("Alarm Name", "State", "Type");
+        let separator = "-".repeat(max_name_len + rest_len);
 
-        let alarms: Vec<String> = resp
+        let mut items = vec![header, separator];
+        let mut cloudwatch_items = vec![CloudwatchItem::Header, CloudwatchItem::Separator];
+        for (name, state, composite) in alarms {
+            items.push(row(name, state, if *composite { "Composite" } else { "" }));
+            cloudwatch_items.push(CloudwatchItem::Alarm {
+                name: name.clone(),
+                state: state.clone(),
+            });
+        }
+        (items, cloudwatch_items)
+    }
+
+    /// "Alarms: 2 ALARM, 40 OK", leaving out states no alarm is in.
+    pub fn summarize_states(alarms: &[(String, String, bool)]) -> String {
+        let mut counts: Vec<(usize, &str, usize)> = Vec::new();
+        for (_, state, _) in alarms {
+            match counts.iter_mut().find(|(_, s, _)| *s == state) {
+                Some((_, _, count)) => *count += 1,
+                None => counts.push((state_rank(state), state, 1)),
+            }
+        }
+        counts.sort();
+        let parts: Vec<String> = counts
+            .iter()
+            .map(|(_, state, count)| format!("{} {}", count, state))
+            .collect();
+        format!("Alarms: {}", parts.join(", "))
+    }
+
+    /// Cycles the alarm list filter: all → ALARM → INSUFFICIENT_DATA → all.
+    pub fn next_state_filter(current: Option<&str>) -> Option<String> {
+        match current {
+            None => Some("ALARM".to_string()),
+            Some("ALARM") => Some("INSUFFICIENT_DATA".to_string()),
+            Some(_) => None,
+        }
+    }
+
+    /// Alarm configuration plus its metric over `range`. Metric-math alarms have no single
+    /// metric to chart, so the series is `None` for them.
+    pub async fn describe_alarm(
+        &self,
+        alarm_name: &str,
+        range: MetricRange,
+    ) -> Result<(Vec<(String, String)>, Option<MetricSeries>)> {
+        let resp = self
+            .client
+            .describe_alarms()
+            .alarm_names(alarm_name)
+            .alarm_types(AlarmType::MetricAlarm)
+            .alarm_types(AlarmType::CompositeAlarm)
+            .send()
+            .await?;
+        if let Some(alarm) = resp.composite_alarms().first() {
+            return Ok((self.describe_composite_alarm(alarm).await?, None));
+        }
+        let alarm = resp
             .metric_alarms()
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Alarm {} not found", alarm_name))?;
+
+        let mut details = vec![
+            ("Alarm Name".to_string(), alarm_name.to_string()),
+            (
+                "State".to_string(),
+                alarm
+                    .state_value()
+                    .map(|s| s.as_str().to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "Reason".to_string(),
+                alarm.state_reason().unwrap_or("-").to_string(),
+            ),
+        ];
+        if let Some(description) = alarm.alarm_description() {
+            details.push(("Description".to_string(), description.to_string()));
+        }
+
+        let (Some(namespace), Some(metric_name)) = (alarm.namespace(), alarm.metric_name()) else {
+            details.push((
+                "Metric".to_string(),
+                "No single metric (metric math alarm)".to_string(),
+            ));
+            return Ok((details, None));
+        };
+
+        let statistic = alarm
+            .extended_statistic()
+            .map(String::from)
+            .or_else(|| alarm.statistic().map(|s| s.as_str().to_string()))
+            .unwrap_or_else(|| "Average".to_string());
+        details.push((
+            "Metric".to_string(),
+            format!("{}/{} ({})", namespace, metric_name, statistic),
+        ));
+        for dimension in alarm.dimensions() {
+            details.push((
+                format!("  {}", dimension.name().unwrap_or("?")),
+                dimension.value().unwrap_or("?").to_string(),
+            ));
+        }
+        if let (Some(operator), Some(threshold)) = (alarm.comparison_operator(), alarm.threshold())
+        {
+            details.push((
+                "Threshold".to_string(),
+                format!("{} {}", operator.as_str(), threshold),
+            ));
+        }
+
+        let end = SystemTime::now();
+        let start = end - range.duration();
+        let mut request = self
+            .client
+            .get_metric_statistics()
+            .namespace(namespace)
+            .metric_name(metric_name)
+            .set_dimensions(Some(alarm.dimensions().to_vec()))
+            .start_time(DateTime::from(start))
+            .end_time(DateTime::from(end))
+            .period(range.period(alarm.period()));
+        request = if alarm.extended_statistic().is_some() {
+            request.extended_statistics(&statistic)
+        } else {
+            request.statistics(Statistic::from(statistic.as_str()))
+        };
+        let stats = request.send().await?;
+
+        let mut datapoints: Vec<&Datapoint> = stats.datapoints().iter().collect();
+        datapoints.sort_by_key(|d| d.timestamp().map(|t| t.secs()));
+        let values = datapoints
             .iter()
-            .filter_map(|a| a.alarm_name().map(String::from))
+            .filter_map(|d| datapoint_value(d, &statistic))
             .collect();
 
-        Ok(alarms)
+        Ok((
+            details,
+            Some(MetricSeries {
+                label: format!("{} ({})", metric_name, statistic),
+                values,
+                threshold: alarm.threshold(),
+                range,
+            }),
+        ))
+    }
+
+    // A composite alarm's rule and the current state of each alarm the rule references
+    async fn describe_composite_alarm(
+        &self,
+        alarm: &CompositeAlarm,
+    ) -> Result<Vec<(String, String)>> {
+        let mut details = vec![
+            (
+                "Alarm Name".to_string(),
+                alarm.alarm_name().unwrap_or_default().to_string(),
+            ),
+            ("Type".to_string(), "Composite".to_string()),
+            (
+                "State".to_string(),
+                alarm
+                    .state_value()
+                    .map(|s| s.as_str().to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "Reason".to_string(),
+                alarm.state_reason().unwrap_or("-").to_string(),
+            ),
+        ];
+        if let Some(description) = alarm.alarm_description() {
+            details.push(("Description".to_string(), description.to_string()));
+        }
+        let rule = alarm.alarm_rule().unwrap_or_default();
+        details.push(("Rule".to_string(), rule.to_string()));
+
+        let children = rule_children(rule);
+        if children.is_empty() {
+            return Ok(details);
+        }
+        let resp = self
+            .client
+            .describe_alarms()
+            // The call takes up to 100 names
+            .set_alarm_names(Some(children.iter().take(100).cloned().collect()))
+            .alarm_types(AlarmType::MetricAlarm)
+            .alarm_types(AlarmType::CompositeAlarm)
+            .send()
+            .await?;
+        let metric = resp
+            .metric_alarms()
+            .iter()
+            .map(|a| (a.alarm_name(), a.state_value()));
+        let composite = resp
+            .composite_alarms()
+            .iter()
+            .map(|a| (a.alarm_name(), a.state_value()));
+        let states: Vec<(&str, &str)> = metric
+            .chain(composite)
+            .filter_map(|(name, state)| Some((name?, state?.as_str())))
+            .collect();
+
+        details.push(("Child Alarms".to_string(), children.len().to_string()));
+        for child in children {
+            let state = states
+                .iter()
+                .find(|(name, _)| *name == child)
+                .map_or("not found", |(_, state)| state);
+            details.push((format!("  {}", child), state.to_string()));
+        }
+        Ok(details)
+    }
+
+    pub async fn count_alarms_firing(&self) -> Result<usize> {
+        let mut count = 0;
+        let mut pages = self
+            .client
+            .describe_alarms()
+            .alarm_types(AlarmType::MetricAlarm)
+            .alarm_types(AlarmType::CompositeAlarm)
+            .state_value(StateValue::Alarm)
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            let page = page?;
+            count += page.metric_alarms().len() + page.composite_alarms().len();
+        }
+        Ok(count)
+    }
+}
+
+/// Names of the alarms a composite alarm rule references, in order and without repeats, e.g.
+/// `["cpu", "disk"]` from `ALARM("cpu") OR OK(disk)`. ARNs are reduced to the alarm name.
+pub fn rule_children(rule: &str) -> Vec<String> {
+    const FUNCTIONS: [&str; 3] = ["ALARM(", "OK(", "INSUFFICIENT_DATA("];
+    let mut children: Vec<String> = Vec::new();
+    let mut rest = rule;
+    while let Some((start, function)) = FUNCTIONS
+        .iter()
+        .filter_map(|f| rest.find(f).map(|i| (i, f)))
+        .min()
+    {
+        let after = &rest[start + function.len()..];
+        let Some(end) = after.find(')') else {
+            break;
+        };
+        let name = after[..end].trim().trim_matches('"');
+        let name = name.rsplit_once(":alarm:").map_or(name, |(_, name)| name);
+        if !name.is_empty() && !children.iter().any(|c| c == name) {
+            children.push(name.to_string());
+        }
+        rest = &after[end + 1..];
+    }
+    children
+}
+
+fn datapoint_value(datapoint: &Datapoint, statistic: &str) -> Option<f64> {
+    match statistic {
+        "Average" => datapoint.average(),
+        "Sum" => datapoint.sum(),
+        "Minimum" => datapoint.minimum(),
+        "Maximum" => datapoint.maximum(),
+        "SampleCount" => datapoint.sample_count(),
+        percentile => datapoint.extended_statistics()?.get(percentile).copied(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alarms() -> Vec<(String, String, bool)> {
+        [
+            ("cpu-high", "OK"),
+            ("disk-full", "ALARM"),
+            ("api-errors", "ALARM"),
+            ("queue-depth", "INSUFFICIENT_DATA"),
+            ("latency", "OK"),
+        ]
+        .iter()
+        .map(|(name, state)| (name.to_string(), state.to_string(), false))
+        .collect()
+    }
+
+    #[test]
+    fn test_format_alarm_list() {
+        let (items, cloudwatch_items) =
+            CloudwatchService::format_alarm_list(&alarms(), None, None, usize::MAX);
+        assert!(items[0].starts_with("Alarm Name"));
+        let names: Vec<&str> = cloudwatch_items
+            .iter()
+            .filter_map(|item| match item {
+                CloudwatchItem::Alarm { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "api-errors",
+                "disk-full",
+                "queue-depth",
+                "cpu-high",
+                "latency"
+            ]
+        );
+        assert!(items[2].ends_with("ALARM"));
+
+        let (items, cloudwatch_items) =
+            CloudwatchService::format_alarm_list(&alarms(), Some("ALARM"), None, usize::MAX);
+        assert_eq!(items.len(), 4);
+        assert_eq!(cloudwatch_items.len(), 4);
+
+        let (items, _) = CloudwatchService::format_alarm_list(
+            &alarms()[..1],
+            Some("INSUFFICIENT_DATA"),
+            None,
+            usize::MAX,
+        );
+        assert_eq!(
+            items,
+            vec!["No CloudWatch Alarms in INSUFFICIENT_DATA state"]
+        );
+        assert!(!items[0].contains("Type"));
+
+        // Composite alarms are listed with the others and marked in a Type column
+        let mut with_composite = alarms();
+        with_composite.push(("service-down".to_string(), "ALARM".to_string(), true));
+        let (items, _) =
+            CloudwatchService::format_alarm_list(&with_composite, None, None, usize::MAX);
+        assert!(items[0].ends_with("Type"));
+        assert!(items[2].starts_with("api-errors") && items[2].ends_with("ALARM"));
+        assert!(items[4].starts_with("service-down") && items[4].ends_with("Composite"));
+        assert_eq!(items[0].find("Type"), items[4].find("Composite"));
+    }
+
+    #[test]
+    fn test_rule_children() {
+        assert_eq!(
+            rule_children(
+                "ALARM(\"cpu-high\") OR (OK(disk) AND NOT INSUFFICIENT_DATA(\"cpu-high\"))"
+            ),
+            vec!["cpu-high", "disk"]
+        );
+        assert_eq!(
+            rule_children("ALARM(arn:aws:cloudwatch:us-east-1:123456789012:alarm:api-errors)"),
+            vec!["api-errors"]
+        );
+        assert!(rule_children("TRUE").is_empty());
+    }
+
+    #[test]
+    fn test_metric_range() {
+        let mut range = MetricRange::default();
+        let mut labels = Vec::new();
+        for _ in 0..4 {
+            labels.push(range.label());
+            range = range.next();
+        }
+        assert_eq!(labels, ["1h", "6h", "24h", "7d"]);
+        assert_eq!(range, MetricRange::Hour);
+
+        // The alarm's period is kept when it fits the range
+        assert_eq!(MetricRange::Hour.period(Some(60)), 60);
+        assert_eq!(MetricRange::Hour.period(Some(86400)), 300);
+        assert_eq!(MetricRange::Day.period(Some(60)), 900);
+        assert_eq!(
+            MetricRange::Week.duration().as_secs() / MetricRange::Week.period(None) as u64,
+            168
+        );
+    }
+
+    #[test]
+    fn test_alarm_states() {
+        assert_eq!(
+            CloudwatchService::summarize_states(&alarms()),
+            "Alarms: 2 ALARM, 1 INSUFFICIENT_DATA, 2 OK"
+        );
+
+        let mut filter = None;
+        let mut seen = Vec::new();
+        for _ in 0..3 {
+            filter = CloudwatchService::next_state_filter(filter.as_deref());
+            seen.push(filter.clone());
+        }
+        assert_eq!(
+            seen,
+            vec![
+                Some("ALARM".to_string()),
+                Some("INSUFFICIENT_DATA".to_string()),
+                None
+            ]
+        );
     }
 }
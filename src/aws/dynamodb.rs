@@ -1,6 +1,15 @@
-use crate::aws::utils::format_size;
+use crate::aws::utils::{
+    Paging, Progress, columns_width, display_width, fit_column_width, fit_to_width, format_size,
+    join_columns, json_fields, json_time, shown_columns,
+};
 use anyhow::Result;
 use aws_sdk_dynamodb::Client;
+use aws_sdk_dynamodb::types::{
+    AttributeDefinition, BillingMode, GlobalSecondaryIndexDescription, KeySchemaElement, KeyType,
+    PointInTimeRecoveryStatus, ProvisionedThroughputDescription, ScalarAttributeType,
+    TableDescription,
+};
+use serde_json::{Value, json};
 
 #[derive(Clone)]
 pub struct DynamoDbService {
@@ -11,7 +20,15 @@ pub struct DynamoDbService {
 pub enum DynamoDbItem {
     Header,
     Separator,
-    Table(String),
+    Table { name: String, status: String },
+}
+
+/// A global secondary index listed in the table details, with the details of its own view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexDetail {
+    pub row: usize, // Position of the index's row in the table details
+    pub name: String,
+    pub details: Vec<(String, String)>,
 }
 
 impl DynamoDbService {
@@ -19,13 +36,38 @@ impl DynamoDbService {
         Self { client }
     }
 
-    pub async fn list_tables_with_details(&self) -> Result<Vec<(String, String, String, String)>> {
-        let resp = self.client.list_tables().send().await?;
-        let table_names = resp.table_names.unwrap_or_default();
+    /// Every table with its status, item count and size; one DescribeTable call per table.
+    pub async fn list_tables_with_details(
+        &self,
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<Vec<(String, String, String, String)>> {
+        let mut table_names = Vec::new();
+        let mut pages = self
+            .client
+            .list_tables()
+            .set_limit(paging.page_size(1..=100))
+            .into_paginator()
+            .send();
+        while !paging.is_full(table_names.len())
+            && let Some(page) = pages.next().await
+        {
+            table_names.extend(page?.table_names().iter().cloned());
+        }
+        // Only the tables that will be shown get described, plus one to flag the cap
+        if let Some(max) = paging.max_items {
+            table_names.truncate(max + 1);
+        }
 
+        let total = table_names.len();
         let mut tables = Vec::new();
 
         for table_name in table_names {
+            progress.report(format!(
+                "Describing table {} of {}...",
+                tables.len() + 1,
+                total
+            ));
             match self
                 .client
                 .describe_table()
@@ -62,17 +104,82 @@ impl DynamoDbService {
         Ok(tables)
     }
 
-    pub async fn describe_table(&self, table_name: &str) -> Result<Vec<(String, String)>> {
+    /// Creates an on-demand table keyed only by `partition_key`. `key_type` is "S", "N" or "B".
+    pub async fn create_table(
+        &self,
+        table_name: &str,
+        partition_key: &str,
+        key_type: &str,
+    ) -> Result<()> {
+        self.client
+            .create_table()
+            .table_name(table_name)
+            .attribute_definitions(
+                AttributeDefinition::builder()
+                    .attribute_name(partition_key)
+                    .attribute_type(ScalarAttributeType::from(key_type))
+                    .build()?,
+            )
+            .key_schema(
+                KeySchemaElement::builder()
+                    .attribute_name(partition_key)
+                    .key_type(KeyType::Hash)
+                    .build()?,
+            )
+            .billing_mode(BillingMode::PayPerRequest)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_table(&self, table_name: &str) -> Result<()> {
+        self.client
+            .delete_table()
+            .table_name(table_name)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    /// The table as `describe-table` returns it, for the raw JSON view.
+    pub async fn describe_table_raw(&self, table_name: &str) -> Result<Value> {
         let resp = self
             .client
             .describe_table()
             .table_name(table_name)
             .send()
             .await?;
+        resp.table()
+            .map(table_json)
+            .ok_or_else(|| anyhow::anyhow!("Table {} not found", table_name))
+    }
+
+    /// Table details plus a drill-down for each global secondary index.
+    pub async fn describe_table(
+        &self,
+        table_name: &str,
+    ) -> Result<(Vec<(String, String)>, Vec<IndexDetail>)> {
+        // Backup settings need their own calls; only the table itself is required
+        let (resp, continuous_backups, backups) = tokio::join!(
+            self.client.describe_table().table_name(table_name).send(),
+            self.client
+                .describe_continuous_backups()
+                .table_name(table_name)
+                .send(),
+            self.count_backups(table_name),
+        );
+        let resp = resp?;
 
         let mut details = Vec::new();
+        let mut indexes = Vec::new();
 
         if let Some(table) = resp.table {
+            let on_demand = table
+                .billing_mode_summary
+                .as_ref()
+                .and_then(|b| b.billing_mode.as_ref())
+                == Some(&BillingMode::PayPerRequest);
+
             details.push((
                 "Table Name".to_string(),
                 table.table_name.unwrap_or_default(),
@@ -84,6 +191,24 @@ impl DynamoDbService {
                     .map(|s| s.as_str().to_string())
                     .unwrap_or_default(),
             ));
+            let pitr = match continuous_backups {
+                Ok(output) => pitr_label(
+                    output
+                        .continuous_backups_description()
+                        .and_then(|c| c.point_in_time_recovery_description())
+                        .and_then(|p| p.point_in_time_recovery_status()),
+                )
+                .to_string(),
+                Err(e) => format!("Error: {}", e),
+            };
+            details.push(("Point-in-Time Recovery".to_string(), pitr));
+            details.push((
+                "On-Demand Backups".to_string(),
+                match backups {
+                    Ok(count) => count.to_string(),
+                    Err(e) => format!("Error: {}", e),
+                },
+            ));
             details.push((
                 "Item Count".to_string(),
                 table.item_count.unwrap_or(0).to_string(),
@@ -131,6 +256,15 @@ impl DynamoDbService {
                         .map(|s| s.as_str())
                         .unwrap_or("?");
                     let items = index.item_count.unwrap_or(0);
+                    indexes.push(IndexDetail {
+                        row: details.len(),
+                        name: name.to_string(),
+                        details: gsi_details(
+                            index,
+                            table.attribute_definitions.as_deref().unwrap_or_default(),
+                            on_demand,
+                        ),
+                    });
                     details.push((
                         "".to_string(),
                         format!("{:<30} {:<15} {:<10}", name, status, items),
@@ -161,11 +295,39 @@ impl DynamoDbService {
             }
         }
 
-        Ok(details)
+        Ok((details, indexes))
+    }
+
+    // On-demand backups of one table, following ListBackups pages
+    async fn count_backups(&self, table_name: &str) -> Result<usize> {
+        let mut count = 0;
+        let mut start_arn: Option<String> = None;
+        loop {
+            let resp = self
+                .client
+                .list_backups()
+                .table_name(table_name)
+                .set_exclusive_start_backup_arn(start_arn)
+                .send()
+                .await?;
+            count += resp.backup_summaries().len();
+            match resp.last_evaluated_backup_arn() {
+                Some(arn) => start_arn = Some(arn.to_string()),
+                None => return Ok(count),
+            }
+        }
     }
 
+    /// Columns of the table list that can be hidden, by config name and header.
+    pub const TABLE_COLUMNS: [(&str, &str); 3] =
+        [("status", "Status"), ("items", "Items"), ("size", "Size")];
+
+    /// The tables, leaving out the `TABLE_COLUMNS` named in `hidden`.
     pub fn format_table_list(
         tables: &[(String, String, String, String)],
+        hidden: &[&str],
+        name_width: Option<usize>,
+        max_width: usize,
     ) -> (Vec<String>, Vec<DynamoDbItem>) {
         if tables.is_empty() {
             return (
@@ -176,35 +338,397 @@ impl DynamoDbService {
 
         let max_name_len = tables
             .iter()
-            .map(|(name, _, _, _)| name.len())
+            .map(|(name, _, _, _)| display_width(name))
             .max()
             .unwrap_or(20)
             .max(20);
-
-        let header = format!(
-            "{:<width$}  {:<10}  {:<12}  Size",
-            "Table Name",
-            "Status",
-            "Items",
-            width = max_name_len
+        let rows = tables
+            .iter()
+            .map(|(_, status, count, size)| vec![status.clone(), count.clone(), size.clone()])
+            .collect();
+        let (headers, cells, widths) = shown_columns(&Self::TABLE_COLUMNS, hidden, rows);
+        let rest_len = columns_width(&widths);
+        let max_name_len = fit_column_width(
+            max_name_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
         );
-        let separator = "-".repeat(max_name_len + 40).to_string();
+
+        let header = fit_to_width("Table Name", max_name_len) + &join_columns(&headers, &widths);
+        let separator = "-".repeat(max_name_len + rest_len);
 
         let mut items = vec![header, separator];
         let mut dynamodb_items = vec![DynamoDbItem::Header, DynamoDbItem::Separator];
 
-        for (name, status, item_count, size) in tables {
-            items.push(format!(
-                "{:<width$}  {:<10}  {:<12}  {}",
-                name,
-                status,
-                item_count,
-                size,
-                width = max_name_len
-            ));
-            dynamodb_items.push(DynamoDbItem::Table(name.clone()));
+        for ((name, status, _, _), values) in tables.iter().zip(&cells) {
+            items.push(fit_to_width(name, max_name_len) + &join_columns(values, &widths));
+            dynamodb_items.push(DynamoDbItem::Table {
+                name: name.clone(),
+                status: status.clone(),
+            });
         }
 
         (items, dynamodb_items)
     }
 }
+
+// Tables that never had PITR configured report no status at all
+fn pitr_label(status: Option<&PointInTimeRecoveryStatus>) -> &'static str {
+    match status {
+        Some(PointInTimeRecoveryStatus::Enabled) => "ENABLED",
+        _ => "DISABLED",
+    }
+}
+
+// Keys, projection and capacity of one GSI. Key types come from the table's attribute
+// definitions since the index key schema only names the attributes.
+fn gsi_details(
+    index: &GlobalSecondaryIndexDescription,
+    attributes: &[AttributeDefinition],
+    on_demand: bool,
+) -> Vec<(String, String)> {
+    let key = |key_type: KeyType| {
+        index
+            .key_schema()
+            .iter()
+            .find(|k| k.key_type == key_type)
+            .map(|k| {
+                let scalar = attributes
+                    .iter()
+                    .find(|a| a.attribute_name == k.attribute_name)
+                    .map(|a| a.attribute_type.as_str())
+                    .unwrap_or("?");
+                format!("{} ({})", k.attribute_name, scalar)
+            })
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    let mut details = vec![
+        (
+            "Index Name".to_string(),
+            index.index_name().unwrap_or("?").to_string(),
+        ),
+        (
+            "Status".to_string(),
+            index
+                .index_status()
+                .map(|s| s.as_str().to_string())
+                .unwrap_or_default(),
+        ),
+        ("Partition Key".to_string(), key(KeyType::Hash)),
+        ("Sort Key".to_string(), key(KeyType::Range)),
+    ];
+
+    let projection = index.projection();
+    details.push((
+        "Projection".to_string(),
+        projection
+            .and_then(|p| p.projection_type())
+            .map(|t| t.as_str().to_string())
+            .unwrap_or_else(|| "-".to_string()),
+    ));
+    if let Some(attributes) = projection.map(|p| p.non_key_attributes())
+        && !attributes.is_empty()
+    {
+        details.push(("Projected Attributes".to_string(), attributes.join(", ")));
+    }
+
+    details.push((
+        "Item Count".to_string(),
+        index.item_count().unwrap_or(0).to_string(),
+    ));
+    details.push((
+        "Size".to_string(),
+        format_size(index.index_size_bytes().unwrap_or(0)),
+    ));
+
+    match index.provisioned_throughput() {
+        Some(throughput) if !on_demand => {
+            details.push((
+                "Read Capacity".to_string(),
+                throughput.read_capacity_units().unwrap_or(0).to_string(),
+            ));
+            details.push((
+                "Write Capacity".to_string(),
+                throughput.write_capacity_units().unwrap_or(0).to_string(),
+            ));
+        }
+        _ => details.push(("Capacity".to_string(), "On-demand".to_string())),
+    }
+    details
+}
+
+pub fn table_json(table: &TableDescription) -> Value {
+    let text = |s: Option<&str>| s.map(|s| json!(s));
+    let key_schema = |keys: &[KeySchemaElement]| {
+        Some(Value::Array(
+            keys.iter()
+                .map(|k| {
+                    json_fields(vec![
+                        ("AttributeName", Some(json!(k.attribute_name()))),
+                        ("KeyType", Some(json!(k.key_type().as_str()))),
+                    ])
+                })
+                .collect(),
+        ))
+    };
+    let throughput = |t: Option<&ProvisionedThroughputDescription>| {
+        t.map(|t| {
+            json_fields(vec![
+                (
+                    "ReadCapacityUnits",
+                    t.read_capacity_units().map(|n| json!(n)),
+                ),
+                (
+                    "WriteCapacityUnits",
+                    t.write_capacity_units().map(|n| json!(n)),
+                ),
+                (
+                    "NumberOfDecreasesToday",
+                    t.number_of_decreases_today().map(|n| json!(n)),
+                ),
+                (
+                    "LastIncreaseDateTime",
+                    json_time(t.last_increase_date_time()),
+                ),
+                (
+                    "LastDecreaseDateTime",
+                    json_time(t.last_decrease_date_time()),
+                ),
+            ])
+        })
+    };
+
+    json_fields(vec![
+        ("TableName", text(table.table_name())),
+        ("TableArn", text(table.table_arn())),
+        ("TableId", text(table.table_id())),
+        (
+            "TableStatus",
+            text(table.table_status().map(|s| s.as_str())),
+        ),
+        ("CreationDateTime", json_time(table.creation_date_time())),
+        (
+            "AttributeDefinitions",
+            Some(Value::Array(
+                table
+                    .attribute_definitions()
+                    .iter()
+                    .map(|a| {
+                        json_fields(vec![
+                            ("AttributeName", Some(json!(a.attribute_name()))),
+                            ("AttributeType", Some(json!(a.attribute_type().as_str()))),
+                        ])
+                    })
+                    .collect(),
+            )),
+        ),
+        ("KeySchema", key_schema(table.key_schema())),
+        (
+            "BillingModeSummary",
+            table.billing_mode_summary().map(|b| {
+                json_fields(vec![(
+                    "BillingMode",
+                    text(b.billing_mode().map(|m| m.as_str())),
+                )])
+            }),
+        ),
+        (
+            "ProvisionedThroughput",
+            throughput(table.provisioned_throughput()),
+        ),
+        ("TableSizeBytes", table.table_size_bytes().map(|n| json!(n))),
+        ("ItemCount", table.item_count().map(|n| json!(n))),
+        (
+            "GlobalSecondaryIndexes",
+            Some(Value::Array(
+                table
+                    .global_secondary_indexes()
+                    .iter()
+                    .map(|index| {
+                        json_fields(vec![
+                            ("IndexName", text(index.index_name())),
+                            ("IndexArn", text(index.index_arn())),
+                            (
+                                "IndexStatus",
+                                text(index.index_status().map(|s| s.as_str())),
+                            ),
+                            ("KeySchema", key_schema(index.key_schema())),
+                            (
+                                "Projection",
+                                index.projection().map(|p| {
+                                    json_fields(vec![
+                                        (
+                                            "ProjectionType",
+                                            text(p.projection_type().map(|t| t.as_str())),
+                                        ),
+                                        ("NonKeyAttributes", Some(json!(p.non_key_attributes()))),
+                                    ])
+                                }),
+                            ),
+                            (
+                                "ProvisionedThroughput",
+                                throughput(index.provisioned_throughput()),
+                            ),
+                            ("IndexSizeBytes", index.index_size_bytes().map(|n| json!(n))),
+                            ("ItemCount", index.item_count().map(|n| json!(n))),
+                        ])
+                    })
+                    .collect(),
+            )),
+        ),
+        (
+            "LocalSecondaryIndexes",
+            Some(Value::Array(
+                table
+                    .local_secondary_indexes()
+                    .iter()
+                    .map(|index| {
+                        json_fields(vec![
+                            ("IndexName", text(index.index_name())),
+                            ("IndexArn", text(index.index_arn())),
+                            ("KeySchema", key_schema(index.key_schema())),
+                            ("IndexSizeBytes", index.index_size_bytes().map(|n| json!(n))),
+                            ("ItemCount", index.item_count().map(|n| json!(n))),
+                        ])
+                    })
+                    .collect(),
+            )),
+        ),
+        (
+            "StreamSpecification",
+            table.stream_specification().map(|s| {
+                json_fields(vec![
+                    ("StreamEnabled", Some(json!(s.stream_enabled()))),
+                    (
+                        "StreamViewType",
+                        text(s.stream_view_type().map(|v| v.as_str())),
+                    ),
+                ])
+            }),
+        ),
+        ("LatestStreamArn", text(table.latest_stream_arn())),
+        ("LatestStreamLabel", text(table.latest_stream_label())),
+        (
+            "SSEDescription",
+            table.sse_description().map(|s| {
+                json_fields(vec![
+                    ("Status", text(s.status().map(|v| v.as_str()))),
+                    ("SSEType", text(s.sse_type().map(|v| v.as_str()))),
+                    ("KMSMasterKeyArn", text(s.kms_master_key_arn())),
+                ])
+            }),
+        ),
+        (
+            "TableClassSummary",
+            table.table_class_summary().map(|c| {
+                json_fields(vec![(
+                    "TableClass",
+                    text(c.table_class().map(|v| v.as_str())),
+                )])
+            }),
+        ),
+        (
+            "DeletionProtectionEnabled",
+            table.deletion_protection_enabled().map(|b| json!(b)),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::types::{Projection, ProjectionType, ProvisionedThroughputDescription};
+
+    #[test]
+    fn test_pitr_label() {
+        assert_eq!(
+            pitr_label(Some(&PointInTimeRecoveryStatus::Enabled)),
+            "ENABLED"
+        );
+        assert_eq!(
+            pitr_label(Some(&PointInTimeRecoveryStatus::Disabled)),
+            "DISABLED"
+        );
+        assert_eq!(pitr_label(None), "DISABLED");
+    }
+
+    #[test]
+    fn test_gsi_details() {
+        let index = GlobalSecondaryIndexDescription::builder()
+            .index_name("by-customer")
+            .key_schema(
+                KeySchemaElement::builder()
+                    .attribute_name("customer")
+                    .key_type(KeyType::Hash)
+                    .build()
+                    .unwrap(),
+            )
+            .projection(
+                Projection::builder()
+                    .projection_type(ProjectionType::Include)
+                    .non_key_attributes("total")
+                    .build(),
+            )
+            .provisioned_throughput(
+                ProvisionedThroughputDescription::builder()
+                    .read_capacity_units(5)
+                    .write_capacity_units(2)
+                    .build(),
+            )
+            .build();
+        let attributes = [AttributeDefinition::builder()
+            .attribute_name("customer")
+            .attribute_type(ScalarAttributeType::S)
+            .build()
+            .unwrap()];
+
+        let details = gsi_details(&index, &attributes, false);
+        let value = |key: &str| {
+            details
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(value("Partition Key"), Some("customer (S)"));
+        assert_eq!(value("Sort Key"), Some("-"));
+        assert_eq!(value("Projection"), Some("INCLUDE"));
+        assert_eq!(value("Projected Attributes"), Some("total"));
+        assert_eq!(value("Read Capacity"), Some("5"));
+
+        let details = gsi_details(&index, &attributes, true);
+        assert!(details.contains(&("Capacity".to_string(), "On-demand".to_string())));
+    }
+
+    #[test]
+    fn test_table_json() {
+        let table = TableDescription::builder()
+            .table_name("orders")
+            .table_status(aws_sdk_dynamodb::types::TableStatus::Active)
+            .item_count(12)
+            .key_schema(
+                KeySchemaElement::builder()
+                    .attribute_name("id")
+                    .key_type(KeyType::Hash)
+                    .build()
+                    .unwrap(),
+            )
+            .billing_mode_summary(
+                aws_sdk_dynamodb::types::BillingModeSummary::builder()
+                    .billing_mode(BillingMode::PayPerRequest)
+                    .build(),
+            )
+            .build();
+
+        let raw = table_json(&table);
+        assert_eq!(raw["TableName"], "orders");
+        assert_eq!(raw["TableStatus"], "ACTIVE");
+        assert_eq!(raw["ItemCount"], 12);
+        assert_eq!(raw["KeySchema"][0]["AttributeName"], "id");
+        assert_eq!(raw["KeySchema"][0]["KeyType"], "HASH");
+        assert_eq!(raw["BillingModeSummary"]["BillingMode"], "PAY_PER_REQUEST");
+        // Unset fields are left out rather than written as null
+        assert!(raw.get("TableArn").is_none());
+    }
+}
@@ -1,5 +1,19 @@
+use crate::aws::utils::{
+    Paging, Progress, TimeFormat, column_width, columns_width, display_width, fit_column_width,
+    fit_to_width, format_duration, json_fields, json_time, time_since,
+};
 use anyhow::Result;
 use aws_sdk_ec2::Client as Ec2Client;
+use aws_sdk_ec2::config::Region;
+use aws_sdk_ec2::error::ProvideErrorMetadata;
+use aws_sdk_ec2::types::{Filter, Instance, Tag};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+// Regions listed at once in the all-regions mode, to stay clear of API throttling
+const REGION_CONCURRENCY: usize = 4;
 
 #[derive(Clone)]
 pub struct Ec2Service {
@@ -10,7 +24,171 @@ pub struct Ec2Service {
 pub enum Ec2Item {
     Header,
     Separator,
-    Instance(String),
+    Instance {
+        id: String,
+        state: String,
+        tags: Vec<(String, String)>,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ec2Instance {
+    pub id: String,
+    pub name: String,
+    pub state: String,
+    pub instance_type: String,
+    pub public_ip: String,
+    pub tags: Vec<(String, String)>, // Sorted by key
+    pub image_id: String,
+    pub key_name: String,
+    pub launch_time: String,
+    pub stopped_at: String, // From the state transition reason; empty unless stopped
+    pub availability_zone: String,
+    pub region: String, // Only set by the all-regions listing
+}
+
+impl Ec2Instance {
+    /// "up 14d" for a running instance, "stopped 3d" for a stopped one.
+    pub fn uptime(&self, now: DateTime<Utc>) -> Option<String> {
+        uptime(&self.state, &self.launch_time, &self.stopped_at, now)
+    }
+}
+
+// How long the instance has been running since its last start, or stopped since its last stop
+fn uptime(state: &str, launch_time: &str, stopped_at: &str, now: DateTime<Utc>) -> Option<String> {
+    if state.eq_ignore_ascii_case("running") {
+        Some(format!(
+            "up {}",
+            format_duration(time_since(launch_time, now)?)
+        ))
+    } else if state.eq_ignore_ascii_case("stopped") {
+        Some(format!(
+            "stopped {}",
+            format_duration(time_since(stopped_at, now)?)
+        ))
+    } else {
+        None
+    }
+}
+
+// The stop time out of a reason like "User initiated (2024-05-01 10:00:00 GMT)", as RFC 3339
+fn stopped_at(reason: &str) -> Option<String> {
+    let (_, rest) = reason.split_once('(')?;
+    let (time, _) = rest.split_once(" GMT)")?;
+    let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").ok()?;
+    Some(
+        time.and_utc()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    )
+}
+
+/// Which columns the instance table shows after the id and name.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Ec2Columns {
+    /// State, type and public IP
+    #[default]
+    Status,
+    /// AMI, key pair, launch time, uptime and availability zone
+    Launch,
+}
+
+impl Ec2Columns {
+    pub fn toggled(self) -> Self {
+        match self {
+            Ec2Columns::Status => Ec2Columns::Launch,
+            Ec2Columns::Launch => Ec2Columns::Status,
+        }
+    }
+
+    pub fn columns(self) -> &'static [Ec2Column] {
+        match self {
+            Ec2Columns::Status => &[Ec2Column::State, Ec2Column::Type, Ec2Column::PublicIp],
+            Ec2Columns::Launch => &[
+                Ec2Column::Ami,
+                Ec2Column::KeyPair,
+                Ec2Column::LaunchTime,
+                Ec2Column::Uptime,
+                Ec2Column::Az,
+            ],
+        }
+    }
+}
+
+/// A column of the instance table that can be shown or hidden. The id and name always show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ec2Column {
+    /// Shown first by the all-regions listing rather than picked
+    Region,
+    State,
+    Type,
+    PublicIp,
+    Ami,
+    KeyPair,
+    LaunchTime,
+    Uptime,
+    Az,
+}
+
+impl Ec2Column {
+    pub const ALL: [Ec2Column; 8] = [
+        Ec2Column::State,
+        Ec2Column::Type,
+        Ec2Column::PublicIp,
+        Ec2Column::Ami,
+        Ec2Column::KeyPair,
+        Ec2Column::LaunchTime,
+        Ec2Column::Uptime,
+        Ec2Column::Az,
+    ];
+
+    pub fn header(self) -> &'static str {
+        match self {
+            Ec2Column::Region => "Region",
+            Ec2Column::State => "State",
+            Ec2Column::Type => "Type",
+            Ec2Column::PublicIp => "Public IP",
+            Ec2Column::Ami => "AMI",
+            Ec2Column::KeyPair => "Key Pair",
+            Ec2Column::LaunchTime => "Launch Time",
+            Ec2Column::Uptime => "Uptime",
+            Ec2Column::Az => "AZ",
+        }
+    }
+
+    // Name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Ec2Column::Region => "region",
+            Ec2Column::State => "state",
+            Ec2Column::Type => "type",
+            Ec2Column::PublicIp => "public_ip",
+            Ec2Column::Ami => "ami",
+            Ec2Column::KeyPair => "key_pair",
+            Ec2Column::LaunchTime => "launch_time",
+            Ec2Column::Uptime => "uptime",
+            Ec2Column::Az => "az",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|column| column.name() == name)
+    }
+
+    fn value(self, instance: &Ec2Instance, time: TimeFormat) -> String {
+        match self {
+            Ec2Column::Region => instance.region.clone(),
+            Ec2Column::State => instance.state.clone(),
+            Ec2Column::Type => instance.instance_type.clone(),
+            Ec2Column::PublicIp => instance.public_ip.clone(),
+            Ec2Column::Ami => instance.image_id.clone(),
+            Ec2Column::KeyPair => instance.key_name.clone(),
+            Ec2Column::LaunchTime => time.render(&instance.launch_time),
+            Ec2Column::Uptime => instance
+                .uptime(Utc::now())
+                .unwrap_or_else(|| "-".to_string()),
+            Ec2Column::Az => instance.availability_zone.clone(),
+        }
+    }
 }
 
 impl Ec2Service {
@@ -18,44 +196,583 @@ impl Ec2Service {
         Self { client }
     }
 
-    pub async fn list_instances(&self) -> Result<Vec<(String, String, String, String, String)>> {
-        let resp = self.client.describe_instances().send().await?;
+    /// Lists instances, optionally narrowed server-side to those carrying the tag `key=value`.
+    pub async fn list_instances(
+        &self,
+        tag_filter: Option<&(String, String)>,
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<Vec<Ec2Instance>> {
+        let mut request = self
+            .client
+            .describe_instances()
+            .set_max_results(paging.page_size(5..=1000));
+        if let Some((key, value)) = tag_filter {
+            request = request.filters(
+                Filter::builder()
+                    .name(format!("tag:{}", key))
+                    .values(value)
+                    .build(),
+            );
+        }
 
         let mut instances = Vec::new();
-        for reservation in resp.reservations() {
-            for instance in reservation.instances() {
-                let id = instance.instance_id().unwrap_or("unknown").to_string();
+        let mut pages = request.into_paginator().send();
+        while !paging.is_full(instances.len())
+            && let Some(page) = pages.next().await
+        {
+            for reservation in page?.reservations() {
+                for instance in reservation.instances() {
+                    let id = instance.instance_id().unwrap_or("unknown").to_string();
 
-                let name = instance
-                    .tags()
-                    .iter()
-                    .find(|t| t.key() == Some("Name"))
-                    .and_then(|t| t.value())
-                    .unwrap_or("-")
-                    .to_string();
+                    let mut tags: Vec<(String, String)> = instance
+                        .tags()
+                        .iter()
+                        .filter_map(|t| Some((t.key()?.to_string(), t.value()?.to_string())))
+                        .collect();
+                    tags.sort();
+
+                    let name = tags
+                        .iter()
+                        .find(|(key, _)| key == "Name")
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_else(|| "-".to_string());
+
+                    let state = instance
+                        .state()
+                        .and_then(|s| s.name())
+                        .map(|n| format!("{:?}", n))
+                        .unwrap_or_else(|| "unknown".to_string());
+
+                    let instance_type = instance
+                        .instance_type()
+                        .map(|t| format!("{:?}", t))
+                        .unwrap_or_else(|| "unknown".to_string());
 
-                let state = instance
+                    let public_ip = instance.public_ip_address().unwrap_or("-").to_string();
+
+                    instances.push(Ec2Instance {
+                        id,
+                        name,
+                        state,
+                        instance_type,
+                        public_ip,
+                        tags,
+                        image_id: instance.image_id().unwrap_or("-").to_string(),
+                        key_name: instance.key_name().unwrap_or("-").to_string(),
+                        launch_time: instance
+                            .launch_time()
+                            .map(|t| t.to_string())
+                            .unwrap_or_else(|| "-".to_string()),
+                        stopped_at: instance
+                            .state_transition_reason()
+                            .and_then(stopped_at)
+                            .unwrap_or_default(),
+                        availability_zone: instance
+                            .placement()
+                            .and_then(|p| p.availability_zone())
+                            .unwrap_or("-")
+                            .to_string(),
+                        region: String::new(),
+                    });
+                }
+            }
+            progress.report(format!("Loaded {} instances...", instances.len()));
+        }
+
+        Ok(instances)
+    }
+
+    /// Lists the instances of every region enabled for the account, a few regions at a time,
+    /// with each instance's region filled in. The cap applies to the merged list. A region that
+    /// fails to list (opt-in trouble, an SCP denying it) is returned with its error next to the
+    /// instances of the others; only every region failing is an error.
+    pub async fn list_instances_all_regions(
+        &self,
+        tag_filter: Option<&(String, String)>,
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<(Vec<Ec2Instance>, Vec<(String, String)>)> {
+        let resp = self.client.describe_regions().send().await?;
+        let mut regions: Vec<String> = resp
+            .regions()
+            .iter()
+            .filter_map(|r| r.region_name().map(str::to_string))
+            .collect();
+        regions.sort();
+
+        let permits = Arc::new(Semaphore::new(REGION_CONCURRENCY));
+        let mut tasks = tokio::task::JoinSet::new();
+        for region in regions.iter().cloned() {
+            let config = self
+                .client
+                .config()
+                .to_builder()
+                .region(Region::new(region.clone()))
+                .build();
+            let service = Ec2Service::new(Ec2Client::from_conf(config));
+            let tag_filter = tag_filter.cloned();
+            let permits = permits.clone();
+            tasks.spawn(async move {
+                let listed = async {
+                    let _permit = permits.acquire_owned().await?;
+                    service
+                        .list_instances(tag_filter.as_ref(), paging, &Progress::default())
+                        .await
+                }
+                .await;
+                (region, listed)
+            });
+        }
+
+        let mut by_region = Vec::new();
+        let mut failed = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            match joined? {
+                (region, Ok(mut instances)) => {
+                    for instance in &mut instances {
+                        instance.region = region.clone();
+                    }
+                    by_region.push((region, instances));
+                }
+                (region, Err(e)) => failed.push((region, format!("{:#}", e))),
+            }
+            progress.report(format!(
+                "Listed {} of {} regions...",
+                by_region.len() + failed.len(),
+                regions.len()
+            ));
+        }
+        if by_region.is_empty()
+            && let Some((region, error)) = failed.first()
+        {
+            anyhow::bail!(
+                "Failed to list instances in any region, e.g. {}: {}",
+                region,
+                error
+            );
+        }
+        by_region.sort_by(|(a, _), (b, _)| a.cmp(b));
+        failed.sort();
+
+        let mut instances: Vec<Ec2Instance> = by_region
+            .into_iter()
+            .flat_map(|(_, instances)| instances)
+            .collect();
+        if let Some(max) = paging.max_items {
+            // Keep the one extra item that tells the caller the list was cut short
+            instances.truncate(max + 1);
+        }
+        Ok((instances, failed))
+    }
+
+    /// Stops the instances. With `dry_run`, EC2 only checks the ids and permissions.
+    pub async fn stop_instances(&self, instance_ids: &[String], dry_run: bool) -> Result<()> {
+        let result = self
+            .client
+            .stop_instances()
+            .set_instance_ids(Some(instance_ids.to_vec()))
+            .dry_run(dry_run)
+            .send()
+            .await;
+        match result {
+            // EC2 answers a dry run that would have succeeded with this error
+            Err(e) if dry_run && matches!(e.code(), Some("DryRunOperation")) => Ok(()),
+            result => result.map(|_| ()).map_err(Into::into),
+        }
+    }
+
+    /// Sets the instance's `Name` tag, replacing any existing value. With `dry_run`, EC2 only
+    /// checks the id and permissions.
+    pub async fn set_name_tag(&self, instance_id: &str, name: &str, dry_run: bool) -> Result<()> {
+        let result = self
+            .client
+            .create_tags()
+            .resources(instance_id)
+            .tags(Tag::builder().key("Name").value(name).build())
+            .dry_run(dry_run)
+            .send()
+            .await;
+        match result {
+            Err(e) if dry_run && matches!(e.code(), Some("DryRunOperation")) => Ok(()),
+            result => result.map(|_| ()).map_err(Into::into),
+        }
+    }
+
+    /// The instance as `describe-instances` returns it, for the raw JSON view.
+    pub async fn describe_instance_raw(&self, instance_id: &str) -> Result<Value> {
+        let resp = self
+            .client
+            .describe_instances()
+            .instance_ids(instance_id)
+            .send()
+            .await?;
+        resp.reservations()
+            .iter()
+            .flat_map(|r| r.instances())
+            .next()
+            .map(Self::instance_json)
+            .ok_or_else(|| anyhow::anyhow!("Instance {} not found", instance_id))
+    }
+
+    pub fn instance_json(instance: &Instance) -> Value {
+        let text = |s: Option<&str>| s.map(|s| json!(s));
+        json_fields(vec![
+            ("InstanceId", text(instance.instance_id())),
+            ("ImageId", text(instance.image_id())),
+            (
+                "InstanceType",
+                text(instance.instance_type().map(|t| t.as_str())),
+            ),
+            (
+                "State",
+                instance.state().map(|s| {
+                    json_fields(vec![
+                        ("Code", s.code().map(|c| json!(c))),
+                        ("Name", text(s.name().map(|n| n.as_str()))),
+                    ])
+                }),
+            ),
+            (
+                "StateTransitionReason",
+                text(instance.state_transition_reason()),
+            ),
+            (
+                "StateReason",
+                instance.state_reason().map(|r| {
+                    json_fields(vec![
+                        ("Code", text(r.code())),
+                        ("Message", text(r.message())),
+                    ])
+                }),
+            ),
+            ("LaunchTime", json_time(instance.launch_time())),
+            ("KeyName", text(instance.key_name())),
+            (
+                "AmiLaunchIndex",
+                instance.ami_launch_index().map(|i| json!(i)),
+            ),
+            (
+                "Architecture",
+                text(instance.architecture().map(|a| a.as_str())),
+            ),
+            ("Platform", text(instance.platform().map(|p| p.as_str()))),
+            ("PlatformDetails", text(instance.platform_details())),
+            (
+                "Placement",
+                instance.placement().map(|p| {
+                    json_fields(vec![
+                        ("AvailabilityZone", text(p.availability_zone())),
+                        ("GroupName", text(p.group_name())),
+                        ("Tenancy", text(p.tenancy().map(|t| t.as_str()))),
+                    ])
+                }),
+            ),
+            (
+                "Monitoring",
+                instance
+                    .monitoring()
+                    .map(|m| json_fields(vec![("State", text(m.state().map(|s| s.as_str())))])),
+            ),
+            ("VpcId", text(instance.vpc_id())),
+            ("SubnetId", text(instance.subnet_id())),
+            ("PrivateDnsName", text(instance.private_dns_name())),
+            ("PrivateIpAddress", text(instance.private_ip_address())),
+            ("PublicDnsName", text(instance.public_dns_name())),
+            ("PublicIpAddress", text(instance.public_ip_address())),
+            (
+                "SourceDestCheck",
+                instance.source_dest_check().map(|b| json!(b)),
+            ),
+            (
+                "NetworkInterfaces",
+                Some(Value::Array(
+                    instance
+                        .network_interfaces()
+                        .iter()
+                        .map(|n| {
+                            json_fields(vec![
+                                ("NetworkInterfaceId", text(n.network_interface_id())),
+                                ("MacAddress", text(n.mac_address())),
+                                ("PrivateIpAddress", text(n.private_ip_address())),
+                                ("SubnetId", text(n.subnet_id())),
+                                ("VpcId", text(n.vpc_id())),
+                                ("Status", text(n.status().map(|s| s.as_str()))),
+                            ])
+                        })
+                        .collect(),
+                )),
+            ),
+            (
+                "SecurityGroups",
+                Some(Value::Array(
+                    instance
+                        .security_groups()
+                        .iter()
+                        .map(|g| {
+                            json_fields(vec![
+                                ("GroupId", text(g.group_id())),
+                                ("GroupName", text(g.group_name())),
+                            ])
+                        })
+                        .collect(),
+                )),
+            ),
+            (
+                "IamInstanceProfile",
+                instance
+                    .iam_instance_profile()
+                    .map(|p| json_fields(vec![("Arn", text(p.arn())), ("Id", text(p.id()))])),
+            ),
+            ("RootDeviceName", text(instance.root_device_name())),
+            (
+                "RootDeviceType",
+                text(instance.root_device_type().map(|t| t.as_str())),
+            ),
+            (
+                "BlockDeviceMappings",
+                Some(Value::Array(
+                    instance
+                        .block_device_mappings()
+                        .iter()
+                        .map(|b| {
+                            json_fields(vec![
+                                ("DeviceName", text(b.device_name())),
+                                (
+                                    "Ebs",
+                                    b.ebs().map(|ebs| {
+                                        json_fields(vec![
+                                            ("VolumeId", text(ebs.volume_id())),
+                                            ("Status", text(ebs.status().map(|s| s.as_str()))),
+                                            ("AttachTime", json_time(ebs.attach_time())),
+                                            (
+                                                "DeleteOnTermination",
+                                                ebs.delete_on_termination().map(|d| json!(d)),
+                                            ),
+                                        ])
+                                    }),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                )),
+            ),
+            ("EbsOptimized", instance.ebs_optimized().map(|b| json!(b))),
+            ("EnaSupport", instance.ena_support().map(|b| json!(b))),
+            (
+                "Hypervisor",
+                text(instance.hypervisor().map(|h| h.as_str())),
+            ),
+            (
+                "VirtualizationType",
+                text(instance.virtualization_type().map(|v| v.as_str())),
+            ),
+            (
+                "InstanceLifecycle",
+                text(instance.instance_lifecycle().map(|l| l.as_str())),
+            ),
+            (
+                "CpuOptions",
+                instance.cpu_options().map(|c| {
+                    json_fields(vec![
+                        ("CoreCount", c.core_count().map(|n| json!(n))),
+                        ("ThreadsPerCore", c.threads_per_core().map(|n| json!(n))),
+                    ])
+                }),
+            ),
+            (
+                "MetadataOptions",
+                instance.metadata_options().map(|m| {
+                    json_fields(vec![
+                        ("HttpTokens", text(m.http_tokens().map(|t| t.as_str()))),
+                        ("HttpEndpoint", text(m.http_endpoint().map(|e| e.as_str()))),
+                        (
+                            "HttpPutResponseHopLimit",
+                            m.http_put_response_hop_limit().map(|n| json!(n)),
+                        ),
+                    ])
+                }),
+            ),
+            ("BootMode", text(instance.boot_mode().map(|b| b.as_str()))),
+            (
+                "Tags",
+                Some(Value::Array(
+                    instance
+                        .tags()
+                        .iter()
+                        .map(|t| {
+                            json_fields(vec![("Key", text(t.key())), ("Value", text(t.value()))])
+                        })
+                        .collect(),
+                )),
+            ),
+        ])
+    }
+
+    pub async fn describe_instance(&self, instance_id: &str) -> Result<Vec<(String, String)>> {
+        let resp = self
+            .client
+            .describe_instances()
+            .instance_ids(instance_id)
+            .send()
+            .await?;
+        let instance = resp
+            .reservations()
+            .iter()
+            .flat_map(|r| r.instances())
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Instance {} not found", instance_id))?;
+
+        let mut details = vec![
+            ("Instance ID".to_string(), instance_id.to_string()),
+            (
+                "State".to_string(),
+                instance
                     .state()
                     .and_then(|s| s.name())
-                    .map(|n| format!("{:?}", n))
-                    .unwrap_or_else(|| "unknown".to_string());
-
-                let instance_type = instance
+                    .map(|n| n.as_str().to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "Type".to_string(),
+                instance
                     .instance_type()
-                    .map(|t| format!("{:?}", t))
-                    .unwrap_or_else(|| "unknown".to_string());
+                    .map(|t| t.as_str().to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "AMI".to_string(),
+                instance.image_id().unwrap_or("-").to_string(),
+            ),
+            (
+                "Availability Zone".to_string(),
+                instance
+                    .placement()
+                    .and_then(|p| p.availability_zone())
+                    .unwrap_or("-")
+                    .to_string(),
+            ),
+            (
+                "VPC".to_string(),
+                instance.vpc_id().unwrap_or("-").to_string(),
+            ),
+            (
+                "Subnet".to_string(),
+                instance.subnet_id().unwrap_or("-").to_string(),
+            ),
+            (
+                "Private IP".to_string(),
+                instance.private_ip_address().unwrap_or("-").to_string(),
+            ),
+            (
+                "Public IP".to_string(),
+                instance.public_ip_address().unwrap_or("-").to_string(),
+            ),
+            (
+                "Launch Time".to_string(),
+                instance
+                    .launch_time()
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+            ),
+        ];
+        let state = instance
+            .state()
+            .and_then(|s| s.name())
+            .map(|n| n.as_str())
+            .unwrap_or_default();
+        let launch_time = instance
+            .launch_time()
+            .map(|t| t.to_string())
+            .unwrap_or_default();
+        let stopped = instance
+            .state_transition_reason()
+            .and_then(stopped_at)
+            .unwrap_or_default();
+        if let Some(uptime) = uptime(state, &launch_time, &stopped, Utc::now()) {
+            details.push(("Uptime".to_string(), uptime));
+        }
 
-                let public_ip = instance.public_ip_address().unwrap_or("-").to_string();
+        for tag in instance.tags() {
+            if let (Some(key), Some(value)) = (tag.key(), tag.value()) {
+                details.push((format!("Tag: {}", key), value.to_string()));
+            }
+        }
 
-                instances.push((id, name, state, instance_type, public_ip));
+        // Format security groups as a table
+        let groups = instance.security_groups();
+        if !groups.is_empty() {
+            details.push(("".to_string(), "".to_string())); // Blank line
+            details.push(("Security Groups".to_string(), "".to_string()));
+            details.push((
+                "".to_string(),
+                format!("{:<22} {}", "Group ID", "Group Name"),
+            ));
+            details.push(("".to_string(), "-".repeat(50)));
+            for group in groups {
+                details.push((
+                    "".to_string(),
+                    format!(
+                        "{:<22} {}",
+                        group.group_id().unwrap_or("?"),
+                        group.group_name().unwrap_or("?")
+                    ),
+                ));
             }
         }
 
-        Ok(instances)
+        // Format attached EBS volumes as a table
+        let volumes = self
+            .client
+            .describe_volumes()
+            .filters(
+                Filter::builder()
+                    .name("attachment.instance-id")
+                    .values(instance_id)
+                    .build(),
+            )
+            .send()
+            .await?;
+        if !volumes.volumes().is_empty() {
+            details.push(("".to_string(), "".to_string())); // Blank line
+            details.push(("EBS Volumes".to_string(), "".to_string()));
+            details.push((
+                "".to_string(),
+                format!(
+                    "{:<22} {:<10} {:<8} {}",
+                    "Volume ID", "Size", "Type", "Device"
+                ),
+            ));
+            details.push(("".to_string(), "-".repeat(55)));
+            for volume in volumes.volumes() {
+                let device = volume
+                    .attachments()
+                    .iter()
+                    .find(|a| a.instance_id() == Some(instance_id))
+                    .and_then(|a| a.device())
+                    .unwrap_or("?");
+                details.push((
+                    "".to_string(),
+                    format!(
+                        "{:<22} {:<10} {:<8} {}",
+                        volume.volume_id().unwrap_or("?"),
+                        format!("{} GiB", volume.size().unwrap_or(0)),
+                        volume.volume_type().map(|t| t.as_str()).unwrap_or("?"),
+                        device
+                    ),
+                ));
+            }
+        }
+
+        Ok(details)
     }
 
     pub fn format_instance_list(
-        instances: &[(String, String, String, String, String)],
+        instances: &[Ec2Instance],
+        columns: &[Ec2Column],
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
     ) -> (Vec<String>, Vec<Ec2Item>) {
         if instances.is_empty() {
             return (
@@ -64,67 +781,88 @@ impl Ec2Service {
             );
         }
 
+        // The all-regions listing puts each instance's region in front of the chosen columns
+        let columns: Vec<Ec2Column> = instances
+            .iter()
+            .any(|i| !i.region.is_empty())
+            .then_some(Ec2Column::Region)
+            .into_iter()
+            .chain(columns.iter().copied())
+            .collect();
+
+        // Everything after the id and name
+        let extra = |instance: &Ec2Instance| -> Vec<String> {
+            columns
+                .iter()
+                .map(|column| column.value(instance, time))
+                .collect()
+        };
+        let extra_headers: Vec<&str> = columns.iter().map(|column| column.header()).collect();
+
         // Calculate column widths
         let max_id_len = instances
             .iter()
-            .map(|(id, _, _, _, _)| id.len())
+            .map(|i| i.id.len())
             .max()
             .unwrap_or(10)
             .max(10);
 
         let max_name_len = instances
             .iter()
-            .map(|(_, name, _, _, _)| name.len())
+            .map(|i| display_width(&i.name))
             .max()
             .unwrap_or(20)
             .max(20);
 
-        let max_state_len = instances
-            .iter()
-            .map(|(_, _, state, _, _)| state.len())
-            .max()
-            .unwrap_or(10)
-            .max(10);
-
-        let max_type_len = instances
+        let values: Vec<Vec<String>> = instances.iter().map(extra).collect();
+        let mut extra_widths: Vec<usize> = extra_headers
             .iter()
-            .map(|(_, _, _, type_, _)| type_.len())
-            .max()
-            .unwrap_or(10)
-            .max(10);
+            .enumerate()
+            .map(|(column, header)| column_width(header, values.iter().map(|v| &v[column])))
+            .collect();
 
-        let header = format!(
-            "{:<width_id$}  {:<width_name$}  {:<width_state$}  {:<width_type$}  Public IP",
-            "Instance ID",
-            "Name",
-            "State",
-            "Type",
-            width_id = max_id_len,
-            width_name = max_name_len,
-            width_state = max_state_len,
-            width_type = max_type_len
+        // The name column gives way when the pane is too narrow for every column
+        let fixed = columns_width(&[max_id_len]) + columns_width(&extra_widths);
+        // The last column is left unpadded
+        extra_widths.pop();
+        let max_name_len = fit_column_width(
+            max_name_len.max(name_width.unwrap_or(0)),
+            fixed,
+            max_width,
+            10,
         );
 
-        let separator_len = max_id_len + max_name_len + max_state_len + max_type_len + 25;
-        let separator = "-".repeat(separator_len).to_string();
+        let row = |id: &str, name: &str, values: &[String]| {
+            let mut line = format!(
+                "{:<width_id$}  {}",
+                id,
+                fit_to_width(name, max_name_len),
+                width_id = max_id_len,
+            );
+            for (column, value) in values.iter().enumerate() {
+                match extra_widths.get(column) {
+                    Some(&width) => line.push_str(&format!("  {}", fit_to_width(value, width))),
+                    None => line.push_str(&format!("  {}", value)),
+                }
+            }
+            line
+        };
+
+        let header_values: Vec<String> = extra_headers.iter().map(|h| h.to_string()).collect();
+        let header = row("Instance ID", "Name", &header_values);
+
+        let separator = "-".repeat(max_name_len + fixed);
 
         let mut items = vec![header, separator];
         let mut ec2_items = vec![Ec2Item::Header, Ec2Item::Separator];
 
-        for (id, name, state, type_, ip) in instances {
-            items.push(format!(
-                "{:<width_id$}  {:<width_name$}  {:<width_state$}  {:<width_type$}  {}",
-                id,
-                name,
-                state,
-                type_,
-                ip,
-                width_id = max_id_len,
-                width_name = max_name_len,
-                width_state = max_state_len,
-                width_type = max_type_len
-            ));
-            ec2_items.push(Ec2Item::Instance(id.clone()));
+        for (instance, values) in instances.iter().zip(&values) {
+            items.push(row(&instance.id, &instance.name, values));
+            ec2_items.push(Ec2Item::Instance {
+                id: instance.id.clone(),
+                state: instance.state.clone(),
+                tags: instance.tags.clone(),
+            });
         }
         (items, ec2_items)
     }
@@ -137,28 +875,49 @@ mod tests {
     #[test]
     fn test_format_instance_list() {
         // Test empty list
-        let (items, ec2_items) = Ec2Service::format_instance_list(&[]);
+        let (items, ec2_items) = Ec2Service::format_instance_list(
+            &[],
+            Ec2Columns::Status.columns(),
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
         assert_eq!(items[0], "No EC2 Instances found");
         assert!(matches!(ec2_items[0], Ec2Item::Header));
 
         // Test populated list
         let instances = vec![
-            (
-                "i-1234567890abcdef0".to_string(),
-                "web-server".to_string(),
-                "running".to_string(),
-                "t2.micro".to_string(),
-                "1.2.3.4".to_string(),
-            ),
-            (
-                "i-0987654321fedcba0".to_string(),
-                "db-server".to_string(),
-                "stopped".to_string(),
-                "m5.large".to_string(),
-                "-".to_string(),
-            ),
+            Ec2Instance {
+                id: "i-1234567890abcdef0".to_string(),
+                name: "web-server".to_string(),
+                state: "running".to_string(),
+                instance_type: "t2.micro".to_string(),
+                public_ip: "1.2.3.4".to_string(),
+                tags: vec![("Team".to_string(), "web".to_string())],
+                image_id: "ami-0abc".to_string(),
+                key_name: "deploy".to_string(),
+                launch_time: "2024-05-01T10:00:00Z".to_string(),
+                stopped_at: String::new(),
+                availability_zone: "eu-west-1a".to_string(),
+                region: String::new(),
+            },
+            Ec2Instance {
+                id: "i-0987654321fedcba0".to_string(),
+                name: "db-server".to_string(),
+                state: "stopped".to_string(),
+                instance_type: "m5.large".to_string(),
+                public_ip: "-".to_string(),
+                tags: Vec::new(),
+                ..Ec2Instance::default()
+            },
         ];
-        let (items, ec2_items) = Ec2Service::format_instance_list(&instances);
+        let (items, ec2_items) = Ec2Service::format_instance_list(
+            &instances,
+            Ec2Columns::Status.columns(),
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
 
         assert_eq!(items.len(), 4); // Header, Separator, 2 instances
         assert!(items[0].contains("Instance ID"));
@@ -166,9 +925,105 @@ mod tests {
         assert!(items[2].contains("i-1234567890abcdef0"));
         assert!(items[3].contains("db-server"));
 
-        assert!(matches!(ec2_items[2], Ec2Item::Instance(_)));
-        if let Ec2Item::Instance(id) = &ec2_items[2] {
-            assert_eq!(id, "i-1234567890abcdef0");
-        }
+        let Ec2Item::Instance { id, state, tags } = &ec2_items[2] else {
+            panic!("expected an instance row, got {:?}", ec2_items[2]);
+        };
+        assert_eq!(id, "i-1234567890abcdef0");
+        assert_eq!(state, "running");
+        assert_eq!(tags, &vec![("Team".to_string(), "web".to_string())]);
+
+        let (items, _) = Ec2Service::format_instance_list(
+            &instances,
+            Ec2Columns::Launch.columns(),
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
+        assert!(items[0].contains("Key Pair"));
+        assert!(!items[0].contains("Public IP"));
+        assert!(items[2].contains("ami-0abc"));
+        assert!(items[2].ends_with("eu-west-1a"));
+
+        // Any mix of columns, in the order given
+        let (items, _) = Ec2Service::format_instance_list(
+            &instances,
+            &[Ec2Column::State, Ec2Column::Ami],
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
+        assert!(items[0].contains("State") && items[0].ends_with("AMI"));
+        assert!(!items[0].contains("Type"));
+        let (items, _) =
+            Ec2Service::format_instance_list(&instances, &[], TimeFormat::Absolute, None, 60);
+        assert!(items[2].starts_with("i-1234567890abcdef0"));
+        assert_eq!(Ec2Column::from_name("public_ip"), Some(Ec2Column::PublicIp));
+
+        // The all-regions listing adds a Region column in front, which can't be picked
+        let instances: Vec<Ec2Instance> = instances
+            .into_iter()
+            .map(|i| Ec2Instance {
+                region: "us-east-1".to_string(),
+                ..i
+            })
+            .collect();
+        let (items, _) = Ec2Service::format_instance_list(
+            &instances,
+            &[Ec2Column::State],
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
+        let header = items[0].split_whitespace().collect::<Vec<_>>();
+        assert_eq!(header[3..], ["Region", "State"]);
+        let row = items[2].split_whitespace().collect::<Vec<_>>();
+        assert_eq!(row[2..], ["us-east-1", "running"]);
+        assert_eq!(Ec2Column::from_name("region"), None);
+    }
+
+    #[test]
+    fn test_uptime() {
+        let now = DateTime::parse_from_rfc3339("2024-05-15T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let stopped = stopped_at("User initiated (2024-05-12 09:30:00 GMT)").unwrap();
+        assert_eq!(stopped, "2024-05-12T09:30:00Z");
+        assert_eq!(stopped_at(""), None);
+
+        assert_eq!(
+            uptime("running", "2024-05-01T10:00:00Z", "", now).as_deref(),
+            Some("up 14d")
+        );
+        assert_eq!(
+            uptime("stopped", "2024-05-01T10:00:00Z", &stopped, now).as_deref(),
+            Some("stopped 3d")
+        );
+        // No stop time in the reason, or a state that isn't settled, shows nothing
+        assert_eq!(uptime("stopped", "2024-05-01T10:00:00Z", "", now), None);
+        assert_eq!(uptime("pending", "2024-05-01T10:00:00Z", "", now), None);
+    }
+
+    #[test]
+    fn test_instance_json() {
+        let instance = Instance::builder()
+            .instance_id("i-0abc")
+            .instance_type(aws_sdk_ec2::types::InstanceType::T3Micro)
+            .state(
+                aws_sdk_ec2::types::InstanceState::builder()
+                    .code(16)
+                    .name(aws_sdk_ec2::types::InstanceStateName::Running)
+                    .build(),
+            )
+            .tags(Tag::builder().key("Name").value("web").build())
+            .build();
+
+        let raw = Ec2Service::instance_json(&instance);
+        assert_eq!(raw["InstanceId"], "i-0abc");
+        assert_eq!(raw["InstanceType"], "t3.micro");
+        assert_eq!(raw["State"]["Code"], 16);
+        assert_eq!(raw["State"]["Name"], "running");
+        assert_eq!(raw["Tags"][0]["Value"], "web");
+        // Unset fields are left out rather than written as null
+        assert!(raw.get("PublicIpAddress").is_none());
     }
 }
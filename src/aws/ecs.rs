@@ -1,18 +1,37 @@
+use crate::aws::utils::{
+    TimeFormat, columns_width, display_width, fit_column_width, fit_to_width, join_columns,
+    shown_columns,
+};
 use anyhow::Result;
 use aws_sdk_ecs::Client as EcsClient;
+use aws_sdk_ecs::types::{Service, Task};
 
 #[derive(Clone)]
 pub struct EcsService {
     client: EcsClient,
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EcsTask {
+    pub id: String,
+    pub arn: String,
+    pub definition: String, // Family and revision, e.g. "web:7"
+    pub last_status: String,
+    pub desired_status: String,
+    pub started_at: String,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum EcsItem {
     Header,
     Separator,
     Cluster(String),
     Service(String),
-    Task(String),
+    Task {
+        id: String,
+        definition: String,
+        status: String,
+    },
     ParentDir,
 }
 
@@ -21,7 +40,8 @@ impl EcsService {
         Self { client }
     }
 
-    pub async fn list_clusters(&self) -> Result<Vec<String>> {
+    /// Cluster names with their ARNs.
+    pub async fn list_clusters(&self) -> Result<Vec<(String, String)>> {
         let resp = self.client.list_clusters().send().await?;
         let clusters = resp.cluster_arns();
 
@@ -30,20 +50,21 @@ impl EcsService {
             // Extract cluster name from ARN
             // ARN format: arn:aws:ecs:region:account-id:cluster/cluster-name
             let name = arn.split('/').next_back().unwrap_or(arn).to_string();
-            cluster_names.push(name);
+            cluster_names.push((name, arn.clone()));
         }
 
         Ok(cluster_names)
     }
 
-    pub async fn list_services(&self, cluster: &str) -> Result<Vec<String>> {
+    /// Service names with their ARNs.
+    pub async fn list_services(&self, cluster: &str) -> Result<Vec<(String, String)>> {
         let resp = self.client.list_services().cluster(cluster).send().await?;
         let services = resp.service_arns();
 
         let mut service_names = Vec::new();
         for arn in services {
             let name = arn.split('/').next_back().unwrap_or(arn).to_string();
-            service_names.push(name);
+            service_names.push((name, arn.clone()));
         }
 
         Ok(service_names)
@@ -53,7 +74,7 @@ impl EcsService {
         &self,
         cluster: &str,
         service_name: Option<&str>,
-    ) -> Result<Vec<(String, String, String, String, String)>> {
+    ) -> Result<Vec<EcsTask>> {
         let mut req = self.client.list_tasks().cluster(cluster);
         if let Some(service) = service_name {
             req = req.service_name(service);
@@ -78,13 +99,8 @@ impl EcsService {
 
         let mut tasks = Vec::new();
         for task in resp.tasks() {
-            let id = task
-                .task_arn()
-                .unwrap_or("")
-                .split('/')
-                .next_back()
-                .unwrap_or("unknown")
-                .to_string();
+            let arn = task.task_arn().unwrap_or("");
+            let id = arn.split('/').next_back().unwrap_or("unknown").to_string();
             let def = task
                 .task_definition_arn()
                 .unwrap_or("")
@@ -99,54 +115,351 @@ impl EcsService {
                 .map(|t| t.to_string())
                 .unwrap_or_else(|| "pending".to_string());
 
-            tasks.push((id, def, last_status, desired_status, started_at));
+            tasks.push(EcsTask {
+                id,
+                arn: arn.to_string(),
+                definition: def,
+                last_status,
+                desired_status,
+                started_at,
+            });
         }
 
         Ok(tasks)
     }
 
-    pub fn format_task_list(
-        tasks: &[(String, String, String, String, String)],
+    pub async fn describe_service(
+        &self,
         cluster: &str,
-        service: Option<&str>,
-    ) -> (Vec<String>, Vec<EcsItem>) {
-        let context = if let Some(svc) = service {
-            format!("Service {}", svc)
-        } else {
-            format!("Cluster {}", cluster)
+        service: &str,
+    ) -> Result<Vec<(String, String)>> {
+        let resp = self
+            .client
+            .describe_services()
+            .cluster(cluster)
+            .services(service)
+            .send()
+            .await?;
+        let service = resp
+            .services()
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Service {} not found", service))?;
+        Ok(Self::service_details_pairs(service))
+    }
+
+    /// Task counts, launch settings, one row per deployment and the attached load balancers:
+    /// what shows whether a deploy is healthy.
+    pub fn service_details_pairs(service: &Service) -> Vec<(String, String)> {
+        let last_segment = |arn: Option<&str>| {
+            arn.and_then(|a| a.split('/').next_back())
+                .unwrap_or("-")
+                .to_string()
         };
+        let mut details = vec![
+            (
+                "Service".to_string(),
+                service.service_name().unwrap_or("-").to_string(),
+            ),
+            (
+                "Status".to_string(),
+                service.status().unwrap_or("-").to_string(),
+            ),
+            ("Desired".to_string(), service.desired_count().to_string()),
+            ("Running".to_string(), service.running_count().to_string()),
+            ("Pending".to_string(), service.pending_count().to_string()),
+            (
+                "Launch Type".to_string(),
+                service
+                    .launch_type()
+                    .map(|l| l.as_str())
+                    .unwrap_or("-")
+                    .to_string(),
+            ),
+            (
+                "Task Definition".to_string(),
+                last_segment(service.task_definition()),
+            ),
+            (
+                "Created At".to_string(),
+                service
+                    .created_at()
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ];
+
+        let deployments = service.deployments();
+        if !deployments.is_empty() {
+            details.push(("".to_string(), "".to_string())); // Blank line
+            details.push(("Deployments".to_string(), "".to_string()));
+            details.push((
+                "".to_string(),
+                format!(
+                    "{:<10} {:<12} {:>7} {:>7} {:>7}  {}",
+                    "Status", "Rollout", "Desired", "Running", "Pending", "Task Definition"
+                ),
+            ));
+            details.push(("".to_string(), "-".repeat(70)));
+            for deployment in deployments {
+                details.push((
+                    "".to_string(),
+                    format!(
+                        "{:<10} {:<12} {:>7} {:>7} {:>7}  {}",
+                        deployment.status().unwrap_or("-"),
+                        deployment
+                            .rollout_state()
+                            .map(|r| r.as_str())
+                            .unwrap_or("-"),
+                        deployment.desired_count(),
+                        deployment.running_count(),
+                        deployment.pending_count(),
+                        last_segment(deployment.task_definition())
+                    ),
+                ));
+            }
+        }
+
+        let load_balancers = service.load_balancers();
+        if !load_balancers.is_empty() {
+            details.push(("".to_string(), "".to_string()));
+            details.push(("Load Balancers".to_string(), "".to_string()));
+            for lb in load_balancers {
+                // Target groups for ALB/NLB, a load balancer name for classic ELB. Target group
+                // ARNs end in "targetgroup/<name>/<id>"
+                let target = lb
+                    .target_group_arn()
+                    .map(|arn| format!("target group {}", arn.split('/').nth(1).unwrap_or(arn)))
+                    .or_else(|| lb.load_balancer_name().map(String::from))
+                    .unwrap_or_else(|| "-".to_string());
+                details.push((
+                    "".to_string(),
+                    format!(
+                        "{} -> {}:{}",
+                        target,
+                        lb.container_name().unwrap_or("-"),
+                        lb.container_port()
+                            .map(|p| p.to_string())
+                            .unwrap_or_else(|| "-".to_string())
+                    ),
+                ));
+            }
+        }
+        details
+    }
+
+    pub async fn describe_task(
+        &self,
+        cluster: &str,
+        task_id: &str,
+    ) -> Result<Vec<(String, String)>> {
+        let resp = self
+            .client
+            .describe_tasks()
+            .cluster(cluster)
+            .tasks(task_id)
+            .send()
+            .await?;
+        let task = resp
+            .tasks()
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
+        Ok(Self::task_details_pairs(task))
+    }
+
+    /// Task-level reservations and networking, then one row per container with the status
+    /// fields that explain a failing task.
+    pub fn task_details_pairs(task: &Task) -> Vec<(String, String)> {
+        let last_segment = |arn: Option<&str>| {
+            arn.and_then(|a| a.split('/').next_back())
+                .unwrap_or("-")
+                .to_string()
+        };
+        let mut details = vec![
+            ("Task ID".to_string(), last_segment(task.task_arn())),
+            (
+                "Definition".to_string(),
+                last_segment(task.task_definition_arn()),
+            ),
+            (
+                "Last Status".to_string(),
+                task.last_status().unwrap_or("-").to_string(),
+            ),
+            (
+                "Desired Status".to_string(),
+                task.desired_status().unwrap_or("-").to_string(),
+            ),
+            (
+                "Health".to_string(),
+                task.health_status()
+                    .map(|h| h.as_str())
+                    .unwrap_or("-")
+                    .to_string(),
+            ),
+            ("CPU".to_string(), task.cpu().unwrap_or("-").to_string()),
+            (
+                "Memory".to_string(),
+                task.memory().unwrap_or("-").to_string(),
+            ),
+            (
+                "Launch Type".to_string(),
+                task.launch_type()
+                    .map(|l| l.as_str())
+                    .unwrap_or("-")
+                    .to_string(),
+            ),
+            (
+                "Availability Zone".to_string(),
+                task.availability_zone().unwrap_or("-").to_string(),
+            ),
+            (
+                "Started At".to_string(),
+                task.started_at()
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "pending".to_string()),
+            ),
+        ];
+
+        if let Some(reason) = task.stopped_reason() {
+            details.push(("Stopped Reason".to_string(), reason.to_string()));
+        }
+        if let Some(code) = task.stop_code() {
+            details.push(("Stop Code".to_string(), code.as_str().to_string()));
+        }
+
+        // awsvpc tasks get an ENI attachment whose details carry the id and private IP
+        for attachment in task.attachments() {
+            if attachment.r#type() != Some("ElasticNetworkInterface") {
+                continue;
+            }
+            for detail in attachment.details() {
+                let label = match detail.name() {
+                    Some("networkInterfaceId") => "ENI",
+                    Some("privateIPv4Address") => "Private IP",
+                    _ => continue,
+                };
+                details.push((label.to_string(), detail.value().unwrap_or("-").to_string()));
+            }
+        }
+
+        let containers = task.containers();
+        if !containers.is_empty() {
+            details.push(("".to_string(), "".to_string())); // Blank line
+            details.push(("Containers".to_string(), "".to_string()));
+            details.push((
+                "".to_string(),
+                format!(
+                    "{:<24} {:<12} {:<10} {:<6} {}",
+                    "Name", "Status", "Health", "Exit", "Reason"
+                ),
+            ));
+            details.push(("".to_string(), "-".repeat(70)));
+            for container in containers {
+                details.push((
+                    "".to_string(),
+                    format!(
+                        "{} {:<12} {:<10} {:<6} {}",
+                        fit_to_width(container.name().unwrap_or("?"), 24),
+                        container.last_status().unwrap_or("-"),
+                        container.health_status().map(|h| h.as_str()).unwrap_or("-"),
+                        container
+                            .exit_code()
+                            .map(|c| c.to_string())
+                            .unwrap_or_else(|| "-".to_string()),
+                        container.reason().unwrap_or("")
+                    ),
+                ));
+            }
+        }
+        details
+    }
+
+    pub async fn stop_task(&self, cluster: &str, task_id: &str, reason: &str) -> Result<()> {
+        self.client
+            .stop_task()
+            .cluster(cluster)
+            .task(task_id)
+            .reason(reason)
+            .send()
+            .await?;
+        Ok(())
+    }
 
+    /// Columns of the task list that can be hidden, by config name and header.
+    pub const TASK_COLUMNS: [(&str, &str); 3] = [
+        ("last_status", "Last Status"),
+        ("desired", "Desired"),
+        ("started_at", "Started At"),
+    ];
+
+    /// Lists the tasks by id, or by full ARN when `full_arns` is set, leaving out the
+    /// `TASK_COLUMNS` named in `hidden`.
+    pub fn format_task_list(
+        tasks: &[EcsTask],
+        service: &str,
+        full_arns: bool,
+        hidden: &[&str],
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<EcsItem>) {
         if tasks.is_empty() {
-            let mut items = vec![format!("No Tasks found in {}", context)];
+            let mut items = vec![format!("No Tasks found in Service {}", service)];
             items.push("..".to_string());
             let ecs_items = vec![EcsItem::Header, EcsItem::ParentDir];
             return (items, ecs_items);
         }
 
+        let shown_id = |task: &EcsTask| {
+            if full_arns {
+                task.arn.clone()
+            } else {
+                task.id.clone()
+            }
+        };
         let max_id_len = tasks
             .iter()
-            .map(|(id, _, _, _, _)| id.len())
+            .map(|task| shown_id(task).len())
             .max()
             .unwrap_or(32)
             .max(32);
         let max_def_len = tasks
             .iter()
-            .map(|(_, def, _, _, _)| def.len())
+            .map(|task| display_width(&task.definition))
             .max()
             .unwrap_or(20)
             .max(20);
-
-        let header = format!(
-            "{:<width_id$}  {:<width_def$}  {:<12}  {:<12}  {}",
-            "Task ID",
-            "Definition",
-            "Last Status",
-            "Desired",
-            "Started At",
-            width_id = max_id_len,
-            width_def = max_def_len
+        let rows = tasks
+            .iter()
+            .map(|task| {
+                vec![
+                    task.last_status.clone(),
+                    task.desired_status.clone(),
+                    time.render(&task.started_at),
+                ]
+            })
+            .collect();
+        let (headers, cells, widths) = shown_columns(&Self::TASK_COLUMNS, hidden, rows);
+        let rest_len = columns_width(&[max_id_len]) + columns_width(&widths);
+        let max_def_len = fit_column_width(
+            max_def_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
         );
-        let separator = "-".repeat(header.len()).to_string();
+        let row = |id: &str, definition: &str, values: &[String]| {
+            format!(
+                "{:<width_id$}  {}{}",
+                id,
+                fit_to_width(definition, max_def_len),
+                join_columns(values, &widths),
+                width_id = max_id_len
+            )
+        };
+
+        let headers: Vec<String> = headers.into_iter().map(String::from).collect();
+        let id_header = if full_arns { "Task ARN" } else { "Task ID" };
+        let header = row(id_header, "Definition", &headers);
+        let separator = "-".repeat(max_def_len + rest_len);
 
         let mut items = vec![header, separator];
         let mut ecs_items = vec![EcsItem::Header, EcsItem::Separator];
@@ -154,23 +467,24 @@ impl EcsService {
         items.push("..".to_string());
         ecs_items.push(EcsItem::ParentDir);
 
-        for (id, def, last, desired, started) in tasks {
-            items.push(format!(
-                "{:<width_id$}  {:<width_def$}  {:<12}  {:<12}  {}",
-                id,
-                def,
-                last,
-                desired,
-                started,
-                width_id = max_id_len,
-                width_def = max_def_len
-            ));
-            ecs_items.push(EcsItem::Task(id.clone()));
+        for (task, values) in tasks.iter().zip(&cells) {
+            items.push(row(&shown_id(task), &task.definition, values));
+            ecs_items.push(EcsItem::Task {
+                id: task.id.clone(),
+                definition: task.definition.clone(),
+                status: task.last_status.clone(),
+            });
         }
         (items, ecs_items)
     }
 
-    pub fn format_cluster_list(clusters: &[String]) -> (Vec<String>, Vec<EcsItem>) {
+    /// Lists clusters by name, or by full ARN when `full_arns` is set.
+    pub fn format_cluster_list(
+        clusters: &[(String, String)],
+        full_arns: bool,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<EcsItem>) {
         if clusters.is_empty() {
             return (
                 vec!["No ECS Clusters found".to_string()],
@@ -178,27 +492,41 @@ impl EcsService {
             );
         }
 
-        let max_name_len = clusters
-            .iter()
-            .map(|name| name.len())
-            .max()
-            .unwrap_or(20)
-            .max(20);
+        let shown_len =
+            |(name, arn): &(String, String)| display_width(if full_arns { arn } else { name });
+        let max_name_len = clusters.iter().map(shown_len).max().unwrap_or(20).max(20);
+        let max_name_len =
+            fit_column_width(max_name_len.max(name_width.unwrap_or(0)), 0, max_width, 10);
 
-        let header = format!("{:<width$}", "Cluster Name", width = max_name_len);
+        let title = if full_arns {
+            "Cluster ARN"
+        } else {
+            "Cluster Name"
+        };
+        let header = format!("{:<width$}", title, width = max_name_len);
         let separator = "-".repeat(max_name_len + 5).to_string();
 
         let mut items = vec![header, separator];
         let mut ecs_items = vec![EcsItem::Header, EcsItem::Separator];
 
-        for name in clusters {
-            items.push(format!("{:<width$}", name, width = max_name_len));
+        for (name, arn) in clusters {
+            items.push(fit_to_width(
+                if full_arns { arn } else { name },
+                max_name_len,
+            ));
             ecs_items.push(EcsItem::Cluster(name.clone()));
         }
         (items, ecs_items)
     }
 
-    pub fn format_service_list(services: &[String], cluster: &str) -> (Vec<String>, Vec<EcsItem>) {
+    /// Lists services by name, or by full ARN when `full_arns` is set.
+    pub fn format_service_list(
+        services: &[(String, String)],
+        cluster: &str,
+        full_arns: bool,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<EcsItem>) {
         if services.is_empty() {
             let mut items = vec![format!("No Services found in cluster {}", cluster)];
             items.push("..".to_string());
@@ -206,14 +534,18 @@ impl EcsService {
             return (items, ecs_items);
         }
 
-        let max_name_len = services
-            .iter()
-            .map(|name| name.len())
-            .max()
-            .unwrap_or(20)
-            .max(20);
+        let shown_len =
+            |(name, arn): &(String, String)| display_width(if full_arns { arn } else { name });
+        let max_name_len = services.iter().map(shown_len).max().unwrap_or(20).max(20);
+        let max_name_len =
+            fit_column_width(max_name_len.max(name_width.unwrap_or(0)), 0, max_width, 10);
 
-        let header = format!("{:<width$}", "Service Name", width = max_name_len);
+        let title = if full_arns {
+            "Service ARN"
+        } else {
+            "Service Name"
+        };
+        let header = format!("{:<width$}", title, width = max_name_len);
         let separator = "-".repeat(max_name_len + 5).to_string();
 
         let mut items = vec![header, separator];
@@ -222,8 +554,11 @@ impl EcsService {
         items.push("..".to_string());
         ecs_items.push(EcsItem::ParentDir);
 
-        for name in services {
-            items.push(format!("{:<width$}", name, width = max_name_len));
+        for (name, arn) in services {
+            items.push(fit_to_width(
+                if full_arns { arn } else { name },
+                max_name_len,
+            ));
             ecs_items.push(EcsItem::Service(name.clone()));
         }
         (items, ecs_items)
@@ -233,44 +568,165 @@ impl EcsService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aws_sdk_ecs::types::{
+        Attachment, Container, Deployment, DeploymentRolloutState, HealthStatus, KeyValuePair,
+        LoadBalancer,
+    };
+
+    #[test]
+    fn test_service_details_pairs() {
+        let service = Service::builder()
+            .service_name("web")
+            .status("ACTIVE")
+            .desired_count(3)
+            .running_count(2)
+            .pending_count(1)
+            .task_definition("arn:aws:ecs:us-east-1:123456789012:task-definition/web:8")
+            .deployments(
+                Deployment::builder()
+                    .status("PRIMARY")
+                    .rollout_state(DeploymentRolloutState::InProgress)
+                    .desired_count(3)
+                    .running_count(2)
+                    .task_definition("arn:aws:ecs:us-east-1:123456789012:task-definition/web:8")
+                    .build(),
+            )
+            .load_balancers(
+                LoadBalancer::builder()
+                    .target_group_arn(
+                        "arn:aws:elasticloadbalancing:us-east-1:123456789012:targetgroup/web-tg/6d0ecf831eec9f09",
+                    )
+                    .container_name("app")
+                    .container_port(8080)
+                    .build(),
+            )
+            .build();
+
+        let details = EcsService::service_details_pairs(&service);
+        assert_eq!(details[2], ("Desired".to_string(), "3".to_string()));
+        assert_eq!(details[3], ("Running".to_string(), "2".to_string()));
+        assert_eq!(
+            details[6],
+            ("Task Definition".to_string(), "web:8".to_string())
+        );
+        assert!(details.iter().any(|(_, row)| row.starts_with("PRIMARY")
+            && row.contains("IN_PROGRESS")
+            && row.ends_with("web:8")));
+        assert_eq!(details.last().unwrap().1, "target group web-tg -> app:8080");
+    }
+
+    #[test]
+    fn test_task_details_pairs() {
+        let task = Task::builder()
+            .task_arn("arn:aws:ecs:us-east-1:123456789012:task/cluster1/abc123")
+            .task_definition_arn("arn:aws:ecs:us-east-1:123456789012:task-definition/web:7")
+            .last_status("STOPPED")
+            .cpu("256")
+            .memory("512")
+            .stopped_reason("Essential container in task exited")
+            .attachments(
+                Attachment::builder()
+                    .r#type("ElasticNetworkInterface")
+                    .details(
+                        KeyValuePair::builder()
+                            .name("privateIPv4Address")
+                            .value("10.0.1.5")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .containers(
+                Container::builder()
+                    .name("app")
+                    .last_status("STOPPED")
+                    .health_status(HealthStatus::Unhealthy)
+                    .exit_code(137)
+                    .reason("OutOfMemoryError")
+                    .build(),
+            )
+            .build();
+
+        let details = EcsService::task_details_pairs(&task);
+        let value = |key: &str| {
+            details
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(value("Task ID"), Some("abc123"));
+        assert_eq!(value("Definition"), Some("web:7"));
+        assert_eq!(value("Memory"), Some("512"));
+        assert_eq!(value("Private IP"), Some("10.0.1.5"));
+        assert_eq!(
+            value("Stopped Reason"),
+            Some("Essential container in task exited")
+        );
+        let row = &details.last().unwrap().1;
+        assert!(row.starts_with("app"));
+        assert!(row.contains("UNHEALTHY"));
+        assert!(row.contains("137"));
+        assert!(row.contains("OutOfMemoryError"));
+    }
 
     #[test]
     fn test_format_cluster_list() {
-        let clusters = vec!["cluster1".to_string(), "cluster2".to_string()];
-        let (items, ecs_items) = EcsService::format_cluster_list(&clusters);
+        let cluster = |name: &str| {
+            (
+                name.to_string(),
+                format!("arn:aws:ecs:us-east-1:123456789012:cluster/{}", name),
+            )
+        };
+        let clusters = vec![cluster("cluster1"), cluster("cluster2")];
+        let (items, ecs_items) =
+            EcsService::format_cluster_list(&clusters, false, None, usize::MAX);
 
         assert_eq!(items.len(), 4); // Header, Separator, 2 clusters
         assert!(items[0].contains("Cluster Name"));
         assert!(items[2].contains("cluster1"));
+        assert!(!items[2].contains("arn:"));
 
         assert!(matches!(ecs_items[2], EcsItem::Cluster(_)));
+
+        // The ARN replaces the name on screen, but rows still navigate by name
+        let (items, ecs_items) = EcsService::format_cluster_list(&clusters, true, None, usize::MAX);
+        assert!(items[0].contains("Cluster ARN"));
+        assert_eq!(
+            items[2].trim_end(),
+            "arn:aws:ecs:us-east-1:123456789012:cluster/cluster1"
+        );
+        assert_eq!(ecs_items[2], EcsItem::Cluster("cluster1".to_string()));
     }
 
     #[test]
     fn test_format_task_list() {
+        let task = |id: &str, def: &str, status: &str, started: &str| EcsTask {
+            id: id.to_string(),
+            arn: format!("arn:aws:ecs:us-east-1:123456789012:task/cluster1/{}", id),
+            definition: def.to_string(),
+            last_status: status.to_string(),
+            desired_status: status.to_string(),
+            started_at: started.to_string(),
+        };
         let tasks = vec![
-            (
-                "task1".to_string(),
-                "def1".to_string(),
-                "RUNNING".to_string(),
-                "RUNNING".to_string(),
-                "2023-01-01".to_string(),
-            ),
-            (
-                "task2".to_string(),
-                "def2".to_string(),
-                "STOPPED".to_string(),
-                "STOPPED".to_string(),
-                "2023-01-02".to_string(),
-            ),
+            task("task1", "def1", "RUNNING", "2023-01-01"),
+            task("task2", "def2", "STOPPED", "2023-01-02"),
         ];
-        let (items, ecs_items) = EcsService::format_task_list(&tasks, "cluster1", Some("service1"));
+        let (items, ecs_items) = EcsService::format_task_list(
+            &tasks,
+            "service1",
+            false,
+            &[],
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
 
         assert_eq!(items.len(), 5); // Header, Separator, ParentDir, 2 tasks
         assert!(items[0].contains("Task ID"));
         assert!(items[3].contains("task1"));
         assert!(items[3].contains("RUNNING"));
 
-        assert!(matches!(ecs_items[3], EcsItem::Task(_)));
+        assert!(matches!(ecs_items[3], EcsItem::Task { .. }));
+        assert!(matches!(&ecs_items[4], EcsItem::Task { status, .. } if status == "STOPPED"));
     }
 }
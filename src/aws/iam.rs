@@ -1,5 +1,184 @@
-use anyhow::Result;
+use crate::aws::utils::{
+    Progress, TimeFormat, column_width, columns_width, display_width, fit_column_width,
+    fit_to_width, format_duration, time_since,
+};
+use anyhow::{Context, Result, anyhow};
 use aws_sdk_iam::Client as IamClient;
+use aws_sdk_iam::types::ReportStateType;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// Access keys that haven't been rotated for this long are flagged in the credential report.
+pub const OLD_KEY_AGE: Duration = Duration::from_secs(90 * 86400);
+
+// The report's name for the account's root user
+const ROOT_USER: &str = "<root_account>";
+
+/// One user's row of the IAM credential report.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CredentialReportEntry {
+    pub user: String,
+    pub arn: String,
+    pub created: String,
+    pub password_enabled: bool,
+    pub password_last_used: String, // A timestamp, or "N/A"/"no_information" when never used
+    pub mfa_active: bool,
+    pub access_keys: [Option<String>; 2], // Last rotation of keys 1 and 2; None when inactive
+}
+
+/// What the credential report flags about a user.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Finding {
+    OldAccessKey,
+    NoMfa,
+    ActivePassword,
+}
+
+impl Finding {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Finding::OldAccessKey => "old access key",
+            Finding::NoMfa => "no MFA",
+            Finding::ActivePassword => "password",
+        }
+    }
+}
+
+impl CredentialReportEntry {
+    pub fn is_root(&self) -> bool {
+        self.user == ROOT_USER
+    }
+
+    /// Active keys older than `OLD_KEY_AGE`, signing in without MFA, and console passwords.
+    /// MFA only matters for users who can sign in: those with a password, and the root user.
+    pub fn findings(&self, now: DateTime<Utc>) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        if self
+            .access_keys
+            .iter()
+            .flatten()
+            .any(|rotated| time_since(rotated, now).is_some_and(|age| age > OLD_KEY_AGE))
+        {
+            findings.push(Finding::OldAccessKey);
+        }
+        if !self.mfa_active && (self.password_enabled || self.is_root()) {
+            findings.push(Finding::NoMfa);
+        }
+        if self.password_enabled {
+            findings.push(Finding::ActivePassword);
+        }
+        findings
+    }
+
+    /// The entry as label/value pairs for the detail popup.
+    pub fn details_pairs(&self, now: DateTime<Utc>) -> Vec<(String, String)> {
+        let yes_no = |b: bool| if b { "yes" } else { "no" }.to_string();
+        let mut details = vec![
+            ("User".to_string(), self.user.clone()),
+            ("ARN".to_string(), self.arn.clone()),
+            ("Created".to_string(), self.created.clone()),
+            ("Password".to_string(), yes_no(self.password_enabled)),
+            (
+                "Password Last Used".to_string(),
+                self.password_last_used.clone(),
+            ),
+            ("MFA".to_string(), yes_no(self.mfa_active)),
+        ];
+        for (i, key) in self.access_keys.iter().enumerate() {
+            let value = match key {
+                Some(rotated) => format!("active, last rotated {}", rotated),
+                None => "inactive".to_string(),
+            };
+            details.push((format!("Access Key {}", i + 1), value));
+        }
+        let findings: Vec<&str> = self.findings(now).iter().map(Finding::label).collect();
+        details.push((
+            "Findings".to_string(),
+            if findings.is_empty() {
+                "None".to_string()
+            } else {
+                findings.join(", ")
+            },
+        ));
+        details
+    }
+}
+
+// Splits one CSV line into its fields. A field in double quotes may hold commas (user names
+// can, e.g. `ops,admin`), and `""` inside the quotes stands for a quote.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c != '"' {
+                field.push(c);
+            } else if chars.peek() == Some(&'"') {
+                chars.next();
+                field.push('"');
+            } else {
+                quoted = false;
+            }
+        } else if c == '"' {
+            quoted = true;
+        } else if c == ',' {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(c);
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Parses the CSV `get_credential_report` returns, finding columns by their header so a
+/// reordered or widened report still reads.
+pub fn parse_credential_report(csv: &str) -> Result<Vec<CredentialReportEntry>> {
+    let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+    let header = split_csv_line(lines.next().context("The credential report is empty")?);
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| *h == name)
+            .ok_or_else(|| anyhow!("The credential report has no {} column", name))
+    };
+    let user = column("user")?;
+    let arn = column("arn")?;
+    let created = column("user_creation_time")?;
+    let password_enabled = column("password_enabled")?;
+    let password_last_used = column("password_last_used")?;
+    let mfa_active = column("mfa_active")?;
+    let keys = [
+        (
+            column("access_key_1_active")?,
+            column("access_key_1_last_rotated")?,
+        ),
+        (
+            column("access_key_2_active")?,
+            column("access_key_2_last_rotated")?,
+        ),
+    ];
+
+    Ok(lines
+        .map(|line| {
+            let fields = split_csv_line(line);
+            let field = |index: usize| fields.get(index).map(String::as_str).unwrap_or_default();
+            CredentialReportEntry {
+                user: field(user).to_string(),
+                arn: field(arn).to_string(),
+                created: field(created).to_string(),
+                password_enabled: field(password_enabled) == "true",
+                password_last_used: field(password_last_used).to_string(),
+                mfa_active: field(mfa_active) == "true",
+                access_keys: keys.map(|(active, rotated)| {
+                    (field(active) == "true").then(|| field(rotated).to_string())
+                }),
+            }
+        })
+        .collect())
+}
 
 #[derive(Clone)]
 pub struct IamService {
@@ -11,24 +190,62 @@ impl IamService {
         Self { client }
     }
 
-    pub async fn list_users(&self) -> Result<Vec<(String, String, String)>> {
+    /// The account alias, if one is set. An account has at most one.
+    pub async fn account_alias(&self) -> Result<Option<String>> {
+        let resp = self.client.list_account_aliases().send().await?;
+        Ok(resp.account_aliases().first().cloned())
+    }
+
+    /// Users as (name, id, creation date, ARN).
+    pub async fn list_users(&self) -> Result<Vec<(String, String, String, String)>> {
         let resp = self.client.list_users().send().await?;
 
-        let users: Vec<(String, String, String)> = resp
+        let users: Vec<(String, String, String, String)> = resp
             .users()
             .iter()
             .map(|u| {
                 let name = u.user_name().to_string();
                 let id = u.user_id().to_string();
                 let date = u.create_date().to_string();
-                (name, id, date)
+                // The ARN carries the user's path, which the name alone doesn't
+                let arn = u.arn().to_string();
+                (name, id, date, arn)
             })
             .collect();
 
         Ok(users)
     }
 
-    pub fn format_user_list(users: &[(String, String, String)]) -> (Vec<String>, Vec<IamItem>) {
+    /// The credential report, asking IAM to generate it first. IAM reuses a report for four
+    /// hours, so this is usually one call each; a new one takes a few seconds to build.
+    pub async fn credential_report(
+        &self,
+        progress: &Progress,
+    ) -> Result<Vec<CredentialReportEntry>> {
+        loop {
+            let resp = self.client.generate_credential_report().send().await?;
+            if resp.state() == Some(&ReportStateType::Complete) {
+                break;
+            }
+            progress.report("Generating the credential report...");
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        let resp = self.client.get_credential_report().send().await?;
+        let csv = resp
+            .content()
+            .map(|content| String::from_utf8_lossy(content.as_ref()).into_owned())
+            .unwrap_or_default();
+        parse_credential_report(&csv)
+    }
+
+    /// Lists users by name, or by full ARN when `full_arns` is set.
+    pub fn format_user_list(
+        users: &[(String, String, String, String)],
+        full_arns: bool,
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<IamItem>) {
         if users.is_empty() {
             return (
                 vec!["No IAM Users found".to_string()],
@@ -37,52 +254,156 @@ impl IamService {
         }
 
         // Calculate column widths
+        let shown = |(name, _, _, arn): &(String, String, String, String)| {
+            if full_arns { arn.clone() } else { name.clone() }
+        };
         let max_name_len = users
             .iter()
-            .map(|(name, _, _)| name.len())
+            .map(|user| display_width(&shown(user)))
             .max()
             .unwrap_or(20)
             .max(20);
 
         let max_id_len = users
             .iter()
-            .map(|(_, id, _)| id.len())
+            .map(|(_, id, _, _)| id.len())
             .max()
             .unwrap_or(20)
             .max(20);
 
+        let created: Vec<String> = users
+            .iter()
+            .map(|(_, _, date, _)| time.render(date))
+            .collect();
+        let rest_len = columns_width(&[max_id_len, column_width("Creation Date", &created)]);
+        let max_name_len = fit_column_width(
+            max_name_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
+        );
+
         let header = format!(
             "{:<width_name$}  {:<width_id$}  Creation Date",
-            "User Name",
+            if full_arns { "User ARN" } else { "User Name" },
             "User ID",
             width_name = max_name_len,
             width_id = max_id_len
         );
-        let separator = "-".repeat(max_name_len + max_id_len + 20).to_string();
+        let separator = "-".repeat(max_name_len + rest_len);
 
         let mut items = vec![header, separator];
         let mut iam_items = vec![IamItem::Header, IamItem::Separator];
 
-        for (name, id, date) in users {
+        for (user, created) in users.iter().zip(&created) {
+            let (name, id, _, _) = user;
             items.push(format!(
-                "{:<width_name$}  {:<width_id$}  {}",
-                name,
+                "{}  {:<width_id$}  {}",
+                fit_to_width(&shown(user), max_name_len),
                 id,
-                date,
-                width_name = max_name_len,
+                created,
                 width_id = max_id_len
             ));
             iam_items.push(IamItem::User(name.clone()));
         }
         (items, iam_items)
     }
+
+    /// The credential report as a table with each active access key's age and what it flags,
+    /// below a `..` row back to the user list.
+    pub fn format_credential_report(
+        entries: &[CredentialReportEntry],
+        time: TimeFormat,
+        now: DateTime<Utc>,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<IamItem>) {
+        let max_name_len = entries
+            .iter()
+            .map(|entry| display_width(&entry.user))
+            .max()
+            .unwrap_or(20)
+            .max(20);
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        // Password, password last used, MFA and the two keys' ages for each entry
+        let cells: Vec<[String; 5]> = entries
+            .iter()
+            .map(|entry| {
+                // The root user's password can't be turned off, so the report doesn't say
+                let password = if entry.is_root() {
+                    "-"
+                } else {
+                    yes_no(entry.password_enabled)
+                };
+                let last_used = if time_since(&entry.password_last_used, now).is_some() {
+                    time.render(&entry.password_last_used)
+                } else {
+                    "never".to_string()
+                };
+                let key_age = |key: Option<&String>| {
+                    key.and_then(|rotated| time_since(rotated, now))
+                        .map(format_duration)
+                        .unwrap_or_else(|| "-".to_string())
+                };
+                [
+                    password.to_string(),
+                    last_used,
+                    yes_no(entry.mfa_active).to_string(),
+                    key_age(entry.access_keys[0].as_ref()),
+                    key_age(entry.access_keys[1].as_ref()),
+                ]
+            })
+            .collect();
+        let headers = ["Password", "Password Last Used", "MFA", "Key 1", "Key 2"];
+        let widths: Vec<usize> = headers
+            .iter()
+            .enumerate()
+            .map(|(column, header)| column_width(header, cells.iter().map(|row| &row[column])))
+            .collect();
+        // Findings run on past the pane, so only the header's room is kept for them
+        let rest_len = columns_width(&widths) + columns_width(&["Findings".len()]);
+        let max_name_len = fit_column_width(
+            max_name_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
+        );
+
+        let row = |user: &str, values: &[String], findings: &str| {
+            let mut line = fit_to_width(user, max_name_len);
+            for (value, width) in values.iter().zip(&widths) {
+                line.push_str(&format!("  {:<width$}", value, width = width));
+            }
+            format!("{}  {}", line, findings).trim_end().to_string()
+        };
+        let header = row("User", &headers.map(String::from), "Findings");
+        let separator = "-".repeat(max_name_len + rest_len);
+
+        let mut items = vec![header, separator, "..".to_string()];
+        let mut iam_items = vec![IamItem::Header, IamItem::Separator, IamItem::ParentDir];
+        for (entry, values) in entries.iter().zip(&cells) {
+            let findings = entry.findings(now);
+            let labels: Vec<&str> = findings.iter().map(Finding::label).collect();
+            items.push(row(&entry.user, values, &labels.join(", ")));
+            iam_items.push(IamItem::ReportEntry {
+                user: entry.user.clone(),
+                findings,
+            });
+        }
+        (items, iam_items)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum IamItem {
     Header,
     Separator,
+    ParentDir,
     User(String),
+    ReportEntry {
+        user: String,
+        findings: Vec<Finding>,
+    },
 }
 
 #[cfg(test)]
@@ -92,24 +413,26 @@ mod tests {
     #[test]
     fn test_format_user_list() {
         // Test empty list
-        let (items, iam_items) = IamService::format_user_list(&[]);
+        let (items, iam_items) =
+            IamService::format_user_list(&[], false, TimeFormat::Absolute, None, usize::MAX);
         assert_eq!(items[0], "No IAM Users found");
         assert!(matches!(iam_items[0], IamItem::Header));
 
         // Test populated list
-        let users = vec![
+        let user = |name: &str, id: &str, date: &str| {
             (
-                "user1".to_string(),
-                "id1".to_string(),
-                "2023-01-01".to_string(),
-            ),
-            (
-                "user2".to_string(),
-                "id2".to_string(),
-                "2023-01-02".to_string(),
-            ),
+                name.to_string(),
+                id.to_string(),
+                date.to_string(),
+                format!("arn:aws:iam::123456789012:user/ops/{}", name),
+            )
+        };
+        let users = vec![
+            user("user1", "id1", "2023-01-01"),
+            user("user2", "id2", "2023-01-02"),
         ];
-        let (items, iam_items) = IamService::format_user_list(&users);
+        let (items, iam_items) =
+            IamService::format_user_list(&users, false, TimeFormat::Absolute, None, usize::MAX);
 
         assert_eq!(items.len(), 4); // Header, Separator, 2 users
         assert!(items[0].contains("User Name"));
@@ -117,9 +440,85 @@ mod tests {
         assert!(items[2].contains("user1"));
         assert!(items[3].contains("user2"));
 
-        assert!(matches!(iam_items[2], IamItem::User(_)));
-        if let IamItem::User(name) = &iam_items[2] {
-            assert_eq!(name, "user1");
-        }
+        assert!(matches!(&iam_items[2], IamItem::User(name) if name == "user1"));
+
+        let (items, iam_items) =
+            IamService::format_user_list(&users, true, TimeFormat::Absolute, None, usize::MAX);
+        assert!(items[0].starts_with("User ARN"));
+        assert!(items[2].starts_with("arn:aws:iam::123456789012:user/ops/user1 "));
+        assert!(matches!(&iam_items[2], IamItem::User(name) if name == "user1"));
+    }
+
+    #[test]
+    fn test_credential_report() {
+        let csv = "user,arn,user_creation_time,password_enabled,password_last_used,\
+password_last_changed,password_next_rotation,mfa_active,access_key_1_active,\
+access_key_1_last_rotated,access_key_1_last_used_date,access_key_2_active,\
+access_key_2_last_rotated
+<root_account>,arn:aws:iam::123456789012:root,2020-01-01T00:00:00+00:00,not_supported,\
+2024-05-10T08:00:00+00:00,not_supported,not_supported,false,false,N/A,N/A,false,N/A
+alice,arn:aws:iam::123456789012:user/alice,2021-03-01T00:00:00+00:00,true,\
+2024-05-14T10:00:00+00:00,2021-03-01T00:00:00+00:00,N/A,true,true,\
+2024-01-01T00:00:00+00:00,2024-05-14T09:00:00+00:00,false,N/A
+ci-bot,arn:aws:iam::123456789012:user/ci-bot,2023-06-01T00:00:00+00:00,false,N/A,N/A,\
+N/A,false,true,2024-05-01T00:00:00+00:00,2024-05-14T00:00:00+00:00,false,N/A
+";
+        let entries = parse_credential_report(csv).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].is_root());
+        assert_eq!(
+            entries[1].access_keys,
+            [Some("2024-01-01T00:00:00+00:00".to_string()), None]
+        );
+        assert!(parse_credential_report("user,arn\nalice,arn").is_err());
+
+        // A user name with a comma comes quoted and doesn't shift the columns after it
+        let quoted =
+            parse_credential_report(&csv.replace("ci-bot,arn", "\"ops,ci-bot\",arn")).unwrap();
+        assert_eq!(quoted[2].user, "ops,ci-bot");
+        assert_eq!(quoted[2].arn, entries[2].arn);
+        assert_eq!(quoted[2].access_keys, entries[2].access_keys);
+
+        let now = DateTime::parse_from_rfc3339("2024-05-15T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(entries[0].findings(now), vec![Finding::NoMfa]);
+        assert_eq!(
+            entries[1].findings(now),
+            vec![Finding::OldAccessKey, Finding::ActivePassword]
+        );
+        // A fresh key and no password to sign in with leave nothing to flag
+        assert!(entries[2].findings(now).is_empty());
+
+        let (items, iam_items) = IamService::format_credential_report(
+            &entries,
+            TimeFormat::Absolute,
+            now,
+            None,
+            usize::MAX,
+        );
+        assert_eq!(items[2], "..");
+        assert!(items[0].ends_with("Findings"));
+        let alice: Vec<&str> = items[4].split_whitespace().collect();
+        assert_eq!(
+            alice,
+            vec![
+                "alice",
+                "yes",
+                "2024-05-14T10:00:00+00:00",
+                "yes",
+                "4mo",
+                "-",
+                "old",
+                "access",
+                "key,",
+                "password"
+            ]
+        );
+        assert!(items[5].contains("never"));
+        assert!(matches!(
+            &iam_items[3],
+            IamItem::ReportEntry { user, findings } if user == "<root_account>" && findings.len() == 1
+        ));
     }
 }
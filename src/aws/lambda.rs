@@ -1,9 +1,18 @@
+use crate::aws::utils::{
+    TimeFormat, column_width, columns_width, display_width, fit_column_width, fit_to_width,
+    join_columns, json_fields, shown_columns,
+};
 use anyhow::Result;
+use aws_sdk_cloudwatchlogs::Client as LogsClient;
+use aws_sdk_cloudwatchlogs::types::{OrderBy, OutputLogEvent};
 use aws_sdk_lambda::Client as LambdaClient;
+use aws_sdk_lambda::types::{AliasConfiguration, FunctionConfiguration};
+use serde_json::{Value, json};
 
 #[derive(Clone)]
 pub struct LambdaService {
     client: LambdaClient,
+    logs: LogsClient,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -11,11 +20,40 @@ pub enum LambdaItem {
     Header,
     Separator,
     Function(String),
+    Version(String),
+    ParentDir,
+}
+
+/// A published version of a function, or `$LATEST`, with the aliases that route to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LambdaVersion {
+    pub version: String,
+    pub aliases: Vec<String>, // "prod", or "canary (10%)" for a weighted share
+    pub last_modified: String,
+    pub description: String,
+}
+
+// Aliases sending traffic to `version`, with the share when it's a weighted extra version
+fn alias_labels(aliases: &[AliasConfiguration], version: &str) -> Vec<String> {
+    let mut labels = Vec::new();
+    for alias in aliases {
+        let name = alias.name().unwrap_or("?");
+        let extra = alias
+            .routing_config()
+            .and_then(|routing| routing.additional_version_weights())
+            .and_then(|weights| weights.get(version));
+        if alias.function_version() == Some(version) {
+            labels.push(name.to_string());
+        } else if let Some(weight) = extra {
+            labels.push(format!("{} ({:.0}%)", name, weight * 100.0));
+        }
+    }
+    labels
 }
 
 impl LambdaService {
-    pub fn new(client: LambdaClient) -> Self {
-        Self { client }
+    pub fn new(client: LambdaClient, logs: LogsClient) -> Self {
+        Self { client, logs }
     }
 
     pub async fn list_functions(&self) -> Result<Vec<(String, String, String)>> {
@@ -51,8 +89,213 @@ impl LambdaService {
             .ok_or_else(|| anyhow::anyhow!("Function configuration not found"))
     }
 
+    /// The last `limit` events of the most recent stream in `log_group`, oldest first, as
+    /// (time, message) rows. `None` when the group doesn't exist yet because the function was
+    /// never invoked.
+    pub async fn tail_logs(
+        &self,
+        log_group: &str,
+        limit: i32,
+    ) -> Result<Option<Vec<(String, String)>>> {
+        let streams = match self
+            .logs
+            .describe_log_streams()
+            .log_group_name(log_group)
+            .order_by(OrderBy::LastEventTime)
+            .descending(true)
+            .limit(1)
+            .send()
+            .await
+        {
+            Ok(resp) => resp,
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_resource_not_found_exception()) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let Some(stream) = streams
+            .log_streams()
+            .first()
+            .and_then(|stream| stream.log_stream_name())
+        else {
+            return Ok(Some(Vec::new()));
+        };
+        let resp = self
+            .logs
+            .get_log_events()
+            .log_group_name(log_group)
+            .log_stream_name(stream)
+            .start_from_head(false)
+            .limit(limit)
+            .send()
+            .await?;
+        Ok(Some(Self::log_rows(resp.events())))
+    }
+
+    fn log_rows(events: &[OutputLogEvent]) -> Vec<(String, String)> {
+        events
+            .iter()
+            .map(|event| {
+                let time = event
+                    .timestamp()
+                    .map(|ms| aws_smithy_types::DateTime::from_millis(ms).to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+                // Lambda ends every line with a newline; the popup shows one row per event
+                let message = event.message().unwrap_or("").trim_end().replace('\n', " ");
+                (time, message)
+            })
+            .collect()
+    }
+
+    /// Every version of `name`, `$LATEST` first and then newest first, with the aliases
+    /// pointing at each. The versions and aliases are listed concurrently.
+    pub async fn list_versions(&self, name: &str) -> Result<Vec<LambdaVersion>> {
+        let versions = async {
+            let mut versions: Vec<FunctionConfiguration> = Vec::new();
+            let mut pages = self
+                .client
+                .list_versions_by_function()
+                .function_name(name)
+                .into_paginator()
+                .send();
+            while let Some(page) = pages.next().await {
+                versions.extend(page?.versions().iter().cloned());
+            }
+            anyhow::Ok(versions)
+        };
+        let aliases = async {
+            let mut aliases: Vec<AliasConfiguration> = Vec::new();
+            let mut pages = self
+                .client
+                .list_aliases()
+                .function_name(name)
+                .into_paginator()
+                .send();
+            while let Some(page) = pages.next().await {
+                aliases.extend(page?.aliases().iter().cloned());
+            }
+            anyhow::Ok(aliases)
+        };
+        let (versions, aliases) = tokio::try_join!(versions, aliases)?;
+        Ok(Self::version_rows(&versions, &aliases))
+    }
+
+    fn version_rows(
+        versions: &[FunctionConfiguration],
+        aliases: &[AliasConfiguration],
+    ) -> Vec<LambdaVersion> {
+        let mut rows: Vec<LambdaVersion> = versions
+            .iter()
+            .map(|config| {
+                let version = config.version().unwrap_or("?").to_string();
+                LambdaVersion {
+                    aliases: alias_labels(aliases, &version),
+                    version,
+                    last_modified: config.last_modified().unwrap_or("unknown").to_string(),
+                    description: config.description().unwrap_or("").to_string(),
+                }
+            })
+            .collect();
+        // Published versions are numbered; $LATEST doesn't parse and sorts first
+        rows.sort_by_key(|row| std::cmp::Reverse(row.version.parse::<u64>().unwrap_or(u64::MAX)));
+        rows
+    }
+
+    pub fn format_version_list(
+        function: &str,
+        versions: &[LambdaVersion],
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<LambdaItem>) {
+        if versions.is_empty() {
+            return (
+                vec![
+                    format!("No versions found for {}", function),
+                    "..".to_string(),
+                ],
+                vec![LambdaItem::Header, LambdaItem::ParentDir],
+            );
+        }
+
+        let aliases: Vec<String> = versions
+            .iter()
+            .map(|v| {
+                if v.aliases.is_empty() {
+                    "-".to_string()
+                } else {
+                    v.aliases.join(", ")
+                }
+            })
+            .collect();
+        let max_aliases_len = aliases
+            .iter()
+            .map(|a| display_width(a))
+            .max()
+            .unwrap_or(20)
+            .max(20);
+        let modified: Vec<String> = versions
+            .iter()
+            .map(|v| time.render(&v.last_modified))
+            .collect();
+        let version_len = column_width("Version", versions.iter().map(|v| &v.version));
+        let modified_len = column_width("Last Modified", &modified);
+        // Descriptions run on past the pane rather than squeezing the aliases, so only the
+        // header's room is kept for them
+        let rest_len = columns_width(&[version_len, modified_len, "Description".len()]);
+        let max_aliases_len = fit_column_width(
+            max_aliases_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
+        );
+
+        let header = format!(
+            "{:<version_width$}  {:<aliases_width$}  {:<modified_width$}  Description",
+            "Version",
+            "Aliases",
+            "Last Modified",
+            version_width = version_len,
+            aliases_width = max_aliases_len,
+            modified_width = modified_len
+        );
+        let separator = "-".repeat(max_aliases_len + rest_len);
+
+        let mut items = vec![header, separator, "..".to_string()];
+        let mut lambda_items = vec![
+            LambdaItem::Header,
+            LambdaItem::Separator,
+            LambdaItem::ParentDir,
+        ];
+        for ((version, aliases), modified) in versions.iter().zip(&aliases).zip(&modified) {
+            items.push(format!(
+                "{:<version_width$}  {}  {:<modified_width$}  {}",
+                version.version,
+                fit_to_width(aliases, max_aliases_len),
+                modified,
+                version.description,
+                version_width = version_len,
+                modified_width = modified_len
+            ));
+            lambda_items.push(LambdaItem::Version(version.version.clone()));
+        }
+        (items, lambda_items)
+    }
+
+    /// Columns of the function list that can be hidden, by config name and header.
+    pub const FUNCTION_COLUMNS: [(&str, &str); 2] =
+        [("runtime", "Runtime"), ("last_modified", "Last Modified")];
+
+    /// The functions, leaving out the `FUNCTION_COLUMNS` named in `hidden`.
     pub fn format_function_list(
         functions: &[(String, String, String)],
+        hidden: &[&str],
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
     ) -> (Vec<String>, Vec<LambdaItem>) {
         if functions.is_empty() {
             return (
@@ -63,42 +306,32 @@ impl LambdaService {
 
         let max_name_len = functions
             .iter()
-            .map(|(name, _, _)| name.len())
+            .map(|(name, _, _)| display_width(name))
             .max()
             .unwrap_or(20)
             .max(20);
 
-        let max_runtime_len = functions
+        let rows = functions
             .iter()
-            .map(|(_, runtime, _)| runtime.len())
-            .max()
-            .unwrap_or(10)
-            .max(10);
-
-        let header = format!(
-            "{:<name_width$}  {:<runtime_width$}  {:<mod_width$}",
-            "Function Name",
-            "Runtime",
-            "Last Modified",
-            name_width = max_name_len,
-            runtime_width = max_runtime_len,
-            mod_width = 25
+            .map(|(_, runtime, last_modified)| vec![runtime.clone(), time.render(last_modified)])
+            .collect();
+        let (headers, cells, widths) = shown_columns(&Self::FUNCTION_COLUMNS, hidden, rows);
+        let rest_len = columns_width(&widths);
+        let max_name_len = fit_column_width(
+            max_name_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
         );
-        let separator = "-".repeat(header.len()).to_string();
+
+        let header = fit_to_width("Function Name", max_name_len) + &join_columns(&headers, &widths);
+        let separator = "-".repeat(max_name_len + rest_len);
 
         let mut items = vec![header, separator];
         let mut lambda_items = vec![LambdaItem::Header, LambdaItem::Separator];
 
-        for (name, runtime, last_modified) in functions {
-            items.push(format!(
-                "{:<name_width$}  {:<runtime_width$}  {:<mod_width$}",
-                name,
-                runtime,
-                last_modified,
-                name_width = max_name_len,
-                runtime_width = max_runtime_len,
-                mod_width = 25
-            ));
+        for ((name, _, _), values) in functions.iter().zip(&cells) {
+            items.push(fit_to_width(name, max_name_len) + &join_columns(values, &widths));
             lambda_items.push(LambdaItem::Function(name.clone()));
         }
         (items, lambda_items)
@@ -160,14 +393,179 @@ impl LambdaService {
                     .unwrap_or("unknown")
                     .to_string(),
             ),
+            ("Log Group".to_string(), Self::log_group_name(config)),
         ]
     }
+
+    pub fn function_json(config: &aws_sdk_lambda::types::FunctionConfiguration) -> Value {
+        let text = |s: Option<&str>| s.map(|s| json!(s));
+        json_fields(vec![
+            ("FunctionName", text(config.function_name())),
+            ("FunctionArn", text(config.function_arn())),
+            ("Runtime", text(config.runtime().map(|r| r.as_str()))),
+            ("Role", text(config.role())),
+            ("Handler", text(config.handler())),
+            ("CodeSize", Some(json!(config.code_size()))),
+            ("Description", text(config.description())),
+            ("Timeout", config.timeout().map(|t| json!(t))),
+            ("MemorySize", config.memory_size().map(|m| json!(m))),
+            ("LastModified", text(config.last_modified())),
+            ("CodeSha256", text(config.code_sha256())),
+            ("Version", text(config.version())),
+            ("RevisionId", text(config.revision_id())),
+            (
+                "PackageType",
+                text(config.package_type().map(|p| p.as_str())),
+            ),
+            (
+                "Architectures",
+                Some(json!(
+                    config
+                        .architectures()
+                        .iter()
+                        .map(|a| a.as_str())
+                        .collect::<Vec<_>>()
+                )),
+            ),
+            ("State", text(config.state().map(|s| s.as_str()))),
+            ("StateReason", text(config.state_reason())),
+            (
+                "LastUpdateStatus",
+                text(config.last_update_status().map(|s| s.as_str())),
+            ),
+            (
+                "VpcConfig",
+                config.vpc_config().map(|v| {
+                    json_fields(vec![
+                        ("VpcId", text(v.vpc_id())),
+                        ("SubnetIds", Some(json!(v.subnet_ids()))),
+                        ("SecurityGroupIds", Some(json!(v.security_group_ids()))),
+                    ])
+                }),
+            ),
+            (
+                "Environment",
+                config
+                    .environment()
+                    .map(|e| json_fields(vec![("Variables", e.variables().map(|v| json!(v)))])),
+            ),
+            (
+                "DeadLetterConfig",
+                config
+                    .dead_letter_config()
+                    .map(|d| json_fields(vec![("TargetArn", text(d.target_arn()))])),
+            ),
+            ("KMSKeyArn", text(config.kms_key_arn())),
+            (
+                "TracingConfig",
+                config
+                    .tracing_config()
+                    .map(|t| json_fields(vec![("Mode", text(t.mode().map(|m| m.as_str())))])),
+            ),
+            (
+                "Layers",
+                Some(Value::Array(
+                    config
+                        .layers()
+                        .iter()
+                        .map(|l| {
+                            json_fields(vec![
+                                ("Arn", text(l.arn())),
+                                ("CodeSize", Some(json!(l.code_size()))),
+                            ])
+                        })
+                        .collect(),
+                )),
+            ),
+            (
+                "EphemeralStorage",
+                config
+                    .ephemeral_storage()
+                    .map(|e| json_fields(vec![("Size", Some(json!(e.size())))])),
+            ),
+            (
+                "LoggingConfig",
+                config.logging_config().map(|l| {
+                    json_fields(vec![
+                        ("LogFormat", text(l.log_format().map(|f| f.as_str()))),
+                        ("LogGroup", text(l.log_group())),
+                    ])
+                }),
+            ),
+        ])
+    }
+
+    /// CloudWatch Logs group the function writes to: the one set in its logging config,
+    /// otherwise the default `/aws/lambda/<name>`.
+    pub fn log_group_name(config: &aws_sdk_lambda::types::FunctionConfiguration) -> String {
+        config
+            .logging_config()
+            .and_then(|logging| logging.log_group())
+            .map(String::from)
+            .unwrap_or_else(|| {
+                format!(
+                    "/aws/lambda/{}",
+                    config.function_name().unwrap_or("unknown")
+                )
+            })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_version_rows() {
+        use aws_sdk_lambda::types::AliasRoutingConfiguration;
+
+        let versions: Vec<FunctionConfiguration> = ["$LATEST", "3", "4", "10"]
+            .iter()
+            .map(|v| {
+                FunctionConfiguration::builder()
+                    .version(*v)
+                    .last_modified("2024-05-01T10:00:00.000+0000")
+                    .build()
+            })
+            .collect();
+        let aliases = vec![
+            AliasConfiguration::builder()
+                .name("prod")
+                .function_version("3")
+                .routing_config(
+                    AliasRoutingConfiguration::builder()
+                        .additional_version_weights("4", 0.1)
+                        .build(),
+                )
+                .build(),
+            AliasConfiguration::builder()
+                .name("dev")
+                .function_version("$LATEST")
+                .build(),
+        ];
+
+        let rows = LambdaService::version_rows(&versions, &aliases);
+        let order: Vec<&str> = rows.iter().map(|r| r.version.as_str()).collect();
+        assert_eq!(order, vec!["$LATEST", "10", "4", "3"]);
+        assert_eq!(rows[0].aliases, vec!["dev"]);
+        assert!(rows[1].aliases.is_empty());
+        assert_eq!(rows[2].aliases, vec!["prod (10%)"]);
+        assert_eq!(rows[3].aliases, vec!["prod"]);
+
+        let (items, lambda_items) = LambdaService::format_version_list(
+            "my-func",
+            &rows,
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
+        assert!(items[0].starts_with("Version"));
+        assert_eq!(lambda_items[2], LambdaItem::ParentDir);
+        assert!(items[4].starts_with("10"));
+        assert!(items[4].contains(" -  "));
+        assert_eq!(lambda_items[6], LambdaItem::Version("3".to_string()));
+    }
+
     #[test]
     fn test_format_function_list() {
         let functions = vec![
@@ -179,10 +577,16 @@ mod tests {
             (
                 "func2".to_string(),
                 "nodejs18.x".to_string(),
-                "2023-01-02".to_string(),
+                "2023-01-02T10:00:00.000+0000".to_string(),
             ),
         ];
-        let (items, lambda_items) = LambdaService::format_function_list(&functions);
+        let (items, lambda_items) = LambdaService::format_function_list(
+            &functions,
+            &[],
+            TimeFormat::Relative,
+            None,
+            usize::MAX,
+        );
 
         assert_eq!(items.len(), 4); // Header, Separator, 2 functions
         assert!(items[0].contains("Function Name"));
@@ -190,10 +594,62 @@ mod tests {
         assert!(items[0].contains("Last Modified"));
         assert!(items[2].contains("func1"));
         assert!(items[2].contains("python3.9"));
+        // Timestamps are shown relative; text that isn't one is left alone
+        assert!(items[2].contains("2023-01-01"));
+        assert!(items[3].contains("y ago"));
+        assert!(!items[3].contains("2023"));
 
         assert!(matches!(lambda_items[2], LambdaItem::Function(_)));
     }
 
+    #[test]
+    fn test_function_json() {
+        let config = aws_sdk_lambda::types::FunctionConfiguration::builder()
+            .function_name("test-func")
+            .runtime(aws_sdk_lambda::types::Runtime::Python39)
+            .memory_size(128)
+            .environment(
+                aws_sdk_lambda::types::EnvironmentResponse::builder()
+                    .variables("STAGE", "prod")
+                    .build(),
+            )
+            .build();
+
+        let raw = LambdaService::function_json(&config);
+        assert_eq!(raw["FunctionName"], "test-func");
+        assert_eq!(raw["Runtime"], "python3.9");
+        assert_eq!(raw["MemorySize"], 128);
+        assert_eq!(raw["Environment"]["Variables"]["STAGE"], "prod");
+        // Unset fields are left out rather than written as null
+        assert!(raw.get("Role").is_none());
+    }
+
+    #[test]
+    fn test_log_rows() {
+        let events = [
+            OutputLogEvent::builder()
+                .timestamp(1_715_767_200_000)
+                .message("START RequestId: abc Version: $LATEST\n")
+                .build(),
+            OutputLogEvent::builder()
+                .message("Traceback:\n  line 3\n")
+                .build(),
+        ];
+
+        let rows = LambdaService::log_rows(&events);
+        assert_eq!(
+            rows[0],
+            (
+                "2024-05-15T10:00:00Z".to_string(),
+                "START RequestId: abc Version: $LATEST".to_string()
+            )
+        );
+        assert_eq!(
+            rows[1],
+            ("unknown".to_string(), "Traceback:   line 3".to_string())
+        );
+    }
+
     #[test]
     fn test_get_function_details_pairs() {
         let config = aws_sdk_lambda::types::FunctionConfiguration::builder()
@@ -207,12 +663,26 @@ mod tests {
 
         let details = LambdaService::get_function_details_pairs(&config);
 
-        assert_eq!(details.len(), 10);
+        assert_eq!(details.len(), 11);
         assert_eq!(details[0], ("Name".to_string(), "test-func".to_string()));
         assert_eq!(details[2], ("Runtime".to_string(), "python3.9".to_string()));
         assert_eq!(
             details[5],
             ("Memory Size".to_string(), "128 MB".to_string())
         );
+        assert_eq!(
+            details[10],
+            ("Log Group".to_string(), "/aws/lambda/test-func".to_string())
+        );
+
+        let config = aws_sdk_lambda::types::FunctionConfiguration::builder()
+            .function_name("test-func")
+            .logging_config(
+                aws_sdk_lambda::types::LoggingConfig::builder()
+                    .log_group("/custom/group")
+                    .build(),
+            )
+            .build();
+        assert_eq!(LambdaService::log_group_name(&config), "/custom/group");
     }
 }
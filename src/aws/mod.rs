@@ -1,5 +1,10 @@
 use anyhow::Result;
-use aws_config::BehaviorVersion;
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::{BehaviorVersion, Region};
+use aws_credential_types::Credentials;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use std::collections::HashMap;
+use std::time::SystemTime;
 
 mod cloudwatch;
 mod dynamodb;
@@ -8,20 +13,54 @@ mod ecs;
 mod iam;
 mod lambda;
 mod mwaa;
+pub mod profile;
 mod s3;
 pub mod utils;
 
-pub use cloudwatch::CloudwatchService;
-pub use dynamodb::{DynamoDbItem, DynamoDbService};
-pub use ec2::{Ec2Item, Ec2Service};
-pub use ecs::{EcsItem, EcsService};
-pub use iam::{IamItem, IamService};
-pub use lambda::{LambdaItem, LambdaService};
+pub use cloudwatch::{CloudwatchItem, CloudwatchService, MetricRange, MetricSeries};
+pub use dynamodb::{DynamoDbItem, DynamoDbService, IndexDetail};
+pub use ec2::{Ec2Column, Ec2Columns, Ec2Instance, Ec2Item, Ec2Service};
+pub use ecs::{EcsItem, EcsService, EcsTask};
+pub use iam::{CredentialReportEntry, Finding, IamItem, IamService};
+pub use lambda::{LambdaItem, LambdaService, LambdaVersion};
 pub use mwaa::{MwaaItem, MwaaService};
-pub use s3::{S3Item, S3NavigationAction, S3Service};
+pub use s3::{
+    BucketTag, FolderStats, S3Item, S3NavigationAction, S3ObjectVersion, S3Service, upload_key,
+    validate_bucket_name,
+};
+pub use utils::{InFlight, Paging, Progress, TimeFormat, with_timeout};
+
+#[derive(Clone, Debug, Default)]
+pub struct ClientOptions {
+    pub profile: Option<String>,
+    pub region: Option<String>,
+    pub assume_role: Option<String>, // Role ARN to assume for all service clients
+    pub endpoint_url: Option<String>, // e.g. LocalStack; AWS_ENDPOINT_URL also works
+    pub s3_force_path_style: bool,   // bucket in the path rather than the host name
+    pub use_fips: bool,              // FIPS 140-validated endpoints, e.g. for GovCloud
+    pub use_dual_stack: bool,        // Endpoints reachable over IPv4 and IPv6
+}
+
+impl ClientOptions {
+    // Short name of the assumed role for display, e.g. "ReadOnly" from its ARN
+    pub fn assumed_role_name(&self) -> Option<&str> {
+        self.assume_role
+            .as_deref()
+            .map(|arn| arn.rsplit('/').next().unwrap_or(arn))
+    }
+}
+
+/// The account the credentials belong to, shown in the header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountIdentity {
+    pub account_id: String,
+    pub alias: Option<String>,
+}
 
 #[derive(Clone)]
 pub struct AwsClient {
+    credentials: Option<SharedCredentialsProvider>,
+    sts_client: aws_sdk_sts::Client,
     ec2_service: Ec2Service,
     s3_service: S3Service,
     iam_service: IamService,
@@ -34,40 +73,227 @@ pub struct AwsClient {
 
 impl AwsClient {
     pub async fn new() -> Result<Self> {
-        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        Self::with_options(&ClientOptions::default()).await
+    }
+
+    pub async fn with_options(options: &ClientOptions) -> Result<Self> {
+        let loader = || {
+            let mut loader = aws_config::defaults(BehaviorVersion::latest());
+            if let Some(profile) = &options.profile {
+                loader = loader.profile_name(profile);
+            }
+            if let Some(region) = &options.region {
+                loader = loader.region(Region::new(region.clone()));
+            }
+            if let Some(url) = &options.endpoint_url {
+                loader = loader.endpoint_url(url);
+            }
+            // Only set when asked for, so AWS_USE_FIPS_ENDPOINT and the profile still apply
+            if options.use_fips {
+                loader = loader.use_fips(true);
+            }
+            if options.use_dual_stack {
+                loader = loader.use_dual_stack(true);
+            }
+            loader
+        };
+        let mut config = loader().load().await;
+
+        // Use the base credentials only to assume the target role
+        if let Some(role_arn) = &options.assume_role {
+            let provider = AssumeRoleProvider::builder(role_arn)
+                .session_name("awsome")
+                .configure(&config)
+                .build()
+                .await;
+            config = loader().credentials_provider(provider).load().await;
+        }
+
+        let s3_config = aws_sdk_s3::config::Builder::from(&config)
+            .force_path_style(options.s3_force_path_style)
+            .build();
 
         Ok(Self {
+            credentials: config.credentials_provider(),
+            sts_client: aws_sdk_sts::Client::new(&config),
             ec2_service: Ec2Service::new(aws_sdk_ec2::Client::new(&config)),
-            s3_service: S3Service::new(aws_sdk_s3::Client::new(&config)),
+            s3_service: S3Service::new(aws_sdk_s3::Client::from_conf(s3_config)),
             iam_service: IamService::new(aws_sdk_iam::Client::new(&config)),
             cloudwatch_service: CloudwatchService::new(aws_sdk_cloudwatch::Client::new(&config)),
             dynamodb_service: DynamoDbService::new(aws_sdk_dynamodb::Client::new(&config)),
             ecs_service: EcsService::new(aws_sdk_ecs::Client::new(&config)),
             mwaa_service: MwaaService::new(aws_sdk_mwaa::Client::new(&config)),
-            lambda_service: LambdaService::new(aws_sdk_lambda::Client::new(&config)),
+            lambda_service: LambdaService::new(
+                aws_sdk_lambda::Client::new(&config),
+                aws_sdk_cloudwatchlogs::Client::new(&config),
+            ),
+        })
+    }
+
+    /// Account id of the credentials, plus its alias when the caller may list aliases.
+    pub async fn account_identity(&self) -> Result<AccountIdentity> {
+        let (identity, alias) = tokio::join!(
+            self.sts_client.get_caller_identity().send(),
+            self.iam_service.account_alias(),
+        );
+        let account_id = identity?
+            .account()
+            .ok_or_else(|| anyhow::anyhow!("STS returned no account id"))?
+            .to_string();
+        Ok(AccountIdentity {
+            account_id,
+            alias: alias.ok().flatten(),
         })
     }
 
+    /// When the resolved credentials stop working. `None` for credentials that don't expire,
+    /// like access keys, or when they can't be resolved.
+    pub async fn credentials_expiry(&self) -> Option<SystemTime> {
+        self.credentials().await.ok()?.expiry()
+    }
+
+    /// The credentials the clients sign with, e.g. an assumed role's, for a child process that
+    /// has to act as the same identity.
+    pub async fn credentials(&self) -> Result<Credentials> {
+        let provider = self
+            .credentials
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no credentials were resolved"))?;
+        Ok(provider.provide_credentials().await?)
+    }
+
     pub async fn list_ec2_instances(
         &self,
-    ) -> Result<Vec<(String, String, String, String, String)>> {
-        self.ec2_service.list_instances().await
+        tag_filter: Option<&(String, String)>,
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<Vec<Ec2Instance>> {
+        self.ec2_service
+            .list_instances(tag_filter, paging, progress)
+            .await
+    }
+
+    pub async fn list_ec2_instances_all_regions(
+        &self,
+        tag_filter: Option<&(String, String)>,
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<(Vec<Ec2Instance>, Vec<(String, String)>)> {
+        self.ec2_service
+            .list_instances_all_regions(tag_filter, paging, progress)
+            .await
+    }
+
+    pub async fn describe_ec2_instance_raw(&self, instance_id: &str) -> Result<serde_json::Value> {
+        self.ec2_service.describe_instance_raw(instance_id).await
+    }
+
+    pub async fn describe_ec2_instance(&self, instance_id: &str) -> Result<Vec<(String, String)>> {
+        self.ec2_service.describe_instance(instance_id).await
+    }
+
+    pub async fn stop_ec2_instances(&self, instance_ids: &[String], dry_run: bool) -> Result<()> {
+        self.ec2_service.stop_instances(instance_ids, dry_run).await
+    }
+
+    pub async fn rename_ec2_instance(
+        &self,
+        instance_id: &str,
+        name: &str,
+        dry_run: bool,
+    ) -> Result<()> {
+        self.ec2_service
+            .set_name_tag(instance_id, name, dry_run)
+            .await
+    }
+
+    pub async fn s3_bucket_tag_values(
+        &self,
+        buckets: &[String],
+        key: &str,
+        progress: &Progress,
+    ) -> Vec<BucketTag> {
+        self.s3_service
+            .bucket_tag_values(buckets, key, progress)
+            .await
+    }
+
+    pub fn s3_clear_bucket_tags(&self) {
+        self.s3_service.clear_bucket_tags()
+    }
+
+    pub async fn s3_folder_stats(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        folders: &[String],
+        progress: &Progress,
+    ) -> HashMap<String, FolderStats> {
+        self.s3_service
+            .folder_stats_all(bucket, prefix, folders, progress)
+            .await
     }
 
     pub async fn list_s3_buckets(&self) -> Result<Vec<(String, String)>> {
         self.s3_service.list_buckets().await
     }
 
-    pub async fn list_iam_users(&self) -> Result<Vec<(String, String, String)>> {
+    pub async fn list_iam_users(&self) -> Result<Vec<(String, String, String, String)>> {
         self.iam_service.list_users().await
     }
 
-    pub async fn list_cloudwatch_alarms(&self) -> Result<Vec<String>> {
-        self.cloudwatch_service.list_alarms().await
+    pub async fn iam_credential_report(
+        &self,
+        progress: &Progress,
+    ) -> Result<Vec<CredentialReportEntry>> {
+        self.iam_service.credential_report(progress).await
+    }
+
+    pub async fn list_cloudwatch_alarms(
+        &self,
+        paging: Paging,
+    ) -> Result<Vec<(String, String, bool)>> {
+        self.cloudwatch_service.list_alarms(paging).await
     }
 
-    pub async fn list_dynamodb_tables(&self) -> Result<Vec<(String, String, String, String)>> {
-        self.dynamodb_service.list_tables_with_details().await
+    pub async fn describe_cloudwatch_alarm(
+        &self,
+        alarm_name: &str,
+        range: MetricRange,
+    ) -> Result<(Vec<(String, String)>, Option<MetricSeries>)> {
+        self.cloudwatch_service
+            .describe_alarm(alarm_name, range)
+            .await
+    }
+
+    pub async fn count_cloudwatch_alarms_firing(&self) -> Result<usize> {
+        self.cloudwatch_service.count_alarms_firing().await
+    }
+
+    pub async fn list_dynamodb_tables(
+        &self,
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<Vec<(String, String, String, String)>> {
+        self.dynamodb_service
+            .list_tables_with_details(paging, progress)
+            .await
+    }
+
+    pub async fn create_s3_bucket(&self, bucket: &str) -> Result<()> {
+        self.s3_service.create_bucket(bucket).await
+    }
+
+    pub async fn get_s3_bucket_summary(
+        &self,
+        bucket: &str,
+        progress: &Progress,
+    ) -> Result<Vec<(String, String)>> {
+        self.s3_service.get_bucket_summary(bucket, progress).await
+    }
+
+    pub async fn delete_s3_bucket(&self, bucket: &str) -> Result<()> {
+        self.s3_service.delete_bucket(bucket).await
     }
 
     pub async fn get_s3_bucket_details(&self, bucket_name: &str) -> Result<Vec<(String, String)>> {
@@ -78,30 +304,139 @@ impl AwsClient {
         &self,
         bucket: &str,
         prefix: &str,
-    ) -> Result<Vec<(String, String, String)>> {
-        self.s3_service.list_objects(bucket, prefix).await
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<Vec<(String, String, String, String)>> {
+        self.s3_service
+            .list_objects(bucket, prefix, paging, progress)
+            .await
+    }
+
+    pub async fn get_s3_versioning_status(&self, bucket: &str) -> Result<Option<String>> {
+        self.s3_service.get_versioning_status(bucket).await
+    }
+
+    pub async fn list_s3_object_versions(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<Vec<S3ObjectVersion>> {
+        self.s3_service
+            .list_object_versions(bucket, prefix, paging, progress)
+            .await
+    }
+
+    pub async fn get_s3_object_storage_class(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<(String, Option<String>)> {
+        self.s3_service.get_object_storage_class(bucket, key).await
+    }
+
+    pub async fn restore_s3_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        tier: &str,
+        days: i32,
+    ) -> Result<()> {
+        self.s3_service
+            .restore_object(bucket, key, tier, days)
+            .await
+    }
+
+    pub async fn delete_s3_object(&self, bucket: &str, key: &str) -> Result<()> {
+        self.s3_service.delete_object(bucket, key).await
+    }
+
+    pub async fn list_s3_keys(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        progress: &Progress,
+    ) -> Result<Vec<String>> {
+        self.s3_service.list_keys(bucket, prefix, progress).await
+    }
+
+    pub async fn delete_s3_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+        progress: &Progress,
+    ) -> Result<usize> {
+        self.s3_service.delete_objects(bucket, keys, progress).await
+    }
+
+    pub async fn upload_s3_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        path: &std::path::Path,
+        progress: &Progress,
+    ) -> Result<u64> {
+        self.s3_service
+            .upload_object(bucket, key, path, progress)
+            .await
+    }
+
+    pub async fn download_s3_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: Option<&str>,
+        path: &std::path::Path,
+        progress: &Progress,
+    ) -> Result<u64> {
+        self.s3_service
+            .download_object(bucket, key, version_id, path, progress)
+            .await
     }
 
     pub async fn get_s3_object_details(
         &self,
         bucket: &str,
         key: &str,
+        version_id: Option<&str>,
     ) -> Result<Vec<(String, String)>> {
-        self.s3_service.get_object_details(bucket, key).await
+        self.s3_service
+            .get_object_details(bucket, key, version_id)
+            .await
+    }
+
+    pub async fn create_dynamodb_table(
+        &self,
+        table_name: &str,
+        partition_key: &str,
+        key_type: &str,
+    ) -> Result<()> {
+        self.dynamodb_service
+            .create_table(table_name, partition_key, key_type)
+            .await
+    }
+
+    pub async fn delete_dynamodb_table(&self, table_name: &str) -> Result<()> {
+        self.dynamodb_service.delete_table(table_name).await
     }
 
     pub async fn get_dynamodb_table_details(
         &self,
         table_name: &str,
-    ) -> Result<Vec<(String, String)>> {
+    ) -> Result<(Vec<(String, String)>, Vec<IndexDetail>)> {
         self.dynamodb_service.describe_table(table_name).await
     }
 
-    pub async fn list_ecs_clusters(&self) -> Result<Vec<String>> {
+    pub async fn describe_dynamodb_table_raw(&self, table_name: &str) -> Result<serde_json::Value> {
+        self.dynamodb_service.describe_table_raw(table_name).await
+    }
+
+    pub async fn list_ecs_clusters(&self) -> Result<Vec<(String, String)>> {
         self.ecs_service.list_clusters().await
     }
 
-    pub async fn list_ecs_services(&self, cluster: &str) -> Result<Vec<String>> {
+    pub async fn list_ecs_services(&self, cluster: &str) -> Result<Vec<(String, String)>> {
         self.ecs_service.list_services(cluster).await
     }
 
@@ -109,10 +444,30 @@ impl AwsClient {
         &self,
         cluster: &str,
         service: Option<&str>,
-    ) -> Result<Vec<(String, String, String, String, String)>> {
+    ) -> Result<Vec<EcsTask>> {
         self.ecs_service.list_tasks(cluster, service).await
     }
 
+    pub async fn describe_ecs_service(
+        &self,
+        cluster: &str,
+        service: &str,
+    ) -> Result<Vec<(String, String)>> {
+        self.ecs_service.describe_service(cluster, service).await
+    }
+
+    pub async fn describe_ecs_task(
+        &self,
+        cluster: &str,
+        task_id: &str,
+    ) -> Result<Vec<(String, String)>> {
+        self.ecs_service.describe_task(cluster, task_id).await
+    }
+
+    pub async fn stop_ecs_task(&self, cluster: &str, task_id: &str, reason: &str) -> Result<()> {
+        self.ecs_service.stop_task(cluster, task_id, reason).await
+    }
+
     pub async fn list_mwaa_environments(&self) -> Result<Vec<String>> {
         self.mwaa_service.list_environments().await
     }
@@ -128,6 +483,18 @@ impl AwsClient {
         self.lambda_service.list_functions().await
     }
 
+    pub async fn list_lambda_versions(&self, name: &str) -> Result<Vec<LambdaVersion>> {
+        self.lambda_service.list_versions(name).await
+    }
+
+    pub async fn tail_lambda_logs(
+        &self,
+        log_group: &str,
+        limit: i32,
+    ) -> Result<Option<Vec<(String, String)>>> {
+        self.lambda_service.tail_logs(log_group, limit).await
+    }
+
     pub async fn get_lambda_function(
         &self,
         name: &str,
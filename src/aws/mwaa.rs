@@ -1,3 +1,4 @@
+use crate::aws::utils::{display_width, fit_column_width, fit_to_width};
 use anyhow::Result;
 use aws_sdk_mwaa::Client as MwaaClient;
 
@@ -29,7 +30,11 @@ impl MwaaService {
             .ok_or_else(|| anyhow::anyhow!("Environment not found"))
     }
 
-    pub fn format_environment_list(envs: &[String]) -> (Vec<String>, Vec<MwaaItem>) {
+    pub fn format_environment_list(
+        envs: &[String],
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<MwaaItem>) {
         if envs.is_empty() {
             return (
                 vec!["No MWAA Environments found".to_string()],
@@ -39,10 +44,12 @@ impl MwaaService {
 
         let max_name_len = envs
             .iter()
-            .map(|name| name.len())
+            .map(|name| display_width(name))
             .max()
             .unwrap_or(20)
             .max(20);
+        let max_name_len =
+            fit_column_width(max_name_len.max(name_width.unwrap_or(0)), 0, max_width, 10);
 
         let header = format!("{:<width$}", "Environment Name", width = max_name_len);
         let separator = "-".repeat(max_name_len + 5).to_string();
@@ -51,7 +58,7 @@ impl MwaaService {
         let mut mwaa_items = vec![MwaaItem::Header, MwaaItem::Separator];
 
         for name in envs {
-            items.push(format!("{:<width$}", name, width = max_name_len));
+            items.push(fit_to_width(name, max_name_len));
             mwaa_items.push(MwaaItem::Environment(name.clone()));
         }
         (items, mwaa_items)
@@ -112,7 +119,7 @@ mod tests {
     #[test]
     fn test_format_environment_list() {
         let envs = vec!["env1".to_string(), "env2".to_string()];
-        let (items, mwaa_items) = MwaaService::format_environment_list(&envs);
+        let (items, mwaa_items) = MwaaService::format_environment_list(&envs, None, usize::MAX);
 
         assert_eq!(items.len(), 4); // Header, Separator, 2 envs
         assert!(items[0].contains("Environment Name"));
@@ -0,0 +1,344 @@
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Profile name -> key/value pairs from an AWS shared config file
+pub type Profiles = HashMap<String, HashMap<String, String>>;
+
+pub fn config_file_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("AWS_CONFIG_FILE") {
+        return Some(PathBuf::from(path));
+    }
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".aws").join("config"))
+}
+
+pub fn credentials_file_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("AWS_SHARED_CREDENTIALS_FILE") {
+        return Some(PathBuf::from(path));
+    }
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".aws").join("credentials"))
+}
+
+pub fn load_config_profiles() -> Profiles {
+    config_file_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|contents| parse_profiles(&contents))
+        .unwrap_or_default()
+}
+
+/// Parses the INI-style config file. Sections are `[default]` or `[profile name]`.
+pub fn parse_profiles(contents: &str) -> Profiles {
+    let mut profiles = Profiles::new();
+    let mut current: Option<String> = None;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let section = section.trim();
+            let name = section.strip_prefix("profile ").unwrap_or(section).trim();
+            profiles.entry(name.to_string()).or_default();
+            current = Some(name.to_string());
+        } else if let (Some(profile), Some((key, value))) = (&current, line.split_once('=')) {
+            profiles
+                .entry(profile.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    profiles
+}
+
+/// Names of the profiles in the config and credentials files, sorted. The credentials file
+/// names its sections without the `profile ` prefix, which `parse_profiles` accepts too.
+pub fn available_profiles() -> Vec<String> {
+    let mut names: Vec<String> = [config_file_path(), credentials_file_path()]
+        .into_iter()
+        .flatten()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .flat_map(|contents| parse_profiles(&contents).into_keys())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Fails with the available profiles listed when `profile` is in neither file, which the SDK
+/// would otherwise only report as a vague credentials error. "default" is left alone since
+/// credentials can come from the environment without any file.
+pub fn check_profile(profile: &str, available: &[String]) -> Result<()> {
+    if profile == "default" || available.iter().any(|name| name == profile) {
+        return Ok(());
+    }
+    if available.is_empty() {
+        bail!(
+            "profile '{}' not found: no profiles are configured in ~/.aws/config or \
+             ~/.aws/credentials",
+            profile
+        );
+    }
+    bail!(
+        "profile '{}' not found in ~/.aws/config or ~/.aws/credentials (available: {})",
+        profile,
+        available.join(", ")
+    )
+}
+
+/// Whether the profile signs in through IAM Identity Center, so `aws sso login` renews it.
+pub fn is_sso_profile(profiles: &Profiles, profile: &str) -> bool {
+    profiles
+        .get(profile)
+        .is_some_and(|p| p.contains_key("sso_session") || p.contains_key("sso_start_url"))
+}
+
+/// The IAM Identity Center start URL a profile signs in at, either on the profile itself or on
+/// the `[sso-session name]` section it points to.
+fn sso_start_url<'a>(profiles: &'a Profiles, profile: &str) -> Option<&'a str> {
+    let section = profiles.get(profile)?;
+    let url = match section.get("sso_session") {
+        Some(session) => profiles
+            .get(&format!("sso-session {}", session))?
+            .get("sso_start_url"),
+        None => section.get("sso_start_url"),
+    };
+    url.map(String::as_str)
+}
+
+/// When the SSO token behind an SSO profile runs out, from `aws sso login`'s cache. The role
+/// credentials it hands out last an hour at most and are renewed silently, so this is the
+/// expiry that actually ends the session. `None` for other profiles or without a cached token.
+pub fn sso_token_expiry(profiles: &Profiles, profile: &str) -> Option<SystemTime> {
+    let start_url = sso_start_url(profiles, profile)?;
+    let home = std::env::var("HOME").ok()?;
+    sso_cache_expiry(&Path::new(&home).join(".aws/sso/cache"), start_url)
+}
+
+/// The latest `expiresAt` among the cached tokens for `start_url`. The files are named after a
+/// hash of the session or URL, so they are matched on their `startUrl` instead.
+fn sso_cache_expiry(dir: &Path, start_url: &str) -> Option<SystemTime> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .filter_map(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+        .filter(|token| token["startUrl"].as_str() == Some(start_url))
+        .filter_map(|token| {
+            // The v1 CLI writes "2026-10-15T12:00:00UTC" rather than a trailing Z
+            let expires = token["expiresAt"].as_str()?.replace("UTC", "Z");
+            let parsed = aws_smithy_types::DateTime::from_str(
+                &expires,
+                aws_smithy_types::date_time::Format::DateTime,
+            )
+            .ok()?;
+            SystemTime::try_from(parsed).ok()
+        })
+        .max()
+}
+
+/// "env credentials" when the keys come from the environment, as under `aws-vault exec`, and no
+/// `--profile` was given. The SDK reads those variables before any profile, so the profile
+/// name would be misleading. aws-vault names its profile in `AWS_VAULT`.
+pub fn env_credentials(explicit_profile: Option<&str>) -> Option<String> {
+    env_credentials_from(explicit_profile, |name| std::env::var(name).ok())
+}
+
+fn env_credentials_from(
+    explicit_profile: Option<&str>,
+    var: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let var = |name: &str| var(name).filter(|value| !value.is_empty());
+    if explicit_profile.is_some()
+        || var("AWS_ACCESS_KEY_ID").is_none()
+        || var("AWS_SECRET_ACCESS_KEY").is_none()
+    {
+        return None;
+    }
+    Some(match var("AWS_VAULT") {
+        Some(vault) => format!("env credentials (aws-vault {})", vault),
+        None => "env credentials".to_string(),
+    })
+}
+
+/// Resolves the profile name: an explicit value, then `AWS_PROFILE`, then "default".
+pub fn resolve_profile(explicit: Option<&str>) -> String {
+    explicit
+        .map(String::from)
+        .or_else(|| std::env::var("AWS_PROFILE").ok())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// Resolves the region in the same order as the AWS CLI: an explicit value,
+/// then `AWS_REGION`, then `AWS_DEFAULT_REGION`, then the profile's `region` key.
+pub fn resolve_region(profile: &str, explicit: Option<&str>) -> Option<String> {
+    let from_env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+    explicit
+        .map(String::from)
+        .or_else(|| from_env("AWS_REGION"))
+        .or_else(|| from_env("AWS_DEFAULT_REGION"))
+        .or_else(|| {
+            load_config_profiles()
+                .get(profile)
+                .and_then(|p| p.get("region").cloned())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profiles() {
+        let contents = "\
+[default]
+region = us-east-1
+output=json
+
+# comment
+[profile dev]
+region = eu-west-1
+sso_start_url = https://example.awsapps.com/start
+";
+        let profiles = parse_profiles(contents);
+
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles["default"]["region"], "us-east-1");
+        assert_eq!(profiles["default"]["output"], "json");
+        assert_eq!(profiles["dev"]["region"], "eu-west-1");
+        assert_eq!(
+            profiles["dev"]["sso_start_url"],
+            "https://example.awsapps.com/start"
+        );
+
+        assert!(is_sso_profile(&profiles, "dev"));
+        assert!(!is_sso_profile(&profiles, "default"));
+        assert!(!is_sso_profile(&profiles, "missing"));
+    }
+
+    #[test]
+    fn test_check_profile() {
+        let available = vec!["default".to_string(), "dev".to_string(), "prod".to_string()];
+        assert!(check_profile("dev", &available).is_ok());
+        assert!(check_profile("default", &[]).is_ok());
+        assert_eq!(
+            check_profile("prd", &available).unwrap_err().to_string(),
+            "profile 'prd' not found in ~/.aws/config or ~/.aws/credentials \
+             (available: default, dev, prod)"
+        );
+        assert!(
+            check_profile("dev", &[])
+                .unwrap_err()
+                .to_string()
+                .contains("no profiles are configured")
+        );
+    }
+
+    #[test]
+    fn test_env_credentials() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let keys = &[
+            ("AWS_ACCESS_KEY_ID", "AKIA..."),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("AWS_SESSION_TOKEN", "token"),
+        ];
+        assert_eq!(
+            env_credentials_from(None, env(keys)).as_deref(),
+            Some("env credentials")
+        );
+        let vault = &[
+            ("AWS_VAULT", "dev"),
+            ("AWS_ACCESS_KEY_ID", "AKIA..."),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+        ];
+        assert_eq!(
+            env_credentials_from(None, env(vault)).as_deref(),
+            Some("env credentials (aws-vault dev)")
+        );
+
+        // An explicit profile, or half a key pair, leaves the profile in charge
+        assert_eq!(env_credentials_from(Some("dev"), env(keys)), None);
+        assert_eq!(
+            env_credentials_from(None, env(&[("AWS_ACCESS_KEY_ID", "AKIA...")])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sso_token_expiry_from_cache() {
+        let profiles = parse_profiles(
+            "\
+[profile legacy]
+sso_start_url = https://legacy.awsapps.com/start
+[profile dev]
+sso_session = corp
+[sso-session corp]
+sso_start_url = https://corp.awsapps.com/start
+[profile keys]
+region = us-east-1
+",
+        );
+        assert_eq!(
+            sso_start_url(&profiles, "legacy"),
+            Some("https://legacy.awsapps.com/start")
+        );
+        assert_eq!(
+            sso_start_url(&profiles, "dev"),
+            Some("https://corp.awsapps.com/start")
+        );
+        assert_eq!(sso_start_url(&profiles, "keys"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, url: &str, expires: &str| {
+            let token = serde_json::json!({ "startUrl": url, "expiresAt": expires });
+            std::fs::write(dir.path().join(name), token.to_string()).unwrap();
+        };
+        write(
+            "a.json",
+            "https://corp.awsapps.com/start",
+            "2026-10-15T12:00:00Z",
+        );
+        write(
+            "b.json",
+            "https://corp.awsapps.com/start",
+            "2026-10-15T20:00:00UTC",
+        );
+        write(
+            "c.json",
+            "https://other.awsapps.com/start",
+            "2026-10-16T00:00:00Z",
+        );
+        std::fs::write(dir.path().join("notes.txt"), "not a token").unwrap();
+
+        let expiry = sso_cache_expiry(dir.path(), "https://corp.awsapps.com/start").unwrap();
+        let expected = aws_smithy_types::DateTime::from_secs(1_792_094_400);
+        assert_eq!(expiry, SystemTime::try_from(expected).unwrap());
+        assert_eq!(
+            sso_cache_expiry(dir.path(), "https://legacy.awsapps.com/start"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_explicit_region_wins() {
+        assert_eq!(
+            resolve_region("default", Some("ap-south-1")),
+            Some("ap-south-1".to_string())
+        );
+    }
+}
@@ -1,15 +1,269 @@
-use crate::aws::utils::format_size;
-use anyhow::Result;
+use crate::aws::utils::{
+    Paging, Progress, TimeFormat, column_width, columns_width, display_width, fit_column_width,
+    fit_to_width, format_size, join_columns, shown_columns,
+};
+use anyhow::{Context, Result};
 use aws_sdk_s3::Client as S3Client;
+use aws_sdk_s3::config::Region;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{
+    BucketLocationConstraint, CompletedMultipartUpload, CompletedPart, CreateBucketConfiguration,
+    Delete, GlacierJobParameters, ObjectIdentifier, RestoreRequest, Tier,
+};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Semaphore;
+
+// Most objects the bucket summary lists before giving partial counts
+const SUMMARY_SCAN_LIMIT: usize = 100_000;
+
+// Uploads above this size go up in parts so progress can be reported
+const MULTIPART_THRESHOLD: u64 = 16 * 1024 * 1024;
+const PART_SIZE: u64 = 8 * 1024 * 1024;
+
+// Buckets whose tags are read at once when grouping by tag
+const TAG_CONCURRENCY: usize = 8;
+
+// Folders listed at once when the folder view counts their contents
+const FOLDER_CONCURRENCY: usize = 8;
+
+/// What the first page of a folder's listing holds directly inside it, for the Items and Size
+/// columns of the folder view.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FolderStats {
+    pub items: usize,    // Objects and subfolders directly inside
+    pub bytes: i64,      // Objects directly inside, not what the subfolders hold
+    pub truncated: bool, // More than one page, so both are lower bounds
+}
+
+/// One version or delete marker of an object, as `list_object_versions` returns it. Folders come
+/// back as entries with a "DIR" size and no version id.
+#[derive(Clone, Debug, PartialEq)]
+pub struct S3ObjectVersion {
+    pub name: String, // Relative to the prefix being browsed
+    pub version_id: String,
+    pub is_latest: bool,
+    pub delete_marker: bool,
+    pub size: String,
+    pub last_modified: String,
+}
 
 #[derive(Clone)]
 pub struct S3Service {
     client: S3Client,
+    // Shared between clones so every task benefits from a lookup
+    bucket_regions: Arc<Mutex<HashMap<String, String>>>,
+    regional_clients: Arc<Mutex<HashMap<String, S3Client>>>,
+    bucket_tags: Arc<Mutex<HashMap<String, Tags>>>,
+}
+
+type Tags = Vec<(String, String)>;
+
+/// A bucket's value for the tag its list is grouped by. Sorts tagged buckets first, by value.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BucketTag {
+    Value(String),
+    Missing,
+    Unreadable, // Reading the tags failed, e.g. AccessDenied
+}
+
+impl BucketTag {
+    // What the tag column shows for the bucket
+    fn cell(&self) -> &str {
+        match self {
+            BucketTag::Value(value) => value,
+            BucketTag::Missing => "-",
+            BucketTag::Unreadable => "unreadable",
+        }
+    }
 }
 
 impl S3Service {
     pub fn new(client: S3Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            bucket_regions: Arc::default(),
+            regional_clients: Arc::default(),
+            bucket_tags: Arc::default(),
+        }
+    }
+
+    /// The bucket's tags, read once and cached. A bucket without tags has an empty list.
+    pub async fn bucket_tags(&self, bucket: &str) -> Result<Vec<(String, String)>> {
+        if let Some(tags) = self.bucket_tags.lock().unwrap().get(bucket) {
+            return Ok(tags.clone());
+        }
+        let client = self.client_for(bucket).await;
+        let tags = match client.get_bucket_tagging().bucket(bucket).send().await {
+            Ok(tagging) => tagging
+                .tag_set()
+                .iter()
+                .map(|tag| (tag.key().to_string(), tag.value().to_string()))
+                .collect(),
+            Err(e) if matches!(e.code(), Some("NoSuchTagSet")) => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        self.bucket_tags
+            .lock()
+            .unwrap()
+            .insert(bucket.to_string(), tags.clone());
+        Ok(tags)
+    }
+
+    /// Forgets the cached bucket tags, so the next grouping reads them again.
+    pub fn clear_bucket_tags(&self) {
+        self.bucket_tags.lock().unwrap().clear();
+    }
+
+    /// Value of the `key` tag on each of `buckets`, in the same order, reading a few buckets'
+    /// tags at a time.
+    pub async fn bucket_tag_values(
+        &self,
+        buckets: &[String],
+        key: &str,
+        progress: &Progress,
+    ) -> Vec<BucketTag> {
+        let permits = Arc::new(Semaphore::new(TAG_CONCURRENCY));
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, bucket) in buckets.iter().cloned().enumerate() {
+            let service = self.clone();
+            let permits = permits.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                (index, service.bucket_tags(&bucket).await)
+            });
+        }
+
+        let mut values = vec![BucketTag::Unreadable; buckets.len()];
+        let mut done = 0;
+        while let Some(joined) = tasks.join_next().await {
+            done += 1;
+            progress.report(format!(
+                "Read tags of {} of {} buckets...",
+                done,
+                buckets.len()
+            ));
+            let Ok((index, tags)) = joined else {
+                continue;
+            };
+            values[index] = match tags {
+                Ok(tags) => match tags.into_iter().find(|(k, _)| k == key) {
+                    Some((_, value)) => BucketTag::Value(value),
+                    None => BucketTag::Missing,
+                },
+                Err(_) => BucketTag::Unreadable,
+            };
+        }
+        values
+    }
+
+    /// Counts the objects and subfolders directly under `prefix` from a single listing page,
+    /// rather than following every page.
+    pub async fn folder_stats(&self, bucket: &str, prefix: &str) -> Result<FolderStats> {
+        let client = self.client_for(bucket).await;
+        let resp = client
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .delimiter("/")
+            .send()
+            .await?;
+        // The folder's own placeholder object isn't one of its children
+        let objects = resp
+            .contents()
+            .iter()
+            .filter(|object| object.key() != Some(prefix));
+        let (count, bytes) = objects.fold((0, 0), |(count, bytes), object| {
+            (count + 1, bytes + object.size().unwrap_or(0))
+        });
+        Ok(FolderStats {
+            items: count + resp.common_prefixes().len(),
+            bytes,
+            truncated: resp.is_truncated().unwrap_or(false),
+        })
+    }
+
+    /// Stats for each of `folders` under `prefix`, keyed by folder name, listing a few folders
+    /// at a time. Folders whose listing fails are left out.
+    pub async fn folder_stats_all(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        folders: &[String],
+        progress: &Progress,
+    ) -> HashMap<String, FolderStats> {
+        let permits = Arc::new(Semaphore::new(FOLDER_CONCURRENCY));
+        let mut tasks = tokio::task::JoinSet::new();
+        for folder in folders.iter().cloned() {
+            let service = self.clone();
+            let permits = permits.clone();
+            let (bucket, path) = (bucket.to_string(), format!("{}{}", prefix, folder));
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                (folder, service.folder_stats(&bucket, &path).await)
+            });
+        }
+
+        let mut stats = HashMap::new();
+        let mut done = 0;
+        while let Some(joined) = tasks.join_next().await {
+            done += 1;
+            progress.report(format!("Counted {} of {} folders...", done, folders.len()));
+            if let Ok((folder, Ok(folder_stats))) = joined {
+                stats.insert(folder, folder_stats);
+            }
+        }
+        stats
+    }
+
+    /// Region the bucket lives in, looked up once and cached.
+    pub async fn bucket_region(&self, bucket: &str) -> Result<String> {
+        if let Some(region) = self.bucket_regions.lock().unwrap().get(bucket) {
+            return Ok(region.clone());
+        }
+        let location = self
+            .client
+            .get_bucket_location()
+            .bucket(bucket)
+            .send()
+            .await?;
+        let region = location_region(location.location_constraint());
+        self.bucket_regions
+            .lock()
+            .unwrap()
+            .insert(bucket.to_string(), region.clone());
+        Ok(region)
+    }
+
+    // Client for requests about objects in `bucket`. Buckets outside the active region get a
+    // client for their own region, which avoids PermanentRedirect errors. When the location
+    // can't be read the default client is used and S3 reports whatever is wrong.
+    async fn client_for(&self, bucket: &str) -> S3Client {
+        let Ok(region) = self.bucket_region(bucket).await else {
+            return self.client.clone();
+        };
+        if self.client.config().region().map(|r| r.as_ref()) == Some(region.as_str()) {
+            return self.client.clone();
+        }
+        self.regional_clients
+            .lock()
+            .unwrap()
+            .entry(region.clone())
+            .or_insert_with(|| {
+                let config = self
+                    .client
+                    .config()
+                    .to_builder()
+                    .region(Region::new(region))
+                    .build();
+                S3Client::from_conf(config)
+            })
+            .clone()
     }
 
     pub async fn list_buckets(&self) -> Result<Vec<(String, String)>> {
@@ -31,25 +285,173 @@ impl S3Service {
         Ok(buckets)
     }
 
+    /// Creates a bucket in the client's region. us-east-1 is the default location and is
+    /// rejected as an explicit location constraint, so it is left out there.
+    pub async fn create_bucket(&self, bucket: &str) -> Result<()> {
+        let mut request = self.client.create_bucket().bucket(bucket);
+        if let Some(region) = self.client.config().region()
+            && region.as_ref() != "us-east-1"
+        {
+            request = request.create_bucket_configuration(
+                CreateBucketConfiguration::builder()
+                    .location_constraint(BucketLocationConstraint::from(region.as_ref()))
+                    .build(),
+            );
+        }
+        match request.send().await {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.code(), Some("BucketAlreadyExists")) => {
+                anyhow::bail!("bucket name {} is already taken", bucket)
+            }
+            Err(e) if matches!(e.code(), Some("BucketAlreadyOwnedByYou")) => {
+                anyhow::bail!("you already own bucket {}", bucket)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Deletes an empty bucket. S3 refuses buckets that still hold objects.
+    pub async fn delete_bucket(&self, bucket: &str) -> Result<()> {
+        match self.client.delete_bucket().bucket(bucket).send().await {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.code(), Some("BucketNotEmpty")) => {
+                anyhow::bail!("bucket {} is not empty; delete its objects first", bucket)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Object count, total size and storage class mix from listing the bucket, plus its
+    /// policy. The listing stops after `SUMMARY_SCAN_LIMIT` objects so huge buckets still
+    /// finish; the counts are then marked as partial.
+    pub async fn get_bucket_summary(
+        &self,
+        bucket: &str,
+        progress: &Progress,
+    ) -> Result<Vec<(String, String)>> {
+        let client = self.client_for(bucket).await;
+        let mut count = 0usize;
+        let mut total_size = 0i64;
+        let mut classes: BTreeMap<String, (usize, i64)> = BTreeMap::new();
+        let mut partial = false;
+
+        let mut pages = client
+            .list_objects_v2()
+            .bucket(bucket)
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            for object in page?.contents() {
+                let size = object.size().unwrap_or(0);
+                // Listings leave the class out for STANDARD on some S3-compatible stores
+                let class = object
+                    .storage_class()
+                    .map(|c| c.as_str().to_string())
+                    .unwrap_or_else(|| "STANDARD".to_string());
+                let entry = classes.entry(class).or_default();
+                entry.0 += 1;
+                entry.1 += size;
+                count += 1;
+                total_size += size;
+            }
+            progress.report(format!("Scanned {} objects in s3://{}...", count, bucket));
+            if count >= SUMMARY_SCAN_LIMIT {
+                partial = true;
+                break;
+            }
+        }
+
+        let at_least = if partial { "at least " } else { "" };
+        let mut details = vec![
+            ("Objects".to_string(), format!("{}{}", at_least, count)),
+            (
+                "Total Size".to_string(),
+                format!("{}{}", at_least, format_size(total_size)),
+            ),
+        ];
+        if partial {
+            details.push((
+                "Scan".to_string(),
+                format!("stopped after {} objects", SUMMARY_SCAN_LIMIT),
+            ));
+        }
+        if !classes.is_empty() {
+            details.push(("".to_string(), "".to_string())); // Blank line
+            details.push(("Storage Classes".to_string(), "".to_string()));
+            details.push((
+                "".to_string(),
+                format!("{:<22} {:>10} {:>12}", "Class", "Objects", "Size"),
+            ));
+            details.push(("".to_string(), "-".repeat(46)));
+            for (class, (objects, size)) in &classes {
+                details.push((
+                    "".to_string(),
+                    format!("{:<22} {:>10} {:>12}", class, objects, format_size(*size)),
+                ));
+            }
+        }
+
+        // The policy is JSON, so the detail popup can pretty-print it
+        let policy = match client.get_bucket_policy().bucket(bucket).send().await {
+            Ok(resp) => resp.policy().unwrap_or("None").to_string(),
+            Err(e) if matches!(e.code(), Some("NoSuchBucketPolicy")) => "None".to_string(),
+            Err(e) => format!("Error: {}", e),
+        };
+        details.push(("".to_string(), "".to_string()));
+        details.push(("Bucket Policy".to_string(), policy));
+        Ok(details)
+    }
+
     pub async fn get_bucket_details(&self, bucket_name: &str) -> Result<Vec<(String, String)>> {
         let mut details = Vec::new();
 
         details.push(("Bucket Name".to_string(), bucket_name.to_string()));
 
+        // Independent lookups run concurrently; results are assembled below in a fixed order
+        let (
+            location,
+            versioning,
+            encryption,
+            acl,
+            public_access,
+            tagging,
+            lifecycle_config,
+            logging_config,
+            replication_config,
+        ) = tokio::join!(
+            self.client.get_bucket_location().bucket(bucket_name).send(),
+            self.client
+                .get_bucket_versioning()
+                .bucket(bucket_name)
+                .send(),
+            self.client
+                .get_bucket_encryption()
+                .bucket(bucket_name)
+                .send(),
+            self.client.get_bucket_acl().bucket(bucket_name).send(),
+            self.client
+                .get_public_access_block()
+                .bucket(bucket_name)
+                .send(),
+            self.client.get_bucket_tagging().bucket(bucket_name).send(),
+            self.client
+                .get_bucket_lifecycle_configuration()
+                .bucket(bucket_name)
+                .send(),
+            self.client.get_bucket_logging().bucket(bucket_name).send(),
+            self.client
+                .get_bucket_replication()
+                .bucket(bucket_name)
+                .send(),
+        );
+
         // Get bucket location
-        match self
-            .client
-            .get_bucket_location()
-            .bucket(bucket_name)
-            .send()
-            .await
-        {
+        match location {
             Ok(location) => {
-                let region = location
-                    .location_constraint()
-                    .map(|lc| format!("{:?}", lc))
-                    .unwrap_or_else(|| "us-east-1".to_string());
-                details.push(("Region".to_string(), region));
+                details.push((
+                    "Region".to_string(),
+                    location_region(location.location_constraint()),
+                ));
             }
             Err(e) => {
                 details.push(("Region".to_string(), format!("Error: {}", e)));
@@ -57,13 +459,7 @@ impl S3Service {
         }
 
         // Get versioning status
-        match self
-            .client
-            .get_bucket_versioning()
-            .bucket(bucket_name)
-            .send()
-            .await
-        {
+        match versioning {
             Ok(versioning) => {
                 let status = versioning
                     .status()
@@ -77,13 +473,7 @@ impl S3Service {
         }
 
         // Get encryption configuration
-        match self
-            .client
-            .get_bucket_encryption()
-            .bucket(bucket_name)
-            .send()
-            .await
-        {
+        match encryption {
             Ok(encryption) => {
                 if let Some(rules) = encryption
                     .server_side_encryption_configuration()
@@ -105,13 +495,7 @@ impl S3Service {
         }
 
         // Get ACL
-        match self
-            .client
-            .get_bucket_acl()
-            .bucket(bucket_name)
-            .send()
-            .await
-        {
+        match acl {
             Ok(acl) => {
                 let grants = acl.grants().len();
                 details.push(("ACL Grants".to_string(), format!("{} grant(s)", grants)));
@@ -129,13 +513,7 @@ impl S3Service {
         }
 
         // Get public access block
-        match self
-            .client
-            .get_public_access_block()
-            .bucket(bucket_name)
-            .send()
-            .await
-        {
+        match public_access {
             Ok(pab) => {
                 if let Some(config) = pab.public_access_block_configuration() {
                     let block_public_acls = config.block_public_acls().unwrap_or(false);
@@ -162,13 +540,7 @@ impl S3Service {
         }
 
         // Get tags
-        match self
-            .client
-            .get_bucket_tagging()
-            .bucket(bucket_name)
-            .send()
-            .await
-        {
+        match tagging {
             Ok(tagging) => {
                 let tag_count = tagging.tag_set().len();
                 details.push(("Tags".to_string(), format!("{} tag(s)", tag_count)));
@@ -185,26 +557,111 @@ impl S3Service {
             }
         }
 
+        // Get lifecycle rules
+        match lifecycle_config {
+            Ok(lifecycle) => {
+                let rules = lifecycle.rules();
+                details.push((
+                    "Lifecycle Rules".to_string(),
+                    format!("{} rule(s)", rules.len()),
+                ));
+
+                // Show first few rules
+                for (idx, rule) in rules.iter().take(3).enumerate() {
+                    let id = rule.id().unwrap_or("(no id)");
+                    let prefix = rule
+                        .filter()
+                        .and_then(|f| f.prefix())
+                        .filter(|p| !p.is_empty())
+                        .unwrap_or("(all objects)");
+                    let expiration = match rule.expiration() {
+                        Some(exp) => match (exp.days(), exp.date()) {
+                            (Some(days), _) => format!("expire after {} days", days),
+                            (None, Some(date)) => format!("expire on {}", date),
+                            _ => "no expiration".to_string(),
+                        },
+                        None => "no expiration".to_string(),
+                    };
+                    details.push((
+                        format!("  Rule {}", idx + 1),
+                        format!("{}: {}, {}", id, prefix, expiration),
+                    ));
+                }
+            }
+            Err(e) => {
+                details.push(("Lifecycle Rules".to_string(), missing_config_or_error(&e)));
+            }
+        }
+
+        // Get server access logging
+        match logging_config {
+            Ok(logging) => {
+                let target = logging
+                    .logging_enabled()
+                    .map(|l| format!("s3://{}/{}", l.target_bucket(), l.target_prefix()))
+                    .unwrap_or_else(|| "None".to_string());
+                details.push(("Access Logging".to_string(), target));
+            }
+            Err(e) => {
+                details.push(("Access Logging".to_string(), format!("Error: {}", e)));
+            }
+        }
+
+        // Get replication status
+        match replication_config {
+            Ok(replication) => {
+                let status = match replication.replication_configuration() {
+                    Some(config) => {
+                        let enabled = config
+                            .rules()
+                            .iter()
+                            .filter(|r| r.status().as_str() == "Enabled")
+                            .count();
+                        format!("{} of {} rule(s) enabled", enabled, config.rules().len())
+                    }
+                    None => "None".to_string(),
+                };
+                details.push(("Replication".to_string(), status));
+            }
+            Err(e) => {
+                details.push(("Replication".to_string(), missing_config_or_error(&e)));
+            }
+        }
+
         Ok(details)
     }
 
+    /// Details of the current object, or of one version of it when `version_id` is given.
     pub async fn get_object_details(
         &self,
         bucket: &str,
         key: &str,
+        version_id: Option<&str>,
     ) -> Result<Vec<(String, String)>> {
+        let client = self.client_for(bucket).await;
         let mut details = Vec::new();
         details.push(("Name".to_string(), key.to_string()));
 
-        match self
-            .client
-            .head_object()
-            .bucket(bucket)
-            .key(key)
-            .send()
-            .await
-        {
+        let (head, tagging) = tokio::join!(
+            client
+                .head_object()
+                .bucket(bucket)
+                .key(key)
+                .set_version_id(version_id.map(String::from))
+                .send(),
+            client
+                .get_object_tagging()
+                .bucket(bucket)
+                .key(key)
+                .set_version_id(version_id.map(String::from))
+                .send(),
+        );
+        match head {
             Ok(head) => {
+                if let Some(version_id) = head.version_id() {
+                    details.push(("Version ID".to_string(), version_id.to_string()));
+                }
+
                 if let Some(size) = head.content_length() {
                     details.push(("Size".to_string(), format_size(size)));
                 }
@@ -217,13 +674,27 @@ impl S3Service {
                     details.push(("ETag".to_string(), etag.to_string()));
                 }
 
-                if let Some(storage_class) = head.storage_class() {
-                    details.push(("Storage Class".to_string(), format!("{:?}", storage_class)));
+                // HeadObject leaves the header out for STANDARD objects
+                let storage_class = head
+                    .storage_class()
+                    .map(|c| c.as_str())
+                    .unwrap_or("STANDARD");
+                details.push(("Storage Class".to_string(), storage_class.to_string()));
+
+                // e.g. ongoing-request="false", expiry-date="..."
+                if let Some(restore) = head.restore() {
+                    details.push(("Restore".to_string(), restore.to_string()));
                 }
 
                 if let Some(content_type) = head.content_type() {
                     details.push(("Content Type".to_string(), content_type.to_string()));
                 }
+
+                // User-defined x-amz-meta-* headers, without the prefix
+                let mut metadata: Vec<(&String, &String)> =
+                    head.metadata().into_iter().flatten().collect();
+                metadata.sort();
+                push_section(&mut details, "Metadata", metadata);
             }
             Err(e) => {
                 details.push((
@@ -233,70 +704,650 @@ impl S3Service {
             }
         }
 
+        match tagging {
+            Ok(tagging) => {
+                let tags = tagging.tag_set().iter().map(|tag| (tag.key(), tag.value()));
+                push_section(&mut details, "Tags", tags);
+            }
+            Err(e) => details.push(("Tags".to_string(), format!("Error: {}", e))),
+        }
+
         Ok(details)
     }
+    /// Returns the object's storage class and its restore header, if any.
+    pub async fn get_object_storage_class(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<(String, Option<String>)> {
+        let client = self.client_for(bucket).await;
+        let head = client.head_object().bucket(bucket).key(key).send().await?;
+        let storage_class = head
+            .storage_class()
+            .map(|c| c.as_str())
+            .unwrap_or("STANDARD")
+            .to_string();
+        Ok((storage_class, head.restore().map(String::from)))
+    }
+
+    /// Requests a temporary copy of an archived object for `days` at the given retrieval tier.
+    pub async fn restore_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        tier: &str,
+        days: i32,
+    ) -> Result<()> {
+        let client = self.client_for(bucket).await;
+        let request = RestoreRequest::builder()
+            .days(days)
+            .glacier_job_parameters(
+                GlacierJobParameters::builder()
+                    .tier(Tier::from(tier))
+                    .build()?,
+            )
+            .build();
+        client
+            .restore_object()
+            .bucket(bucket)
+            .key(key)
+            .restore_request(request)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_object(&self, bucket: &str, key: &str) -> Result<()> {
+        let client = self.client_for(bucket).await;
+        client
+            .delete_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    /// Uploads the local file at `path` to `bucket/key` and returns its size. Large files use a
+    /// multipart upload, which is aborted if a part fails.
+    pub async fn upload_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        path: &Path,
+        progress: &Progress,
+    ) -> Result<u64> {
+        let size = tokio::fs::metadata(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .len();
+        let client = self.client_for(bucket).await;
+
+        if size <= MULTIPART_THRESHOLD {
+            progress.report(format!(
+                "Uploading {} ({})...",
+                key,
+                format_size(size as i64)
+            ));
+            client
+                .put_object()
+                .bucket(bucket)
+                .key(key)
+                .body(ByteStream::from_path(path).await?)
+                .send()
+                .await?;
+            return Ok(size);
+        }
+
+        let upload = client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await?;
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| anyhow::anyhow!("S3 returned no upload id for {}", key))?;
+        let mut pending = PendingUpload {
+            client: client.clone(),
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            upload_id: upload_id.to_string(),
+            complete: false,
+        };
+        let parts = upload_parts(&client, bucket, key, upload_id, path, size, progress).await?;
+        client
+            .complete_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await?;
+        pending.complete = true;
+        Ok(size)
+    }
+
+    /// Every key under `prefix`, across all pages and without folding folders.
+    pub async fn list_keys(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        progress: &Progress,
+    ) -> Result<Vec<String>> {
+        let client = self.client_for(bucket).await;
+        let mut keys = Vec::new();
+        let mut pages = client
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            keys.extend(
+                page?
+                    .contents()
+                    .iter()
+                    .filter_map(|o| o.key())
+                    .map(String::from),
+            );
+            progress.report(format!(
+                "Listed {} objects under s3://{}/{}...",
+                keys.len(),
+                bucket,
+                prefix
+            ));
+        }
+        Ok(keys)
+    }
+
+    /// Deletes `keys` in batches of 1000, the DeleteObjects limit. Returns how many were deleted.
+    pub async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+        progress: &Progress,
+    ) -> Result<usize> {
+        let client = self.client_for(bucket).await;
+        let mut deleted = 0;
+        for batch in keys.chunks(1000) {
+            let objects = batch
+                .iter()
+                .map(|key| ObjectIdentifier::builder().key(key).build())
+                .collect::<Result<Vec<_>, _>>()?;
+            let resp = client
+                .delete_objects()
+                .bucket(bucket)
+                .delete(
+                    Delete::builder()
+                        .set_objects(Some(objects))
+                        .quiet(true)
+                        .build()?,
+                )
+                .send()
+                .await?;
+
+            // Quiet mode only reports failures
+            if let Some(error) = resp.errors().first() {
+                anyhow::bail!(
+                    "Failed to delete {} ({} of {} keys in batch failed)",
+                    error.key().unwrap_or("unknown"),
+                    resp.errors().len(),
+                    batch.len()
+                );
+            }
+            deleted += batch.len();
+            progress.report(format!("Deleted {} of {} objects...", deleted, keys.len()));
+        }
+        Ok(deleted)
+    }
+
+    /// Folders and objects directly under `prefix`, following every page of the listing.
     pub async fn list_objects(
         &self,
         bucket: &str,
         prefix: &str,
-    ) -> Result<Vec<(String, String, String)>> {
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<Vec<(String, String, String, String)>> {
+        let client = self.client_for(bucket).await;
         let mut objects = Vec::new();
 
-        let resp = self
-            .client
+        let mut pages = client
             .list_objects_v2()
             .bucket(bucket)
             .prefix(prefix)
             .delimiter("/")
-            .send()
-            .await?;
+            .set_max_keys(paging.page_size(1..=1000))
+            .into_paginator()
+            .send();
+        while !paging.is_full(objects.len())
+            && let Some(page) = pages.next().await
+        {
+            let resp = page?;
 
-        // Add folders (CommonPrefixes)
-        for cp in resp.common_prefixes() {
-            if let Some(folder_prefix) = cp.prefix() {
-                // Remove the parent prefix from the display name
-                // Actually we want the relative name
+            // Add folders (CommonPrefixes)
+            for cp in resp.common_prefixes() {
+                if let Some(folder_prefix) = cp.prefix() {
+                    // Remove the parent prefix from the display name
+                    // Actually we want the relative name
 
-                // If we are in "folder/", and we get "folder/sub/", we want to show "sub/"
+                    // If we are in "folder/", and we get "folder/sub/", we want to show "sub/"
 
-                let name = if !prefix.is_empty() && folder_prefix.starts_with(prefix) {
-                    folder_prefix.strip_prefix(prefix).unwrap_or(folder_prefix)
-                } else {
-                    folder_prefix
-                };
+                    let name = if !prefix.is_empty() && folder_prefix.starts_with(prefix) {
+                        folder_prefix.strip_prefix(prefix).unwrap_or(folder_prefix)
+                    } else {
+                        folder_prefix
+                    };
 
-                objects.push((name.to_string(), "DIR".to_string(), "".to_string()));
+                    objects.push((
+                        name.to_string(),
+                        "DIR".to_string(),
+                        "".to_string(),
+                        "".to_string(),
+                    ));
+                }
             }
-        }
 
-        // Add files (Contents)
-        // Add files (Contents)
-        for object in resp.contents() {
-            if let Some(key) = object.key() {
-                // Skip the folder object itself if it exists
-                if key == prefix {
-                    continue;
+            // Add files (Contents)
+            for object in resp.contents() {
+                if let Some(key) = object.key() {
+                    // Skip the folder object itself if it exists
+                    if key == prefix {
+                        continue;
+                    }
+
+                    let name = if !prefix.is_empty() && key.starts_with(prefix) {
+                        key.strip_prefix(prefix).unwrap_or(key)
+                    } else {
+                        key
+                    };
+
+                    let size = object.size().unwrap_or(0);
+                    let size_str = format_size(size);
+
+                    let date = object
+                        .last_modified()
+                        .map(|d| d.to_string())
+                        .unwrap_or_else(|| "Unknown".to_string());
+
+                    // S3 omits the class for some STANDARD objects
+                    let storage_class = object
+                        .storage_class()
+                        .map(|c| c.as_str().to_string())
+                        .unwrap_or_else(|| "STANDARD".to_string());
+
+                    objects.push((name.to_string(), size_str, date, storage_class));
                 }
+            }
+            progress.report(format!(
+                "Loaded {} objects from s3://{}/{}...",
+                objects.len(),
+                bucket,
+                prefix
+            ));
+        }
+        // Each page lists its folders first; keep all folders above all objects
+        objects.sort_by_key(|(_, size, _, _)| size != "DIR");
 
-                let name = if !prefix.is_empty() && key.starts_with(prefix) {
-                    key.strip_prefix(prefix).unwrap_or(key)
-                } else {
-                    key
+        Ok(objects)
+    }
+
+    /// "Enabled" or "Suspended", or None when versioning was never turned on for the bucket.
+    pub async fn get_versioning_status(&self, bucket: &str) -> Result<Option<String>> {
+        let client = self.client_for(bucket).await;
+        let versioning = client.get_bucket_versioning().bucket(bucket).send().await?;
+        Ok(versioning.status().map(|s| s.as_str().to_string()))
+    }
+
+    /// Lists every version and delete marker under `prefix`, one level deep like `list_objects`.
+    /// Each key's versions are listed newest first.
+    pub async fn list_object_versions(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        paging: Paging,
+        progress: &Progress,
+    ) -> Result<Vec<S3ObjectVersion>> {
+        let client = self.client_for(bucket).await;
+        let relative = |key: &str| key.strip_prefix(prefix).unwrap_or(key).to_string();
+        let mut versions = Vec::new();
+        let mut key_marker = None;
+        let mut version_id_marker = None;
+
+        // The SDK has no paginator for this call since it pages on two markers
+        while !paging.is_full(versions.len()) {
+            let resp = client
+                .list_object_versions()
+                .bucket(bucket)
+                .prefix(prefix)
+                .delimiter("/")
+                .set_max_keys(paging.page_size(1..=1000))
+                .set_key_marker(key_marker.take())
+                .set_version_id_marker(version_id_marker.take())
+                .send()
+                .await?;
+
+            for cp in resp.common_prefixes() {
+                if let Some(folder_prefix) = cp.prefix() {
+                    versions.push(S3ObjectVersion {
+                        name: relative(folder_prefix),
+                        version_id: String::new(),
+                        is_latest: true,
+                        delete_marker: false,
+                        size: "DIR".to_string(),
+                        last_modified: String::new(),
+                    });
+                }
+            }
+            let date = |d: Option<&aws_sdk_s3::primitives::DateTime>| {
+                d.map(|d| d.to_string())
+                    .unwrap_or_else(|| "Unknown".to_string())
+            };
+            for version in resp.versions() {
+                let Some(key) = version.key().filter(|key| *key != prefix) else {
+                    continue;
+                };
+                versions.push(S3ObjectVersion {
+                    name: relative(key),
+                    version_id: version.version_id().unwrap_or("null").to_string(),
+                    is_latest: version.is_latest().unwrap_or(false),
+                    delete_marker: false,
+                    size: format_size(version.size().unwrap_or(0)),
+                    last_modified: date(version.last_modified()),
+                });
+            }
+            for marker in resp.delete_markers() {
+                let Some(key) = marker.key().filter(|key| *key != prefix) else {
+                    continue;
                 };
+                versions.push(S3ObjectVersion {
+                    name: relative(key),
+                    version_id: marker.version_id().unwrap_or("null").to_string(),
+                    is_latest: marker.is_latest().unwrap_or(false),
+                    delete_marker: true,
+                    size: String::new(),
+                    last_modified: date(marker.last_modified()),
+                });
+            }
+            progress.report(format!(
+                "Loaded {} versions from s3://{}/{}...",
+                versions.len(),
+                bucket,
+                prefix
+            ));
 
-                let size = object.size().unwrap_or(0);
-                let size_str = format_size(size);
+            if !resp.is_truncated().unwrap_or(false) {
+                break;
+            }
+            key_marker = resp.next_key_marker().map(String::from);
+            version_id_marker = resp.next_version_id_marker().map(String::from);
+        }
+        // Versions and delete markers come back in separate lists; interleave them by key,
+        // newest first, with the folders on top
+        versions.sort_by_key(|v| {
+            (
+                v.size != "DIR",
+                v.name.clone(),
+                Reverse(v.last_modified.clone()),
+            )
+        });
 
-                let date = object
-                    .last_modified()
-                    .map(|d| d.to_string())
-                    .unwrap_or_else(|| "Unknown".to_string());
+        Ok(versions)
+    }
 
-                objects.push((name.to_string(), size_str, date));
+    /// Streams the object, or one version of it, into a new file at `path` and returns the
+    /// bytes written. An existing file is never overwritten.
+    pub async fn download_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: Option<&str>,
+        path: &Path,
+        progress: &Progress,
+    ) -> Result<u64> {
+        let client = self.client_for(bucket).await;
+        let resp = client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .set_version_id(version_id.map(String::from))
+            .send()
+            .await?;
+        let size = resp.content_length().unwrap_or(0).max(0) as u64;
+        if tokio::fs::try_exists(path).await.unwrap_or(false) {
+            anyhow::bail!("{} already exists", path.display());
+        }
+        // The object is written next to the destination and only takes its name once complete
+        let mut partial = PartialFile {
+            path: part_path(path),
+            complete: false,
+        };
+        let part = partial.path.clone();
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&part)
+            .await
+            .with_context(|| format!("Failed to create {}", part.display()))?;
+
+        let mut body = resp.body;
+        let mut written = 0u64;
+        async {
+            while let Some(chunk) = body.try_next().await? {
+                file.write_all(&chunk).await?;
+                written += chunk.len() as u64;
+                if let Some(percent) = (written * 100).checked_div(size) {
+                    progress.report(format!(
+                        "Downloading {}: {}% ({} of {})",
+                        key,
+                        percent,
+                        format_size(written as i64),
+                        format_size(size as i64)
+                    ));
+                }
             }
+            file.flush().await?;
+            anyhow::Ok(())
         }
+        .await?;
+        drop(file);
+        if tokio::fs::try_exists(path).await.unwrap_or(false) {
+            anyhow::bail!("{} already exists", path.display());
+        }
+        tokio::fs::rename(&part, path)
+            .await
+            .with_context(|| format!("Failed to move the download to {}", path.display()))?;
+        partial.complete = true;
+        Ok(written)
+    }
+}
 
-        Ok(objects)
+// Where a download is written until it completes: the destination with `.part` appended
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+// Deletes an incomplete download when dropped, including when the task running it is aborted
+// with Esc, so a partial file never lingers
+struct PartialFile {
+    path: PathBuf,
+    complete: bool, // Moved into place, so there is nothing to clean up
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        if !self.complete {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+// Aborts a multipart upload that didn't complete, including when the task running it is
+// aborted with Esc. Otherwise the uploaded parts linger and are billed until a lifecycle rule
+// cleans them up
+struct PendingUpload {
+    client: S3Client,
+    bucket: String,
+    key: String,
+    upload_id: String,
+    complete: bool,
+}
+
+impl Drop for PendingUpload {
+    fn drop(&mut self) {
+        if self.complete {
+            return;
+        }
+        let abort = self
+            .client
+            .abort_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(&self.upload_id);
+        // Drop can't wait for the call, so it runs as a task of its own
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = abort.send().await;
+            });
+        }
+    }
+}
+
+// Sends the file in PART_SIZE chunks, reading one chunk at a time
+async fn upload_parts(
+    client: &S3Client,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    path: &Path,
+    size: u64,
+    progress: &Progress,
+) -> Result<Vec<CompletedPart>> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut parts = Vec::new();
+    let mut sent = 0;
+    for number in 1.. {
+        let mut chunk = Vec::with_capacity(PART_SIZE as usize);
+        (&mut file).take(PART_SIZE).read_to_end(&mut chunk).await?;
+        if chunk.is_empty() {
+            break;
+        }
+        sent += chunk.len() as u64;
+        let resp = client
+            .upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(number)
+            .body(ByteStream::from(chunk))
+            .send()
+            .await?;
+        parts.push(
+            CompletedPart::builder()
+                .part_number(number)
+                .set_e_tag(resp.e_tag().map(String::from))
+                .build(),
+        );
+        progress.report(format!(
+            "Uploading {}: {}% ({} of {})",
+            key,
+            sent * 100 / size,
+            format_size(sent as i64),
+            format_size(size as i64)
+        ));
+    }
+    Ok(parts)
+}
+
+/// Key an uploaded file gets by default: its file name under `prefix`.
+pub fn upload_key(prefix: &str, path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    Some(format!("{}{}", prefix, name))
+}
+
+// GetBucketLocation reports us-east-1 as no constraint and eu-west-1 by its legacy name "EU"
+fn location_region(constraint: Option<&BucketLocationConstraint>) -> String {
+    match constraint.map(|c| c.as_str()) {
+        None | Some("") => "us-east-1".to_string(),
+        Some("EU") => "eu-west-1".to_string(),
+        Some(region) => region.to_string(),
+    }
+}
+
+/// Checks `name` against the S3 bucket naming rules so a bad name fails before the API call.
+pub fn validate_bucket_name(name: &str) -> Result<()> {
+    if !(3..=63).contains(&name.len()) {
+        anyhow::bail!("must be 3 to 63 characters long");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '-')
+    {
+        anyhow::bail!("only lowercase letters, digits, dots and hyphens are allowed");
+    }
+    let alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    if !name.starts_with(alphanumeric) || !name.ends_with(alphanumeric) {
+        anyhow::bail!("must start and end with a letter or digit");
+    }
+    if name.contains("..") {
+        anyhow::bail!("must not contain two adjacent dots");
+    }
+    if name.parse::<std::net::Ipv4Addr>().is_ok() {
+        anyhow::bail!("must not be formatted as an IP address");
+    }
+    if let Some(prefix) = ["xn--", "sthree-", "amzn-s3-demo-"]
+        .iter()
+        .find(|p| name.starts_with(*p))
+    {
+        anyhow::bail!("must not start with {}", prefix);
+    }
+    if let Some(suffix) = ["-s3alias", "--ol-s3", ".mrap", "--x-s3"]
+        .iter()
+        .find(|s| name.ends_with(*s))
+    {
+        anyhow::bail!("must not end with {}", suffix);
+    }
+    Ok(())
+}
+
+// A heading followed by its key/value pairs indented under it, or "None" beside the heading
+// when there are none
+fn push_section(
+    details: &mut Vec<(String, String)>,
+    heading: &str,
+    entries: impl IntoIterator<Item = (impl ToString, impl ToString)>,
+) {
+    let start = details.len();
+    details.push((heading.to_string(), String::new()));
+    details.extend(
+        entries
+            .into_iter()
+            .map(|(key, value)| (format!("  {}", key.to_string()), value.to_string())),
+    );
+    if details.len() == start + 1 {
+        details[start].1 = "None".to_string();
+    }
+}
+
+// "None" when the bucket simply has no such configuration, the error otherwise
+fn missing_config_or_error<E: ProvideErrorMetadata + std::error::Error + 'static>(
+    err: &SdkError<E, HttpResponse>,
+) -> String {
+    match err.code() {
+        Some(code) if code.starts_with("NoSuch") || code.ends_with("NotFoundError") => {
+            "None".to_string()
+        }
+        _ => format!("Error: {}", err),
     }
 }
 
@@ -312,14 +1363,44 @@ pub enum S3NavigationAction {
 pub enum S3Item {
     Header,
     Separator,
+    Group(String), // Tag value heading a group of buckets
     Bucket(String),
     Folder(String),
     Object(String),
+    Version {
+        name: String,
+        version_id: String,
+        delete_marker: bool,
+    },
     ParentDir,
 }
 
 impl S3Service {
-    pub fn format_bucket_list(buckets: &[(String, String)]) -> (Vec<String>, Vec<S3Item>) {
+    /// Columns of the object list that can be hidden, by config name and header.
+    pub const OBJECT_COLUMNS: [(&str, &str); 3] = [
+        ("size", "Size"),
+        ("class", "Class"),
+        ("last_modified", "Last Modified"),
+    ];
+
+    pub fn format_bucket_list(
+        buckets: &[(String, String)],
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<S3Item>) {
+        Self::bucket_rows(buckets, None, time, name_width, max_width)
+    }
+
+    // The bucket list, with a column of tag values after the date when `tag` holds the key and
+    // a value per bucket
+    fn bucket_rows(
+        buckets: &[(String, String)],
+        tag: Option<(&str, Vec<&str>)>,
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<S3Item>) {
         if buckets.is_empty() {
             return (
                 vec!["No S3 Buckets found".to_string()],
@@ -329,47 +1410,214 @@ impl S3Service {
 
         let max_name_len = buckets
             .iter()
-            .map(|(name, _)| name.len())
+            .map(|(name, _)| display_width(name))
             .max()
             .unwrap_or(20)
             .max(20);
-
-        let header = format!(
-            "{:<width$}  Creation Date",
-            "Bucket Name",
-            width = max_name_len
+        let mut headers = vec!["Creation Date"];
+        let mut rows: Vec<Vec<&str>> = Vec::new();
+        let dates: Vec<String> = buckets.iter().map(|(_, date)| time.render(date)).collect();
+        for date in &dates {
+            rows.push(vec![date]);
+        }
+        if let Some((key, values)) = &tag {
+            headers.push(key);
+            for (row, value) in rows.iter_mut().zip(values) {
+                row.push(value);
+            }
+        }
+        let widths: Vec<usize> = headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| column_width(header, rows.iter().map(|row| row[i])))
+            .collect();
+        let rest_len = columns_width(&widths);
+        let max_name_len = fit_column_width(
+            max_name_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
         );
-        let separator = "-".repeat(max_name_len + 25).to_string();
+
+        let header = fit_to_width("Bucket Name", max_name_len) + &join_columns(&headers, &widths);
+        let separator = "-".repeat(max_name_len + rest_len);
 
         let mut items = vec![header, separator];
         let mut s3_items = vec![S3Item::Header, S3Item::Separator];
 
-        for (name, date) in buckets {
-            items.push(format!("{:<width$}  {}", name, date, width = max_name_len));
+        for ((name, _), row) in buckets.iter().zip(&rows) {
+            items.push(fit_to_width(name, max_name_len) + &join_columns(row, &widths));
             s3_items.push(S3Item::Bucket(name.clone()));
         }
         (items, s3_items)
     }
 
+    /// The bucket list grouped by the value of the `key` tag, each group under a heading row
+    /// with its size, and the value in a column of its own. `buckets` holds name, creation date
+    /// and tag; buckets without the tag come after the tagged ones, and those whose tags
+    /// couldn't be read last.
+    pub fn format_bucket_groups(
+        buckets: &[(String, String, BucketTag)],
+        key: &str,
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<S3Item>) {
+        let mut sorted: Vec<&(String, String, BucketTag)> = buckets.iter().collect();
+        sorted.sort_by(|(a_name, _, a), (b_name, _, b)| (a, a_name).cmp(&(b, b_name)));
+        let plain: Vec<(String, String)> = sorted
+            .iter()
+            .map(|(name, date, _)| (name.clone(), date.clone()))
+            .collect();
+        let tags = sorted.iter().map(|(_, _, tag)| tag.cell()).collect();
+        let (rows, row_items) =
+            Self::bucket_rows(&plain, Some((key, tags)), time, name_width, max_width);
+        if buckets.is_empty() {
+            return (rows, row_items);
+        }
+
+        let mut items = rows[..2].to_vec();
+        let mut s3_items = row_items[..2].to_vec();
+        let mut bucket_rows = rows.into_iter().zip(row_items).skip(2);
+        for group in sorted.chunk_by(|(_, _, a), (_, _, b)| a == b) {
+            let tag = &group[0].2;
+            let heading = match tag {
+                BucketTag::Value(value) => format!("▸ {}: {} ({})", key, value, group.len()),
+                BucketTag::Missing => format!("▸ No {} tag ({})", key, group.len()),
+                BucketTag::Unreadable => format!("▸ Tags unreadable ({})", group.len()),
+            };
+            items.push(heading);
+            s3_items.push(S3Item::Group(tag.cell().to_string()));
+            for (row, item) in bucket_rows.by_ref().take(group.len()) {
+                items.push(row);
+                s3_items.push(item);
+            }
+        }
+        (items, s3_items)
+    }
+
+    /// The objects and folders under a prefix, leaving out the `OBJECT_COLUMNS` named in
+    /// `hidden`.
     pub fn format_object_list(
-        objects: &[(String, String, String)],
-        _bucket: &str,
-        _prefix: &str,
+        objects: &[(String, String, String, String)],
+        folders: &HashMap<String, FolderStats>,
+        hidden: &[&str],
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
     ) -> (Vec<String>, Vec<S3Item>) {
         let max_name_len = objects
             .iter()
-            .map(|(name, _, _)| name.len())
+            .map(|(name, _, _, _)| display_width(name))
+            .max()
+            .unwrap_or(20)
+            .max(20);
+        // The Items column only appears once the folders have been counted
+        let counted = !folders.is_empty();
+        let mut columns = Self::OBJECT_COLUMNS.to_vec();
+        if counted {
+            columns.insert(1, ("items", "Items"));
+        }
+        let rows = objects
+            .iter()
+            .map(|(name, size, date, class)| {
+                // Counted folders show their total size and item count
+                let (size, count) = match folders.get(name) {
+                    Some(stats) => (
+                        format_size(stats.bytes),
+                        format!("{}{}", stats.items, if stats.truncated { "+" } else { "" }),
+                    ),
+                    None => (size.clone(), String::new()),
+                };
+                let mut row = vec![size, class.clone(), time.render(date)];
+                if counted {
+                    row.insert(1, count);
+                }
+                row
+            })
+            .collect();
+        let (headers, cells, widths) = shown_columns(&columns, hidden, rows);
+        let rest_len = columns_width(&widths);
+        let max_name_len = fit_column_width(
+            max_name_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
+        );
+        let header = fit_to_width("Name", max_name_len) + &join_columns(&headers, &widths);
+        let separator = "-".repeat(max_name_len + rest_len);
+
+        let mut items = vec![header, separator];
+        let mut s3_items = vec![S3Item::Header, S3Item::Separator];
+
+        items.push("..".to_string());
+        s3_items.push(S3Item::ParentDir);
+
+        for ((name, size, _, _), values) in objects.iter().zip(&cells) {
+            items.push(fit_to_width(name, max_name_len) + &join_columns(values, &widths));
+            if folders.contains_key(name) || size == "DIR" {
+                s3_items.push(S3Item::Folder(name.clone()));
+            } else {
+                s3_items.push(S3Item::Object(name.clone()));
+            }
+        }
+        (items, s3_items)
+    }
+
+    pub fn format_version_list(
+        versions: &[S3ObjectVersion],
+        time: TimeFormat,
+        name_width: Option<usize>,
+        max_width: usize,
+    ) -> (Vec<String>, Vec<S3Item>) {
+        let max_name_len = versions
+            .iter()
+            .map(|v| display_width(&v.name))
             .max()
             .unwrap_or(20)
             .max(20);
+        let sizes: Vec<&str> = versions
+            .iter()
+            .map(|v| {
+                if v.delete_marker {
+                    "delete marker"
+                } else {
+                    v.size.as_str()
+                }
+            })
+            .collect();
+        let dates: Vec<String> = versions
+            .iter()
+            .map(|v| time.render(&v.last_modified))
+            .collect();
+        let max_version_len = column_width("Version ID", versions.iter().map(|v| &v.version_id));
+        let latest_len = column_width("Latest", ["yes"]);
+        let size_len = column_width("Size", &sizes);
+        let rest_len = columns_width(&[
+            max_version_len,
+            latest_len,
+            size_len,
+            column_width("Last Modified", &dates),
+        ]);
+        let max_name_len = fit_column_width(
+            max_name_len.max(name_width.unwrap_or(0)),
+            rest_len,
+            max_width,
+            10,
+        );
 
         let header = format!(
-            "{:<width$}  {:<10}  Last Modified",
+            "{:<width$}  {:<width_version$}  {:<width_latest$}  {:<width_size$}  Last Modified",
             "Name",
+            "Version ID",
+            "Latest",
             "Size",
-            width = max_name_len
+            width = max_name_len,
+            width_version = max_version_len,
+            width_latest = latest_len,
+            width_size = size_len
         );
-        let separator = "-".repeat(max_name_len + 30).to_string();
+        let separator = "-".repeat(max_name_len + rest_len);
 
         let mut items = vec![header, separator];
         let mut s3_items = vec![S3Item::Header, S3Item::Separator];
@@ -377,18 +1625,30 @@ impl S3Service {
         items.push("..".to_string());
         s3_items.push(S3Item::ParentDir);
 
-        for (name, size, date) in objects {
+        for ((version, size), date) in versions.iter().zip(&sizes).zip(&dates) {
             items.push(format!(
-                "{:<width$}  {:<10}  {}",
-                name,
+                "{}  {:<width_version$}  {:<width_latest$}  {:<width_size$}  {}",
+                fit_to_width(&version.name, max_name_len),
+                version.version_id,
+                if version.is_latest && version.size != "DIR" {
+                    "yes"
+                } else {
+                    ""
+                },
                 size,
                 date,
-                width = max_name_len
+                width_version = max_version_len,
+                width_latest = latest_len,
+                width_size = size_len
             ));
-            if size == "DIR" {
-                s3_items.push(S3Item::Folder(name.clone()));
+            if version.size == "DIR" {
+                s3_items.push(S3Item::Folder(version.name.clone()));
             } else {
-                s3_items.push(S3Item::Object(name.clone()));
+                s3_items.push(S3Item::Version {
+                    name: version.name.clone(),
+                    version_id: version.version_id.clone(),
+                    delete_marker: version.delete_marker,
+                });
             }
         }
         (items, s3_items)
@@ -404,7 +1664,7 @@ impl S3Service {
                     S3NavigationAction::None
                 }
             }
-            S3Item::Object(key) => {
+            S3Item::Object(key) | S3Item::Version { name: key, .. } => {
                 if let Some(path) = current_path {
                     // Construct full key
                     let parts: Vec<&str> = path.splitn(2, '/').collect();
@@ -436,10 +1696,37 @@ mod tests {
         assert_eq!(format_size(1024 * 1024 * 1024), "1.00 GB");
     }
 
+    #[test]
+    fn test_upload_key() {
+        let key = |prefix, path| upload_key(prefix, Path::new(path));
+        assert_eq!(
+            key("logs/", "/tmp/app.log"),
+            Some("logs/app.log".to_string())
+        );
+        assert_eq!(key("", "report.csv"), Some("report.csv".to_string()));
+        assert_eq!(key("logs/", "/"), None);
+    }
+
+    #[test]
+    fn test_validate_bucket_name() {
+        assert!(validate_bucket_name("my-bucket.logs-2024").is_ok());
+        assert!(validate_bucket_name("ab").is_err());
+        assert!(validate_bucket_name(&"a".repeat(64)).is_err());
+        assert!(validate_bucket_name("My-Bucket").is_err());
+        assert!(validate_bucket_name("my_bucket").is_err());
+        assert!(validate_bucket_name("-bucket").is_err());
+        assert!(validate_bucket_name("bucket.").is_err());
+        assert!(validate_bucket_name("my..bucket").is_err());
+        assert!(validate_bucket_name("192.168.5.4").is_err());
+        assert!(validate_bucket_name("xn--bucket").is_err());
+        assert!(validate_bucket_name("bucket-s3alias").is_err());
+    }
+
     #[test]
     fn test_format_bucket_list() {
         // Test empty list
-        let (items, s3_items) = S3Service::format_bucket_list(&[]);
+        let (items, s3_items) =
+            S3Service::format_bucket_list(&[], TimeFormat::Absolute, None, usize::MAX);
         assert_eq!(items[0], "No S3 Buckets found");
         assert!(matches!(s3_items[0], S3Item::Header));
 
@@ -448,31 +1735,101 @@ mod tests {
             ("bucket1".to_string(), "2023-01-01".to_string()),
             ("bucket2".to_string(), "2023-01-02".to_string()),
         ];
-        let (items, s3_items) = S3Service::format_bucket_list(&buckets);
+        let (items, s3_items) =
+            S3Service::format_bucket_list(&buckets, TimeFormat::Absolute, None, usize::MAX);
 
         assert_eq!(items.len(), 4); // Header, Separator, 2 buckets
         assert!(items[0].contains("Bucket Name"));
         assert!(items[2].contains("bucket1"));
         assert!(items[3].contains("bucket2"));
 
-        assert!(matches!(s3_items[2], S3Item::Bucket(_)));
-        if let S3Item::Bucket(name) = &s3_items[2] {
-            assert_eq!(name, "bucket1");
-        }
+        assert!(matches!(&s3_items[2], S3Item::Bucket(name) if name == "bucket1"));
+
+        // Narrow panes truncate the name column but keep the full name on the item. The date
+        // column is as wide as its "Creation Date" header, which leaves 35 for the name
+        let long = vec![(
+            "a-bucket-name-that-is-far-too-long-to-fit".to_string(),
+            "2023-01-01".to_string(),
+        )];
+        let (items, s3_items) =
+            S3Service::format_bucket_list(&long, TimeFormat::Absolute, None, 50);
+        assert!(items[2].starts_with("a-bucket-name-that-is-far-too-long…  2023-01-01"));
+        assert!(
+            matches!(&s3_items[2], S3Item::Bucket(name) if name == "a-bucket-name-that-is-far-too-long-to-fit")
+        );
+    }
+
+    #[test]
+    fn test_format_bucket_groups() {
+        let bucket = |name: &str, tag: BucketTag| (name.to_string(), "2023-01-01".to_string(), tag);
+        let team = |value: &str| BucketTag::Value(value.to_string());
+        let buckets = vec![
+            bucket("locked", BucketTag::Unreadable),
+            bucket("web-assets", team("web")),
+            bucket("scratch", BucketTag::Missing),
+            bucket("data-raw", team("data")),
+            bucket("web-logs", team("web")),
+        ];
+        let (items, s3_items) = S3Service::format_bucket_groups(
+            &buckets,
+            "Team",
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
+        let names: Vec<&str> = items[2..]
+            .iter()
+            .map(|row| row.split("  ").next().unwrap().trim())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "▸ Team: data (1)",
+                "data-raw",
+                "▸ Team: web (2)",
+                "web-assets",
+                "web-logs",
+                "▸ No Team tag (1)",
+                "scratch",
+                "▸ Tags unreadable (1)",
+                "locked",
+            ]
+        );
+        // The value has a column of its own, headed by the key
+        assert!(items[0].ends_with("Creation Date  Team"));
+        assert!(items[5].ends_with("2023-01-01     web"));
+        assert!(items[8].ends_with("2023-01-01     -"));
+        assert!(items[10].ends_with("2023-01-01     unreadable"));
+        assert!(matches!(&s3_items[4], S3Item::Group(team) if team == "web"));
+        assert!(matches!(&s3_items[5], S3Item::Bucket(name) if name == "web-assets"));
+        assert_eq!(items.len(), s3_items.len());
     }
 
     #[test]
     fn test_format_object_list() {
         let objects = vec![
-            ("folder/".to_string(), "DIR".to_string(), "".to_string()),
+            (
+                "folder/".to_string(),
+                "DIR".to_string(),
+                "".to_string(),
+                "".to_string(),
+            ),
             (
                 "file.txt".to_string(),
                 "1.00 KB".to_string(),
                 "2023-01-01".to_string(),
+                "GLACIER".to_string(),
             ),
         ];
 
-        let (items, s3_items) = S3Service::format_object_list(&objects, "bucket", "");
+        let (items, s3_items) = S3Service::format_object_list(
+            &objects,
+            &HashMap::new(),
+            &[],
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
 
         assert_eq!(items.len(), 5); // Header, Separator, ParentDir, Folder, File
         assert_eq!(items[2], "..");
@@ -481,7 +1838,87 @@ mod tests {
         assert!(items[3].contains("folder/"));
         assert!(matches!(s3_items[3], S3Item::Folder(_)));
 
+        assert!(items[0].contains("Class"));
         assert!(items[4].contains("file.txt"));
+        assert!(items[4].contains("GLACIER"));
         assert!(matches!(s3_items[4], S3Item::Object(_)));
+        assert!(!items[0].contains("Items"));
+
+        // Counted folders show their size and child count instead of DIR
+        let stats = FolderStats {
+            items: 1000,
+            bytes: 2048,
+            truncated: true,
+        };
+        let folders = HashMap::from([("folder/".to_string(), stats)]);
+        let (items, s3_items) = S3Service::format_object_list(
+            &objects,
+            &folders,
+            &[],
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
+        assert!(items[0].contains("Items"));
+        let folder: Vec<&str> = items[3].split_whitespace().collect();
+        assert_eq!(folder, vec!["folder/", "2.00", "KB", "1000+"]);
+        assert!(matches!(s3_items[3], S3Item::Folder(_)));
+        assert_eq!(items[0].find("Class"), items[4].find("GLACIER"));
+
+        // Wide characters take two columns each, so the next column still lines up on screen
+        let objects = vec![
+            (
+                "日本語のレポート.pdf".to_string(),
+                "2.00 KB".to_string(),
+                "2023-01-01".to_string(),
+                "STANDARD".to_string(),
+            ),
+            objects[1].clone(),
+        ];
+        let (items, _) = S3Service::format_object_list(
+            &objects,
+            &HashMap::new(),
+            &[],
+            TimeFormat::Absolute,
+            None,
+            usize::MAX,
+        );
+        let size_column = |row: &str| display_width(&row[..row.find(" KB").unwrap()]);
+        assert_eq!(size_column(&items[3]), size_column(&items[4]));
+    }
+
+    #[test]
+    fn test_format_version_list() {
+        let version =
+            |name: &str, version_id: &str, is_latest, delete_marker, size: &str| S3ObjectVersion {
+                name: name.to_string(),
+                version_id: version_id.to_string(),
+                is_latest,
+                delete_marker,
+                size: size.to_string(),
+                last_modified: "2024-01-01T00:00:00Z".to_string(),
+            };
+        let versions = vec![
+            version("folder/", "", true, false, "DIR"),
+            version("file.txt", "v2", true, true, ""),
+            version("file.txt", "v1", false, false, "1.00 KB"),
+        ];
+
+        let (items, s3_items) =
+            S3Service::format_version_list(&versions, TimeFormat::Absolute, None, usize::MAX);
+
+        assert_eq!(items.len(), 6); // Header, Separator, ParentDir, Folder, two versions
+        assert!(items[0].contains("Version ID"));
+        assert!(matches!(s3_items[3], S3Item::Folder(_)));
+        assert!(items[4].contains("yes") && items[4].contains("delete marker"));
+        assert!(matches!(
+            &s3_items[4],
+            S3Item::Version { version_id, delete_marker: true, .. } if version_id == "v2"
+        ));
+        assert!(!items[5].contains("yes") && items[5].contains("1.00 KB"));
+
+        // A version opens its details under the full key
+        let action = S3Service::handle_selection(&s3_items[5], &Some("bucket/logs/".to_string()));
+        assert!(matches!(action, S3NavigationAction::ShowDetails(key) if key == "logs/file.txt"));
     }
 }
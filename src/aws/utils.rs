@@ -1,3 +1,13 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
 pub fn format_size(size: i64) -> String {
     const KB: i64 = 1024;
     const MB: i64 = KB * 1024;
@@ -13,3 +23,575 @@ pub fn format_size(size: i64) -> String {
         format!("{} B", size)
     }
 }
+
+/// Coarse length of time: seconds, then minutes, hours, days, months and years, e.g. "14d".
+pub fn format_duration(length: Duration) -> String {
+    let secs = length.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        86400..2_592_000 => format!("{}d", secs / 86400),
+        2_592_000..31_536_000 => format!("{}mo", secs / 2_592_000),
+        _ => format!("{}y", secs / 31_536_000),
+    }
+}
+
+/// Coarse age, e.g. "3d ago", or "just now" for the first ten seconds.
+pub fn format_age(age: Duration) -> String {
+    if age.as_secs() < 10 {
+        return "just now".to_string();
+    }
+    format!("{} ago", format_duration(age))
+}
+
+/// Time left as a countdown: "42m", or "1h 05m" past the hour and "30s" in the last minute.
+pub fn format_remaining(left: Duration) -> String {
+    let secs = left.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// "3d ago" for a timestamp as the SDK prints it ("2024-05-01T10:00:00Z") or as Lambda
+/// returns it ("2024-05-01T10:00:00.000+0000"). `None` for text that isn't a timestamp.
+pub fn relative_time(timestamp: &str, now: DateTime<Utc>) -> Option<String> {
+    Some(format_age(time_since(timestamp, now)?))
+}
+
+/// Time from `timestamp`, in either of the formats `relative_time` reads, until `now`.
+pub fn time_since(timestamp: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let time = DateTime::parse_from_rfc3339(timestamp)
+        .or_else(|_| DateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .ok()?;
+    // A clock running slightly behind AWS makes fresh resources look like they're from the future
+    Some(
+        (now - time.with_timezone(&Utc))
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// How the list views show timestamps. The detail popups always show the full time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    #[default]
+    Relative,
+    Absolute,
+}
+
+impl TimeFormat {
+    pub fn render(&self, timestamp: &str) -> String {
+        match self {
+            TimeFormat::Relative => {
+                relative_time(timestamp, Utc::now()).unwrap_or_else(|| timestamp.to_string())
+            }
+            TimeFormat::Absolute => timestamp.to_string(),
+        }
+    }
+}
+
+/// How much of a paginated listing to fetch. The default follows every page at the API's own
+/// page size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Paging {
+    pub page_size: Option<i32>,
+    pub max_items: Option<usize>,
+}
+
+impl Paging {
+    /// Page size clamped to what the API accepts.
+    pub fn page_size(&self, accepted: RangeInclusive<i32>) -> Option<i32> {
+        self.page_size
+            .map(|size| size.clamp(*accepted.start(), *accepted.end()))
+    }
+
+    /// Whether enough has been fetched to stop paging. One item past the cap is needed to
+    /// tell a truncated listing from one that happens to be exactly that long.
+    pub fn is_full(&self, fetched: usize) -> bool {
+        self.max_items.is_some_and(|max| fetched > max)
+    }
+}
+
+/// Sends "Loaded 3000 objects..." style updates from paginated calls to the status bar.
+/// The default has no receiver and drops every update, for callers that don't show progress.
+#[derive(Clone, Debug, Default)]
+pub struct Progress(Option<UnboundedSender<String>>);
+
+impl Progress {
+    pub fn channel() -> (Self, UnboundedReceiver<String>) {
+        let (tx, rx) = unbounded_channel();
+        (Self(Some(tx)), rx)
+    }
+
+    pub fn report(&self, message: impl Into<String>) {
+        if let Some(tx) = &self.0 {
+            // The receiver is gone once the listing was cancelled; nothing left to tell
+            let _ = tx.send(message.into());
+        }
+    }
+}
+
+/// Counts AWS calls running in the background. A tracked future counts from the moment it is
+/// wrapped until it completes or is dropped, so aborted tasks don't leak into the count.
+#[derive(Clone, Debug, Default)]
+pub struct InFlight(Arc<AtomicUsize>);
+
+struct InFlightGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl InFlight {
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn track<F: Future>(&self, future: F) -> impl Future<Output = F::Output> + use<F> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        let guard = InFlightGuard(self.0.clone());
+        async move {
+            let _guard = guard;
+            future.await
+        }
+    }
+}
+
+/// A call that got no answer within the app-level timeout.
+#[derive(Debug)]
+pub struct TimedOut(pub Duration);
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request timed out after {}s", self.0.as_secs())
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Fails with `TimedOut` when `future` takes longer than `limit`. The SDK retries a hanging
+/// endpoint indefinitely, so this is the only thing that stops the spinner.
+pub async fn with_timeout<T>(
+    limit: Duration,
+    future: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    tokio::time::timeout(limit, future)
+        .await
+        .map_err(|_| anyhow::Error::new(TimedOut(limit)))?
+}
+
+/// Width for a flexible column so a row fits in `max_width` after the `fixed` columns,
+/// never wider than its content and never narrower than `min`.
+pub fn fit_column_width(natural: usize, fixed: usize, max_width: usize, min: usize) -> usize {
+    natural.min(max_width.saturating_sub(fixed)).max(min)
+}
+
+/// Width of a column sized to what it shows: its widest value or its header.
+pub fn column_width<S: AsRef<str>>(header: &str, values: impl IntoIterator<Item = S>) -> usize {
+    values
+        .into_iter()
+        .map(|value| display_width(value.as_ref()))
+        .fold(display_width(header), usize::max)
+}
+
+/// Room the columns beside a flexible one take up, each with the two-space gap that sets it
+/// apart, for `fit_column_width`'s `fixed`. The flexible column's width plus this is the
+/// width of a full row.
+pub fn columns_width(widths: &[usize]) -> usize {
+    widths.iter().map(|width| width + 2).sum()
+}
+
+/// Keeps the optional columns of a table not named in `hidden`. `columns` are config name and
+/// header pairs, and every row has a cell for each. Returns the headers, rows and widths of
+/// the columns kept.
+pub fn shown_columns(
+    columns: &[(&str, &'static str)],
+    hidden: &[&str],
+    rows: Vec<Vec<String>>,
+) -> (Vec<&'static str>, Vec<Vec<String>>, Vec<usize>) {
+    let kept: Vec<usize> = (0..columns.len())
+        .filter(|&column| !hidden.contains(&columns[column].0))
+        .collect();
+    let headers: Vec<&'static str> = kept.iter().map(|&column| columns[column].1).collect();
+    let rows: Vec<Vec<String>> = rows
+        .into_iter()
+        .map(|mut row| {
+            kept.iter()
+                .map(|&column| std::mem::take(&mut row[column]))
+                .collect()
+        })
+        .collect();
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(column, header)| column_width(header, rows.iter().map(|row| &row[column])))
+        .collect();
+    (headers, rows, widths)
+}
+
+/// The columns after a table's flexible first one, each after the two-space gap and padded to
+/// its width but the last, which runs on.
+pub fn join_columns<S: AsRef<str>>(values: &[S], widths: &[usize]) -> String {
+    let last = values.len().saturating_sub(1);
+    values
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(column, (value, &width))| {
+            if column == last {
+                format!("  {}", value.as_ref())
+            } else {
+                format!("  {:<width$}", value.as_ref(), width = width)
+            }
+        })
+        .collect()
+}
+
+/// Terminal columns `text` takes up: wide characters such as CJK count twice and combining
+/// marks not at all, unlike `len()` (bytes) or `chars().count()`.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Shortens `text` to at most `width` terminal columns, marking the cut with an ellipsis.
+pub fn truncate_with_ellipsis(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width - 1 {
+            break;
+        }
+        truncated.push(c);
+        used += w;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// `text` cut to `width` terminal columns and padded with spaces to fill them. Stands in for
+/// `{:<width$}`, which pads by characters and so misaligns wide ones.
+pub fn fit_to_width(text: &str, width: usize) -> String {
+    let text = truncate_with_ellipsis(text, width);
+    let padding = width.saturating_sub(display_width(&text));
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
+    ExpiredToken,
+    InvalidCredentials,
+    AccessDenied(Option<String>),
+    Network,
+    TimedOut,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn summary(&self) -> &str {
+        match self {
+            ErrorKind::ExpiredToken => "Credentials have expired",
+            ErrorKind::InvalidCredentials => "Credentials are missing or invalid",
+            ErrorKind::AccessDenied(_) => "Access denied",
+            ErrorKind::Network => "Network error",
+            ErrorKind::TimedOut => "Request timed out",
+            ErrorKind::Other => "Failed to load resources",
+        }
+    }
+
+    pub fn guidance(&self, profile: &str) -> Vec<String> {
+        match self {
+            ErrorKind::ExpiredToken => vec![
+                "Your session token or SSO login has expired.".to_string(),
+                format!("- Run `aws sso login --profile {}` and refresh", profile),
+                "- Or obtain fresh temporary credentials".to_string(),
+            ],
+            ErrorKind::InvalidCredentials => vec![
+                "No valid credentials were found for this request.".to_string(),
+                format!(
+                    "- Check the credentials configured for profile '{}'",
+                    profile
+                ),
+                "- Verify AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY if set".to_string(),
+            ],
+            ErrorKind::AccessDenied(detail) => {
+                let mut lines = vec!["The credentials are valid but lack permission.".to_string()];
+                if let Some(detail) = detail {
+                    lines.push(format!("- Denied: {}", detail));
+                }
+                lines.push("- Ask an administrator to grant the missing IAM action".to_string());
+                lines
+            }
+            ErrorKind::Network => vec![
+                "The AWS endpoint could not be reached.".to_string(),
+                "- Check your network connection, VPN or proxy".to_string(),
+                "- Verify the configured region and endpoint".to_string(),
+            ],
+            ErrorKind::TimedOut => vec![
+                "The AWS endpoint accepted the connection but never answered.".to_string(),
+                "- Check VPC endpoints, proxies and firewalls for this service".to_string(),
+                "- Raise request_timeout_secs in the config file for slow calls".to_string(),
+            ],
+            ErrorKind::Other => vec![
+                "Possible causes:".to_string(),
+                "- Invalid AWS credentials".to_string(),
+                "- Insufficient IAM permissions".to_string(),
+                "- Network connectivity issues".to_string(),
+            ],
+        }
+    }
+}
+
+pub fn classify_error(error: &anyhow::Error) -> ErrorKind {
+    // SDK errors only expose the service error code further down the source
+    // chain, so match against the whole chain rather than the top-level message.
+    if error.downcast_ref::<TimedOut>().is_some() {
+        return ErrorKind::TimedOut;
+    }
+    let message = format!("{:#}", error);
+    let lower = message.to_lowercase();
+
+    const EXPIRED: [&str; 6] = [
+        "expiredtoken",
+        "tokenrefreshrequired",
+        "requestexpired",
+        "token included in the request is expired",
+        "sso session",
+        "sso token",
+    ];
+    const INVALID: [&str; 6] = [
+        "invalidclienttokenid",
+        "unrecognizedclient",
+        "invalidaccesskeyid",
+        "signaturedoesnotmatch",
+        "no providers in chain provided credentials",
+        "failed to load credentials",
+    ];
+    const DENIED: [&str; 4] = [
+        "accessdenied",
+        "unauthorizedoperation",
+        "unauthorizedaccess",
+        "not authorized to perform",
+    ];
+    const NETWORK: [&str; 6] = [
+        "dispatch failure",
+        "timeout",
+        "timed out",
+        "connection",
+        "dns error",
+        "failed to lookup address",
+    ];
+
+    if EXPIRED.iter().any(|p| lower.contains(p)) {
+        ErrorKind::ExpiredToken
+    } else if INVALID.iter().any(|p| lower.contains(p)) {
+        ErrorKind::InvalidCredentials
+    } else if DENIED.iter().any(|p| lower.contains(p)) {
+        ErrorKind::AccessDenied(extract_denied_action(&message))
+    } else if NETWORK.iter().any(|p| lower.contains(p)) {
+        ErrorKind::Network
+    } else {
+        ErrorKind::Other
+    }
+}
+
+/// Builds a JSON object from API-style field names, leaving out fields that are unset. The SDK
+/// types don't implement Serialize, so the raw JSON views list each field by hand with this.
+pub fn json_fields(fields: Vec<(&str, Option<serde_json::Value>)>) -> serde_json::Value {
+    serde_json::Value::Object(
+        fields
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value?)))
+            .collect(),
+    )
+}
+
+pub fn json_time(time: Option<&aws_smithy_types::DateTime>) -> Option<serde_json::Value> {
+    time.map(|t| serde_json::Value::String(t.to_string()))
+}
+
+/// The AWS request id from an SDK error's full text, for quoting in a support ticket.
+pub fn extract_request_id(message: &str) -> Option<String> {
+    const MARKER: &str = "aws_request_id: \"";
+    let start = message.find(MARKER)? + MARKER.len();
+    let end = message[start..].find('"')?;
+    let id = &message[start..start + end];
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+// Pulls "service:Action on resource: arn" out of an IAM access denied message
+fn extract_denied_action(message: &str) -> Option<String> {
+    const MARKER: &str = "not authorized to perform: ";
+    let start = message.find(MARKER)? + MARKER.len();
+    let rest = &message[start..];
+    let end = [" because", "\"", "\n", ". "]
+        .iter()
+        .filter_map(|stop| rest.find(stop))
+        .min()
+        .unwrap_or(rest.len());
+    let detail = rest[..end].trim();
+    if detail.is_empty() {
+        None
+    } else {
+        Some(detail.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_time() {
+        let now = DateTime::parse_from_rfc3339("2024-05-04T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let relative = |text| relative_time(text, now);
+        assert_eq!(relative("2024-05-01T10:00:00Z").as_deref(), Some("3d ago"));
+        assert_eq!(
+            relative("2024-05-04T08:00:00.000+0000").as_deref(),
+            Some("2h ago")
+        );
+        assert_eq!(relative("2023-01-01T00:00:00Z").as_deref(), Some("1y ago"));
+        assert_eq!(
+            relative("2024-05-04T10:00:30Z").as_deref(),
+            Some("just now")
+        );
+        assert_eq!(relative("pending"), None);
+
+        assert_eq!(format_remaining(Duration::from_secs(42 * 60 + 10)), "42m");
+        assert_eq!(format_remaining(Duration::from_secs(3900)), "1h 05m");
+        assert_eq!(format_remaining(Duration::from_secs(30)), "30s");
+
+        assert_eq!(TimeFormat::Relative.render("Unknown"), "Unknown");
+        assert_eq!(
+            TimeFormat::Absolute.render("2024-05-01T10:00:00Z"),
+            "2024-05-01T10:00:00Z"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_timeout() {
+        let limit = Duration::from_millis(10);
+        assert_eq!(with_timeout(limit, async { Ok(1) }).await.unwrap(), 1);
+
+        let err = with_timeout(limit, std::future::pending::<anyhow::Result<()>>())
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "request timed out after 0s");
+        assert_eq!(classify_error(&err), ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_count() {
+        let in_flight = InFlight::default();
+        let pending = in_flight.track(std::future::pending::<()>());
+        let done = in_flight.track(async { 1 });
+        assert_eq!(in_flight.count(), 2);
+
+        assert_eq!(done.await, 1);
+        assert_eq!(in_flight.count(), 1);
+        // Dropping an unfinished future, as aborting a task does, also releases it
+        drop(pending);
+        assert_eq!(in_flight.count(), 0);
+    }
+
+    #[test]
+    fn test_fit_and_truncate() {
+        assert_eq!(fit_column_width(30, 20, usize::MAX, 10), 30);
+        assert_eq!(fit_column_width(30, 20, 40, 10), 20);
+        assert_eq!(fit_column_width(30, 20, 25, 10), 10);
+
+        assert_eq!(column_width("Size", ["1 KB", "12.50 MB"]), 8);
+        assert_eq!(column_width("Last Status", ["RUNNING"]), 11);
+        assert_eq!(columns_width(&[10, 12, 8]), 36);
+        assert_eq!(
+            join_columns(&["ACTIVE", "12", "1 KB"], &[6, 5, 4]),
+            "  ACTIVE  12     1 KB"
+        );
+        assert_eq!(join_columns::<&str>(&[], &[]), "");
+
+        let columns = [("status", "Status"), ("items", "Items"), ("size", "Size")];
+        let rows = vec![vec![
+            "ACTIVE".to_string(),
+            "12".to_string(),
+            "1 KB".to_string(),
+        ]];
+        let (headers, rows, widths) = shown_columns(&columns, &["items"], rows);
+        assert_eq!(headers, vec!["Status", "Size"]);
+        assert_eq!(rows, vec![vec!["ACTIVE".to_string(), "1 KB".to_string()]]);
+        assert_eq!(widths, vec![6, 4]);
+
+        assert_eq!(truncate_with_ellipsis("short", 10), "short");
+        assert_eq!(truncate_with_ellipsis("a-very-long-name", 8), "a-very-…");
+        assert_eq!(truncate_with_ellipsis("abc", 0), "");
+
+        // Wide characters take two columns and combining marks none
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("cafe\u{301}"), 4);
+        assert_eq!(truncate_with_ellipsis("日本語のバケット", 6), "日本…");
+        assert_eq!(fit_to_width("日本", 6), "日本  ");
+        assert_eq!(fit_to_width("cafe\u{301}", 6), "cafe\u{301}  ");
+        assert_eq!(fit_to_width("a-very-long-name", 8), "a-very-…");
+    }
+
+    #[test]
+    fn test_classify_error() {
+        let expired = anyhow::anyhow!("service error: Error {{ code: \"ExpiredToken\" }}");
+        assert_eq!(classify_error(&expired), ErrorKind::ExpiredToken);
+
+        // The SDK's SSO token provider fails before any request is sent
+        let sso = anyhow::anyhow!(
+            "failed to load credentials: the cached SSO token is expired and could not be refreshed"
+        );
+        assert_eq!(classify_error(&sso), ErrorKind::ExpiredToken);
+
+        let invalid =
+            anyhow::anyhow!("dispatch failure: no providers in chain provided credentials");
+        assert_eq!(classify_error(&invalid), ErrorKind::InvalidCredentials);
+
+        let network = anyhow::anyhow!("dispatch failure: io error: connection refused");
+        assert_eq!(classify_error(&network), ErrorKind::Network);
+
+        let other = anyhow::anyhow!("something unexpected");
+        assert_eq!(classify_error(&other), ErrorKind::Other);
+    }
+
+    #[test]
+    fn test_extract_request_id() {
+        let message = "service error: unhandled error (NoSuchBucket): Error { code: \"NoSuchBucket\", aws_request_id: \"4442587FB7D0A2F9\", s3_extended_request_id: \"abc=\" }";
+        assert_eq!(
+            extract_request_id(message),
+            Some("4442587FB7D0A2F9".to_string())
+        );
+        assert_eq!(extract_request_id("dispatch failure: io error"), None);
+    }
+
+    #[test]
+    fn test_classify_access_denied_extracts_action() {
+        let denied = anyhow::anyhow!(
+            "service error: Error {{ code: \"AccessDenied\", message: \"User: arn:aws:iam::123456789012:user/bob is not authorized to perform: iam:ListUsers on resource: arn:aws:iam::123456789012:user/ because no identity-based policy allows the iam:ListUsers action\" }}"
+        );
+        assert_eq!(
+            classify_error(&denied),
+            ErrorKind::AccessDenied(Some(
+                "iam:ListUsers on resource: arn:aws:iam::123456789012:user/".to_string()
+            ))
+        );
+
+        let bare = anyhow::anyhow!("service error: AccessDenied");
+        assert_eq!(classify_error(&bare), ErrorKind::AccessDenied(None));
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use serde_json::{Value, json};
+
+use crate::app::ServiceType;
+use crate::aws::profile::{
+    available_profiles, check_profile, env_credentials, resolve_profile, resolve_region,
+};
+use crate::aws::{
+    AwsClient, ClientOptions, CloudwatchService, DynamoDbService, Ec2Columns, Ec2Service,
+    EcsService, IamService, LambdaService, MwaaService, Paging, Progress, S3Service, TimeFormat,
+};
+
+#[derive(Parser, Debug, Default)]
+#[command(name = "awsome", version, about = "CLI/TUI for AWS Management")]
+pub struct Cli {
+    /// List a service non-interactively and exit (ec2, s3, iam, cloudwatch, dynamodb, ecs, mwaa, lambda)
+    #[arg(long, value_parser = parse_service)]
+    pub service: Option<ServiceType>,
+
+    /// AWS profile to use instead of AWS_PROFILE
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// AWS region to use instead of the profile default
+    #[arg(long)]
+    pub region: Option<String>,
+
+    /// Role ARN to assume before creating the service clients
+    #[arg(long, value_name = "ROLE_ARN")]
+    pub assume_role: Option<String>,
+
+    /// Send every request to this endpoint instead of AWS, e.g. http://localhost:4566 for
+    /// LocalStack. AWS_ENDPOINT_URL does the same
+    #[arg(long, value_name = "URL")]
+    pub endpoint_url: Option<String>,
+
+    /// Address S3 buckets in the path rather than the host name, as LocalStack expects
+    #[arg(long)]
+    pub s3_force_path_style: bool,
+
+    /// Use FIPS endpoints, as GovCloud and FIPS-mandated environments require
+    #[arg(long)]
+    pub fips: bool,
+
+    /// Use dual-stack (IPv4 and IPv6) endpoints
+    #[arg(long)]
+    pub dualstack: bool,
+
+    /// Disable every action that changes resources or writes files (stop, restore, delete,
+    /// create, download)
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Confirming a stop, delete, create or rename shows the AWS call it would make instead of
+    /// making it; EC2 calls go out with DryRun to check permissions
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Output format for non-interactive mode
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub output: OutputFormat,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Table,
+}
+
+impl Cli {
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            profile: self.profile.clone(),
+            region: self.region.clone(),
+            assume_role: self.assume_role.clone(),
+            endpoint_url: self.endpoint_url.clone(),
+            s3_force_path_style: self.s3_force_path_style,
+            use_fips: self.fips,
+            use_dual_stack: self.dualstack,
+        }
+    }
+}
+
+pub(crate) fn parse_service(name: &str) -> Result<ServiceType, String> {
+    ServiceType::from_name(name).ok_or_else(|| {
+        let known: Vec<String> = ServiceType::ALL
+            .iter()
+            .map(|s| s.short_name().to_lowercase())
+            .collect();
+        format!(
+            "unknown service '{}' (expected one of: {})",
+            name,
+            known.join(", ")
+        )
+    })
+}
+
+/// Lists `service` and prints the result to stdout without starting the TUI.
+pub async fn run(
+    service: ServiceType,
+    options: &ClientOptions,
+    output: OutputFormat,
+) -> Result<()> {
+    let profile = resolve_profile(options.profile.as_deref());
+    if env_credentials(options.profile.as_deref()).is_none() {
+        check_profile(&profile, &available_profiles())?;
+    }
+    let options = ClientOptions {
+        region: resolve_region(&profile, options.region.as_deref()),
+        ..options.clone()
+    };
+    let client = AwsClient::with_options(&options).await?;
+    let (rows, table) = list_service(&client, service).await?;
+
+    match output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&Value::Array(rows))?),
+        OutputFormat::Table => {
+            for line in table {
+                println!("{}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Returns both the JSON rows and the same table the TUI would render
+async fn list_service(
+    client: &AwsClient,
+    service: ServiceType,
+) -> Result<(Vec<Value>, Vec<String>)> {
+    let result = match service {
+        ServiceType::EC2 => {
+            let instances = client
+                .list_ec2_instances(None, Paging::default(), &Progress::default())
+                .await?;
+            let rows = instances
+                .iter()
+                .map(|instance| {
+                    let tags: serde_json::Map<String, Value> = instance
+                        .tags
+                        .iter()
+                        .map(|(key, value)| (key.clone(), json!(value)))
+                        .collect();
+                    json!({
+                        "instance_id": instance.id,
+                        "name": instance.name,
+                        "state": instance.state,
+                        "instance_type": instance.instance_type,
+                        "public_ip": instance.public_ip,
+                        "tags": tags,
+                    })
+                })
+                .collect();
+            (
+                rows,
+                Ec2Service::format_instance_list(
+                    &instances,
+                    Ec2Columns::Status.columns(),
+                    TimeFormat::Absolute,
+                    None,
+                    usize::MAX,
+                )
+                .0,
+            )
+        }
+        ServiceType::S3 => {
+            let buckets = client.list_s3_buckets().await?;
+            let rows = buckets
+                .iter()
+                .map(
+                    |(name, creation_date)| json!({ "name": name, "creation_date": creation_date }),
+                )
+                .collect();
+            (
+                rows,
+                S3Service::format_bucket_list(&buckets, TimeFormat::Absolute, None, usize::MAX).0,
+            )
+        }
+        ServiceType::IAM => {
+            let users = client.list_iam_users().await?;
+            let rows = users
+                .iter()
+                .map(|(name, id, created, arn)| {
+                    json!({
+                        "user_name": name,
+                        "user_id": id,
+                        "create_date": created,
+                        "arn": arn,
+                    })
+                })
+                .collect();
+            (
+                rows,
+                IamService::format_user_list(&users, false, TimeFormat::Absolute, None, usize::MAX)
+                    .0,
+            )
+        }
+        ServiceType::CloudWatch => {
+            let alarms = client.list_cloudwatch_alarms(Paging::default()).await?;
+            let rows = alarms
+                .iter()
+                .map(|(name, state, composite)| {
+                    json!({
+                        "alarm_name": name,
+                        "state": state,
+                        "type": if *composite { "composite" } else { "metric" },
+                    })
+                })
+                .collect();
+            (
+                rows,
+                CloudwatchService::format_alarm_list(&alarms, None, None, usize::MAX).0,
+            )
+        }
+        ServiceType::DynamoDB => {
+            let tables = client
+                .list_dynamodb_tables(Paging::default(), &Progress::default())
+                .await?;
+            let rows = tables
+                .iter()
+                .map(|(name, status, item_count, size)| {
+                    json!({
+                        "table_name": name,
+                        "status": status,
+                        "item_count": item_count,
+                        "size": size,
+                    })
+                })
+                .collect();
+            (
+                rows,
+                DynamoDbService::format_table_list(&tables, &[], None, usize::MAX).0,
+            )
+        }
+        ServiceType::ECS => {
+            let clusters = client.list_ecs_clusters().await?;
+            let rows = clusters
+                .iter()
+                .map(|(name, arn)| json!({ "cluster_name": name, "arn": arn }))
+                .collect();
+            (
+                rows,
+                EcsService::format_cluster_list(&clusters, false, None, usize::MAX).0,
+            )
+        }
+        ServiceType::MWAA => {
+            let envs = client.list_mwaa_environments().await?;
+            let rows = envs
+                .iter()
+                .map(|name| json!({ "environment_name": name }))
+                .collect();
+            (
+                rows,
+                MwaaService::format_environment_list(&envs, None, usize::MAX).0,
+            )
+        }
+        ServiceType::Lambda => {
+            let functions = client.list_lambda_functions().await?;
+            let rows = functions
+                .iter()
+                .map(|(name, runtime, last_modified)| {
+                    json!({
+                        "function_name": name,
+                        "runtime": runtime,
+                        "last_modified": last_modified,
+                    })
+                })
+                .collect();
+            (
+                rows,
+                LambdaService::format_function_list(
+                    &functions,
+                    &[],
+                    TimeFormat::Absolute,
+                    None,
+                    usize::MAX,
+                )
+                .0,
+            )
+        }
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let cli = Cli::parse_from(["awsome", "--service", "ec2", "--region", "eu-west-1"]);
+        assert_eq!(cli.service, Some(ServiceType::EC2));
+        assert_eq!(cli.region.as_deref(), Some("eu-west-1"));
+        assert_eq!(cli.output, OutputFormat::Json);
+
+        let cli = Cli::parse_from(["awsome", "--service", "S3", "--output", "table"]);
+        assert_eq!(cli.service, Some(ServiceType::S3));
+        assert_eq!(cli.output, OutputFormat::Table);
+
+        // No --service means the TUI runs
+        let cli = Cli::parse_from(["awsome", "--profile", "dev"]);
+        assert!(cli.service.is_none());
+        assert!(!cli.read_only && !cli.dry_run);
+        assert_eq!(cli.client_options().profile.as_deref(), Some("dev"));
+
+        let cli = Cli::parse_from([
+            "awsome",
+            "--assume-role",
+            "arn:aws:iam::123456789012:role/ReadOnly",
+        ]);
+        assert_eq!(cli.client_options().assumed_role_name(), Some("ReadOnly"));
+
+        let cli = Cli::parse_from([
+            "awsome",
+            "--endpoint-url",
+            "http://localhost:4566",
+            "--s3-force-path-style",
+        ]);
+        let options = cli.client_options();
+        assert_eq!(
+            options.endpoint_url.as_deref(),
+            Some("http://localhost:4566")
+        );
+        assert!(options.s3_force_path_style);
+        assert!(!options.use_fips);
+
+        let options = Cli::parse_from(["awsome", "--fips", "--dualstack"]).client_options();
+        assert!(options.use_fips && options.use_dual_stack);
+
+        assert!(Cli::try_parse_from(["awsome", "--service", "rds"]).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Clipboard tools tried in order; the first one that runs successfully wins
+const COMMANDS: [(&str, &[&str]); 4] = [
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// Copies `text` to the system clipboard and returns what did the copying, for the status bar.
+/// Without a clipboard tool it falls back to the OSC 52 escape sequence, which most terminals
+/// (also over SSH) turn into a clipboard write.
+pub fn copy(text: &str) -> Result<&'static str> {
+    for (program, args) in COMMANDS {
+        if pipe_to(program, args, text).is_ok() {
+            return Ok(program);
+        }
+    }
+
+    let mut stdout = std::io::stdout();
+    write!(stdout, "{}", osc52(text)).context("Failed to write to the terminal")?;
+    stdout.flush()?;
+    Ok("terminal")
+}
+
+// Tools that print the clipboard, tried in the same order as the copy ones
+const PASTE_COMMANDS: [(&str, &[&str]); 4] = [
+    ("pbpaste", &[]),
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-out"]),
+    ("xsel", &["--clipboard", "--output"]),
+];
+
+/// Reads the system clipboard through the first clipboard tool that runs. There is no terminal
+/// fallback for reading, but most terminals paste on their own shortcut, which arrives as a
+/// bracketed paste instead.
+pub fn paste() -> Result<String> {
+    for (program, args) in PASTE_COMMANDS {
+        if let Ok(output) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            && output.status.success()
+        {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+    }
+    bail!("no clipboard tool found (pbpaste, wl-paste, xclip or xsel)")
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Dropping stdin closes the pipe so the tool sees EOF
+    child
+        .stdin
+        .take()
+        .context("stdin not captured")?
+        .write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(osc52("aws s3 ls"), "\x1b]52;c;YXdzIHMzIGxz\x07");
+    }
+}
@@ -0,0 +1,339 @@
+use crate::app::ServiceType;
+use crate::aws::{ClientOptions, TimeFormat};
+use crate::cli::parse_service;
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+/// User settings read from `~/.config/awsome/config.toml`. Missing keys fall back to defaults.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Ask for y/n before quitting
+    pub confirm_quit: bool,
+    /// List every resource a bulk stop or delete touches and have the count typed to confirm,
+    /// instead of a y/n over the first few
+    pub confirm_bulk: bool,
+    /// Disable every action that changes resources, same as `--read-only`
+    pub read_only: bool,
+    /// Show the call a confirmed stop, delete, create or rename would make instead of making it,
+    /// same as `--dry-run`
+    pub dry_run: bool,
+    /// Give up on an AWS list or detail call after this many seconds
+    pub request_timeout_secs: u64,
+    /// Seconds between describe calls for a resource being watched with `W`
+    pub watch_interval_secs: u64,
+    /// Use FIPS endpoints, same as `--fips`
+    pub fips: bool,
+    /// Use dual-stack (IPv4 and IPv6) endpoints, same as `--dualstack`
+    pub dualstack: bool,
+    /// Leave zero-byte keys ending in `/`, which some tools create to fake folders, out of S3
+    /// listings
+    pub hide_folder_placeholders: bool,
+    /// Leave out the separator row and show the column header in the list title, toggled with z
+    pub dense: bool,
+    /// "relative" for "3d ago" in the lists, "absolute" for the full timestamp
+    pub time_format: TimeFormat,
+    /// Items per request for the paginated listings, clamped to what each API accepts
+    pub page_size: Option<i32>,
+    /// Stop listing a service after this many items, e.g. `s3 = 500`. Services left out list
+    /// everything
+    pub max_items: BTreeMap<String, usize>,
+    /// Service to open on startup and start loading right away, e.g. `"s3"`. Takes precedence
+    /// over the tab that was open on the last exit
+    pub default_service: Option<String>,
+    /// Service tab that was open on the last exit
+    pub active_service: Option<usize>,
+    /// Region that was selected on the last exit
+    pub region: Option<String>,
+    /// Account ids whose header badge is shown in red as a warning, e.g. `["123456789012"]`
+    pub prod_accounts: Vec<String>,
+    /// Minimum width of each service's name column, e.g. `ec2 = 30`. Services left out fit
+    /// the column to the data
+    pub column_widths: BTreeMap<String, usize>,
+    /// Columns shown per service, e.g. `ec2 = ["state", "public_ip", "ami"]`, as picked with C.
+    /// Services left out show their default columns
+    pub columns: BTreeMap<String, Vec<String>>,
+    /// Key overrides, action name -> keys, e.g. `next = ["n", "down"]`
+    pub keys: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            confirm_quit: true,
+            confirm_bulk: true,
+            read_only: false,
+            dry_run: false,
+            request_timeout_secs: 15,
+            fips: false,
+            dualstack: false,
+            hide_folder_placeholders: false,
+            dense: false,
+            watch_interval_secs: 5,
+            time_format: TimeFormat::Relative,
+            page_size: None,
+            max_items: BTreeMap::new(),
+            default_service: None,
+            active_service: None,
+            region: None,
+            prod_accounts: Vec::new(),
+            column_widths: BTreeMap::new(),
+            columns: BTreeMap::new(),
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        if let Ok(path) = std::env::var("AWSOME_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        std::env::var("XDG_CONFIG_HOME")
+            .ok()
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var("HOME")
+                    .ok()
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })
+            .map(|dir| dir.join("awsome").join("config.toml"))
+    }
+
+    /// Loads the config file, returning defaults when it doesn't exist.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+
+    /// Saves the session state to the config file: the tab and region open on exit, and the
+    /// column widths and picks. Only those keys are edited, so the rest of the file stays as
+    /// written, comments included. A file named by `AWSOME_CONFIG` is edited but never created.
+    pub fn save_session(&self) -> Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        let contents = if path.exists() {
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read config file {}", path.display()))?
+        } else if std::env::var_os("AWSOME_CONFIG").is_some() {
+            return Ok(());
+        } else {
+            String::new()
+        };
+        let updated = self
+            .with_session(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+        if updated == contents {
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&path, updated)
+            .with_context(|| format!("Failed to write config file {}", path.display()))
+    }
+
+    // `contents` with the session keys set to this config's values
+    fn with_session(&self, contents: &str) -> Result<String> {
+        let mut doc: DocumentMut = contents.parse()?;
+        set_value(
+            doc.as_table_mut(),
+            "active_service",
+            self.active_service.map(|index| Value::from(index as i64)),
+        );
+        set_value(
+            doc.as_table_mut(),
+            "region",
+            self.region.as_deref().map(Value::from),
+        );
+        set_table(
+            &mut doc,
+            "column_widths",
+            self.column_widths
+                .iter()
+                .map(|(service, width)| (service.as_str(), Value::from(*width as i64)))
+                .collect(),
+        );
+        set_table(
+            &mut doc,
+            "columns",
+            self.columns
+                .iter()
+                .map(|(service, names)| (service.as_str(), Value::from_iter(names)))
+                .collect(),
+        );
+        Ok(doc.to_string())
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let config: Self = toml::from_str(contents)?;
+        config.default_service()?;
+        Ok(config)
+    }
+
+    /// The `default_service` as one of the known services.
+    pub fn default_service(&self) -> Result<Option<ServiceType>> {
+        self.default_service
+            .as_deref()
+            .map(|name| parse_service(name).map_err(|e| anyhow!("default_service: {}", e)))
+            .transpose()
+    }
+
+    /// Turns on the endpoint settings from the file on top of the command-line ones. The flags
+    /// can only add to the file, not switch it off.
+    pub fn apply_endpoints(&self, options: ClientOptions) -> ClientOptions {
+        ClientOptions {
+            use_fips: options.use_fips || self.fips,
+            use_dual_stack: options.use_dual_stack || self.dualstack,
+            ..options
+        }
+    }
+}
+
+// Sets `key` to `value`, or removes it for None, keeping the comments around it
+fn set_value(table: &mut dyn TableLike, key: &str, value: Option<Value>) {
+    let Some(mut value) = value else {
+        table.remove(key);
+        return;
+    };
+    match table.get_mut(key).and_then(Item::as_value_mut) {
+        Some(current) => {
+            *value.decor_mut() = current.decor().clone();
+            *current = value;
+        }
+        None => {
+            table.insert(key, Item::Value(value));
+        }
+    }
+}
+
+// Sets the `key` table to `entries`, or removes it when there are none
+fn set_table(doc: &mut DocumentMut, key: &str, entries: Vec<(&str, Value)>) {
+    if entries.is_empty() {
+        doc.remove(key);
+        return;
+    }
+    if !doc.get(key).is_some_and(|item| item.is_table_like()) {
+        doc.insert(key, toml_edit::table());
+    }
+    let Some(table) = doc.get_mut(key).and_then(Item::as_table_like_mut) else {
+        return;
+    };
+    let stale: Vec<String> = table
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .filter(|name| entries.iter().all(|(entry, _)| entry != name))
+        .collect();
+    for name in stale {
+        table.remove(&name);
+    }
+    for (name, value) in entries {
+        set_value(table, name, Some(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert!(Config::default().confirm_quit && Config::default().confirm_bulk);
+
+        let config = Config::parse("confirm_quit = false").unwrap();
+        assert!(!config.confirm_quit);
+
+        assert!(Config::parse("confirm_quit = \"nope\"").is_err());
+
+        let config = Config::parse("time_format = \"absolute\"").unwrap();
+        assert_eq!(config.time_format, TimeFormat::Absolute);
+
+        let config = Config::parse("fips = true").unwrap();
+        let options = config.apply_endpoints(ClientOptions::default());
+        assert!(options.use_fips && !options.use_dual_stack);
+
+        let config = Config::parse("hide_folder_placeholders = true\ndense = true").unwrap();
+        assert!(config.hide_folder_placeholders && config.dense);
+
+        let config = Config::parse("read_only = true\ndry_run = true").unwrap();
+        assert!(config.read_only && config.dry_run);
+
+        let config = Config::parse("watch_interval_secs = 2").unwrap();
+        assert_eq!(config.watch_interval_secs, 2);
+
+        let config = Config::parse("prod_accounts = [\"123456789012\"]").unwrap();
+        assert_eq!(config.prod_accounts, vec!["123456789012"]);
+
+        let config = Config::parse("page_size = 100\n[max_items]\ns3 = 500").unwrap();
+        assert_eq!(config.page_size, Some(100));
+        assert_eq!(config.max_items["s3"], 500);
+
+        let config = Config::parse("[column_widths]\nec2 = 30").unwrap();
+        assert_eq!(config.column_widths["ec2"], 30);
+
+        let config = Config::parse("[columns]\nec2 = [\"state\", \"ami\"]").unwrap();
+        assert_eq!(config.columns["ec2"], vec!["state", "ami"]);
+
+        let config = Config::parse("default_service = \"cloudwatch\"").unwrap();
+        assert_eq!(
+            config.default_service().unwrap(),
+            Some(ServiceType::CloudWatch)
+        );
+        let err = Config::parse("default_service = \"rds\"").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("default_service: unknown service 'rds'")
+        );
+
+        let config = Config::parse("[keys]\nquit = [\"ctrl+c\", \"q\"]").unwrap();
+        assert_eq!(config.keys["quit"], vec!["ctrl+c", "q"]);
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let written = "# Start in Frankfurt\n\
+region = \"eu-central-1\"  # closest\n\
+confirm_quit = false\n\
+\n\
+# Wide enough for our naming scheme\n\
+[column_widths]\n\
+ec2 = 30\n\
+s3 = 40\n";
+        let mut config = Config::parse(written).unwrap();
+        config.active_service = Some(1);
+        config.region = Some("eu-west-1".to_string());
+        config.column_widths.remove("s3");
+        config
+            .columns
+            .insert("ec2".to_string(), vec!["state".to_string()]);
+
+        let saved = config.with_session(written).unwrap();
+        assert_eq!(Config::parse(&saved).unwrap(), config);
+        // Comments survive and defaults aren't written out
+        assert!(saved.starts_with("# Start in Frankfurt\nregion = \"eu-west-1\"  # closest\n"));
+        assert!(saved.contains("# Wide enough for our naming scheme\n[column_widths]\nec2 = 30\n"));
+        assert!(!saved.contains("s3") && !saved.contains("confirm_bulk"));
+
+        // Unset session fields are left out rather than written as empty values, and a file
+        // already up to date is left alone
+        let fresh = Config::default().with_session("").unwrap();
+        assert!(!fresh.contains("region"));
+        assert_eq!(config.with_session(&saved).unwrap(), saved);
+    }
+}
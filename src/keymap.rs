@@ -0,0 +1,549 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+
+/// Main-view commands that can be rebound in the `[keys]` table of the config file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ToggleServices,
+    Details,
+    Watch,
+    Connect,
+    Refresh,
+    ReassumeRole,
+    Stop,
+    Mark,
+    EventLog,
+    ErrorDetails,
+    Search,
+    TagFilter,
+    StorageClassFilter,
+    StateFilter,
+    JumpToPrefix,
+    Versions,
+    Restore,
+    Upload,
+    Download,
+    Delete,
+    Create,
+    Rename,
+    Columns,
+    PickColumns,
+    FullArns,
+    CredentialReport,
+    Dense,
+    AllRegions,
+    GroupByTag,
+    FolderStats,
+    JumpToLetter,
+    WidenColumn,
+    NarrowColumn,
+    CopyCommand,
+    YankAll,
+    Snapshot,
+    Diff,
+    Dashboard,
+    Breadcrumb,
+    Palette,
+    Next,
+    Prev,
+    First,
+    Last,
+    PageDown,
+    PageUp,
+    Select,
+}
+
+impl Action {
+    pub const ALL: [Action; 48] = [
+        Action::Quit,
+        Action::ToggleServices,
+        Action::Details,
+        Action::Watch,
+        Action::Connect,
+        Action::Refresh,
+        Action::ReassumeRole,
+        Action::Stop,
+        Action::Mark,
+        Action::EventLog,
+        Action::ErrorDetails,
+        Action::Search,
+        Action::TagFilter,
+        Action::StorageClassFilter,
+        Action::StateFilter,
+        Action::JumpToPrefix,
+        Action::Versions,
+        Action::Restore,
+        Action::Upload,
+        Action::Download,
+        Action::Delete,
+        Action::Create,
+        Action::Rename,
+        Action::Columns,
+        Action::PickColumns,
+        Action::FullArns,
+        Action::CredentialReport,
+        Action::Dense,
+        Action::AllRegions,
+        Action::GroupByTag,
+        Action::FolderStats,
+        Action::JumpToLetter,
+        Action::WidenColumn,
+        Action::NarrowColumn,
+        Action::CopyCommand,
+        Action::YankAll,
+        Action::Snapshot,
+        Action::Diff,
+        Action::Dashboard,
+        Action::Breadcrumb,
+        Action::Palette,
+        Action::Next,
+        Action::Prev,
+        Action::First,
+        Action::Last,
+        Action::PageDown,
+        Action::PageUp,
+        Action::Select,
+    ];
+
+    // Name used in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::ToggleServices => "toggle_services",
+            Action::Details => "details",
+            Action::Watch => "watch",
+            Action::Connect => "connect",
+            Action::Refresh => "refresh",
+            Action::ReassumeRole => "reassume_role",
+            Action::Stop => "stop",
+            Action::Mark => "mark",
+            Action::EventLog => "event_log",
+            Action::ErrorDetails => "error_details",
+            Action::Search => "search",
+            Action::TagFilter => "tag_filter",
+            Action::StorageClassFilter => "storage_class_filter",
+            Action::StateFilter => "state_filter",
+            Action::JumpToPrefix => "jump_to_prefix",
+            Action::Versions => "versions",
+            Action::Restore => "restore",
+            Action::Upload => "upload",
+            Action::Download => "download",
+            Action::Delete => "delete",
+            Action::Create => "create",
+            Action::Rename => "rename",
+            Action::Columns => "columns",
+            Action::PickColumns => "pick_columns",
+            Action::FullArns => "full_arns",
+            Action::CredentialReport => "credential_report",
+            Action::Dense => "dense",
+            Action::AllRegions => "all_regions",
+            Action::GroupByTag => "group_by_tag",
+            Action::FolderStats => "folder_stats",
+            Action::JumpToLetter => "jump_to_letter",
+            Action::WidenColumn => "widen_column",
+            Action::NarrowColumn => "narrow_column",
+            Action::CopyCommand => "copy_command",
+            Action::YankAll => "yank_all",
+            Action::Snapshot => "snapshot",
+            Action::Diff => "diff",
+            Action::Dashboard => "dashboard",
+            Action::Breadcrumb => "breadcrumb",
+            Action::Palette => "palette",
+            Action::Next => "next",
+            Action::Prev => "prev",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::Select => "select",
+        }
+    }
+
+    // Name shown in the command palette
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::ToggleServices => "Switch service",
+            Action::Details => "Show details",
+            Action::Watch => "Watch selected resource",
+            Action::Connect => "Start SSM session",
+            Action::Refresh => "Refresh",
+            Action::ReassumeRole => "Re-assume role",
+            Action::Stop => "Stop",
+            Action::Mark => "Mark row",
+            Action::EventLog => "Event log",
+            Action::ErrorDetails => "Error details",
+            Action::Search => "Search",
+            Action::TagFilter => "Filter by tag",
+            Action::StorageClassFilter => "Filter by storage class",
+            Action::StateFilter => "Filter by alarm state",
+            Action::JumpToPrefix => "Jump to prefix",
+            Action::Versions => "Toggle object versions",
+            Action::Restore => "Restore from Glacier",
+            Action::Upload => "Upload file",
+            Action::Download => "Download object",
+            Action::Delete => "Delete",
+            Action::Create => "Create",
+            Action::Rename => "Rename",
+            Action::Columns => "Switch columns",
+            Action::PickColumns => "Show or hide columns",
+            Action::FullArns => "Toggle full ARNs",
+            Action::CredentialReport => "Toggle IAM credential report",
+            Action::Dense => "Toggle dense rows",
+            Action::AllRegions => "Toggle EC2 in all regions",
+            Action::GroupByTag => "Group buckets by tag",
+            Action::FolderStats => "Toggle folder sizes",
+            Action::JumpToLetter => "Jump to a name by its first letter",
+            Action::WidenColumn => "Widen name column",
+            Action::NarrowColumn => "Narrow name column",
+            Action::CopyCommand => "Copy CLI command",
+            Action::YankAll => "Copy all rows",
+            Action::Snapshot => "Snapshot list",
+            Action::Diff => "Toggle diff against snapshot",
+            Action::Dashboard => "Dashboard",
+            Action::Breadcrumb => "Go up one level",
+            Action::Palette => "Command palette",
+            Action::Next => "Next row",
+            Action::Prev => "Previous row",
+            Action::First => "First row",
+            Action::Last => "Last row",
+            Action::PageDown => "Page down",
+            Action::PageUp => "Page up",
+            Action::Select => "Open",
+        }
+    }
+
+    /// Whether the command palette lists this action. Movement stays on the keyboard.
+    pub fn in_palette(&self) -> bool {
+        !matches!(
+            self,
+            Action::Palette
+                | Action::Next
+                | Action::Prev
+                | Action::First
+                | Action::Last
+                | Action::PageDown
+                | Action::PageUp
+                | Action::Select
+        )
+    }
+
+    /// Whether a count typed before the key applies to this action, e.g. `5j`.
+    pub fn takes_count(&self) -> bool {
+        matches!(self, Action::Next | Action::Prev | Action::Breadcrumb)
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q", "Q"],
+            Action::ToggleServices => &["space"],
+            Action::Details => &["i", "I"],
+            Action::Watch => &["W"],
+            Action::Connect => &["a"],
+            Action::Refresh => &["r", "R"],
+            Action::ReassumeRole => &["A"],
+            Action::Stop => &["s"],
+            Action::Mark => &["x"],
+            Action::EventLog => &["L"],
+            Action::ErrorDetails => &["E"],
+            Action::Search => &["/"],
+            Action::TagFilter => &["t"],
+            Action::StorageClassFilter => &["c"],
+            Action::StateFilter => &["f"],
+            Action::JumpToPrefix => &["o"],
+            Action::Versions => &["V"],
+            Action::Restore => &["u"],
+            Action::Upload => &["U"],
+            Action::Download => &["w"],
+            Action::Delete => &["D"],
+            Action::Create => &["n"],
+            Action::Rename => &["e"],
+            Action::Columns => &["v"],
+            Action::PickColumns => &["C"],
+            Action::FullArns => &["N"],
+            Action::CredentialReport => &["K"],
+            Action::Dense => &["z"],
+            Action::AllRegions => &["M"],
+            Action::GroupByTag => &["T"],
+            Action::FolderStats => &["F"],
+            Action::JumpToLetter => &["'"],
+            Action::WidenColumn => &[">"],
+            Action::NarrowColumn => &["<"],
+            Action::CopyCommand => &["y"],
+            Action::YankAll => &["Y"],
+            Action::Snapshot => &["S"],
+            Action::Diff => &["="],
+            Action::Dashboard => &["d"],
+            Action::Breadcrumb => &["b"],
+            Action::Palette => &[":", "ctrl+p"],
+            Action::Next => &["down", "j"],
+            Action::Prev => &["up", "k"],
+            Action::First => &["home", "g"],
+            Action::Last => &["end", "G"],
+            Action::PageDown => &["pagedown"],
+            Action::PageUp => &["pageup"],
+            Action::Select => &["enter"],
+        }
+    }
+}
+
+/// A key plus the Ctrl/Alt modifiers it needs. Shift is carried by the character itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Parses "j", "G", "ctrl+c", "alt+x", "enter", "pagedown", "space", "f5" and so on.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if lower.starts_with("ctrl+") && rest.len() > 5 {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[5..];
+            } else if lower.starts_with("alt+") && rest.len() > 4 {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        Some(Self { code, modifiers })
+    }
+
+    /// The key as it would be written in the config file, e.g. "ctrl+p" or "pagedown".
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("f{}", n),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::Backspace => "backspace".to_string(),
+            KeyCode::Up => "up".to_string(),
+            KeyCode::Down => "down".to_string(),
+            KeyCode::Left => "left".to_string(),
+            KeyCode::Right => "right".to_string(),
+            KeyCode::Home => "home".to_string(),
+            KeyCode::End => "end".to_string(),
+            KeyCode::PageUp => "pageup".to_string(),
+            KeyCode::PageDown => "pagedown".to_string(),
+            KeyCode::Delete => "delete".to_string(),
+            KeyCode::Insert => "insert".to_string(),
+            other => format!("{:?}", other).to_lowercase(),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("ctrl+");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("alt+");
+        }
+        label + &key
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        let relevant = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        self.code == key.code && self.modifiers == key.modifiers & relevant
+    }
+}
+
+/// Resolves key presses to actions. Built from the defaults with the `[keys]` overrides on top.
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::from_config(&BTreeMap::new()).0
+    }
+}
+
+impl KeyMap {
+    /// Builds the map from `action = ["key", ...]` overrides. An override replaces the action's
+    /// default keys. Unknown actions, unparsable keys and keys claimed by two actions are
+    /// reported as warnings; a conflicting key stays with the action that claimed it first.
+    pub fn from_config(overrides: &BTreeMap<String, Vec<String>>) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        for name in overrides.keys() {
+            if !Action::ALL.iter().any(|a| a.name() == name) {
+                warnings.push(format!("Unknown action '{}' in [keys]", name));
+            }
+        }
+
+        // Overridden actions claim their keys first so a remap can take a default key
+        let mut ordered: Vec<(Action, Vec<String>, bool)> = Action::ALL
+            .iter()
+            .map(|action| match overrides.get(action.name()) {
+                Some(keys) => (*action, keys.clone(), true),
+                None => (
+                    *action,
+                    action
+                        .default_keys()
+                        .iter()
+                        .map(|k| k.to_string())
+                        .collect(),
+                    false,
+                ),
+            })
+            .collect();
+        ordered.sort_by_key(|(_, _, overridden)| !overridden);
+
+        let mut bindings: Vec<(KeyBinding, Action)> = Vec::new();
+        for (action, keys, overridden) in ordered {
+            for key in keys {
+                let Some(binding) = KeyBinding::parse(&key) else {
+                    warnings.push(format!("Invalid key '{}' for {}", key, action.name()));
+                    continue;
+                };
+                match bindings.iter().find(|(b, _)| *b == binding) {
+                    None => bindings.push((binding, action)),
+                    // A default losing its key to a remapped action is expected
+                    Some(_) if !overridden => {}
+                    Some((_, owner)) => warnings.push(format!(
+                        "Key '{}' for {} is already bound to {}",
+                        key,
+                        action.name(),
+                        owner.name()
+                    )),
+                }
+            }
+        }
+        (Self { bindings }, warnings)
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(binding, _)| binding.matches(key))
+            .map(|&(_, action)| action)
+    }
+
+    /// Keys bound to `action`, in the order they were configured.
+    pub fn keys_for(&self, action: Action) -> Vec<KeyBinding> {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|&(binding, _)| binding)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_key_binding() {
+        let parse = |text| KeyBinding::parse(text).map(|b| (b.code, b.modifiers));
+        assert_eq!(parse("j"), Some((KeyCode::Char('j'), KeyModifiers::NONE)));
+        assert_eq!(parse("G"), Some((KeyCode::Char('G'), KeyModifiers::NONE)));
+        assert_eq!(
+            parse("Ctrl+c"),
+            Some((KeyCode::Char('c'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            parse("PageDown"),
+            Some((KeyCode::PageDown, KeyModifiers::NONE))
+        );
+        assert_eq!(parse("f5"), Some((KeyCode::F(5), KeyModifiers::NONE)));
+        assert_eq!(parse("ctrl+"), None);
+        assert_eq!(parse("banana"), None);
+
+        for text in ["j", "ctrl+p", "alt+x", "space", "pagedown", "f5", ":"] {
+            assert_eq!(KeyBinding::parse(text).unwrap().label(), text);
+        }
+    }
+
+    #[test]
+    fn test_default_key_map() {
+        let (keymap, warnings) = KeyMap::from_config(&BTreeMap::new());
+        assert!(warnings.is_empty());
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('j'), KeyModifiers::NONE)),
+            Some(Action::Next)
+        );
+        // Uppercase letters arrive with Shift set
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('G'), KeyModifiers::SHIFT)),
+            Some(Action::Last)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('p'), KeyModifiers::CONTROL)),
+            Some(Action::Palette)
+        );
+        let labels: Vec<String> = keymap
+            .keys_for(Action::Palette)
+            .iter()
+            .map(KeyBinding::label)
+            .collect();
+        assert_eq!(labels, vec![":", "ctrl+p"]);
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('q'), KeyModifiers::CONTROL)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_key_map_overrides() {
+        let overrides = BTreeMap::from([
+            ("quit".to_string(), vec!["ctrl+c".to_string()]),
+            // Colemak: n/e instead of j/k; n is create by default
+            ("next".to_string(), vec!["n".to_string()]),
+            ("prev".to_string(), vec!["e".to_string(), "n".to_string()]),
+            ("jump".to_string(), vec!["x".to_string()]),
+        ]);
+        let (keymap, warnings) = KeyMap::from_config(&overrides);
+
+        let action = |c| keymap.action(&press(KeyCode::Char(c), KeyModifiers::NONE));
+        assert_eq!(action('n'), Some(Action::Next));
+        assert_eq!(action('e'), Some(Action::Prev));
+        assert_eq!(action('j'), None);
+        assert_eq!(action('q'), None);
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+
+        assert_eq!(
+            warnings,
+            vec![
+                "Unknown action 'jump' in [keys]".to_string(),
+                "Key 'n' for prev is already bound to next".to_string(),
+            ]
+        );
+    }
+}
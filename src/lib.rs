@@ -1,85 +1,506 @@
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::execute;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
 use ratatui::Terminal;
 
 pub mod app;
 pub mod aws;
+pub mod cli;
+pub mod clipboard;
+pub mod config;
+pub mod keymap;
 pub mod ui;
 
 use app::App;
+use keymap::Action;
+
+// Characters a column grows or shrinks by per > or <
+const COLUMN_STEP: isize = 4;
 
 // Re-export run_app so it can be used by main.rs
 pub async fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
 ) -> Result<()> {
+    // Redraw only when something on screen can have changed: an event (resizes included), a
+    // spinner frame, the in-flight count, or the "updated ..." label ticking over
+    let mut dirty = true;
+    let mut drawn = None;
     loop {
-        terminal.draw(|f| ui::draw(f, app))?;
+        if app.is_busy() {
+            app.tick_animation();
+        }
+        let shown = (
+            app.animation_frame,
+            app.in_flight.count(),
+            app.refreshed_label(),
+            app.watch_frame(),
+            app.credentials_countdown(std::time::SystemTime::now()),
+        );
+        if dirty || drawn.as_ref() != Some(&shown) {
+            terminal.draw(|f| ui::draw(f, app))?;
+            dirty = false;
+            drawn = Some(shown);
+        }
 
-        if event::poll(std::time::Duration::from_millis(100))?
-            && let Event::Key(key) = event::read()?
-        {
-            // Handle popup-specific controls first
-            if app.show_quit_confirm {
-                match key.code {
-                    KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(()),
-                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                        app.hide_quit_confirmation();
+        if event::poll(std::time::Duration::from_millis(100))? {
+            dirty = true;
+            match event::read()? {
+                Event::Key(key) => {
+                    // Handle popup-specific controls first
+                    if app.show_quit_confirm {
+                        match key.code {
+                            KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(()),
+                            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                                app.hide_quit_confirmation();
+                            }
+                            _ => {}
+                        }
+                    } else if let Some(prompt) = &app.prompt {
+                        let has_input = prompt.input.is_some();
+                        match key.code {
+                            KeyCode::Enter => app.confirm_prompt().await?,
+                            KeyCode::Esc => app.cancel_prompt(),
+                            KeyCode::Char('y') | KeyCode::Char('Y') if !has_input => {
+                                app.confirm_prompt().await?;
+                            }
+                            KeyCode::Char('n') | KeyCode::Char('N') if !has_input => {
+                                app.cancel_prompt();
+                            }
+                            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.paste_clipboard();
+                            }
+                            KeyCode::Char(c) => app.prompt_input(c),
+                            KeyCode::Backspace => app.prompt_backspace(),
+                            KeyCode::Down => app.prompt_scroll_down(),
+                            KeyCode::Up => app.prompt_scroll_up(),
+                            _ => {}
+                        }
+                    } else if app.show_error_popup {
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Char('E') | KeyCode::Char('q') => {
+                                app.toggle_error_details();
+                            }
+                            KeyCode::Down | KeyCode::Char('j') => app.error_scroll_down(),
+                            KeyCode::Up | KeyCode::Char('k') => app.error_scroll_up(),
+                            _ => {}
+                        }
+                    } else if app.show_event_log {
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Char('L') | KeyCode::Char('q') => {
+                                app.toggle_event_log();
+                            }
+                            KeyCode::Down | KeyCode::Char('j') => app.event_log_scroll_down(),
+                            KeyCode::Up | KeyCode::Char('k') => app.event_log_scroll_up(),
+                            _ => {}
+                        }
+                    } else if app.show_detail_popup {
+                        match key.code {
+                            KeyCode::Esc if app.cancel_detail_task() => {}
+                            // q backs out one level like Esc; quitting needs the top-level view
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q')
+                                if app.detail_back() => {}
+                            KeyCode::Esc
+                            | KeyCode::Char('i')
+                            | KeyCode::Char('I')
+                            | KeyCode::Char('q')
+                            | KeyCode::Char('Q') => {
+                                app.close_detail_popup();
+                            }
+                            KeyCode::Enter => app.open_detail_index(),
+                            KeyCode::Down | KeyCode::Char('j') => app.detail_scroll_down(),
+                            KeyCode::Up | KeyCode::Char('k') => app.detail_scroll_up(),
+                            KeyCode::Char('p') => app.toggle_detail_json(),
+                            KeyCode::Char('e') => app.open_detail_in_editor(),
+                            KeyCode::Char('t') => app.cycle_metric_range(),
+                            KeyCode::Char('J') => app.toggle_detail_raw().await?,
+                            KeyCode::Char('S') => app.prompt_s3_bucket_summary(),
+                            KeyCode::Char('V') => app.open_lambda_versions().await?,
+                            KeyCode::Char('l') => app.tail_lambda_logs(),
+                            _ => {}
+                        }
+                    } else if app.show_column_picker {
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') => {
+                                app.toggle_column_picker();
+                            }
+                            KeyCode::Down | KeyCode::Char('j') => app.column_picker_next(),
+                            KeyCode::Up | KeyCode::Char('k') => app.column_picker_previous(),
+                            KeyCode::Enter | KeyCode::Char(' ') => app.toggle_picked_column(),
+                            _ => {}
+                        }
+                    } else if app.show_palette {
+                        match key.code {
+                            KeyCode::Esc => app.toggle_palette(),
+                            KeyCode::Down => app.palette_next(),
+                            KeyCode::Up => app.palette_previous(),
+                            KeyCode::Enter => {
+                                if let Some(action) = app.take_palette_action() {
+                                    run_action(app, action).await?;
+                                }
+                            }
+                            KeyCode::Backspace => app.palette_backspace(),
+                            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.toggle_palette();
+                            }
+                            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.paste_clipboard();
+                            }
+                            KeyCode::Char(c) => app.palette_input(c),
+                            _ => {}
+                        }
+                    } else if app.show_service_popup {
+                        // Letters go to the filter once one is typed; until then j/k/f/q keep
+                        // their shortcuts
+                        let filtering = !app.service_filter.is_empty();
+                        match key.code {
+                            KeyCode::Esc if filtering => app.clear_service_filter(),
+                            KeyCode::Esc | KeyCode::Char(' ') => app.toggle_service_popup(),
+                            KeyCode::Down => app.popup_next(),
+                            KeyCode::Up => app.popup_previous(),
+                            KeyCode::Enter => app.select_popup_service(),
+                            KeyCode::Backspace => app.service_filter_backspace(),
+                            KeyCode::Char('q') | KeyCode::Char('Q') if !filtering => {
+                                app.toggle_service_popup();
+                            }
+                            KeyCode::Char('j') if !filtering => app.popup_next(),
+                            KeyCode::Char('k') if !filtering => app.popup_previous(),
+                            KeyCode::Char('f') | KeyCode::Char('F') if !filtering => {
+                                app.toggle_favorite();
+                            }
+                            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.paste_clipboard();
+                            }
+                            KeyCode::Char(c) => app.service_filter_input(c),
+                            _ => {}
+                        }
+                    } else if app.show_dashboard {
+                        match key.code {
+                            KeyCode::Esc if app.cancel_dashboard() => {}
+                            KeyCode::Esc | KeyCode::Char('d') | KeyCode::Char('q') => {
+                                app.close_dashboard();
+                            }
+                            KeyCode::Down | KeyCode::Char('j') => app.dashboard_next(),
+                            KeyCode::Up | KeyCode::Char('k') => app.dashboard_previous(),
+                            KeyCode::Char('r') | KeyCode::Char('R') => {
+                                app.refresh_dashboard();
+                            }
+                            KeyCode::Enter if app.open_dashboard_row() => {
+                                app.refresh_resources().await?;
+                            }
+                            _ => {}
+                        }
+                    } else if app.search_editing {
+                        match key.code {
+                            KeyCode::Enter => app.finish_search(),
+                            KeyCode::Esc => app.clear_search(),
+                            KeyCode::Down => app.next_item(),
+                            KeyCode::Up => app.previous_item(),
+                            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.paste_clipboard();
+                            }
+                            KeyCode::Char(c) => app.search_input(c),
+                            KeyCode::Backspace => app.search_backspace(),
+                            _ => {}
+                        }
+                    } else if app.letter_jump_pending {
+                        // Any other key gives up on the jump
+                        match key.code {
+                            KeyCode::Char(c) if c.is_alphanumeric() => app.jump_to_letter(c),
+                            _ => app.cancel_letter_jump(),
+                        }
+                    } else {
+                        // Handle main view controls; most keys come from the configurable map
+                        match key.code {
+                            KeyCode::Esc if app.cancel_prefix_task() => {}
+                            KeyCode::Esc if app.is_refreshing() => app.cancel_refresh(),
+                            KeyCode::Esc if !app.marked_rows.is_empty() => app.clear_marks(),
+                            KeyCode::Esc if !app.search_query.is_empty() => app.clear_search(),
+                            KeyCode::Esc if app.ec2_tag_filter.is_some() => {
+                                app.clear_ec2_tag_filter().await?;
+                            }
+                            _ => match app.keymap.action(&key) {
+                                Some(action) => {
+                                    if !action.takes_count() {
+                                        app.clear_count();
+                                    }
+                                    run_action(app, action).await?;
+                                }
+                                // Digits build a count for the next movement, e.g. 5j or 2b
+                                None => match key.code {
+                                    KeyCode::Char(c) if c.is_ascii_digit() => {
+                                        app.push_count_digit(c.to_digit(10).unwrap_or(0));
+                                    }
+                                    _ => app.clear_count(),
+                                },
+                            },
+                        }
                     }
-                    _ => {}
                 }
-            } else if app.show_detail_popup {
-                match key.code {
-                    KeyCode::Esc | KeyCode::Char('i') | KeyCode::Char('I') => {
-                        app.close_detail_popup();
-                    }
-                    KeyCode::Down | KeyCode::Char('j') => app.detail_scroll_down(),
-                    KeyCode::Up | KeyCode::Char('k') => app.detail_scroll_up(),
-                    KeyCode::Char('q') | KeyCode::Char('Q') => {
-                        app.show_quit_confirmation();
-                    }
-                    _ => {}
+                Event::Mouse(mouse) => handle_mouse_event(app, mouse).await?,
+                Event::Resize(..) => {
+                    // The columns fit the pane, whose new size is only known once drawn
+                    terminal.draw(|f| ui::draw(f, app))?;
+                    app.relayout_listing();
                 }
+                Event::Paste(text) => app.paste(&text),
+                _ => {}
+            }
+        }
+
+        if app.should_quit {
+            return Ok(());
+        }
+        app.check_credentials_expiry(std::time::SystemTime::now());
+
+        if let Some(profile) = app.sso_login_requested.take() {
+            let args = ["sso", "login", "--profile", &profile].map(String::from);
+            let result = run_in_terminal(terminal, "aws", &args, &[]).await?;
+            app.finish_sso_login(result).await?;
+            dirty = true;
+        }
+        if let Some(instance_id) = app.ssm_session_requested.take() {
+            if let Some((args, env)) = app.ssm_session_command(&instance_id).await {
+                let result = run_in_terminal(terminal, "aws", &args, &env).await?;
+                app.finish_ssm_session(&instance_id, result);
+            }
+            dirty = true;
+        }
+        if let Some(path) = app.editor_requested.take() {
+            let (program, mut args) = editor_command(|name| std::env::var(name).ok());
+            args.push(path.display().to_string());
+            let result = run_in_terminal(terminal, &program, &args, &[]).await;
+            drop(path); // Deletes the temp file
+            app.finish_editor(result?);
+            dirty = true;
+        }
+
+        // Pick up progress and results from a listing running in the background. Dirty covers
+        // the final result, which lands after is_refreshing has gone false
+        if app.is_refreshing() {
+            app.poll_refresh().await?;
+            dirty = true;
+        }
+        if app.is_watching() {
+            app.poll_watch().await?;
+        }
+        if app.is_loading_detail() {
+            app.poll_detail_task().await;
+            dirty = true;
+        }
+        if app.is_loading_dashboard() {
+            app.poll_dashboard().await;
+            dirty = true;
+        }
+        if app.is_running_prefix_task() {
+            app.poll_prefix_task().await?;
+            dirty = true;
+        }
+    }
+}
+
+// The program and leading arguments from $EDITOR, then $PAGER, e.g. "code --wait"
+fn editor_command(var: impl Fn(&str) -> Option<String>) -> (String, Vec<String>) {
+    let command = ["EDITOR", "PAGER"]
+        .into_iter()
+        .filter_map(var)
+        .find(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| "less".to_string());
+    let mut words = command.split_whitespace().map(String::from);
+    let program = words.next().unwrap_or_default();
+    (program, words.collect())
+}
+
+// Hands the terminal to a command that needs it, like `aws sso login` printing a device code,
+// an interactive `aws ssm start-session` or an editor, then takes it back. A failure waits for
+// Enter so its output can be read before the TUI redraws over it. `env` is added to the
+// command's environment. The outer Result is for terminal errors, the inner one is the command's
+async fn run_in_terminal<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    program: &str,
+    args: &[String],
+    env: &[(String, String)],
+) -> Result<Result<()>> {
+    let mut stdout = std::io::stdout();
+    disable_raw_mode()?;
+    execute!(
+        stdout,
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+    let command = format!("{} {}", program, args.join(" "));
+    println!("Running {}", command);
+
+    let status = tokio::process::Command::new(program)
+        .args(args)
+        .envs(env.iter().map(|(name, value)| (name, value)))
+        .status()
+        .await;
+    if !matches!(&status, Ok(status) if status.success()) {
+        println!("Press Enter to return to awsome");
+        std::io::stdin().read_line(&mut String::new())?;
+    }
+
+    enable_raw_mode()?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    terminal.clear()?;
+
+    Ok(match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(anyhow::anyhow!("{} exited with {}", command, status)),
+        Err(e) => Err(anyhow::anyhow!("could not run {}: {}", program, e)),
+    })
+}
+
+async fn run_action(app: &mut App, action: Action) -> Result<()> {
+    if !app.check_action(action) {
+        return Ok(());
+    }
+    match action {
+        Action::Quit => app.show_quit_confirmation(),
+        Action::ToggleServices => app.toggle_service_popup(),
+        Action::Details => app.show_resource_details().await?,
+        Action::Watch => app.start_watch(),
+        Action::Connect => app.start_ssm_session(),
+        Action::Refresh => app.refresh_or_reconnect().await?,
+        Action::ReassumeRole => app.reassume_role().await?,
+        Action::Stop => app.stop_selected_resource(),
+        Action::Mark => app.toggle_mark(),
+        Action::EventLog => app.toggle_event_log(),
+        Action::ErrorDetails => app.toggle_error_details(),
+        Action::Search => app.start_search(),
+        Action::TagFilter => app.prompt_ec2_tag_filter(),
+        Action::StorageClassFilter => app.cycle_s3_storage_class_filter().await?,
+        Action::StateFilter => app.cycle_alarm_state_filter(),
+        Action::JumpToPrefix => app.prompt_s3_prefix_jump(),
+        Action::Versions => app.toggle_s3_versions().await?,
+        Action::Restore => app.restore_selected_s3_object().await?,
+        Action::Upload => app.prompt_s3_upload(),
+        Action::Download => app.prompt_s3_download(),
+        Action::Delete => app.delete_selected_resource().await?,
+        Action::Create => app.prompt_create_resource(),
+        Action::Rename => app.prompt_rename_ec2_instance(),
+        Action::Columns => app.toggle_ec2_columns(),
+        Action::PickColumns => app.toggle_column_picker(),
+        Action::FullArns => app.toggle_full_arns(),
+        Action::CredentialReport => app.toggle_credential_report().await?,
+        Action::Dense => app.toggle_dense(),
+        Action::AllRegions => app.toggle_ec2_all_regions().await?,
+        Action::GroupByTag => app.prompt_s3_group_by_tag(),
+        Action::FolderStats => app.toggle_s3_folder_stats().await?,
+        Action::JumpToLetter => app.start_letter_jump(),
+        Action::WidenColumn => app.nudge_column_width(COLUMN_STEP),
+        Action::NarrowColumn => app.nudge_column_width(-COLUMN_STEP),
+        Action::CopyCommand => app.copy_cli_command(),
+        Action::YankAll => app.copy_visible_ids(),
+        Action::Snapshot => app.take_snapshot(),
+        Action::Diff => app.toggle_diff(),
+        Action::Dashboard => app.toggle_dashboard(),
+        Action::Breadcrumb => app.jump_to_counted_breadcrumb().await?,
+        Action::Palette => app.toggle_palette(),
+        Action::Next => app.next_item(),
+        Action::Prev => app.previous_item(),
+        Action::First => app.first_item(),
+        Action::Last => app.last_item(),
+        Action::PageDown => app.page_down(),
+        Action::PageUp => app.page_up(),
+        Action::Select => app.select_item().await?,
+    }
+    Ok(())
+}
+
+async fn handle_mouse_event(app: &mut App, mouse: MouseEvent) -> Result<()> {
+    if app.show_quit_confirm || app.prompt.is_some() {
+        return Ok(());
+    }
+
+    if app.show_palette {
+        match mouse.kind {
+            MouseEventKind::ScrollDown => app.palette_next(),
+            MouseEventKind::ScrollUp => app.palette_previous(),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if app.show_column_picker {
+        match mouse.kind {
+            MouseEventKind::ScrollDown => app.column_picker_next(),
+            MouseEventKind::ScrollUp => app.column_picker_previous(),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if app.show_error_popup {
+        match mouse.kind {
+            MouseEventKind::ScrollDown => app.error_scroll_down(),
+            MouseEventKind::ScrollUp => app.error_scroll_up(),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if app.show_event_log {
+        match mouse.kind {
+            MouseEventKind::ScrollDown => app.event_log_scroll_down(),
+            MouseEventKind::ScrollUp => app.event_log_scroll_up(),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if app.show_dashboard && !app.show_service_popup {
+        match mouse.kind {
+            MouseEventKind::ScrollDown => app.dashboard_next(),
+            MouseEventKind::ScrollUp => app.dashboard_previous(),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    match mouse.kind {
+        MouseEventKind::ScrollDown => {
+            if app.show_detail_popup {
+                app.detail_scroll_down();
             } else if app.show_service_popup {
-                match key.code {
-                    KeyCode::Esc | KeyCode::Char(' ') => {
-                        app.toggle_service_popup();
-                    }
-                    KeyCode::Down | KeyCode::Char('j') => app.popup_next(),
-                    KeyCode::Up | KeyCode::Char('k') => app.popup_previous(),
-                    KeyCode::Enter => app.select_popup_service(),
-                    KeyCode::Char('f') | KeyCode::Char('F') => {
-                        app.toggle_favorite();
-                    }
-                    KeyCode::Char('q') | KeyCode::Char('Q') => {
-                        app.show_quit_confirmation();
-                    }
-                    _ => {}
-                }
+                app.popup_next();
             } else {
-                // Handle main view controls
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') => {
-                        app.show_quit_confirmation();
-                    }
-                    KeyCode::Char(' ') => app.toggle_service_popup(),
-                    KeyCode::Char('i') | KeyCode::Char('I') => {
-                        app.show_resource_details().await?;
-                    }
-                    KeyCode::Char('r') | KeyCode::Char('R') => {
-                        app.refresh_resources().await?;
-                    }
-                    KeyCode::Down | KeyCode::Char('j') => app.next_item(),
-                    KeyCode::Up | KeyCode::Char('k') => app.previous_item(),
-                    KeyCode::Enter => app.select_item().await?,
-                    _ => {}
-                }
+                app.next_item();
             }
         }
-
-        // Update animation frame if loading
-        if app.is_loading() {
-            app.tick_animation();
+        MouseEventKind::ScrollUp => {
+            if app.show_detail_popup {
+                app.detail_scroll_up();
+            } else if app.show_service_popup {
+                app.popup_previous();
+            } else {
+                app.previous_item();
+            }
+        }
+        MouseEventKind::Down(MouseButton::Left)
+            if !app.show_detail_popup && !app.show_service_popup =>
+        {
+            if let Some(level) = app.breadcrumb_at(mouse.column, mouse.row) {
+                app.jump_to_breadcrumb(level).await?;
+                return Ok(());
+            }
+            app.click_header(mouse.column, mouse.row);
+            app.click_list_row(mouse.column, mouse.row);
         }
+        _ => {}
     }
+    Ok(())
 }
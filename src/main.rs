@@ -1,8 +1,11 @@
 use anyhow::Result;
 use awsome::app::App;
+use awsome::cli::{self, Cli};
+use awsome::config::Config;
 use awsome::run_app;
+use clap::Parser;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
@@ -11,18 +14,39 @@ use std::io;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = Cli::parse();
+
+    // Load settings before touching the terminal so parse errors are readable
+    let config = Config::load()?;
+    let options = config.apply_endpoints(args.client_options());
+
+    // Non-interactive mode: print the listing and exit without touching the terminal
+    if let Some(service) = args.service {
+        return cli::run(service, &options, args.output).await;
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = App::new();
+    let mut app = App::with_options(options, config);
+    // The flag only applies to this run, so it isn't written back to the config file
+    app.read_only |= args.read_only;
+    app.dry_run |= args.dry_run;
 
-    // Initialize AWS client
-    let _ = app.initialize_aws_client().await;
+    // Initialize AWS client, and start loading the default service instead of waiting for r
+    if app.initialize_aws_client().await.is_ok() && app.config.default_service.is_some() {
+        let _ = app.refresh_resources().await;
+    }
 
     // Run the app
     let res = run_app(&mut terminal, &mut app).await;
@@ -32,7 +56,8 @@ async fn main() -> Result<()> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -40,5 +65,9 @@ async fn main() -> Result<()> {
         println!("Error: {:?}", err);
     }
 
+    if let Err(err) = app.save_session() {
+        eprintln!("Warning: {:?}", err);
+    }
+
     Ok(())
 }
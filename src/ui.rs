@@ -3,12 +3,27 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Sparkline, Wrap},
 };
 
-use crate::app::{App, LoadingState};
+use crate::app::{
+    App, DRY_RUN_PREFIX, LoadingState, Prompt, RowChange, ServiceType, account_label,
+};
+use crate::aws::utils::extract_request_id;
+use crate::aws::{CloudwatchItem, DynamoDbItem, Ec2Item, EcsItem, Finding, IamItem, MetricSeries};
+use std::time::SystemTime;
+
+// Below this size the tables and popups can't be laid out legibly
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 15;
+
+pub fn draw(f: &mut Frame, app: &mut App) {
+    let area = f.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        draw_too_small(f, area);
+        return;
+    }
 
-pub fn draw(f: &mut Frame, app: &App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -19,7 +34,11 @@ pub fn draw(f: &mut Frame, app: &App) {
         .split(f.area());
 
     draw_header(f, chunks[0], app);
-    draw_main_content(f, chunks[1], app);
+    if app.show_dashboard {
+        draw_dashboard(f, chunks[1], app);
+    } else {
+        draw_main_content(f, chunks[1], app);
+    }
     draw_footer(f, chunks[2], app);
 
     // Draw popups on top if active
@@ -31,12 +50,54 @@ pub fn draw(f: &mut Frame, app: &App) {
         draw_detail_popup(f, app);
     }
 
+    if app.show_palette {
+        draw_palette(f, app);
+    }
+
+    if app.show_column_picker {
+        draw_column_picker(f, app);
+    }
+
+    if app.show_event_log {
+        draw_event_log(f, app);
+    }
+
+    if app.show_error_popup {
+        draw_error_popup(f, app);
+    }
+
+    if let Some(prompt) = &app.prompt {
+        draw_prompt(f, prompt, app.prompt_scroll);
+    }
+
     if app.show_quit_confirm {
         draw_quit_confirmation(f);
     }
 }
 
-fn draw_header(f: &mut Frame, area: Rect, app: &App) {
+fn draw_too_small(f: &mut Frame, area: Rect) {
+    let lines = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!("{}x{}", area.width, area.height)),
+        Line::from(format!("Need at least {}x{}", MIN_WIDTH, MIN_HEIGHT)),
+    ];
+    // Vertically center the message
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let message_area = Rect {
+        y: area.y + top,
+        height: area.height - top,
+        ..area
+    };
+    let message = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(message, message_area);
+}
+
+fn draw_header(f: &mut Frame, area: Rect, app: &mut App) {
     // Create inner area (without borders)
     let inner_area = Rect {
         x: area.x + 1,
@@ -45,16 +106,75 @@ fn draw_header(f: &mut Frame, area: Rect, app: &App) {
         height: area.height.saturating_sub(2),
     };
 
+    // Right side - profile, assumed role and region
+    let mut profile_spans = vec![
+        Span::styled("@ ", Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            app.env_credentials
+                .clone()
+                .unwrap_or_else(|| app.profile_name.clone()),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ];
+    if let Some(role) = app.client_options.assumed_role_name() {
+        profile_spans.push(Span::styled(" as ", Style::default().fg(Color::DarkGray)));
+        profile_spans.push(Span::styled(
+            role.to_string(),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if app.client_options.use_fips {
+        profile_spans.push(Span::styled(
+            " FIPS",
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(account) = &app.account {
+        // Production stands out so nobody runs a delete there by accident
+        let style = if app.is_prod_account() {
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Green)
+        };
+        profile_spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
+        profile_spans.push(Span::styled(account_label(account), style));
+    }
+    if let Some((countdown, urgent)) = app.credentials_countdown(SystemTime::now()) {
+        let style = if urgent {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        profile_spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
+        profile_spans.push(Span::styled(countdown, style));
+    }
+    profile_spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
+    profile_spans.push(Span::styled(
+        app.region
+            .clone()
+            .unwrap_or_else(|| "no region".to_string()),
+        Style::default().fg(Color::Cyan),
+    ));
+    let profile_width: usize = profile_spans.iter().map(|s| s.width()).sum();
+
     // Split into left and right sections
     let header_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
             Constraint::Min(0),
-            Constraint::Length((app.profile_name.len() + 3) as u16), // Profile text + padding
+            Constraint::Length((profile_width + 1) as u16), // Profile text + padding
         ])
         .split(inner_area);
 
     let favorites = app.get_favorite_services();
+    let mut service_areas = Vec::new();
 
     // Left side - services
     let mut left_spans = vec![
@@ -66,6 +186,20 @@ fn draw_header(f: &mut Frame, area: Rect, app: &App) {
         ),
         Span::raw("| "),
     ];
+    if app.read_only {
+        left_spans.push(Span::styled(
+            "[read-only mode] ",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.dry_run {
+        left_spans.push(Span::styled(
+            "[dry-run] ",
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
 
     if favorites.is_empty() {
         left_spans.push(Span::styled(
@@ -84,7 +218,19 @@ fn draw_header(f: &mut Frame, area: Rect, app: &App) {
                 Style::default().fg(Color::White)
             };
 
-            left_spans.push(Span::styled(format!(" {} ", service.short_name()), style));
+            // Remember where each service name lands so mouse clicks can switch to it
+            let offset: usize = left_spans.iter().map(|s| s.width()).sum();
+            let span = Span::styled(format!(" {} ", service.short_name()), style);
+            service_areas.push((
+                Rect {
+                    x: header_chunks[0].x + offset as u16,
+                    y: header_chunks[0].y,
+                    width: span.width() as u16,
+                    height: 1,
+                },
+                *service_idx,
+            ));
+            left_spans.push(span);
 
             if idx < favorites.len() - 1 {
                 left_spans.push(Span::raw("• "));
@@ -98,16 +244,7 @@ fn draw_header(f: &mut Frame, area: Rect, app: &App) {
         ));
     }
 
-    // Right side - profile
-    let profile_spans = vec![
-        Span::styled("@ ", Style::default().fg(Color::DarkGray)),
-        Span::styled(
-            &app.profile_name,
-            Style::default()
-                .fg(Color::Magenta)
-                .add_modifier(Modifier::BOLD),
-        ),
-    ];
+    app.header_service_areas = service_areas;
 
     // Render border
     let border = Block::default().borders(Borders::ALL);
@@ -122,7 +259,20 @@ fn draw_header(f: &mut Frame, area: Rect, app: &App) {
     f.render_widget(right_paragraph, header_chunks[1]);
 }
 
-fn draw_main_content(f: &mut Frame, area: Rect, app: &App) {
+fn draw_main_content(f: &mut Frame, area: Rect, app: &mut App) {
+    let crumbs = app.breadcrumbs();
+    let area = if crumbs.is_empty() {
+        app.breadcrumb_areas.clear();
+        area
+    } else {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(area);
+        draw_breadcrumbs(f, chunks[0], app, &crumbs);
+        chunks[1]
+    };
+
     // Determine color based on loading state
     let (title_color, border_style) = match app.loading_state {
         LoadingState::Loading => (Color::Yellow, Style::default().fg(Color::Yellow)),
@@ -131,84 +281,22 @@ fn draw_main_content(f: &mut Frame, area: Rect, app: &App) {
         LoadingState::Idle => (Color::White, Style::default()),
     };
 
-    let items: Vec<ListItem> = app
-        .items
+    let visible_rows = app.visible_rows();
+    let changes = app.row_changes();
+    let removed = app.removed_rows();
+    // A gutter for +/~/- appears only while the diff has something to show
+    let diff_gutter = !changes.is_empty() || !removed.is_empty();
+    let mut items: Vec<ListItem> = visible_rows
         .iter()
-        .enumerate()
-        .map(|(i, item)| {
-            // Check if it's a header/separator for S3 or IAM
-            let is_header_or_sep = match app.get_active_service().service_type {
-                crate::app::ServiceType::S3 => {
-                    if i < app.s3_items.len() {
-                        matches!(
-                            app.s3_items[i],
-                            crate::aws::S3Item::Header | crate::aws::S3Item::Separator
-                        )
-                    } else {
-                        false
-                    }
-                }
-                crate::app::ServiceType::IAM => {
-                    if i < app.iam_items.len() {
-                        matches!(
-                            app.iam_items[i],
-                            crate::aws::IamItem::Header | crate::aws::IamItem::Separator
-                        )
-                    } else {
-                        false
-                    }
-                }
-                crate::app::ServiceType::DynamoDB => {
-                    if i < app.dynamodb_items.len() {
-                        matches!(
-                            app.dynamodb_items[i],
-                            crate::aws::DynamoDbItem::Header | crate::aws::DynamoDbItem::Separator
-                        )
-                    } else {
-                        false
-                    }
-                }
-                crate::app::ServiceType::EC2 => {
-                    if i < app.ec2_items.len() {
-                        matches!(
-                            app.ec2_items[i],
-                            crate::aws::Ec2Item::Header | crate::aws::Ec2Item::Separator
-                        )
-                    } else {
-                        false
-                    }
-                }
-                crate::app::ServiceType::ECS => {
-                    if i < app.ecs_items.len() {
-                        matches!(
-                            app.ecs_items[i],
-                            crate::aws::EcsItem::Header | crate::aws::EcsItem::Separator
-                        )
-                    } else {
-                        false
-                    }
-                }
-                crate::app::ServiceType::MWAA => {
-                    if i < app.mwaa_items.len() {
-                        matches!(
-                            app.mwaa_items[i],
-                            crate::aws::MwaaItem::Header | crate::aws::MwaaItem::Separator
-                        )
-                    } else {
-                        false
-                    }
-                }
-                crate::app::ServiceType::Lambda => {
-                    if i < app.lambda_items.len() {
-                        matches!(
-                            app.lambda_items[i],
-                            crate::aws::LambdaItem::Header | crate::aws::LambdaItem::Separator
-                        )
-                    } else {
-                        false
-                    }
-                }
-                _ => false,
+        .map(|&i| {
+            let item = &app.items[i];
+            let is_header_or_sep = !app.is_item_row(i);
+
+            let row_color = match (app.loading_state, changes.get(&i)) {
+                (LoadingState::Error, _) => None,
+                (_, Some(RowChange::Added)) => Some(Color::Green),
+                (_, Some(RowChange::Changed)) => Some(Color::Yellow),
+                (_, None) => row_status_color(app, i),
             };
 
             let style = if is_header_or_sep {
@@ -222,18 +310,48 @@ fn draw_main_content(f: &mut Frame, area: Rect, app: &App) {
                     .fg(if app.loading_state == LoadingState::Error {
                         Color::Red
                     } else {
-                        Color::White
+                        row_color.unwrap_or(Color::White)
                     })
             } else {
                 Style::default().fg(if app.loading_state == LoadingState::Error {
                     Color::LightRed
                 } else {
-                    Color::White
+                    row_color.unwrap_or(Color::White)
                 })
             };
-            ListItem::new(item.as_str()).style(style)
+            let mut line = if is_header_or_sep {
+                Line::from(item.as_str())
+            } else {
+                highlight_matches(item, &app.search_query)
+            };
+            // A mark column appears only while something is marked, so columns stay put otherwise
+            if !app.marked_rows.is_empty() {
+                let mark = if app.marked_rows.contains(&i) {
+                    "✓ "
+                } else {
+                    "  "
+                };
+                line.spans.insert(0, Span::raw(mark));
+            }
+            if diff_gutter {
+                let marker = match changes.get(&i) {
+                    Some(RowChange::Added) => "+ ",
+                    Some(RowChange::Changed) => "~ ",
+                    None => "  ",
+                };
+                line.spans.insert(0, Span::raw(marker));
+            }
+            ListItem::new(line).style(style)
         })
         .collect();
+    // Rows gone since the snapshot trail the list; they can't be selected
+    items.extend(removed.iter().map(|row| {
+        ListItem::new(format!("- {}", row)).style(
+            Style::default()
+                .fg(Color::Red)
+                .add_modifier(Modifier::CROSSED_OUT),
+        )
+    }));
 
     let loading_indicator = match app.loading_state {
         LoadingState::Loading => " [LOADING...]",
@@ -242,17 +360,206 @@ fn draw_main_content(f: &mut Frame, area: Rect, app: &App) {
         LoadingState::Idle => "",
     };
 
-    let title = format!("{}{}", app.get_active_service().as_str(), loading_indicator);
+    let count_badge = match (app.matched_resource_count(), app.resource_count()) {
+        (Some(matched), Some(total)) => format!(" ({}/{})", matched, total),
+        (None, Some(total)) => format!(" ({})", total),
+        _ => String::new(),
+    };
+    let count_badge = match app.capped_at {
+        Some(max) => format!("{} (showing first {})", count_badge, max),
+        None => count_badge,
+    };
+
+    let refreshed = app
+        .refreshed_label()
+        .map(|label| format!(" · {}", label))
+        .unwrap_or_default();
+
+    let diff = if diff_gutter {
+        let count = |change| changes.values().filter(|&&c| c == change).count();
+        format!(
+            " · diff +{} ~{} -{}",
+            count(RowChange::Added),
+            count(RowChange::Changed),
+            removed.len()
+        )
+    } else {
+        String::new()
+    };
+
+    let alarm_states = app
+        .alarm_state_summary()
+        .map(|summary| format!(" · {}", summary))
+        .unwrap_or_default();
+
+    let title = format!(
+        "{}{}{}{}{}{}",
+        app.get_active_service().as_str(),
+        count_badge,
+        diff,
+        alarm_states,
+        loading_indicator,
+        refreshed
+    );
+
+    let title = Span::styled(
+        title,
+        Style::default()
+            .fg(title_color)
+            .add_modifier(Modifier::BOLD),
+    );
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style);
+    block = match app.column_header() {
+        // Dense rows: the header sits on the top border over its columns and the title moves
+        // right, cut short so the two never overlap
+        Some(header) => {
+            let gutter = match (diff_gutter, !app.marked_rows.is_empty()) {
+                (true, true) => "    ",
+                (true, false) | (false, true) => "  ",
+                (false, false) => "",
+            };
+            let room = (area.width as usize)
+                .saturating_sub(title.width() + 3)
+                .saturating_sub(gutter.len());
+            let header: String = header.chars().take(room).collect();
+            block
+                .title(Line::from(vec![
+                    Span::raw(gutter),
+                    Span::styled(
+                        header,
+                        Style::default()
+                            .fg(Color::Blue)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                ]))
+                .title(Line::from(title).right_aligned())
+        }
+        None => block.title(title),
+    };
+    if let Some((position, total)) = app.scroll_position() {
+        let indicator = match position {
+            Some(position) => format!(" {}/{} {}% ", position, total, position * 100 / total),
+            None => format!(" -/{} ", total),
+        };
+        block = block.title_bottom(
+            Line::from(Span::styled(
+                indicator,
+                Style::default().fg(Color::DarkGray),
+            ))
+            .right_aligned(),
+        );
+    }
+
+    let list = List::new(items).block(block).highlight_style(
+        Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut state = ListState::default()
+        .with_offset(app.list_offset)
+        .with_selected(visible_rows.iter().position(|&i| i == app.selected_index));
+    f.render_stateful_widget(list, area, &mut state);
+
+    // Remember the rendered geometry so mouse events can be mapped back to rows
+    app.list_area = area;
+    app.list_offset = state.offset();
+}
+
+// "1 Buckets > 2 bucket > 3 logs" above the list; `<n>b` jumps to crumb n
+fn draw_breadcrumbs(f: &mut Frame, area: Rect, app: &mut App, crumbs: &[String]) {
+    let mut spans = vec![Span::raw(" ")];
+    let mut areas = Vec::new();
+    for (level, crumb) in crumbs.iter().enumerate() {
+        if level > 0 {
+            spans.push(Span::styled(" > ", Style::default().fg(Color::DarkGray)));
+        }
+        let is_current = level + 1 == crumbs.len();
+        if level < 9 {
+            spans.push(Span::styled(
+                format!("{} ", level + 1),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        let style = if is_current {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::UNDERLINED)
+        };
+        let offset: usize = spans.iter().map(|s| s.width()).sum();
+        let span = Span::styled(crumb.as_str(), style);
+        areas.push((
+            Rect {
+                x: area.x + offset as u16,
+                y: area.y,
+                width: span.width() as u16,
+                height: 1,
+            },
+            level,
+        ));
+        spans.push(span);
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+
+    // Drop crumbs that were cut off at the right edge so clicks can't land on them
+    areas.retain(|(rect, _)| rect.x < area.x + area.width);
+    app.breadcrumb_areas = areas;
+}
+
+fn draw_dashboard(f: &mut Frame, area: Rect, app: &App) {
+    let name_width = app
+        .dashboard_rows
+        .iter()
+        .map(|(index, _)| app.services[*index].service_type.short_name().len())
+        .max()
+        .unwrap_or(0);
+
+    let items: Vec<ListItem> = if app.dashboard_rows.is_empty() {
+        vec![
+            ListItem::new("No favorite services - Press Space to pick some")
+                .style(Style::default().fg(Color::DarkGray)),
+        ]
+    } else {
+        app.dashboard_rows
+            .iter()
+            .map(|(index, summary)| {
+                let summary_color = if summary.starts_with("Error") {
+                    Color::LightRed
+                } else {
+                    Color::White
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "{:<width$}  ",
+                            app.services[*index].service_type.short_name(),
+                            width = name_width
+                        ),
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(summary.as_str(), Style::default().fg(summary_color)),
+                ]))
+            })
+            .collect()
+    };
 
     let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(border_style)
+                .border_style(Style::default().fg(Color::Cyan))
                 .title(Span::styled(
-                    title,
+                    "Dashboard",
                     Style::default()
-                        .fg(title_color)
+                        .fg(Color::Cyan)
                         .add_modifier(Modifier::BOLD),
                 )),
         )
@@ -262,21 +569,137 @@ fn draw_main_content(f: &mut Frame, area: Rect, app: &App) {
                 .add_modifier(Modifier::BOLD),
         );
 
-    f.render_widget(list, area);
+    let selected = (!app.dashboard_rows.is_empty()).then_some(app.dashboard_selected);
+    let mut state = ListState::default().with_selected(selected);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+// Splits a row into spans so case-insensitive matches of the search query stand out
+fn highlight_matches<'a>(text: &'a str, query: &str) -> Line<'a> {
+    if query.is_empty() {
+        return Line::from(text);
+    }
+
+    // ASCII lowercasing keeps byte offsets aligned with the original text
+    let haystack = text.to_ascii_lowercase();
+    let needle = query.to_ascii_lowercase();
+    let highlight = Style::default()
+        .fg(Color::Black)
+        .bg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for (start, matched) in haystack.match_indices(&needle) {
+        if start > last {
+            spans.push(Span::raw(&text[last..start]));
+        }
+        let end = start + matched.len();
+        spans.push(Span::styled(&text[start..end], highlight));
+        last = end;
+    }
+    if last < text.len() {
+        spans.push(Span::raw(&text[last..]));
+    }
+    Line::from(spans)
+}
+
+// Color for rows whose resource carries a status, e.g. EC2 instances or ECS tasks
+fn row_status_color(app: &App, index: usize) -> Option<Color> {
+    match app.get_active_service().service_type {
+        ServiceType::EC2 => match app.ec2_items.get(index)? {
+            Ec2Item::Instance { state, .. } => Some(ec2_state_color(state)),
+            _ => None,
+        },
+        ServiceType::ECS => match app.ecs_items.get(index)? {
+            EcsItem::Task { status, .. } => Some(ecs_status_color(status)),
+            _ => None,
+        },
+        ServiceType::CloudWatch => match app.cloudwatch_items.get(index)? {
+            CloudwatchItem::Alarm { state, .. } => Some(alarm_state_color(state)),
+            _ => None,
+        },
+        ServiceType::DynamoDB => match app.dynamodb_items.get(index)? {
+            DynamoDbItem::Table { status, .. } => Some(dynamodb_status_color(status)),
+            _ => None,
+        },
+        ServiceType::IAM => match app.iam_items.get(index)? {
+            IamItem::ReportEntry { findings, .. } => findings_color(findings),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn ec2_state_color(state: &str) -> Color {
+    match state.to_lowercase().as_str() {
+        "running" => Color::Green,
+        "stopped" => Color::Red,
+        "pending" | "stopping" | "shutting-down" | "shuttingdown" => Color::Yellow,
+        "terminated" => Color::Gray,
+        _ => Color::White,
+    }
+}
+
+fn ecs_status_color(status: &str) -> Color {
+    match status {
+        "RUNNING" => Color::Green,
+        "PROVISIONING" | "PENDING" | "ACTIVATING" => Color::Yellow,
+        "STOPPED" | "DELETED" => Color::Red,
+        "DEACTIVATING" | "STOPPING" | "DEPROVISIONING" => Color::Gray,
+        _ => Color::White,
+    }
+}
+
+fn dynamodb_status_color(status: &str) -> Color {
+    match status {
+        "ACTIVE" => Color::Green,
+        "CREATING" | "UPDATING" | "ARCHIVING" => Color::Yellow,
+        "DELETING" | "INACCESSIBLE_ENCRYPTION_CREDENTIALS" | "ARCHIVED" => Color::Red,
+        _ => Color::White,
+    }
+}
+
+// Red for old keys and sign-ins without MFA, yellow for a password that is merely on
+fn findings_color(findings: &[Finding]) -> Option<Color> {
+    if findings
+        .iter()
+        .any(|f| matches!(f, Finding::OldAccessKey | Finding::NoMfa))
+    {
+        Some(Color::Red)
+    } else if findings.is_empty() {
+        None
+    } else {
+        Some(Color::Yellow)
+    }
+}
+
+fn alarm_state_color(state: &str) -> Color {
+    match state {
+        "ALARM" => Color::Red,
+        "INSUFFICIENT_DATA" => Color::Yellow,
+        "OK" => Color::Green,
+        _ => Color::White,
+    }
 }
 
 fn draw_footer(f: &mut Frame, area: Rect, app: &App) {
-    let status_color = match app.loading_state {
-        LoadingState::Loading => Color::Yellow,
-        LoadingState::Error => Color::Red,
-        LoadingState::Loaded => Color::Green,
-        LoadingState::Idle => Color::Cyan,
+    // A reported dry run stands apart from the results of calls that were made
+    let status_color = if app.status_message.starts_with(DRY_RUN_PREFIX) {
+        Color::Magenta
+    } else {
+        match app.loading_state {
+            LoadingState::Loading => Color::Yellow,
+            LoadingState::Error => Color::Red,
+            LoadingState::Loaded => Color::Green,
+            LoadingState::Idle => Color::Cyan,
+        }
     };
 
-    // Build status line with spinner on the left if loading
+    // Build status line with spinner on the left while anything is in progress
     let mut status_spans = Vec::new();
 
-    if app.is_loading() {
+    if app.is_busy() {
         status_spans.push(Span::styled(
             format!("{} ", app.get_loading_spinner()),
             Style::default()
@@ -285,6 +708,133 @@ fn draw_footer(f: &mut Frame, area: Rect, app: &App) {
         ));
     }
 
+    let in_flight = app.in_flight.count();
+    if in_flight > 0 {
+        status_spans.push(Span::styled(
+            format!("⟳{} ", in_flight),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+
+    if app.auth_paused() {
+        status_spans.push(Span::styled(
+            " CREDENTIALS FAILING: re-authenticate ",
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        ));
+        status_spans.push(Span::raw(" "));
+    }
+
+    if let Some((key, value)) = &app.ec2_tag_filter
+        && app.get_active_service().service_type == ServiceType::EC2
+    {
+        status_spans.push(Span::styled(
+            format!("[tag:{}={}] ", key, value),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if app.ec2_all_regions && app.get_active_service().service_type == ServiceType::EC2 {
+        status_spans.push(Span::styled(
+            "[all regions] ",
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if let Some(class) = &app.s3_storage_class_filter
+        && app.get_active_service().service_type == ServiceType::S3
+        && app.current_path.is_some()
+    {
+        status_spans.push(Span::styled(
+            format!("[class:{}] ", class),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if let Some(key) = &app.s3_group_by
+        && app.get_active_service().service_type == ServiceType::S3
+        && app.current_path.is_none()
+    {
+        status_spans.push(Span::styled(
+            format!("[group:{}] ", key),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if app.s3_folder_stats
+        && !app.s3_show_versions
+        && app.get_active_service().service_type == ServiceType::S3
+        && app.current_path.is_some()
+    {
+        status_spans.push(Span::styled(
+            "[folder sizes] ",
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if app.s3_show_versions
+        && app.get_active_service().service_type == ServiceType::S3
+        && app.current_path.is_some()
+    {
+        status_spans.push(Span::styled(
+            "[versions] ",
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if let Some(state) = &app.alarm_state_filter
+        && app.get_active_service().service_type == ServiceType::CloudWatch
+    {
+        status_spans.push(Span::styled(
+            format!("[state:{}] ", state),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if app.search_editing || !app.search_query.is_empty() {
+        let cursor = if app.search_editing { "▏" } else { "" };
+        status_spans.push(Span::styled(
+            format!("/{}{}  ", app.search_query, cursor),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if app.letter_jump_pending {
+        status_spans.push(Span::styled(
+            "' ",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if let Some(count) = app.pending_count {
+        status_spans.push(Span::styled(
+            format!("{} ", count),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
     status_spans.push(Span::styled(
         app.status_message.as_str(),
         Style::default().fg(status_color),
@@ -297,15 +847,22 @@ fn draw_footer(f: &mut Frame, area: Rect, app: &App) {
 }
 
 fn draw_service_popup(f: &mut Frame, app: &App) {
-    // Calculate popup size and position (centered)
-    let area = centered_rect(60, 60, f.area());
+    let services = app.popup_services();
+    // Tall enough for every service plus borders and help, up to 80% of the screen
+    let height = (services.len().max(1) as u16 + 5).min(f.area().height * 4 / 5);
+    let area = centered_rect_with_height(60, height, f.area());
 
     // Clear the background
     f.render_widget(Clear, area);
 
     // Create the popup container
+    let title = if app.service_filter.is_empty() {
+        "Select Service".to_string()
+    } else {
+        format!("Select Service: {}▏", app.service_filter)
+    };
     let popup_block = Block::default()
-        .title("Select Service")
+        .title(title)
         .title_alignment(Alignment::Center)
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan));
@@ -327,11 +884,10 @@ fn draw_service_popup(f: &mut Frame, app: &App) {
         .split(inner_area);
 
     // Create service list items
-    let items: Vec<ListItem> = app
-        .services
+    let items: Vec<ListItem> = services
         .iter()
-        .enumerate()
-        .map(|(i, service)| {
+        .map(|&i| {
+            let service = &app.services[i];
             let is_selected = i == app.popup_selected_index;
             let favorite_marker = if service.favorite { "★ " } else { "  " };
 
@@ -352,8 +908,11 @@ fn draw_service_popup(f: &mut Frame, app: &App) {
         })
         .collect();
 
+    // The list scrolls to keep the selection in view when it doesn't fit
     let list = List::new(items);
-    f.render_widget(list, chunks[0]);
+    let selected = services.iter().position(|&i| i == app.popup_selected_index);
+    let mut state = ListState::default().with_selected(selected);
+    f.render_stateful_widget(list, chunks[0], &mut state);
 
     // Draw help text at bottom
     let help_text = vec![Line::from(vec![
@@ -363,7 +922,9 @@ fn draw_service_popup(f: &mut Frame, app: &App) {
         Span::raw(": Select  "),
         Span::styled("f", Style::default().fg(Color::Yellow)),
         Span::raw(": Toggle ★  "),
-        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::styled("type", Style::default().fg(Color::Yellow)),
+        Span::raw(": Filter  "),
+        Span::styled("Esc/q", Style::default().fg(Color::Yellow)),
         Span::raw(": Close"),
     ])];
 
@@ -374,6 +935,211 @@ fn draw_service_popup(f: &mut Frame, app: &App) {
     f.render_widget(help, chunks[1]);
 }
 
+fn draw_column_picker(f: &mut Frame, app: &App) {
+    let choices = app.column_choices();
+    let height = (choices.len() as u16 + 5).min(f.area().height * 4 / 5);
+    let area = centered_rect_with_height(40, height, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!("{} Columns", app.get_active_service().short_name()))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(2)])
+        .split(inner);
+
+    let items: Vec<ListItem> = choices
+        .iter()
+        .enumerate()
+        .map(|(i, (header, shown))| {
+            let style = if i == app.column_picker_selected {
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else if *shown {
+                Style::default().fg(Color::White)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            let check = if *shown { "[x]" } else { "[ ]" };
+            ListItem::new(format!("  {} {}", check, header)).style(style)
+        })
+        .collect();
+
+    let mut state = ListState::default().with_selected(Some(app.column_picker_selected));
+    f.render_stateful_widget(List::new(items), chunks[0], &mut state);
+
+    let help_text = vec![Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)),
+        Span::raw(": Navigate  "),
+        Span::styled("Space", Style::default().fg(Color::Yellow)),
+        Span::raw(": Show/hide  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(": Close"),
+    ])];
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::DarkGray));
+    f.render_widget(help, chunks[1]);
+}
+
+fn draw_palette(f: &mut Frame, app: &App) {
+    let actions = app.palette_actions();
+    let height = (actions.len().max(1) as u16 + 5).min(f.area().height * 4 / 5);
+    let area = centered_rect_with_height(50, height, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!("Command: {}▏", app.palette_filter))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(inner);
+
+    // Keys are right-aligned so the labels line up
+    let width = chunks[0].width as usize;
+    let items: Vec<ListItem> = if actions.is_empty() {
+        vec![ListItem::new("  No matching commands").style(Style::default().fg(Color::DarkGray))]
+    } else {
+        actions
+            .iter()
+            .enumerate()
+            .map(|(i, action)| {
+                let keys: Vec<String> = app
+                    .keymap
+                    .keys_for(*action)
+                    .iter()
+                    .map(|key| key.label())
+                    .collect();
+                let keys = keys.join(" ");
+                let label = format!("  {}", action.label());
+                let gap = width.saturating_sub(label.chars().count() + keys.chars().count() + 1);
+                let style = if i == app.palette_selected {
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{}{}", label, " ".repeat(gap))),
+                    Span::styled(keys, Style::default().fg(Color::Cyan)),
+                ]))
+                .style(style)
+            })
+            .collect()
+    };
+
+    let mut state = ListState::default().with_selected(Some(app.palette_selected));
+    f.render_stateful_widget(List::new(items), chunks[0], &mut state);
+
+    let help_text = vec![Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)),
+        Span::raw(": Navigate  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)),
+        Span::raw(": Run  "),
+        Span::styled("type", Style::default().fg(Color::Yellow)),
+        Span::raw(": Filter  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(": Close"),
+    ])];
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::DarkGray));
+    f.render_widget(help, chunks[1]);
+}
+
+// The alarm's metric over the chosen range. Bars are scaled so the threshold sits at a fixed
+// height, which makes it easy to see how close the metric came to breaching.
+fn draw_metric_sparkline(f: &mut Frame, metric: &MetricSeries, area: Rect) {
+    let range = metric.range.label();
+    let mut title = format!(" {}, last {}", metric.label, range);
+    if let Some(latest) = metric.values.last() {
+        title.push_str(&format!(" | latest {:.2}", latest));
+    }
+    if let Some(threshold) = metric.threshold {
+        title.push_str(&format!(" | threshold {:.2}", threshold));
+    }
+    let mut block = Block::default()
+        .title(format!("{} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+
+    if metric.values.is_empty() {
+        let empty = Paragraph::new(format!("No datapoints in the last {}", range))
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        f.render_widget(empty, area);
+        return;
+    }
+
+    // The lowest and highest datapoints label the bottom border's ends
+    let low = metric.values.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = metric
+        .values
+        .iter()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    block = block
+        .title_bottom(format!(" min {:.2} ", low))
+        .title_bottom(Line::from(format!(" max {:.2} ", high)).right_aligned());
+
+    let (data, max) = scale_metric_values(&metric.values, metric.threshold);
+    let color = match metric.threshold {
+        Some(threshold) if metric.values.iter().any(|v| *v >= threshold) => Color::Red,
+        _ => Color::Green,
+    };
+    let sparkline = Sparkline::default()
+        .block(block)
+        .data(&data)
+        .max(max)
+        .style(Style::default().fg(color));
+    f.render_widget(sparkline, area);
+}
+
+// Sparkline only takes integers, so map values onto 0..=100 of max(values, threshold)
+fn scale_metric_values(values: &[f64], threshold: Option<f64>) -> (Vec<u64>, u64) {
+    const SCALE: f64 = 100.0;
+    let top = values
+        .iter()
+        .copied()
+        .chain(threshold)
+        .fold(0.0_f64, f64::max);
+    if top <= 0.0 {
+        return (vec![0; values.len()], SCALE as u64);
+    }
+    let data = values
+        .iter()
+        .map(|v| (v.max(0.0) / top * SCALE).round() as u64)
+        .collect();
+    (data, SCALE as u64)
+}
+
+// Compliance checks stand out from the other details
+fn detail_value_style(key: &str, value: &str) -> Style {
+    match (key, value) {
+        ("Point-in-Time Recovery", "ENABLED") => Style::default().fg(Color::Green),
+        ("Point-in-Time Recovery", _) => {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        }
+        _ => Style::default().fg(Color::White),
+    }
+}
+
 fn draw_detail_popup(f: &mut Frame, app: &App) {
     // Calculate popup size and position (centered, larger)
     let area = centered_rect(70, 70, f.area());
@@ -382,12 +1148,18 @@ fn draw_detail_popup(f: &mut Frame, app: &App) {
     f.render_widget(Clear, area);
 
     // Create the popup container
-    let title = if app.detail_loading {
-        "Loading Details..."
+    let title = if let Some(title) = app.watch_title() {
+        title
+    } else if app.detail_loading {
+        "Loading Details...".to_string()
+    } else if let Some(parent) = &app.detail_parent {
+        format!("Index {}", parent.index_name)
+    } else if let Some(group) = &app.detail_logs {
+        format!("Latest events of {}", group)
     } else if app.selected_index < app.items.len() {
-        "Resource Details"
+        "Resource Details".to_string()
     } else {
-        "Details"
+        "Details".to_string()
     };
 
     let popup_block = Block::default()
@@ -412,12 +1184,31 @@ fn draw_detail_popup(f: &mut Frame, app: &App) {
         .constraints([Constraint::Min(0), Constraint::Length(2)])
         .split(inner_area);
 
-    // Create detail items with key-value formatting
+    // Create detail items with key-value formatting; JSON lines sit under their detail
     let items: Vec<ListItem> = app
-        .detail_content
-        .iter()
-        .map(|(key, value)| {
-            let content = if value.is_empty() {
+        .detail_rows()
+        .into_iter()
+        .enumerate()
+        .map(|(row, (index, json))| {
+            let (key, value) = &app.detail_content[index];
+            let style = if row == app.detail_scroll {
+                Style::default().bg(Color::DarkGray)
+            } else if json.is_none() && app.watch_flashing(key) {
+                Style::default().bg(Color::Yellow).fg(Color::Black)
+            } else {
+                Style::default()
+            };
+            if let Some(line) = json {
+                return ListItem::new(json_line(&line)).style(style);
+            }
+            let content = if app.detail_pretty.contains(&index) {
+                Line::from(vec![Span::styled(
+                    format!("{}: (JSON)", key),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                )])
+            } else if value.is_empty() {
                 Line::from(vec![Span::styled(
                     key,
                     Style::default()
@@ -432,25 +1223,72 @@ fn draw_detail_popup(f: &mut Frame, app: &App) {
                             .fg(Color::Cyan)
                             .add_modifier(Modifier::BOLD),
                     ),
-                    Span::styled(value, Style::default().fg(Color::White)),
+                    Span::styled(value, detail_value_style(key, value)),
                 ])
             };
-            ListItem::new(content)
+            ListItem::new(content).style(style)
         })
         .collect();
 
     let list = List::new(items);
-    f.render_widget(list, chunks[0]);
+    let mut state = ListState::default().with_selected(Some(app.detail_scroll));
+    match &app.detail_metric {
+        Some(metric) => {
+            let parts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(8)])
+                .split(chunks[0]);
+            f.render_stateful_widget(list, parts[0], &mut state);
+            draw_metric_sparkline(f, metric, parts[1]);
+        }
+        None => f.render_stateful_widget(list, chunks[0], &mut state),
+    }
 
     // Draw help text at bottom
-    let help_text = vec![Line::from(vec![
+    let mut help_spans = vec![
         Span::styled("↑/↓/j/k", Style::default().fg(Color::Yellow)),
         Span::raw(": Scroll  "),
-        Span::styled("Esc", Style::default().fg(Color::Yellow)),
-        Span::raw(" or "),
-        Span::styled("i", Style::default().fg(Color::Yellow)),
-        Span::raw(": Close"),
-    ])];
+        Span::styled("p", Style::default().fg(Color::Yellow)),
+        Span::raw(": Pretty JSON  "),
+        Span::styled("J", Style::default().fg(Color::Yellow)),
+        Span::raw(if app.detail_curated.is_some() {
+            ": Details  "
+        } else {
+            ": Raw JSON  "
+        }),
+        Span::styled("e", Style::default().fg(Color::Yellow)),
+        Span::raw(": Editor  "),
+    ];
+    if app.detail_metric.is_some() {
+        help_spans.push(Span::styled("t", Style::default().fg(Color::Yellow)));
+        help_spans.push(Span::raw(": Time range  "));
+    }
+    if app.detail_bucket().is_some() {
+        help_spans.push(Span::styled("S", Style::default().fg(Color::Yellow)));
+        help_spans.push(Span::raw(": Summarize  "));
+    }
+    if app.detail_lambda_function().is_some() {
+        help_spans.push(Span::styled("V", Style::default().fg(Color::Yellow)));
+        help_spans.push(Span::raw(": Versions  "));
+        help_spans.push(Span::styled("l", Style::default().fg(Color::Yellow)));
+        help_spans.push(Span::raw(": Logs  "));
+    }
+    if app.detail_parent.is_some() {
+        help_spans.push(Span::styled("Esc/q", Style::default().fg(Color::Yellow)));
+        help_spans.push(Span::raw(": Back to table"));
+    } else {
+        if !app.detail_indexes.is_empty() {
+            help_spans.push(Span::styled("Enter", Style::default().fg(Color::Yellow)));
+            help_spans.push(Span::raw(": Open index  "));
+        }
+        help_spans.extend([
+            Span::styled("Esc/q", Style::default().fg(Color::Yellow)),
+            Span::raw(" or "),
+            Span::styled("i", Style::default().fg(Color::Yellow)),
+            Span::raw(": Close"),
+        ]);
+    }
+    let help_text = vec![Line::from(help_spans)];
 
     let help = Paragraph::new(help_text)
         .alignment(Alignment::Center)
@@ -459,6 +1297,37 @@ fn draw_detail_popup(f: &mut Frame, app: &App) {
     f.render_widget(help, chunks[1]);
 }
 
+// One pretty-printed JSON line: keys cyan, strings green, numbers yellow, literals magenta
+fn json_line(text: &str) -> Line<'static> {
+    let body = text.trim_start();
+    let indent = &text[..text.len() - body.len()];
+    let mut spans = vec![Span::raw(format!("  {}", indent))];
+
+    let value = match body.split_once("\": ") {
+        Some((key, value)) if body.starts_with('"') => {
+            spans.push(Span::styled(
+                format!("{}\": ", key),
+                Style::default().fg(Color::Cyan),
+            ));
+            value
+        }
+        _ => body,
+    };
+    let (value, comma) = match value.strip_suffix(',') {
+        Some(value) => (value, ","),
+        None => (value, ""),
+    };
+    let color = match value.chars().next() {
+        Some('"') => Color::Green,
+        Some(c) if c == '-' || c.is_ascii_digit() => Color::Yellow,
+        Some('t' | 'f' | 'n') => Color::Magenta,
+        _ => Color::DarkGray,
+    };
+    spans.push(Span::styled(value.to_string(), Style::default().fg(color)));
+    spans.push(Span::styled(comma, Style::default().fg(Color::DarkGray)));
+    Line::from(spans)
+}
+
 fn draw_quit_confirmation(f: &mut Frame) {
     // Calculate popup size and position (small, centered)
     let area = centered_rect(40, 20, f.area());
@@ -518,6 +1387,206 @@ fn draw_quit_confirmation(f: &mut Frame) {
     f.render_widget(buttons_widget, chunks[2]);
 }
 
+fn draw_event_log(f: &mut Frame, app: &App) {
+    let area = centered_rect(80, 70, f.area());
+
+    // Clear the background
+    f.render_widget(Clear, area);
+
+    let popup_block = Block::default()
+        .title(format!("Event Log ({})", app.event_log.len()))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    f.render_widget(popup_block, area);
+
+    // Create inner area for content
+    let inner_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner_area);
+
+    let items: Vec<ListItem> = app
+        .event_log
+        .iter()
+        .map(|entry| {
+            let style = if entry.contains("Error") {
+                Style::default().fg(Color::LightRed)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            ListItem::new(entry.as_str()).style(style)
+        })
+        .collect();
+
+    let list = List::new(items).highlight_style(Style::default().bg(Color::DarkGray));
+    let mut state = ListState::default().with_selected(Some(app.event_log_scroll));
+    f.render_stateful_widget(list, chunks[0], &mut state);
+
+    let help_text = Line::from(vec![
+        Span::styled("↑/↓/j/k", Style::default().fg(Color::Yellow)),
+        Span::raw(": Scroll  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(" or "),
+        Span::styled("L", Style::default().fg(Color::Yellow)),
+        Span::raw(": Close"),
+    ]);
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::DarkGray));
+    f.render_widget(help, chunks[1]);
+}
+
+fn draw_error_popup(f: &mut Frame, app: &App) {
+    let area = centered_rect(80, 70, f.area());
+    f.render_widget(Clear, area);
+
+    let popup_block = Block::default()
+        .title("Error Details")
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red));
+    let inner_area = popup_block.inner(area);
+    f.render_widget(popup_block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner_area);
+
+    let message = app.error_message.as_deref().unwrap_or_default();
+    let mut lines = Vec::new();
+    // The request id goes first, set apart, so it is easy to select and copy
+    if let Some(request_id) = extract_request_id(message) {
+        lines.push(Line::from(vec![
+            Span::styled("Request ID: ", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                request_id,
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]));
+        lines.push(Line::from(""));
+    }
+    lines.extend(message.lines().map(|line| Line::from(line.to_string())));
+
+    let text = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((app.error_scroll, 0));
+    f.render_widget(text, chunks[0]);
+
+    let help_text = Line::from(vec![
+        Span::styled("↑/↓/j/k", Style::default().fg(Color::Yellow)),
+        Span::raw(": Scroll  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(" or "),
+        Span::styled("E", Style::default().fg(Color::Yellow)),
+        Span::raw(": Close"),
+    ]);
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::DarkGray));
+    f.render_widget(help, chunks[1]);
+}
+
+fn draw_prompt(f: &mut Frame, prompt: &Prompt, scroll: usize) {
+    let area = centered_rect(60, 30, f.area());
+
+    // Clear the background
+    f.render_widget(Clear, area);
+
+    let popup_block = Block::default()
+        .title(prompt.title.as_str())
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+
+    f.render_widget(popup_block, area);
+
+    // Create inner area for content
+    let inner_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+
+    // Split inner area for context lines, input and help text
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(2),
+            Constraint::Length(1),
+        ])
+        .split(inner_area);
+
+    let lines: Vec<Line> = prompt
+        .lines
+        .iter()
+        .map(|line| {
+            Line::from(Span::styled(
+                line.as_str(),
+                Style::default().fg(Color::White),
+            ))
+        })
+        .collect();
+    let overflows = lines.len() > chunks[0].height as usize;
+    f.render_widget(
+        Paragraph::new(lines).scroll((scroll.min(u16::MAX as usize) as u16, 0)),
+        chunks[0],
+    );
+
+    if let Some(input) = &prompt.input {
+        let input_line = Line::from(vec![
+            Span::styled(
+                format!("{}: ", prompt.input_label),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(input.as_str(), Style::default().fg(Color::White)),
+            Span::styled("▏", Style::default().fg(Color::Yellow)),
+        ]);
+        f.render_widget(Paragraph::new(input_line), chunks[1]);
+    }
+
+    let mut help_spans = vec![
+        Span::styled("Enter", Style::default().fg(Color::Yellow)),
+        Span::raw(": Confirm  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(": Cancel"),
+    ];
+    if overflows {
+        help_spans.push(Span::styled("  ↑↓", Style::default().fg(Color::Yellow)));
+        help_spans.push(Span::raw(": Scroll"));
+    }
+    let help_text = Line::from(help_spans);
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::DarkGray));
+    f.render_widget(help, chunks[2]);
+}
+
+// A rect `height` rows tall, centered vertically, and `percent_x` of the width
+fn centered_rect_with_height(percent_x: u16, height: u16, r: Rect) -> Rect {
+    let height = height.min(r.height);
+    Rect {
+        y: r.y + (r.height - height) / 2,
+        height,
+        ..centered_rect(percent_x, 100, r)
+    }
+}
+
 // Helper function to create a centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
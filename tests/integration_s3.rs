@@ -1,9 +1,40 @@
+use aws_sdk_s3::error::ErrorMetadata;
+use aws_sdk_s3::operation::abort_multipart_upload::AbortMultipartUploadOutput;
+use aws_sdk_s3::operation::complete_multipart_upload::CompleteMultipartUploadOutput;
+use aws_sdk_s3::operation::create_multipart_upload::CreateMultipartUploadOutput;
+use aws_sdk_s3::operation::delete_bucket::DeleteBucketError;
+use aws_sdk_s3::operation::delete_objects::DeleteObjectsOutput;
+use aws_sdk_s3::operation::get_bucket_location::GetBucketLocationOutput;
+use aws_sdk_s3::operation::get_bucket_policy::GetBucketPolicyError;
+use aws_sdk_s3::operation::get_bucket_tagging::{GetBucketTaggingError, GetBucketTaggingOutput};
+use aws_sdk_s3::operation::get_object::GetObjectOutput;
+use aws_sdk_s3::operation::get_object_tagging::GetObjectTaggingOutput;
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
 use aws_sdk_s3::operation::list_buckets::ListBucketsOutput;
+use aws_sdk_s3::operation::list_object_versions::ListObjectVersionsOutput;
 use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Output;
-use aws_sdk_s3::types::{Bucket, Object};
-use aws_smithy_mocks::{mock, mock_client};
+use aws_sdk_s3::operation::put_object::PutObjectOutput;
+use aws_sdk_s3::operation::upload_part::UploadPartOutput;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{
+    Bucket, BucketLocationConstraint, CommonPrefix, DeleteMarkerEntry, Object, ObjectStorageClass,
+    ObjectVersion, Tag,
+};
+use aws_smithy_mocks::{Rule, RuleMode, mock, mock_client};
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+use aws_smithy_runtime_api::http::StatusCode;
+use aws_smithy_types::body::SdkBody;
 use aws_smithy_types::date_time::DateTime;
-use awsome::aws::S3Service;
+use awsome::aws::{BucketTag, FolderStats, Paging, Progress, S3Service};
+
+// Object calls look up the bucket's region first; the mock client is in us-east-1
+fn bucket_location_rule(region: Option<&'static str>) -> Rule {
+    mock!(aws_sdk_s3::Client::get_bucket_location).then_output(move || {
+        GetBucketLocationOutput::builder()
+            .set_location_constraint(region.map(BucketLocationConstraint::from))
+            .build()
+    })
+}
 
 #[tokio::test]
 async fn test_list_buckets() {
@@ -63,17 +94,699 @@ async fn test_list_objects() {
                 .build()
         });
 
-    let client = mock_client!(aws_sdk_s3, [&list_objects_rule]);
+    let client = mock_client!(
+        aws_sdk_s3,
+        [&bucket_location_rule(None), &list_objects_rule]
+    );
     let s3_service = S3Service::new(client);
 
     let objects = s3_service
-        .list_objects("test-bucket", "folder/")
+        .list_objects(
+            "test-bucket",
+            "folder/",
+            Paging::default(),
+            &Progress::default(),
+        )
         .await
         .expect("failed to list objects");
 
     assert_eq!(objects.len(), 1);
     assert_eq!(objects[0].0, "file1.txt"); // Name should be stripped of prefix
     assert_eq!(objects[0].1, "1.00 KB"); // Size formatted
+    assert_eq!(objects[0].3, "STANDARD"); // Missing storage class means STANDARD
 
     assert_eq!(list_objects_rule.num_calls(), 1);
 }
+
+#[tokio::test]
+async fn test_list_objects_follows_pages() {
+    let object = |key: &str| {
+        Object::builder()
+            .key(key)
+            .size(1)
+            .last_modified(DateTime::from_secs(1672531200))
+            .build()
+    };
+    let first_page = mock!(aws_sdk_s3::Client::list_objects_v2)
+        .match_requests(|req| req.continuation_token().is_none())
+        .then_output(move || {
+            ListObjectsV2Output::builder()
+                .contents(object("a.txt"))
+                .is_truncated(true)
+                .next_continuation_token("page2")
+                .build()
+        });
+    let second_page = mock!(aws_sdk_s3::Client::list_objects_v2)
+        .match_requests(|req| req.continuation_token() == Some("page2"))
+        .then_output(move || {
+            ListObjectsV2Output::builder()
+                .contents(object("b.txt"))
+                .build()
+        });
+
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [&bucket_location_rule(None), &first_page, &second_page]
+    );
+    let s3_service = S3Service::new(client);
+
+    let (progress, mut updates) = Progress::channel();
+    let objects = s3_service
+        .list_objects("test-bucket", "", Paging::default(), &progress)
+        .await
+        .expect("failed to list objects");
+
+    assert_eq!(objects.len(), 2);
+    assert_eq!(second_page.num_calls(), 1);
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        "Loaded 1 objects from s3://test-bucket/..."
+    );
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        "Loaded 2 objects from s3://test-bucket/..."
+    );
+
+    // A cap stops paging once it is exceeded, leaving the caller one item to spot the cut
+    let paging = Paging {
+        page_size: None,
+        max_items: Some(0),
+    };
+    let objects = s3_service
+        .list_objects("test-bucket", "", paging, &Progress::default())
+        .await
+        .expect("failed to list objects");
+    assert_eq!(objects.len(), 1);
+    assert_eq!(second_page.num_calls(), 1);
+}
+
+#[tokio::test]
+async fn test_delete_objects_in_batches() {
+    let delete_rule = mock!(aws_sdk_s3::Client::delete_objects)
+        .match_requests(|req| req.bucket() == Some("test-bucket"))
+        .then_output(|| DeleteObjectsOutput::builder().build());
+
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [&bucket_location_rule(None), &delete_rule]
+    );
+    let s3_service = S3Service::new(client);
+
+    let keys: Vec<String> = (0..1500).map(|i| format!("folder/{}", i)).collect();
+    let deleted = s3_service
+        .delete_objects("test-bucket", &keys, &Progress::default())
+        .await
+        .expect("failed to delete objects");
+
+    assert_eq!(deleted, 1500);
+    // 1000 keys per DeleteObjects request
+    assert_eq!(delete_rule.num_calls(), 2);
+}
+
+#[tokio::test]
+async fn test_upload_object() {
+    let put_rule = mock!(aws_sdk_s3::Client::put_object)
+        .match_requests(|req| req.key() == Some("folder/small.txt"))
+        .then_output(|| PutObjectOutput::builder().build());
+    let create_rule = mock!(aws_sdk_s3::Client::create_multipart_upload).then_output(|| {
+        CreateMultipartUploadOutput::builder()
+            .upload_id("u1")
+            .build()
+    });
+    let part_rule = mock!(aws_sdk_s3::Client::upload_part)
+        .match_requests(|req| req.upload_id() == Some("u1"))
+        .then_output(|| UploadPartOutput::builder().e_tag("etag").build());
+    let complete_rule = mock!(aws_sdk_s3::Client::complete_multipart_upload)
+        .match_requests(|req| {
+            req.multipart_upload()
+                .is_some_and(|upload| upload.parts().len() == 3)
+        })
+        .then_output(|| CompleteMultipartUploadOutput::builder().build());
+
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [
+            &bucket_location_rule(None),
+            &put_rule,
+            &create_rule,
+            &part_rule,
+            &complete_rule
+        ]
+    );
+    let s3_service = S3Service::new(client);
+
+    let dir = std::env::temp_dir().join(format!("awsome-upload-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let small = dir.join("small.txt");
+    std::fs::write(&small, "hello").unwrap();
+    // Two full 8 MiB parts and a short last one
+    let large = dir.join("large.bin");
+    std::fs::write(&large, vec![0u8; 17 * 1024 * 1024]).unwrap();
+
+    let (progress, mut updates) = Progress::channel();
+    let size = s3_service
+        .upload_object("test-bucket", "folder/small.txt", &small, &progress)
+        .await
+        .expect("failed to upload small file");
+    assert_eq!(size, 5);
+    assert_eq!(put_rule.num_calls(), 1);
+
+    s3_service
+        .upload_object("test-bucket", "folder/large.bin", &large, &progress)
+        .await
+        .expect("failed to upload large file");
+    assert_eq!(part_rule.num_calls(), 3);
+    assert_eq!(complete_rule.num_calls(), 1);
+
+    let mut last = String::new();
+    while let Ok(message) = updates.try_recv() {
+        last = message;
+    }
+    assert_eq!(
+        last,
+        "Uploading folder/large.bin: 100% (17.00 MB of 17.00 MB)"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_upload_cancelled() {
+    let create_rule = mock!(aws_sdk_s3::Client::create_multipart_upload).then_output(|| {
+        CreateMultipartUploadOutput::builder()
+            .upload_id("u2")
+            .build()
+    });
+    let part_rule = mock!(aws_sdk_s3::Client::upload_part)
+        .match_requests(|req| req.part_number() == Some(1))
+        .then_output(|| UploadPartOutput::builder().e_tag("etag").build());
+    // The second part never gets its response, so the upload is still running when dropped
+    let stalled_rule = mock!(aws_sdk_s3::Client::upload_part)
+        .match_requests(|req| req.part_number() == Some(2))
+        .then_http_response(|| {
+            HttpResponse::new(
+                StatusCode::try_from(200).unwrap(),
+                SdkBody::from_body_1_x(StalledBody { sent: false }),
+            )
+        });
+    let complete_rule = mock!(aws_sdk_s3::Client::complete_multipart_upload)
+        .then_output(|| CompleteMultipartUploadOutput::builder().build());
+    let abort_rule = mock!(aws_sdk_s3::Client::abort_multipart_upload)
+        .match_requests(|req| req.upload_id() == Some("u2") && req.key() == Some("large.bin"))
+        .then_output(|| AbortMultipartUploadOutput::builder().build());
+
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [
+            &bucket_location_rule(None),
+            &create_rule,
+            &part_rule,
+            &stalled_rule,
+            &complete_rule,
+            &abort_rule
+        ]
+    );
+    let s3_service = S3Service::new(client);
+
+    let dir = std::env::temp_dir().join(format!("awsome-upload-cancel-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let large = dir.join("large.bin");
+    std::fs::write(&large, vec![0u8; 17 * 1024 * 1024]).unwrap();
+
+    // Cancel while the second part is on its way, the way Esc aborts the upload task
+    let upload = tokio::spawn({
+        let large = large.clone();
+        async move {
+            let (progress, _updates) = Progress::channel();
+            s3_service
+                .upload_object("test-bucket", "large.bin", &large, &progress)
+                .await
+        }
+    });
+    for _ in 0..100 {
+        if stalled_rule.num_calls() > 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(stalled_rule.num_calls(), 1);
+    upload.abort();
+    assert!(upload.await.unwrap_err().is_cancelled());
+
+    // The abort runs as its own task once the upload is dropped
+    for _ in 0..100 {
+        if abort_rule.num_calls() > 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(abort_rule.num_calls(), 1);
+    assert_eq!(complete_rule.num_calls(), 0);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_list_object_versions() {
+    // The second page is asked for with the markers the first one returned
+    let first_page = mock!(aws_sdk_s3::Client::list_object_versions)
+        .match_requests(|req| req.key_marker().is_none())
+        .then_output(|| {
+            ListObjectVersionsOutput::builder()
+                .common_prefixes(CommonPrefix::builder().prefix("logs/old/").build())
+                .versions(
+                    ObjectVersion::builder()
+                        .key("logs/app.log")
+                        .version_id("v1")
+                        .is_latest(false)
+                        .size(1024)
+                        .last_modified(DateTime::from_secs(1672531200))
+                        .build(),
+                )
+                .is_truncated(true)
+                .next_key_marker("logs/app.log")
+                .next_version_id_marker("v1")
+                .build()
+        });
+    let second_page = mock!(aws_sdk_s3::Client::list_object_versions)
+        .match_requests(|req| {
+            req.key_marker() == Some("logs/app.log") && req.version_id_marker() == Some("v1")
+        })
+        .then_output(|| {
+            ListObjectVersionsOutput::builder()
+                .delete_markers(
+                    DeleteMarkerEntry::builder()
+                        .key("logs/app.log")
+                        .version_id("v2")
+                        .is_latest(true)
+                        .last_modified(DateTime::from_secs(1672617600))
+                        .build(),
+                )
+                .is_truncated(false)
+                .build()
+        });
+
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [&bucket_location_rule(None), &first_page, &second_page]
+    );
+    let s3_service = S3Service::new(client);
+
+    let versions = s3_service
+        .list_object_versions(
+            "test-bucket",
+            "logs/",
+            Paging::default(),
+            &Progress::default(),
+        )
+        .await
+        .expect("failed to list object versions");
+
+    assert_eq!(versions.len(), 3);
+    assert_eq!(versions[0].name, "old/");
+    assert_eq!(versions[0].size, "DIR");
+    // The newer delete marker is listed above the version it hides
+    assert_eq!(versions[1].version_id, "v2");
+    assert!(versions[1].delete_marker && versions[1].is_latest);
+    assert_eq!(versions[2].name, "app.log");
+    assert_eq!(versions[2].size, "1.00 KB");
+}
+
+#[tokio::test]
+async fn test_download_object() {
+    let get_rule = mock!(aws_sdk_s3::Client::get_object)
+        .match_requests(|req| req.version_id() == Some("v1"))
+        .then_output(|| {
+            GetObjectOutput::builder()
+                .content_length(5)
+                .body(ByteStream::from_static(b"hello"))
+                .build()
+        });
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [&bucket_location_rule(None), &get_rule]
+    );
+    let s3_service = S3Service::new(client);
+
+    let dir = std::env::temp_dir().join(format!("awsome-download-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("app.log");
+
+    let (progress, mut updates) = Progress::channel();
+    let size = s3_service
+        .download_object("test-bucket", "logs/app.log", Some("v1"), &path, &progress)
+        .await
+        .expect("failed to download object");
+    assert_eq!(size, 5);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello");
+    // The object is written to app.log.part and renamed once complete
+    assert!(!dir.join("app.log.part").exists());
+    let mut last = String::new();
+    while let Ok(message) = updates.try_recv() {
+        last = message;
+    }
+    assert_eq!(last, "Downloading logs/app.log: 100% (5 B of 5 B)");
+
+    // An existing file is left alone
+    assert!(
+        s3_service
+            .download_object("test-bucket", "logs/app.log", Some("v1"), &path, &progress)
+            .await
+            .is_err()
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// A body that sends its first bytes and then never finishes, like a slow download
+struct StalledBody {
+    sent: bool,
+}
+
+impl http_body::Body for StalledBody {
+    type Data = bytes::Bytes;
+    type Error = std::io::Error;
+
+    fn poll_frame(
+        mut self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        if self.sent {
+            return std::task::Poll::Pending;
+        }
+        self.sent = true;
+        let chunk = bytes::Bytes::from_static(b"hel");
+        std::task::Poll::Ready(Some(Ok(http_body::Frame::data(chunk))))
+    }
+}
+
+#[tokio::test]
+async fn test_download_cancelled() {
+    let get_rule = mock!(aws_sdk_s3::Client::get_object).then_output(|| {
+        GetObjectOutput::builder()
+            .content_length(5)
+            .body(ByteStream::from_body_1_x(StalledBody { sent: false }))
+            .build()
+    });
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [&bucket_location_rule(None), &get_rule]
+    );
+    let s3_service = S3Service::new(client);
+
+    let dir = std::env::temp_dir().join(format!("awsome-cancel-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("app.log");
+    let part = dir.join("app.log.part");
+
+    let download = tokio::spawn({
+        let path = path.clone();
+        async move {
+            s3_service
+                .download_object(
+                    "test-bucket",
+                    "logs/app.log",
+                    None,
+                    &path,
+                    &Progress::default(),
+                )
+                .await
+        }
+    });
+    // The first bytes land in the .part file, never under the destination name
+    while std::fs::metadata(&part).map_or(0, |m| m.len()) < 3 {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert!(!path.exists());
+
+    // Esc aborts the task; the partial file goes with it
+    download.abort();
+    assert!(download.await.unwrap_err().is_cancelled());
+    assert!(!part.exists() && !path.exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_delete_bucket_not_empty() {
+    let delete_bucket_rule = mock!(aws_sdk_s3::Client::delete_bucket).then_error(|| {
+        DeleteBucketError::generic(ErrorMetadata::builder().code("BucketNotEmpty").build())
+    });
+    let client = mock_client!(aws_sdk_s3, [&delete_bucket_rule]);
+    let s3_service = S3Service::new(client);
+
+    let err = s3_service.delete_bucket("logs").await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "bucket logs is not empty; delete its objects first"
+    );
+}
+
+#[tokio::test]
+async fn test_bucket_tags_are_cached() {
+    let tagged_rule = mock!(aws_sdk_s3::Client::get_bucket_tagging)
+        .match_requests(|req| req.bucket() == Some("site"))
+        .then_output(|| {
+            GetBucketTaggingOutput::builder()
+                .tag_set(Tag::builder().key("Team").value("web").build().unwrap())
+                .build()
+                .unwrap()
+        });
+    let untagged_rule = mock!(aws_sdk_s3::Client::get_bucket_tagging)
+        .match_requests(|req| req.bucket() == Some("scratch"))
+        .then_error(|| {
+            GetBucketTaggingError::generic(ErrorMetadata::builder().code("NoSuchTagSet").build())
+        });
+    let denied_rule = mock!(aws_sdk_s3::Client::get_bucket_tagging)
+        .match_requests(|req| req.bucket() == Some("locked"))
+        .then_error(|| {
+            GetBucketTaggingError::generic(ErrorMetadata::builder().code("AccessDenied").build())
+        });
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [
+            &bucket_location_rule(None),
+            &tagged_rule,
+            &untagged_rule,
+            &denied_rule
+        ]
+    );
+    let s3_service = S3Service::new(client);
+
+    let buckets = vec![
+        "site".to_string(),
+        "scratch".to_string(),
+        "locked".to_string(),
+    ];
+    for _ in 0..2 {
+        let values = s3_service
+            .bucket_tag_values(&buckets, "Team", &Progress::default())
+            .await;
+        assert_eq!(
+            values,
+            vec![
+                BucketTag::Value("web".to_string()),
+                BucketTag::Missing,
+                BucketTag::Unreadable
+            ]
+        );
+    }
+    assert_eq!(tagged_rule.num_calls(), 1);
+    assert_eq!(untagged_rule.num_calls(), 1);
+    // A failed read isn't cached, so it is tried again
+    assert_eq!(denied_rule.num_calls(), 2);
+
+    // An explicit refresh forgets the cache
+    s3_service.clear_bucket_tags();
+    s3_service
+        .bucket_tag_values(&buckets, "Team", &Progress::default())
+        .await;
+    assert_eq!(tagged_rule.num_calls(), 2);
+}
+
+#[tokio::test]
+async fn test_folder_stats() {
+    let object = |key: &str, size| Object::builder().key(key).size(size).build();
+    let data_rule = mock!(aws_sdk_s3::Client::list_objects_v2)
+        .match_requests(|req| req.prefix() == Some("data/") && req.delimiter() == Some("/"))
+        .then_output(move || {
+            ListObjectsV2Output::builder()
+                .contents(object("data/", 0))
+                .contents(object("data/a.csv", 100))
+                .contents(object("data/b.csv", 50))
+                .common_prefixes(CommonPrefix::builder().prefix("data/raw/").build())
+                .is_truncated(false)
+                .build()
+        });
+    let logs_rule = mock!(aws_sdk_s3::Client::list_objects_v2)
+        .match_requests(|req| req.prefix() == Some("logs/"))
+        .then_output(move || {
+            ListObjectsV2Output::builder()
+                .contents(object("logs/1.log", 10))
+                .is_truncated(true)
+                .build()
+        });
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [&bucket_location_rule(None), &data_rule, &logs_rule]
+    );
+    let s3_service = S3Service::new(client);
+
+    let folders = vec!["data/".to_string(), "logs/".to_string()];
+    let stats = s3_service
+        .folder_stats_all("bucket", "", &folders, &Progress::default())
+        .await;
+    // The placeholder object doesn't count as a child
+    let data = FolderStats {
+        items: 3,
+        bytes: 150,
+        truncated: false,
+    };
+    assert_eq!(stats["data/"], data);
+    assert!(stats["logs/"].truncated);
+    assert_eq!(stats["logs/"].items, 1);
+    // One page per folder, however many there are
+    assert_eq!(data_rule.num_calls(), 1);
+    assert_eq!(logs_rule.num_calls(), 1);
+}
+
+#[tokio::test]
+async fn test_object_details_metadata_and_tags() {
+    let head_rule = mock!(aws_sdk_s3::Client::head_object).then_output(|| {
+        HeadObjectOutput::builder()
+            .content_length(10)
+            .metadata("stage", "parsed")
+            .metadata("attempts", "2")
+            .build()
+    });
+    let tagging_rule = mock!(aws_sdk_s3::Client::get_object_tagging)
+        .match_requests(|req| req.key() == Some("data/a.csv"))
+        .then_output(|| {
+            GetObjectTaggingOutput::builder()
+                .set_tag_set(Some(Vec::new()))
+                .build()
+                .unwrap()
+        });
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [&bucket_location_rule(None), &head_rule, &tagging_rule]
+    );
+    let s3_service = S3Service::new(client);
+
+    let details = s3_service
+        .get_object_details("bucket", "data/a.csv", None)
+        .await
+        .unwrap();
+    let at = |label: &str| details.iter().position(|(l, _)| l == label).unwrap();
+    // Metadata keys are sorted under their heading, and no tags reads "None"
+    let metadata = at("Metadata");
+    assert_eq!(details[metadata].1, "");
+    assert_eq!(
+        details[metadata + 1..metadata + 3],
+        [
+            ("  attempts".to_string(), "2".to_string()),
+            ("  stage".to_string(), "parsed".to_string()),
+        ]
+    );
+    assert_eq!(details[at("Tags")].1, "None");
+    assert_eq!(tagging_rule.num_calls(), 1);
+}
+
+#[tokio::test]
+async fn test_bucket_region_is_cached() {
+    let location_rule = bucket_location_rule(Some("EU"));
+    let list_objects_rule = mock!(aws_sdk_s3::Client::list_objects_v2)
+        .then_output(|| ListObjectsV2Output::builder().build());
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [&location_rule, &list_objects_rule]
+    );
+    let s3_service = S3Service::new(client);
+
+    // The legacy "EU" constraint means eu-west-1
+    assert_eq!(
+        s3_service.bucket_region("archive").await.unwrap(),
+        "eu-west-1"
+    );
+    for _ in 0..2 {
+        s3_service
+            .list_objects("archive", "", Paging::default(), &Progress::default())
+            .await
+            .expect("failed to list objects");
+    }
+    assert_eq!(location_rule.num_calls(), 1);
+    assert_eq!(list_objects_rule.num_calls(), 2);
+}
+
+#[tokio::test]
+async fn test_bucket_summary() {
+    let object = |key: &str, size: i64, class: Option<ObjectStorageClass>| {
+        Object::builder()
+            .key(key)
+            .size(size)
+            .set_storage_class(class)
+            .build()
+    };
+    let list_objects_rule = mock!(aws_sdk_s3::Client::list_objects_v2).then_output(move || {
+        ListObjectsV2Output::builder()
+            .contents(object("a", 1024, None))
+            .contents(object("b", 1024, Some(ObjectStorageClass::Standard)))
+            .contents(object("c", 2048, Some(ObjectStorageClass::Glacier)))
+            .build()
+    });
+    let policy_rule = mock!(aws_sdk_s3::Client::get_bucket_policy).then_error(|| {
+        GetBucketPolicyError::generic(ErrorMetadata::builder().code("NoSuchBucketPolicy").build())
+    });
+    let client = mock_client!(
+        aws_sdk_s3,
+        RuleMode::MatchAny,
+        [
+            &bucket_location_rule(None),
+            &list_objects_rule,
+            &policy_rule
+        ]
+    );
+    let s3_service = S3Service::new(client);
+
+    let (progress, mut updates) = Progress::channel();
+    let summary = s3_service
+        .get_bucket_summary("logs", &progress)
+        .await
+        .expect("failed to summarize bucket");
+    let value = |key: &str| {
+        summary
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+    assert_eq!(value("Objects"), Some("3"));
+    assert_eq!(value("Total Size"), Some("4.00 KB"));
+    assert_eq!(value("Bucket Policy"), Some("None"));
+    // Objects without a class count as STANDARD
+    assert!(
+        summary
+            .iter()
+            .any(|(_, row)| row.starts_with("GLACIER") && row.contains(" 1 "))
+    );
+    assert!(
+        summary
+            .iter()
+            .any(|(_, row)| row.starts_with("STANDARD") && row.contains(" 2 "))
+    );
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        "Scanned 3 objects in s3://logs..."
+    );
+}
@@ -1,7 +1,9 @@
-use awsome::app::App;
+use awsome::app::{App, LoadingState};
+use awsome::aws::{DynamoDbService, MetricRange, MetricSeries};
 use awsome::ui;
 use ratatui::Terminal;
 use ratatui::backend::TestBackend;
+use ratatui::style::Color;
 
 #[test]
 fn test_ui_initial_state() {
@@ -31,6 +33,162 @@ fn test_ui_initial_state() {
     assert!(content.contains("EC2 Instances")); // Active service tab
 }
 
+#[test]
+fn test_ui_too_small() {
+    let backend = TestBackend::new(40, 10);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let content = buffer_to_string(terminal.backend().buffer());
+    assert!(content.contains("Terminal too small"));
+    assert!(!content.contains("Initializing AWS client..."));
+}
+
+#[test]
+fn test_ui_alarm_metric_sparkline() {
+    let backend = TestBackend::new(120, 40);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new();
+    app.show_detail_popup = true;
+    app.detail_content = vec![("Alarm Name".to_string(), "high-cpu".to_string())];
+    app.detail_metric = Some(MetricSeries {
+        label: "CPUUtilization (Average)".to_string(),
+        values: vec![10.0, 40.0, 95.0],
+        threshold: Some(80.0),
+        range: MetricRange::default(),
+    });
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let content = buffer_to_string(terminal.backend().buffer());
+    assert!(content.contains("CPUUtilization (Average), last 1h"));
+    assert!(content.contains("latest 95.00"));
+    assert!(content.contains("threshold 80.00"));
+    assert!(content.contains("min 10.00") && content.contains("max 95.00"));
+    assert!(content.contains("t: Time range"));
+
+    app.detail_metric.as_mut().unwrap().range = MetricRange::Week;
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let content = buffer_to_string(terminal.backend().buffer());
+    assert!(content.contains("CPUUtilization (Average), last 7d"));
+}
+
+#[test]
+fn test_ui_error_popup_shows_request_id() {
+    let backend = TestBackend::new(120, 40);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new();
+    app.error_message = Some(
+        "service error: unhandled error (AccessDenied): Error { code: \"AccessDenied\", aws_request_id: \"7f3c-91ab\" }"
+            .to_string(),
+    );
+    app.toggle_error_details();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let content = buffer_to_string(terminal.backend().buffer());
+    assert!(content.contains("Error Details"));
+    assert!(content.contains("Request ID: 7f3c-91ab"));
+}
+
+#[test]
+fn test_ui_service_popup_scrolls_to_selection() {
+    // Too short for every service, so the popup list has to scroll
+    let backend = TestBackend::new(80, 15);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new();
+    app.toggle_service_popup();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(!buffer_to_string(terminal.backend().buffer()).contains("Lambda"));
+
+    app.popup_selected_index = app.services.len() - 1;
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_to_string(terminal.backend().buffer()).contains("Lambda"));
+}
+
+#[test]
+fn test_ui_dynamodb_status_colors() {
+    let backend = TestBackend::new(100, 20);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new();
+    app.switch_service(4); // DynamoDB
+    let table = |name: &str, status: &str| {
+        (
+            name.to_string(),
+            status.to_string(),
+            "0".to_string(),
+            "0 B".to_string(),
+        )
+    };
+    (app.items, app.dynamodb_items) = DynamoDbService::format_table_list(
+        &[
+            table("orders", "ACTIVE"),
+            table("migrating", "UPDATING"),
+            table("locked", "INACCESSIBLE_ENCRYPTION_CREDENTIALS"),
+        ],
+        &[],
+        None,
+        usize::MAX,
+    );
+    app.selected_index = 0;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let color_of = |name: &str| {
+        let content = buffer_to_string(buffer);
+        let (y, line) = content
+            .lines()
+            .enumerate()
+            .find(|(_, line)| line.contains(name))
+            .unwrap();
+        let x = line[..line.find(name).unwrap()].chars().count();
+        buffer[(x as u16, y as u16)].fg
+    };
+    assert_eq!(color_of("orders"), Color::Green);
+    assert_eq!(color_of("migrating"), Color::Yellow);
+    assert_eq!(color_of("locked"), Color::Red);
+}
+
+#[test]
+fn test_ui_dense_rows() {
+    let backend = TestBackend::new(100, 20);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new();
+    app.switch_service(4); // DynamoDB
+    let tables = [(
+        "orders".to_string(),
+        "ACTIVE".to_string(),
+        "12".to_string(),
+        "1.00 KB".to_string(),
+    )];
+    (app.items, app.dynamodb_items) =
+        DynamoDbService::format_table_list(&tables, &[], None, usize::MAX);
+    app.loading_state = LoadingState::Loaded;
+    app.selected_index = 2;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let content = buffer_to_string(terminal.backend().buffer());
+    assert!(content.contains("------"));
+
+    app.toggle_dense();
+    assert_eq!(app.visible_rows(), vec![2]);
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let content = buffer_to_string(terminal.backend().buffer());
+    assert!(!content.contains("------"));
+    // The header shares the top border with the title, right above the first table
+    let lines: Vec<&str> = content.lines().collect();
+    let header = lines
+        .iter()
+        .position(|line| line.contains("Table Name"))
+        .unwrap();
+    assert!(lines[header].contains("DynamoDB"));
+    assert!(lines[header + 1].contains("orders"));
+}
+
 fn buffer_to_string(buffer: &ratatui::buffer::Buffer) -> String {
     let mut s = String::new();
     for y in 0..buffer.area.height {